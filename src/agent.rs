@@ -0,0 +1,163 @@
+use crate::models::Account;
+use std::process::{Command, Stdio};
+
+/// The agent that holds SSH keys for this session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Agent {
+    /// ssh-agent, or the Windows OpenSSH agent service, driven with `ssh-add`.
+    OpenSsh,
+    /// PuTTY's Pageant on Windows; keys are added by handing them to `pageant`.
+    Pageant,
+}
+
+pub fn detect() -> Option<Agent> {
+    // `ssh-add -l` exits 2 when it can't reach an agent, 1 when it is empty.
+    let reachable = Command::new("ssh-add")
+        .arg("-l")
+        .stdin(Stdio::null())
+        .output()
+        .is_ok_and(|o| o.status.code() != Some(2));
+    if reachable {
+        return Some(Agent::OpenSsh);
+    }
+    (cfg!(windows) && pageant_running()).then_some(Agent::Pageant)
+}
+
+fn pageant_running() -> bool {
+    Command::new("tasklist")
+        .args(["/FI", "IMAGENAME eq pageant.exe", "/NH"])
+        .output()
+        .is_ok_and(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .to_lowercase()
+                .contains("pageant.exe")
+        })
+}
+
+/// `SHA256:...` fingerprint of a key file, as `ssh-add -l` prints it.
+fn fingerprint(key: &str) -> Option<String> {
+    let output = Command::new("ssh-keygen")
+        .args(["-lf", key])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)
+        .map(str::to_string)
+}
+
+/// Whether the key file is protected by a passphrase, so ssh has to ask for
+/// it unless the agent already holds the key.
+pub fn needs_passphrase(key: &str) -> bool {
+    Command::new("ssh-keygen")
+        .args(["-y", "-P", "", "-f", key])
+        .stdin(Stdio::null())
+        .output()
+        .is_ok_and(|o| !o.status.success())
+}
+
+/// Whether ssh-agent holds the key.
+pub fn is_loaded(key: &str) -> bool {
+    fingerprint(key).is_some_and(|f| loaded_fingerprints().contains(&f))
+}
+
+/// Load `account`'s key into ssh-agent; `ssh-add` asks for its passphrase.
+/// With `ssh_confirm` the agent asks before every use.
+pub fn add(account: &Account, key: &str) -> Result<(), String> {
+    let mut cmd = Command::new("ssh-add");
+    cmd.arg("-q");
+    if account.ssh_confirm {
+        cmd.arg("-c");
+    }
+    let status = cmd
+        .arg(key)
+        .status()
+        .map_err(|e| format!("Could not run ssh-add: {}", e))?;
+    if !status.success() {
+        return Err(format!("ssh-add could not load {}", key));
+    }
+    Ok(())
+}
+
+fn loaded_fingerprints() -> Vec<String> {
+    let Ok(output) = Command::new("ssh-add")
+        .arg("-l")
+        .stdin(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1).map(str::to_string))
+        .collect()
+}
+
+/// Load `account`'s key into the agent and unload the keys of every other
+/// account, so the forge can't be offered the wrong identity first. Returns
+/// what changed, one line each. `ssh-add` may ask for the key's passphrase.
+pub fn sync(accounts: &[Account], account: &Account) -> Result<Vec<String>, String> {
+    let Some(key) = account.ssh_key.as_deref() else {
+        return Err(format!("{} has no SSH key", account.username));
+    };
+    let agent = detect().ok_or_else(|| {
+        "No SSH agent is running (start ssh-agent or Pageant, or set SSH_AUTH_SOCK)".to_string()
+    })?;
+    let others: Vec<&str> = accounts
+        .iter()
+        .filter_map(|a| a.ssh_key.as_deref())
+        .filter(|other| *other != key)
+        .collect();
+
+    match agent {
+        Agent::OpenSsh => {
+            let mut notes = Vec::new();
+            let loaded = loaded_fingerprints();
+            for other in others {
+                if fingerprint(other).is_some_and(|f| loaded.contains(&f))
+                    && Command::new("ssh-add")
+                        .args(["-q", "-d", other])
+                        .stdin(Stdio::null())
+                        .output()
+                        .is_ok_and(|o| o.status.success())
+                {
+                    notes.push(format!("Removed {} from ssh-agent", other));
+                }
+            }
+            if fingerprint(key).is_some_and(|f| loaded.contains(&f)) {
+                notes.push(format!("{} is already in ssh-agent", key));
+                return Ok(notes);
+            }
+            add(account, key)?;
+            notes.push(format!("Added {} to ssh-agent", key));
+            Ok(notes)
+        }
+        Agent::Pageant => {
+            // Pageant reads PuTTY keys; prefer a converted copy next to the key.
+            let ppk = std::path::Path::new(key).with_extension("ppk");
+            let file = if ppk.is_file() {
+                ppk.to_string_lossy().to_string()
+            } else {
+                key.to_string()
+            };
+            let status = Command::new("pageant")
+                .arg(&file)
+                .status()
+                .map_err(|e| format!("Could not run pageant: {}", e))?;
+            if !status.success() {
+                return Err(format!("Pageant could not load {}", file));
+            }
+            let mut notes = vec![format!("Added {} to Pageant", file)];
+            if !others.is_empty() {
+                notes.push(
+                    "Pageant can't unload keys from the command line; remove other accounts' keys in its window"
+                        .to_string(),
+                );
+            }
+            Ok(notes)
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::models::Account;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// What an audit entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Push,
+    /// Identity written to git config from the account list.
+    Switch,
+    /// A guard (e.g. the push check) caught commits under the wrong identity.
+    Guard,
+    /// An account's token was stored, removed or revoked.
+    Token,
+}
+
+/// One line of the append-only history log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unix seconds.
+    pub time: u64,
+    pub event: Event,
+    /// `username` or `username:alias`.
+    pub account: String,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refspecs: Vec<String>,
+    /// `global` or `local` for switches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// What a guard caught, or what happened to a token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Entry {
+    /// An entry stamped now, for the repository in the current directory.
+    pub fn new(event: Event, account: &Account) -> Self {
+        Self {
+            time: crate::cache::now_secs(),
+            event,
+            account: account_id(account),
+            email: account.email.clone(),
+            repo: crate::utils::git_toplevel(),
+            remote: None,
+            refspecs: Vec::new(),
+            scope: None,
+            detail: None,
+        }
+    }
+}
+
+/// Record a token change. Tokens belong to the account, not to the
+/// repository the command ran in.
+pub fn record_token(account: &Account, detail: &str) {
+    record(&Entry {
+        repo: None,
+        detail: Some(detail.to_string()),
+        ..Entry::new(Event::Token, account)
+    });
+}
+
+/// How entries name an account: `username` or `username:alias`.
+pub fn account_id(account: &Account) -> String {
+    match &account.alias {
+        Some(alias) => format!("{}:{}", account.username, alias),
+        None => account.username.clone(),
+    }
+}
+
+/// Size at which `rotate` moves the log aside.
+const ROTATE_BYTES: u64 = 1024 * 1024;
+
+fn history_dir() -> Option<PathBuf> {
+    let dir = dirs::data_local_dir()?.join("gitas");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+fn history_path() -> Option<PathBuf> {
+    Some(history_dir()?.join("history.jsonl"))
+}
+
+fn rotated_path() -> Option<PathBuf> {
+    Some(history_dir()?.join("history.1.jsonl"))
+}
+
+/// Append an entry, and announce it on the desktop if its event is turned on.
+/// Failures are ignored: auditing never blocks the command.
+pub fn record(entry: &Entry) {
+    crate::notify::entry(entry);
+    if crate::models::is_ephemeral() {
+        return;
+    }
+    let Some(path) = history_path() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(entry) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// All readable entries, including the rotated log, oldest first. Corrupt
+/// lines are skipped.
+pub fn entries() -> Vec<Entry> {
+    [rotated_path(), history_path()]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .flat_map(|data| {
+            data.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<Entry>>()
+        })
+        .collect()
+}
+
+/// Move the log aside once it grows past [`ROTATE_BYTES`], keeping one old
+/// generation. Returns whether a rotation happened.
+pub fn rotate() -> bool {
+    let (Some(current), Some(rotated)) = (history_path(), rotated_path()) else {
+        return false;
+    };
+    let too_big = fs::metadata(&current).is_ok_and(|meta| meta.len() > ROTATE_BYTES);
+    too_big && fs::rename(current, rotated).is_ok()
+}
+
+/// (year, month, day) of a day count since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // Howard Hinnant's algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Inverse of [`civil_from_days`].
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_time(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60
+    )
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`, for exports other tools read.
+pub fn format_rfc3339(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60,
+        rem % 60
+    )
+}
+
+/// Start of a `YYYY-MM-DD` day (UTC) in Unix seconds.
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Reject dates like 02-31 that would roll into the next month.
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    u64::try_from(days).ok().map(|days| days * 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_time(0), "1970-01-01 00:00");
+        assert_eq!(format_time(1_709_210_096), "2024-02-29 12:34");
+        assert_eq!(format_rfc3339(1_709_210_096), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn parses_calendar_dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
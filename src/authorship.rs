@@ -0,0 +1,140 @@
+use crate::models::{Account, Config};
+use crate::push_check::account_owns_email;
+use crate::utils::{format_account_label, git_subcommand_index};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::process::Command;
+
+/// What `gitas git` does when a commit it is about to rewrite was authored
+/// by someone other than the selected account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PreserveAuthor {
+    /// Ask each time (keeps the author when there is no terminal)
+    #[default]
+    Ask,
+    /// Keep the original author without asking
+    Always,
+    /// Stamp the selected account as before
+    Never,
+}
+
+/// A git invocation that recreates an existing commit.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rewrite {
+    /// `commit --amend`: the author can be reset with `--reset-author`.
+    Amend,
+    /// `cherry-pick --continue` or `rebase --continue`: git always reuses the
+    /// author recorded for `rev`, only the committer follows the account.
+    Continue { rev: &'static str },
+}
+
+pub fn rewrite(args: &[String]) -> Option<Rewrite> {
+    let index = git_subcommand_index(args)?;
+    let rest = &args[index + 1..];
+    let has = |flag: &str| rest.iter().any(|a| a == flag);
+    match args[index].as_str() {
+        "commit" if has("--amend") => {
+            let explicit = has("--reset-author")
+                || rest
+                    .iter()
+                    .any(|a| a == "--author" || a.starts_with("--author="));
+            (!explicit).then_some(Rewrite::Amend)
+        }
+        "cherry-pick" if has("--continue") => Some(Rewrite::Continue {
+            rev: "CHERRY_PICK_HEAD",
+        }),
+        "rebase" if has("--continue") => Some(Rewrite::Continue { rev: "REBASE_HEAD" }),
+        _ => None,
+    }
+}
+
+/// `(name, email)` of the author of `rev`, in the repository the git
+/// options before the subcommand (`-C`, `--git-dir`, ...) point at.
+fn author_of(global: &[String], rev: &str) -> Option<(String, String)> {
+    let output = Command::new("git")
+        .args(global)
+        .args(["log", "-1", "--format=%an%x00%ae", rev, "--"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (name, email) = text.trim_end().split_once('\0')?;
+    Some((name.to_string(), email.to_string()))
+}
+
+/// Whether a rewrite should append `--reset-author`. When the commit belongs
+/// to someone else, keep its author or stamp the selected account, following
+/// `config.preserve_author`. Either way the rewrite runs as the selected
+/// account, so only the author is preserved.
+pub fn reset_author(config: &Config, account: &Account, args: &[String]) -> bool {
+    let Some(rewrite) = rewrite(args) else {
+        return false;
+    };
+    let rev = match rewrite {
+        Rewrite::Amend => "HEAD",
+        // git reuses the recorded author of a continuation either way.
+        Rewrite::Continue { .. } => return false,
+    };
+    let global = &args[..git_subcommand_index(args).unwrap_or(0)];
+    let Some((name, email)) = author_of(global, rev) else {
+        return false;
+    };
+    if account_owns_email(account, &email) {
+        return false;
+    }
+
+    let original = format!("{} <{}>", name, email);
+    let interactive = std::io::stdin().is_terminal() && !crate::models::is_strict();
+    match config.preserve_author.unwrap_or_default() {
+        PreserveAuthor::Never => true,
+        PreserveAuthor::Ask if interactive => {
+            let items = [
+                format!("Keep {} as author", original),
+                format!("Re-author as {}", format_account_label(account)),
+            ];
+            let prompt = format!("{} was authored by {}", rev, original.cyan());
+            crate::tui::enter_raw_mode();
+            let selection = crate::tui::raw_select(&prompt, &items, 0);
+            crate::tui::exit_raw_mode();
+            match selection {
+                Some(0) => false,
+                Some(_) => true,
+                None => crate::tui::exit_cancelled(),
+            }
+        }
+        _ => {
+            println!(
+                "  {}",
+                format!("Keeping {} as author of {}.", original, rev).dimmed()
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn detects_commands_that_reuse_an_author() {
+        assert_eq!(
+            rewrite(&args("-C repo commit --amend --no-edit")),
+            Some(Rewrite::Amend)
+        );
+        assert_eq!(rewrite(&args("commit --amend --reset-author")), None);
+        assert_eq!(rewrite(&args("commit --amend --author=x")), None);
+        assert_eq!(
+            rewrite(&args("rebase --continue")),
+            Some(Rewrite::Continue { rev: "REBASE_HEAD" })
+        );
+        assert_eq!(rewrite(&args("cherry-pick abc123")), None);
+        assert_eq!(rewrite(&args("commit -m amend")), None);
+    }
+}
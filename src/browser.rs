@@ -0,0 +1,93 @@
+use colored::Colorize;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Environment variable holding a browser command, overriding the config.
+pub const BROWSER_ENV: &str = "GITAS_BROWSER";
+
+struct BrowserPreference {
+    disabled: bool,
+    command: Option<String>,
+}
+
+static PREFERENCE: OnceLock<BrowserPreference> = OnceLock::new();
+
+/// Record how URLs should be opened for the rest of this process.
+pub fn configure(no_browser: bool, configured_command: Option<String>) {
+    let command = std::env::var(BROWSER_ENV)
+        .ok()
+        .filter(|c| !c.trim().is_empty())
+        .or(configured_command);
+    let _ = PREFERENCE.set(BrowserPreference {
+        disabled: no_browser,
+        command,
+    });
+}
+
+/// Open a URL with the configured browser command or the platform default.
+/// Whenever that isn't possible, print the URL prominently instead.
+pub fn open_url(url: &str) {
+    let preference = PREFERENCE.get();
+    if preference.is_some_and(|p| p.disabled) {
+        print_fallback(url);
+        return;
+    }
+
+    let opened = match preference.and_then(|p| p.command.as_deref()) {
+        Some(command) => run_browser_command(command, url),
+        None => open::that(url).is_ok(),
+    };
+
+    if !opened {
+        println!("  (Failed to open browser automatically)");
+        print_fallback(url);
+    }
+}
+
+fn print_fallback(url: &str) {
+    println!();
+    println!(
+        "  {} {}",
+        "Open this URL in your browser:".bold(),
+        url.cyan().bold().underline()
+    );
+    println!();
+}
+
+/// Run `command` with the URL substituted for `%s`, or appended when absent.
+fn run_browser_command(command: &str, url: &str) -> bool {
+    let args = browser_args(command, url);
+    let Some((program, rest)) = args.split_first() else {
+        return false;
+    };
+    Command::new(program).args(rest).spawn().is_ok()
+}
+
+fn browser_args(command: &str, url: &str) -> Vec<String> {
+    let mut args: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+    if args.iter().any(|a| a.contains("%s")) {
+        for arg in &mut args {
+            *arg = arg.replace("%s", url);
+        }
+    } else {
+        args.push(url.to_string());
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_or_appends_url() {
+        assert_eq!(
+            browser_args("flatpak-spawn --host xdg-open", "https://x"),
+            vec!["flatpak-spawn", "--host", "xdg-open", "https://x"]
+        );
+        assert_eq!(
+            browser_args("firefox --new-tab=%s", "https://x"),
+            vec!["firefox", "--new-tab=https://x"]
+        );
+    }
+}
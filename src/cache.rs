@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How long a token → login lookup stays trusted before hitting the API again.
+const TOKEN_LOGIN_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Cache {
+    #[serde(default)]
+    token_logins: HashMap<String, TokenLogin>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pending_login: Option<PendingLogin>,
+}
+
+/// A device-flow login that was started but hasn't produced a token yet.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub interval: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TokenLogin {
+    /// Fingerprint of the token the login was fetched with, so a rotated token
+    /// invalidates the entry without storing the token itself.
+    fingerprint: String,
+    login: String,
+    checked_at: u64,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let dir = dirs::cache_dir()?.join("gitas");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("cache.json"))
+}
+
+fn load() -> Cache {
+    cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save(cache: &Cache) {
+    if crate::models::is_ephemeral() {
+        return;
+    }
+    if let Some(path) = cache_path()
+        && let Ok(data) = serde_json::to_string_pretty(cache)
+    {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // New files are private from the start; existing ones are narrowed
+        // before the device code goes in.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        if let Ok(mut file) = options.open(&path) {
+            restrict_permissions(&file);
+            let _ = file.write_all(data.as_bytes());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn restrict_permissions(file: &fs::File) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = file.set_permissions(fs::Permissions::from_mode(0o600));
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_file: &fs::File) {}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// First 8 bytes of the token's SHA-256, hex: stable across Rust versions,
+/// unlike `DefaultHasher`, so saved entries keep matching.
+fn fingerprint(token: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, token.as_bytes()).as_ref()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Login a token was last seen to belong to, if checked recently.
+pub fn token_login(account_key: &str, token: &str) -> Option<String> {
+    let cache = load();
+    let entry = cache.token_logins.get(account_key)?;
+    let fresh = now_secs().saturating_sub(entry.checked_at) < TOKEN_LOGIN_TTL_SECS;
+    (fresh && entry.fingerprint == fingerprint(token)).then(|| entry.login.clone())
+}
+
+pub fn store_token_login(account_key: &str, token: &str, login: &str) {
+    let mut cache = load();
+    cache.token_logins.insert(
+        account_key.to_string(),
+        TokenLogin {
+            fingerprint: fingerprint(token),
+            login: login.to_string(),
+            checked_at: now_secs(),
+        },
+    );
+    save(&cache);
+}
+
+pub fn pending_login() -> Option<PendingLogin> {
+    load().pending_login
+}
+
+pub fn store_pending_login(pending: Option<&PendingLogin>) {
+    let mut cache = load();
+    cache.pending_login = pending.cloned();
+    save(&cache);
+}
@@ -1,11 +1,67 @@
-use crate::github;
+use crate::confirm::{self, Action, Decision, Policy};
 use crate::models::{Account, Config, save_config, set_token};
+use crate::plugins::{self, HookPoint};
+use crate::policy;
+use crate::provider::{self, LoginResult, Provider, ProviderKind};
 use crate::tui::{
     enter_raw_mode, exit_raw_mode, raw_confirm, raw_input, raw_password, raw_println, raw_select,
+    raw_with_loader,
 };
 use colored::Colorize;
+use zeroize::Zeroizing;
+
+/// Account fields for adding without prompts; giving `--username` enables it.
+#[derive(clap::Args)]
+pub struct AddArgs {
+    /// Username; with --email, adds the account without any prompts
+    #[arg(long, requires = "email", conflicts_with = "resume")]
+    username: Option<String>,
+    /// Commit email
+    #[arg(long, requires = "username")]
+    email: Option<String>,
+    /// Alias to tell apart accounts with the same username
+    #[arg(long, requires = "username")]
+    alias: Option<String>,
+    /// Host (defaults to github.com)
+    #[arg(long, requires = "username")]
+    host: Option<String>,
+    /// Forge type of --host, when it can't be detected
+    #[arg(long, requires = "host")]
+    provider: Option<ProviderKind>,
+    /// Path to the SSH private key
+    #[arg(long, requires = "username")]
+    ssh_key: Option<String>,
+    /// Authenticate over SSH only
+    #[arg(long, requires = "ssh_key", conflicts_with_all = ["token_stdin", "token_env"])]
+    ssh_only: bool,
+    /// Group label
+    #[arg(long, requires = "username")]
+    group: Option<String>,
+    /// Read the token from the first line of stdin
+    #[arg(long, requires = "username", conflicts_with = "token_env")]
+    token_stdin: bool,
+    /// Read the token from this environment variable
+    #[arg(long, value_name = "VAR", requires = "username")]
+    token_env: Option<String>,
+    /// Replace an existing account with the same username and alias
+    #[arg(long, requires = "username")]
+    force: bool,
+}
+
+pub fn run(config: &mut Config, resume: bool, batch: bool, args: AddArgs) {
+    if args.username.is_some() {
+        add_from_args(config, args);
+        return;
+    }
+    if resume {
+        add_github(config, true, &[]);
+        return;
+    }
+    if batch {
+        add_batch(config);
+        return;
+    }
 
-pub fn run(config: &mut Config) {
     enter_raw_mode(); // Start raw mode immediately
 
     raw_println("");
@@ -18,174 +74,745 @@ pub fn run(config: &mut Config) {
         "GitHub Browser Login".to_string(),
     ];
 
-    let selection = raw_select("Authentication Method", &methods, 0);
+    loop {
+        match raw_select("Authentication Method", &methods, 0) {
+            Some(0) => {
+                // Manual - stay in raw mode; Esc on its first question comes back here.
+                if !add_manual(config) {
+                    continue;
+                }
+                exit_raw_mode();
+            }
+            Some(1) => {
+                // GitHub - exit raw mode because github::login prints standard output and opens browser
+                exit_raw_mode();
+                add_github(config, false, &[]);
+            }
+            _ => crate::tui::exit_cancelled(),
+        }
+        return;
+    }
+}
 
-    match selection {
-        Some(0) => {
-            // Manual - stay in raw mode
-            add_manual(config);
-            exit_raw_mode();
+/// `gitas add --batch`: browser logins back to back, one account each,
+/// with a pause between rounds to switch the browser to the next account.
+fn add_batch(config: &mut Config) {
+    let mut added: Vec<usize> = Vec::new();
+    let mut updated: Vec<usize> = Vec::new();
+    let mut logins: Vec<String> = Vec::new();
+
+    for round in 1.. {
+        println!(
+            "\n  {} {}",
+            "Add Git Account".bold(),
+            format!("#{}", round).dimmed()
+        );
+        println!("  {}", "─".repeat(48).dimmed());
+
+        let before = config.accounts.len();
+        if let Some(index) = add_github(config, false, &logins) {
+            let account = &config.accounts[index];
+            logins.push(account.username.clone());
+            // The same login twice is listed once.
+            if !added.contains(&index) && !updated.contains(&index) {
+                if index >= before {
+                    added.push(index);
+                } else {
+                    updated.push(index);
+                }
+            }
         }
-        Some(1) => {
-            // GitHub - exit raw mode because github::login prints standard output and opens browser
-            exit_raw_mode();
-            add_github(config);
+
+        // Between rounds the browser is still signed in to the last account.
+        enter_raw_mode();
+        raw_println(&format!(
+            "  {}",
+            "Sign out of GitHub in the browser, or switch to another profile or container, before the next login."
+                .dimmed()
+        ));
+        let another = raw_confirm("Add another account?", true);
+        exit_raw_mode();
+        if another != Some(true) {
+            break;
         }
-        _ => {
-            exit_raw_mode();
+    }
+
+    println!();
+    if added.is_empty() && updated.is_empty() {
+        println!("  {}\n", "No accounts added.".dimmed());
+        return;
+    }
+    for (label, indexes) in [("Added", &added), ("Updated", &updated)] {
+        for index in indexes {
+            println!(
+                "  {} {} {}",
+                "✓".green().bold(),
+                label,
+                crate::utils::format_account_label(&config.accounts[*index]).cyan()
+            );
         }
     }
+    println!();
 }
 
-fn add_github(config: &mut Config) {
+/// Browser login and review. Returns the saved account's index; `batch`
+/// holds the logins earlier rounds of `--batch` already added.
+fn add_github(config: &mut Config, resume: bool, batch: &[String]) -> Option<usize> {
     // Normal terminal mode
-    let Some((username, email, _name, token)) = github::login() else {
-        return;
+    let github = provider::for_host("github.com", ProviderKind::Github);
+    let login = if resume {
+        crate::github::resume_login()?
+    } else {
+        match github.device_login() {
+            Ok(login) => login,
+            Err(e) => {
+                println!("  {}", e.red());
+                return None;
+            }
+        }
     };
 
-    println!(
-        "  Authenticated as: {} <{}>",
-        username.cyan(),
-        email.dimmed()
-    );
-
-    // We could re-enter raw mode here for the alias input, but mixing modes is complex.
-    // Let's stick to standard input for consistency within this flow since we already left raw mode.
-
+    // Back to raw mode: review what GitHub returned before anything is saved.
     enter_raw_mode();
+    raw_println("");
+    for line in login_summary(github.as_ref(), &login) {
+        raw_println(&line);
+    }
+    raw_println("");
+    if batch.contains(&login.login) {
+        raw_println(&format!(
+            "  {} {} was already added in this batch; the browser is still signed in to it.\n",
+            "⚠".yellow(),
+            login.login.cyan()
+        ));
+    }
 
-    let alias = raw_input("Alias (optional)", "").unwrap_or_default();
-    let alias = if alias.is_empty() { None } else { Some(alias) };
-
-    // Check for duplicate
-    let existing_idx = config
-        .accounts
-        .iter()
-        .position(|a| a.username == username && a.alias == alias);
+    let mut account = Account {
+        username: login.login.clone(),
+        email: login.email.clone(),
+        ..Default::default()
+    };
 
-    if existing_idx.is_some() {
-        let prompt = format!(
-            "Account '{}' (alias: {}) already exists. Overwrite?",
-            username.yellow(),
-            alias.as_deref().unwrap_or("none").yellow()
-        );
+    loop {
+        let mut items = vec![
+            format!(
+                "{:<10} {}",
+                "Email:".dimmed(),
+                crate::utils::redact_email(&account.email)
+            ),
+            format!(
+                "{:<10} {}",
+                "Alias:".dimmed(),
+                account.alias.as_deref().unwrap_or("none")
+            ),
+        ];
+        if !config.groups.is_empty() {
+            items.push(format!(
+                "{:<10} {}",
+                "Group:".dimmed(),
+                account.group.as_deref().unwrap_or("none")
+            ));
+        }
+        let save_index = items.len();
+        items.push("Save Account".green().to_string());
+        items.push("Cancel".dimmed().to_string());
 
-        if raw_confirm(&prompt, false) != Some(true) {
-            raw_println(&format!("\n  {}\n", "Cancelled.".dimmed()));
+        let Some(selection) = raw_select("Review Account", &items, save_index) else {
+            raw_println(&format!("  {}\n", "Cancelled.".dimmed()));
             exit_raw_mode();
-            return;
+            return None;
+        };
+
+        match selection {
+            0 => {
+                if let Some(val) = choose_email(&login.emails, &account.email) {
+                    account.email = val;
+                }
+            }
+            1 => {
+                if let Some(val) = raw_input("Alias", account.alias.as_deref().unwrap_or("")) {
+                    account.alias = if val.is_empty() { None } else { Some(val) };
+                }
+            }
+            // Esc on the overwrite question comes back to the review.
+            i if i == save_index => match confirm_overwrite(config, &account) {
+                Some(true) => break,
+                Some(false) => {
+                    raw_println(&format!("\n  {}\n", "Cancelled.".dimmed()));
+                    exit_raw_mode();
+                    return None;
+                }
+                None => {}
+            },
+            i if i == save_index + 1 => {
+                raw_println(&format!("  {}\n", "Cancelled.".dimmed()));
+                exit_raw_mode();
+                return None;
+            }
+            _ => {
+                if let Some(group) = select_group(config) {
+                    account.group = group;
+                }
+            }
         }
     }
 
-    let account = Account {
-        username: username.clone(),
-        email,
-        alias: alias.clone(),
-        host: None,
-        ssh_key: None,
-    };
+    if github.noreply_login(&account.email).is_none()
+        && crate::warnings::enabled(config, None, crate::warnings::Warning::Noreply)
+        && let Some(noreply) = login
+            .emails
+            .iter()
+            .find(|e| github.noreply_login(&e.email).is_some())
+    {
+        raw_println(&format!(
+            "  {} Commits will publish {}. Use {} to keep it private.",
+            "⚠".yellow(),
+            account.email.yellow(),
+            noreply.email.cyan()
+        ));
+    }
 
-    set_token(&username, alias.as_deref(), &token);
+    let existing_idx = find_existing(config, &account);
 
-    if let Some(idx) = existing_idx {
-        upsert_account_raw(config, account, Some(idx));
-    } else {
-        upsert_account_raw(config, account, None);
+    if !check_policy(config, &account) {
+        exit_raw_mode();
+        return None;
     }
+
+    set_token(&account.username, account.alias.as_deref(), &login.token);
+    crate::audit::record_token(&account, "stored");
+
+    let index = upsert_account_raw(config, account, existing_idx);
     exit_raw_mode();
+    Some(index)
 }
 
-use crate::utils::scan_ssh_keys;
+/// Compact card describing what the browser login returned.
+fn login_summary(forge: &dyn Provider, login: &LoginResult) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "  {} Authenticated as {}",
+            "✓".green().bold(),
+            crate::utils::redact_name(&login.login).cyan().bold()
+        ),
+        format!("  {}", "─".repeat(48).dimmed()),
+        format!(
+            "  {:<10} {}",
+            "Name:".dimmed(),
+            login
+                .name
+                .as_deref()
+                .map_or("-".to_string(), crate::utils::redact_name)
+        ),
+    ];
 
-fn add_manual(config: &mut Config) {
-    let Some(username) = raw_input("Username", "").filter(|u| !u.is_empty()) else {
-        return;
-    };
-    let Some(email) = raw_input("Email", "").filter(|e| !e.is_empty()) else {
-        return;
+    let primary = login.emails.iter().find(|e| e.primary);
+    let noreply = login
+        .emails
+        .iter()
+        .find(|e| forge.noreply_login(&e.email).is_some());
+    if let Some(primary) = primary {
+        lines.push(format!(
+            "  {:<10} {}",
+            "Primary:".dimmed(),
+            crate::utils::redact_email(&primary.email)
+        ));
+    }
+    if let Some(noreply) = noreply {
+        lines.push(format!(
+            "  {:<10} {}",
+            "Noreply:".dimmed(),
+            crate::utils::redact_email(&noreply.email)
+        ));
+    }
+    let others: Vec<_> = login
+        .emails
+        .iter()
+        .filter(|e| !e.primary && forge.noreply_login(&e.email).is_none())
+        .collect();
+    if !others.is_empty() {
+        let verified = others.iter().filter(|e| e.verified).count();
+        lines.push(format!(
+            "  {:<10} {}",
+            "",
+            format!("+{} more ({} verified)", others.len(), verified).dimmed()
+        ));
+    }
+    lines.push(format!(
+        "  {:<10} {}",
+        "Scopes:".dimmed(),
+        login.scopes.as_deref().unwrap_or("-")
+    ));
+    lines
+}
+
+use crate::utils::choose_email;
+
+/// Steps of the manual add wizard.
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Username,
+    Email,
+    Alias,
+    Overwrite,
+    Auth,
+    Token,
+    Host,
+    SshKey,
+    SshKeyPath,
+    Group,
+    Save,
+}
+
+/// Ask for each field in turn; Esc goes back to the previous question.
+/// Returns false when the user backed out of the first one.
+fn add_manual(config: &mut Config) -> bool {
+    let mut username = String::new();
+    let mut email = String::new();
+    let mut alias: Option<String> = None;
+    let mut ssh_only = false;
+    let mut token = Zeroizing::new(String::new());
+    let mut host = None;
+    let mut provider = None;
+    let mut ssh_key = None;
+    let mut group = None;
+
+    let mut step = Step::Username;
+    // Steps that showed a prompt, for Esc to return to.
+    let mut history = Vec::new();
+    let account = loop {
+        let mut prompted = true;
+        let next = match step {
+            Step::Username => raw_input("Username", &username).map(|value| {
+                if value.is_empty() {
+                    return Step::Username;
+                }
+                username = value;
+                Step::Email
+            }),
+            Step::Email => raw_input("Email", &email).map(|value| {
+                if value.is_empty() {
+                    return Step::Email;
+                }
+                email = value;
+                Step::Alias
+            }),
+            Step::Alias => {
+                raw_input("Alias (optional)", alias.as_deref().unwrap_or("")).map(|value| {
+                    alias = (!value.is_empty()).then_some(value);
+                    Step::Overwrite
+                })
+            }
+            Step::Overwrite => {
+                let candidate = Account {
+                    username: username.clone(),
+                    alias: alias.clone(),
+                    ..Default::default()
+                };
+                prompted = asks_overwrite(config, &candidate);
+                match confirm_overwrite(config, &candidate) {
+                    Some(true) => Some(Step::Auth),
+                    Some(false) => {
+                        raw_println(&format!("\n  {}\n", "Cancelled.".dimmed()));
+                        return true;
+                    }
+                    None => None,
+                }
+            }
+            Step::Auth => {
+                let auth_types = vec!["Token/PAT".to_string(), "SSH Key".to_string()];
+                raw_select("Authentication Type", &auth_types, usize::from(ssh_only)).map(
+                    |choice| {
+                        ssh_only = choice == 1;
+                        if ssh_only { Step::SshKey } else { Step::Token }
+                    },
+                )
+            }
+            Step::Token => raw_password("Token/PAT (optional)").map(|value| {
+                token = value;
+                host = None;
+                provider = None;
+                ssh_key = None;
+                Step::Host
+            }),
+            Step::Host => {
+                crate::utils::choose_host(config, "Host", host.as_deref()).map(|picked| {
+                    match &picked {
+                        Some(picked) => match detect_provider(picked) {
+                            Some(kind) => {
+                                provider = Some(kind).filter(|k| *k != ProviderKind::Github)
+                            }
+                            // Esc on the forge type: pick the host again.
+                            None => return Step::Host,
+                        },
+                        None => provider = None,
+                    }
+                    host = picked;
+                    Step::Group
+                })
+            }
+            Step::SshKey => {
+                let (display_items, paths, default_idx) = crate::ssh::scan_keys(&username, &email);
+                raw_select("Keys in ~/.ssh", &display_items, default_idx).map(|selection| {
+                    token = Zeroizing::new(String::new());
+                    host = None;
+                    provider = None;
+                    if selection < paths.len() {
+                        ssh_key = Some(paths[selection].to_string_lossy().to_string());
+                        Step::Group
+                    } else if selection == paths.len() {
+                        Step::SshKeyPath
+                    } else {
+                        ssh_key = None;
+                        Step::Group
+                    }
+                })
+            }
+            Step::SshKeyPath => {
+                raw_input("SSH Key Path", ssh_key.as_deref().unwrap_or("")).map(|value| {
+                    ssh_key = (!value.is_empty()).then_some(value);
+                    Step::Group
+                })
+            }
+            Step::Group => {
+                prompted = !config.groups.is_empty();
+                select_group(config).map(|picked| {
+                    group = picked;
+                    Step::Save
+                })
+            }
+            Step::Save => {
+                let account = Account {
+                    username: username.clone(),
+                    email: email.clone(),
+                    alias: alias.clone(),
+                    host: host.clone(),
+                    ssh_key: ssh_key.clone(),
+                    ssh_only,
+                    gpg_key: None,
+                    provider,
+                    group: group.clone(),
+                    ..Default::default()
+                };
+                if !check_policy(config, &account) {
+                    return true;
+                }
+                if token.is_empty() {
+                    break account;
+                }
+                match raw_confirm("Verify before saving?", true) {
+                    Some(false) => break account,
+                    Some(true) => match verify_token(&account, &token) {
+                        Some(true) => break account,
+                        Some(false) => return true,
+                        // Esc on the probe URL: ask again.
+                        None => Some(Step::Save),
+                    },
+                    None => None,
+                }
+            }
+        };
+        match next {
+            Some(next) => {
+                if prompted && next != step {
+                    history.push(step);
+                }
+                step = next;
+            }
+            None => match history.pop() {
+                Some(previous) => step = previous,
+                None => return false,
+            },
+        }
     };
 
-    let alias = raw_input("Alias (optional)", "").unwrap_or_default();
-    let alias = if alias.is_empty() { None } else { Some(alias) };
+    if !token.is_empty() {
+        set_token(&username, alias.as_deref(), &token);
+        crate::audit::record_token(&account, "stored");
+    } else {
+        crate::models::delete_token(&username, alias.as_deref());
+    }
 
-    // Check duplicate
-    let existing_idx = config
+    let existing_idx = find_existing(config, &account);
+    upsert_account_raw(config, account, existing_idx);
+    true
+}
+
+fn find_existing(config: &Config, account: &Account) -> Option<usize> {
+    config
         .accounts
         .iter()
-        .position(|a| a.username == username && a.alias == alias);
+        .position(|a| a.username == account.username && a.alias == account.alias)
+}
 
-    if existing_idx.is_some() {
-        let prompt = format!(
-            "Account '{}' (alias: {}) already exists. Overwrite?",
-            username.yellow(),
-            alias.as_deref().unwrap_or("none").yellow()
-        );
+/// Whether saving `account` would replace one and the `credential-overwrite`
+/// policy wants that confirmed.
+fn asks_overwrite(config: &Config, account: &Account) -> bool {
+    find_existing(config, account).is_some()
+        && confirm::policy(config, Action::CredentialOverwrite) != Policy::Never
+}
 
-        if raw_confirm(&prompt, false) != Some(true) {
-            raw_println(&format!("\n  {}\n", "Cancelled.".dimmed()));
-            return;
-        }
+/// Ask before replacing an account with the same username and alias.
+/// `Some(true)` when there is none or nothing needs asking.
+fn confirm_overwrite(config: &Config, account: &Account) -> Option<bool> {
+    if !asks_overwrite(config, account) {
+        return Some(true);
     }
+    let prompt = overwrite_prompt(account);
+    raw_confirm(&prompt, false)
+}
 
-    let auth_types = vec!["Token/PAT".to_string(), "SSH Key".to_string()];
-    let Some(auth_choice) = raw_select("Authentication Type", &auth_types, 0) else {
-        return;
-    };
+fn overwrite_prompt(account: &Account) -> String {
+    format!(
+        "Account '{}' (alias: {}) already exists. Overwrite?",
+        account.username.yellow(),
+        account.alias.as_deref().unwrap_or("none").yellow()
+    )
+}
 
-    let mut token = String::new();
-    let mut host = None;
-    let mut ssh_key = None;
+fn add_from_args(config: &mut Config, args: AddArgs) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
 
-    if auth_choice == 0 {
-        // Token/PAT
-        token = raw_password("Token/PAT (optional)").unwrap_or_default();
-        let host_in = raw_input("Host", "github.com").unwrap_or_else(|| "github.com".to_string());
-        host = if host_in == "github.com" || host_in.is_empty() {
-            None
-        } else {
-            Some(host_in)
-        };
+    let token = if args.token_stdin {
+        super::edit::read_token_stdin()
+    } else if let Some(var) = &args.token_env {
+        match std::env::var(var) {
+            Ok(token) if !token.trim().is_empty() => {
+                Some(Zeroizing::new(Zeroizing::new(token).trim().to_string()))
+            }
+            _ => fail(format!("Environment variable {} is not set.", var)),
+        }
     } else {
-        // SSH Key
-        let (display_items, paths, default_idx) = scan_ssh_keys(&username, &email);
-        let Some(selection) = raw_select("Keys in ~/.ssh", &display_items, default_idx) else {
-            return;
-        };
+        None
+    };
 
-        if selection < paths.len() {
-            ssh_key = Some(paths[selection].to_string_lossy().to_string());
-        } else if selection == paths.len() {
-            let manual = raw_input("SSH Key Path", "").unwrap_or_default();
-            ssh_key = if manual.is_empty() {
-                None
-            } else {
-                Some(manual)
-            };
-        }
+    let host = args.host.as_deref().and_then(crate::host::for_account);
+    let provider = match (&host, args.provider) {
+        (None, _) => None,
+        (Some(_), Some(kind)) => Some(kind),
+        (Some(host), None) => match provider::detect(host) {
+            Some(kind) => Some(kind),
+            None => fail(format!(
+                "Could not detect the forge at {}. Pass --provider.",
+                host
+            )),
+        },
     }
+    .filter(|kind| *kind != ProviderKind::Github);
 
     let account = Account {
-        username: username.clone(),
-        email,
-        alias: alias.clone(),
+        username: args.username.unwrap_or_default(),
+        email: args.email.unwrap_or_default(),
+        alias: args.alias.filter(|a| !a.is_empty()),
         host,
-        ssh_key,
+        ssh_key: args.ssh_key.filter(|k| !k.is_empty()),
+        ssh_only: args.ssh_only,
+        provider,
+        group: args.group.filter(|g| !g.is_empty()),
+        ..Default::default()
     };
 
-    if !token.is_empty() {
-        set_token(&username, alias.as_deref(), &token);
-    } else {
-        crate::models::delete_token(&username, alias.as_deref());
+    let existing = config
+        .accounts
+        .iter()
+        .position(|a| a.username == account.username && a.alias == account.alias);
+    if existing.is_some() && !args.force {
+        match confirm::decide(config, Action::CredentialOverwrite) {
+            Decision::Proceed => {}
+            Decision::Ask => {
+                enter_raw_mode();
+                let confirmed = raw_confirm(&overwrite_prompt(&account), false);
+                exit_raw_mode();
+                if confirmed != Some(true) {
+                    println!("\n  {}\n", "Cancelled.".dimmed());
+                    return;
+                }
+            }
+            Decision::Refuse => fail(format!(
+                "{} already exists. Pass --force to replace it.",
+                crate::utils::format_account_label(&account)
+            )),
+        }
     }
 
-    if let Some(idx) = existing_idx {
-        upsert_account_raw(config, account, Some(idx));
-    } else {
-        upsert_account_raw(config, account, None);
+    let index = match super::edit::commit(
+        config,
+        existing,
+        account,
+        token.as_deref().map(String::as_str).into(),
+    ) {
+        Ok(index) => index,
+        Err(problems) => {
+            eprintln!();
+            for problem in problems {
+                eprintln!("  {} {}", "✗".red().bold(), problem);
+            }
+            eprintln!("\n  {}\n", "Account not saved.".dimmed());
+            std::process::exit(1);
+        }
+    };
+
+    let saved = &config.accounts[index];
+    println!(
+        "\n  {} {} {}",
+        "✓".green().bold(),
+        if existing.is_some() {
+            "Replaced"
+        } else {
+            "Added"
+        },
+        crate::utils::format_account_label(saved).cyan()
+    );
+    if let Err(e) = plugins::run(&config.hooks, HookPoint::PostAdd, saved, None) {
+        println!("  {} {}", "⚠".yellow(), e);
     }
+    println!();
 }
 
-fn upsert_account_raw(config: &mut Config, account: Account, index: Option<usize>) {
+/// Pick a group when any are defined. Returns None if the user cancelled.
+fn select_group(config: &Config) -> Option<Option<String>> {
+    if config.groups.is_empty() {
+        return Some(None);
+    }
+    let mut items = vec!["None".to_string()];
+    items.extend(config.groups.keys().cloned());
+    let selection = raw_select("Group", &items, 0)?;
+    Some((selection > 0).then(|| items[selection].clone()))
+}
+
+/// Refuse to save an account that breaks its group's policy.
+fn check_policy(config: &Config, account: &Account) -> bool {
+    let mut problems: Vec<String> =
+        crate::utils::validate_identity(&account.username, &account.email)
+            .err()
+            .into_iter()
+            .collect();
+    problems.extend(policy::violations(config, account));
+    if problems.is_empty() {
+        return true;
+    }
+    raw_println("");
+    for problem in problems {
+        raw_println(&format!("  {} {}", "✗".red().bold(), problem));
+    }
+    raw_println(&format!("\n  {}\n", "Account not saved.".dimmed()));
+    false
+}
+
+/// Check a manually entered token against the forge API and, optionally, a
+/// repository on the host. Returns false when the user chose not to save, and
+/// `None` when they backed out with Esc.
+fn verify_token(account: &Account, token: &str) -> Option<bool> {
+    let host = account.host.as_deref().unwrap_or("github.com");
+    let default_probe = crate::utils::get_http_remotes()
+        .into_iter()
+        .find(|remote| {
+            crate::utils::parse_remote_url(&remote.url)
+                .is_some_and(|(remote_host, _)| crate::host::matches(host, &remote_host))
+        })
+        .map(|remote| remote.url)
+        .unwrap_or_default();
+    let probe_url = raw_input("Repository URL to probe (optional)", &default_probe)?;
+
+    let check_account = account.clone();
+    let check_token = token.to_string();
+    let results = raw_with_loader("Verifying token…", move || {
+        let mut results = vec![
+            provider::for_account(&check_account)
+                .verify_token(&check_token, &check_account.username)
+                .map(|login| format!("API accepts the token as {}", login)),
+        ];
+        if !probe_url.is_empty() {
+            results.push(
+                ls_remote(&probe_url, &check_account.username, &check_token)
+                    .map(|()| format!("git can read {}", probe_url))
+                    .map_err(|e| format!("git ls-remote {} failed: {}", probe_url, e)),
+            );
+        }
+        results
+    })
+    .unwrap_or_else(|_| vec![Err("Verification panicked".to_string())]);
+
+    let mut ok = true;
+    for result in &results {
+        match result {
+            Ok(line) => raw_println(&format!("  {} {}", "✓".green().bold(), line)),
+            Err(line) => {
+                ok = false;
+                raw_println(&format!("  {} {}", "✗".red().bold(), line));
+            }
+        }
+    }
+    if ok {
+        return Some(true);
+    }
+    match raw_confirm("Save anyway?", false) {
+        Some(true) => Some(true),
+        Some(false) => {
+            raw_println(&format!("\n  {}\n", "Account not saved.".dimmed()));
+            Some(false)
+        }
+        None => None,
+    }
+}
+
+/// `git ls-remote` with only the given credentials and no prompting.
+fn ls_remote(url: &str, username: &str, token: &str) -> Result<(), String> {
+    let mut cmd = std::process::Command::new("git");
+    crate::utils::append_git_config_env(
+        &mut cmd,
+        &[
+            ("credential.helper", ""),
+            ("credential.helper", crate::utils::CREDENTIAL_HELPER),
+        ],
+    );
+    let output = cmd
+        .envs(crate::utils::credential_env(username, token))
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .args(["ls-remote", "--heads", url])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or("unknown error")
+        .trim()
+        .to_string())
+}
+
+/// Probe an unknown host for its forge type, falling back to asking the user.
+pub(crate) fn detect_provider(host: &str) -> Option<ProviderKind> {
+    let probe_host = host.to_string();
+    let detected = raw_with_loader(&format!("Detecting forge at {}…", host), move || {
+        provider::detect(&probe_host)
+    })
+    .ok()
+    .flatten();
+
+    if let Some(kind) = detected {
+        raw_println(&format!(
+            "  {} Detected {} at {}",
+            "✓".green().bold(),
+            kind.label().cyan(),
+            host
+        ));
+        return Some(kind);
+    }
+
+    let kinds = ProviderKind::ALL;
+    let items: Vec<String> = kinds.iter().map(|k| k.label().to_string()).collect();
+    let prompt = format!("Could not detect forge at {}. Select type", host);
+    raw_select(&prompt, &items, 0).map(|index| kinds[index])
+}
+
+fn upsert_account_raw(config: &mut Config, account: Account, index: Option<usize>) -> usize {
     let username = account.username.clone();
     if let Some(idx) = index {
         config.accounts[idx] = account;
@@ -203,4 +830,15 @@ fn upsert_account_raw(config: &mut Config, account: Account, index: Option<usize
         ));
     }
     save_config(config);
+
+    let index = index.unwrap_or(config.accounts.len() - 1);
+    if let Err(e) = plugins::run(
+        &config.hooks,
+        HookPoint::PostAdd,
+        &config.accounts[index],
+        None,
+    ) {
+        raw_println(&format!("  {} {}\n", "⚠".yellow(), e));
+    }
+    index
 }
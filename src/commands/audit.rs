@@ -0,0 +1,164 @@
+use crate::audit::{self, Entry};
+use crate::models::Config;
+use crate::utils::find_account;
+use colored::Colorize;
+
+/// Output formats for `gitas audit export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+const CSV_COLUMNS: [&str; 9] = [
+    "timestamp",
+    "event",
+    "account",
+    "email",
+    "repo",
+    "remote",
+    "refspecs",
+    "scope",
+    "detail",
+];
+
+/// `gitas audit export`: the history log, oldest first, limited to the
+/// `since..=until` days and optionally one account.
+pub fn export(
+    config: &Config,
+    account_id: Option<String>,
+    format: ExportFormat,
+    since: Option<String>,
+    until: Option<String>,
+    file: Option<String>,
+) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+    let day = |date: Option<String>| {
+        date.map(|date| {
+            audit::parse_date(&date)
+                .unwrap_or_else(|| fail(format!("'{}' is not a valid YYYY-MM-DD date", date)))
+        })
+    };
+    let since = day(since);
+    // --until names the last day included.
+    let until = day(until).map(|start| start + 86_400);
+
+    let account = account_id.map(|id| match find_account(config, &id) {
+        Some(account) => audit::account_id(account),
+        None => id,
+    });
+    let entries: Vec<Entry> = audit::entries()
+        .into_iter()
+        .filter(|e| since.is_none_or(|since| e.time >= since))
+        .filter(|e| until.is_none_or(|until| e.time < until))
+        .filter(|e| account.as_ref().is_none_or(|a| &e.account == a))
+        .collect();
+
+    let data = match format {
+        ExportFormat::Csv => to_csv(&entries),
+        ExportFormat::Json => to_json(&entries),
+    };
+    match file.as_deref() {
+        None | Some("-") => print!("{}", data),
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &data) {
+                fail(format!("Could not write {}: {}", path, e));
+            }
+            eprintln!(
+                "\n  {} Exported {} entries to {}\n",
+                "✓".green().bold(),
+                entries.len(),
+                path
+            );
+        }
+    }
+}
+
+fn event_name(entry: &Entry) -> String {
+    serde_json::to_value(entry.event)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn to_csv(entries: &[Entry]) -> String {
+    let mut out = CSV_COLUMNS.join(",");
+    out.push('\n');
+    for entry in entries {
+        let fields = [
+            audit::format_rfc3339(entry.time),
+            event_name(entry),
+            entry.account.clone(),
+            entry.email.clone(),
+            entry.repo.clone().unwrap_or_default(),
+            entry.remote.clone().unwrap_or_default(),
+            entry.refspecs.join(" "),
+            entry.scope.clone().unwrap_or_default(),
+            entry.detail.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Quote a field when it holds a separator, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The log entries as a JSON array, each with a readable `timestamp` next
+/// to the raw Unix `time`.
+fn to_json(entries: &[Entry]) -> String {
+    let values: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut value = serde_json::to_value(entry).expect("Could not serialize entry");
+            if let Some(object) = value.as_object_mut() {
+                object.insert(
+                    "timestamp".to_string(),
+                    audit::format_rfc3339(entry.time).into(),
+                );
+            }
+            value
+        })
+        .collect();
+    let mut out = serde_json::to_string_pretty(&values).expect("Could not serialize export");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::Event;
+
+    #[test]
+    fn csv_quotes_awkward_fields() {
+        let entry = Entry {
+            time: 0,
+            event: Event::Guard,
+            account: "octo".to_string(),
+            email: "o@w.com".to_string(),
+            repo: None,
+            remote: Some("origin".to_string()),
+            refspecs: vec!["main".to_string(), "dev".to_string()],
+            scope: None,
+            detail: Some("said \"hi\", twice".to_string()),
+        };
+        let csv = to_csv(&[entry]);
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            row,
+            "1970-01-01T00:00:00Z,guard,octo,o@w.com,,origin,main dev,,\"said \"\"hi\"\", twice\""
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use crate::models::{Account, Config, get_token, make_key};
+use crate::utils::format_account_label;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use zeroize::Zeroizing;
+
+/// File written by `gitas export`. A plain accounts.json also imports, since
+/// only `accounts` is required.
+#[derive(Serialize, Deserialize)]
+struct Backup {
+    #[serde(default)]
+    version: u64,
+    accounts: Vec<Account>,
+    /// Keychain tokens by `username` or `username::alias`, only with --with-tokens.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    tokens: BTreeMap<String, String>,
+}
+
+impl Drop for Backup {
+    fn drop(&mut self) {
+        for token in self.tokens.values_mut() {
+            zeroize::Zeroize::zeroize(token);
+        }
+    }
+}
+
+pub fn export(config: &Config, path: Option<String>, with_tokens: bool) {
+    let mut backup = Backup {
+        version: crate::migrate::CONFIG_VERSION,
+        accounts: config.accounts.clone(),
+        tokens: BTreeMap::new(),
+    };
+    if with_tokens {
+        eprintln!(
+            "\n  {} The export contains plaintext tokens. Store it somewhere safe and delete it after importing.",
+            "⚠".yellow().bold()
+        );
+        for account in config.accounts.iter().filter(|a| !a.ssh_only) {
+            if let Some(token) =
+                get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+            {
+                backup.tokens.insert(
+                    make_key(&account.username, account.alias.as_deref()),
+                    token.to_string(),
+                );
+            }
+        }
+    }
+
+    let data =
+        Zeroizing::new(serde_json::to_string_pretty(&backup).expect("Could not serialize export"));
+    match path.as_deref() {
+        None | Some("-") => println!("{}", data.as_str()),
+        Some(path) => {
+            if let Err(e) = write_private(path, &data) {
+                eprintln!("\n  {} Could not write {}: {}\n", "✗".red().bold(), path, e);
+                std::process::exit(1);
+            }
+            eprintln!(
+                "\n  {} Exported {} account(s){} to {}\n",
+                "✓".green().bold(),
+                backup.accounts.len(),
+                if with_tokens {
+                    format!(" and {} token(s)", backup.tokens.len())
+                } else {
+                    String::new()
+                },
+                path.cyan()
+            );
+        }
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &str, data: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(data.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &str, data: &str) -> std::io::Result<()> {
+    fs::write(path, data)
+}
+
+/// Merge (or with `replace`, swap in) accounts from an export. Existing
+/// accounts with the same username and alias are kept unless `force`.
+pub fn import(config: &mut Config, path: String, replace: bool, force: bool) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+
+    let mut data = Zeroizing::new(String::new());
+    let read = if path == "-" {
+        std::io::stdin().read_to_string(&mut data).map(|_| ())
+    } else {
+        fs::read_to_string(&path).map(|content| *data = content)
+    };
+    if let Err(e) = read {
+        fail(format!("Could not read {}: {}", path, e));
+    }
+    let mut raw: serde_json::Value = serde_json::from_str(&data)
+        .unwrap_or_else(|e| fail(format!("{} is not valid JSON: {}", path, e)));
+    crate::migrate::upgrade(&mut raw);
+    let mut backup: Backup = serde_json::from_value(raw)
+        .unwrap_or_else(|e| fail(format!("{} is not a gitas export: {}", path, e)));
+
+    for account in &mut backup.accounts {
+        account.host = account.host.as_deref().and_then(crate::host::for_account);
+    }
+
+    if replace {
+        // Nothing is removed until the whole import is known to save: check
+        // it as if it were the only accounts.
+        let current = std::mem::take(&mut config.accounts);
+        let mut problems = Vec::new();
+        for account in &backup.accounts {
+            problems.extend(
+                super::edit::check(config, None, account)
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", format_account_label(account), problem)),
+            );
+            config.accounts.push(account.clone());
+        }
+        config.accounts = current;
+        if !problems.is_empty() {
+            eprintln!();
+            for problem in problems {
+                eprintln!("  {} {}", "✗".red().bold(), problem);
+            }
+            fail("Nothing was imported or removed.".to_string());
+        }
+
+        // Accounts coming back in the import keep their keychain token.
+        let (kept, removed): (Vec<Account>, Vec<Account>) = std::mem::take(&mut config.accounts)
+            .into_iter()
+            .partition(|account| {
+                backup
+                    .accounts
+                    .iter()
+                    .any(|a| a.username == account.username && a.alias == account.alias)
+            });
+        if !removed.is_empty() {
+            confirm_removal(config, &removed);
+        }
+        config.accounts = kept;
+        let grace_days = config
+            .token_grace_days
+            .unwrap_or(crate::tombstone::DEFAULT_GRACE_DAYS);
+        for account in &removed {
+            config.retarget_account(&crate::audit::account_id(account), None);
+            crate::tombstone::bury(account, grace_days);
+        }
+    }
+
+    println!();
+    let mut failed = false;
+    for account in std::mem::take(&mut backup.accounts) {
+        let label = format_account_label(&account);
+        let existing = config
+            .accounts
+            .iter()
+            .position(|a| a.username == account.username && a.alias == account.alias);
+        if existing.is_some() && !force {
+            println!(
+                "  {} {} {}",
+                "○".dimmed(),
+                label,
+                "(exists, skipped; --force replaces it)".dimmed()
+            );
+            continue;
+        }
+
+        let key = make_key(&account.username, account.alias.as_deref());
+        let token = match backup.tokens.get(&key) {
+            Some(token) => super::edit::TokenChange::Set(token),
+            None => super::edit::TokenChange::Keep,
+        };
+        match super::edit::commit(config, existing, account, token) {
+            Ok(_) => println!(
+                "  {} {} {}",
+                "✓".green().bold(),
+                label.cyan(),
+                if existing.is_some() {
+                    "(replaced)"
+                } else {
+                    "(added)"
+                }
+                .dimmed()
+            ),
+            Err(problems) => {
+                failed = true;
+                println!("  {} {} {}", "✗".red().bold(), label, problems.join("; "));
+            }
+        }
+    }
+    if replace {
+        crate::models::save_config(config);
+    }
+    println!();
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Ask, by the `delete` confirm policy, before `--replace` removes
+/// `accounts`. Exits when that is refused or cancelled.
+fn confirm_removal(config: &Config, accounts: &[Account]) {
+    use crate::confirm::{Action, Decision};
+    let labels: Vec<String> = accounts.iter().map(format_account_label).collect();
+    match crate::confirm::decide(config, Action::Delete) {
+        Decision::Proceed => {}
+        Decision::Refuse => {
+            eprintln!(
+                "\n  {} Refusing to remove {} without a terminal to confirm.",
+                "✗".red().bold(),
+                labels.join(", ")
+            );
+            eprintln!(
+                "  {}\n",
+                "`gitas confirm set delete tty-only` lets scripts go ahead.".dimmed()
+            );
+            std::process::exit(1);
+        }
+        Decision::Ask => {
+            crate::tui::enter_raw_mode();
+            let confirmed = crate::tui::raw_confirm(
+                &format!("Replacing removes {}. Continue?", labels.join(", ")),
+                false,
+            );
+            crate::tui::exit_raw_mode();
+            if confirmed != Some(true) {
+                println!("\n  {}\n", "Cancelled; nothing was imported.".dimmed());
+                std::process::exit(if crate::tui::interrupted() { 130 } else { 0 });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_plain_config_as_backup() {
+        let backup: Backup = serde_json::from_str(
+            r#"{"accounts": [{"username": "octo", "email": "o@x.com"}], "push_check": "warn"}"#,
+        )
+        .unwrap();
+        assert_eq!(backup.accounts.len(), 1);
+        assert!(backup.tokens.is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::models::{Config, save_config};
+use crate::utils::{
+    find_account_by_id, find_account_index, format_account_label, git_toplevel, redact_email,
+};
+use colored::Colorize;
+use std::path::Path;
+
+/// `gitas use`: bind the current repository to an account, or show and clear
+/// the binding.
+pub fn run(config: &mut Config, account: Option<String>, clear: bool, list: bool) {
+    if list {
+        show_all(config);
+        return;
+    }
+
+    let Some(toplevel) = git_toplevel() else {
+        eprintln!("\n  {} Not inside a git repository.\n", "✗".red().bold());
+        std::process::exit(1);
+    };
+
+    if clear {
+        if config.repos.remove(&toplevel).is_some() {
+            save_config(config);
+            println!(
+                "\n  {} {} is no longer bound to an account.\n",
+                "✓".green().bold(),
+                toplevel.cyan()
+            );
+        } else {
+            println!(
+                "\n  {}\n",
+                "No account is bound to this repository.".dimmed()
+            );
+        }
+        return;
+    }
+
+    let Some(id) = account else {
+        match config.repos.get(&toplevel) {
+            Some(id) => println!(
+                "\n  {} {}\n",
+                "Bound to:".dimmed(),
+                match find_account_by_id(config, id) {
+                    Some(account) => format_account_label(account).cyan().to_string(),
+                    None => format!("{} {}", id.yellow(), "(account no longer exists)".dimmed()),
+                }
+            ),
+            None => println!(
+                "\n  {} Bind one with {}.\n",
+                "No account is bound to this repository.".dimmed(),
+                "gitas use <account>".cyan()
+            ),
+        }
+        return;
+    };
+
+    let Some(index) = find_account_index(config, &id) else {
+        eprintln!(
+            "\n  {} No account matching '{}'.\n",
+            "✗".red().bold(),
+            id.yellow()
+        );
+        std::process::exit(1);
+    };
+    let plus = crate::plus::assign(&mut config.accounts[index], Path::new(&toplevel));
+    let account = &config.accounts[index];
+    let label = format_account_label(account);
+    config
+        .repos
+        .insert(toplevel.clone(), crate::audit::account_id(account));
+    save_config(config);
+    println!(
+        "\n  {} {} now uses {}.",
+        "✓".green().bold(),
+        toplevel.cyan(),
+        label.cyan()
+    );
+    match plus {
+        Some(Ok(address)) => match set_local_email(&toplevel, &address) {
+            Ok(()) => println!(
+                "  {} {}",
+                "Commit email here:".dimmed(),
+                redact_email(&address).cyan()
+            ),
+            Err(e) => println!("  {} {}", "⚠".yellow(), e),
+        },
+        Some(Err(e)) => println!("  {} {}", "⚠".yellow(), e),
+        None => {}
+    }
+    println!(
+        "  {}\n",
+        "gitas git and exec run as it here without asking. Switch locally to also set user.name and user.email."
+            .dimmed()
+    );
+}
+
+fn set_local_email(toplevel: &str, email: &str) -> Result<(), String> {
+    let status = std::process::Command::new("git")
+        .args(["-C", toplevel, "config", "--local", "user.email", email])
+        .status()
+        .map_err(|e| format!("could not run git config: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("could not set user.email in this repository".to_string())
+    }
+}
+
+fn show_all(config: &Config) {
+    if config.repos.is_empty() {
+        println!("\n  {}\n", "No repositories are bound.".dimmed());
+        return;
+    }
+    println!();
+    for (path, id) in &config.repos {
+        let account = match find_account_by_id(config, id) {
+            Some(account) => format_account_label(account).cyan().to_string(),
+            None => format!("{} {}", id.yellow(), "(account no longer exists)".dimmed()),
+        };
+        let missing = if Path::new(path).is_dir() {
+            String::new()
+        } else {
+            format!(" {}", "(missing)".dimmed())
+        };
+        println!("  {}{} → {}", path, missing, account);
+    }
+    println!();
+}
@@ -0,0 +1,198 @@
+use crate::models::{Config, save_config};
+use crate::trust::{self, Presented};
+use colored::Colorize;
+use std::io::IsTerminal;
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+/// `gitas cert pin`: trust only one certificate (the host's own, or the
+/// internal CA that issued it) for API calls to `host`. The certificate comes
+/// from `file`, or from what the host presents; with `fingerprint` it must
+/// match, otherwise the user picks one from the chain.
+pub fn pin(config: &mut Config, host: String, file: Option<String>, fingerprint: Option<String>) {
+    let host = crate::host::normalize(&host);
+    if host.is_empty() {
+        fail("No host given.".to_string());
+    }
+    let expected = fingerprint.map(|value| {
+        trust::normalize_fingerprint(&value).unwrap_or_else(|| {
+            fail(format!(
+                "'{}' is not a SHA-256 fingerprint (64 hex digits).",
+                value
+            ))
+        })
+    });
+
+    let chain: Vec<Presented> = match &file {
+        Some(file) => {
+            let text = std::fs::read_to_string(file)
+                .unwrap_or_else(|e| fail(format!("Could not read {}: {}", file, e)));
+            trust::parse_certs(&text)
+                .into_iter()
+                .enumerate()
+                .map(|(i, (pem, cert))| Presented {
+                    subject: format!("{} #{}", file, i + 1),
+                    pem,
+                    cert,
+                })
+                .collect()
+        }
+        None => trust::fetch_chain(&host).unwrap_or_else(|e| fail(e)),
+    };
+    if chain.is_empty() {
+        fail(format!(
+            "{} holds no PEM certificate.",
+            file.unwrap_or_default()
+        ));
+    }
+
+    let chosen = match &expected {
+        Some(expected) => chain
+            .iter()
+            .find(|presented| trust::fingerprint(presented.cert.der()) == *expected)
+            .unwrap_or_else(|| {
+                for presented in &chain {
+                    eprintln!(
+                        "  {} {}",
+                        trust::fingerprint(presented.cert.der()).dimmed(),
+                        presented.subject
+                    );
+                }
+                fail(format!(
+                    "No certificate above has the fingerprint {}.",
+                    expected
+                ))
+            }),
+        None if chain.len() == 1 => &chain[0],
+        None => {
+            if crate::models::is_strict() || !std::io::stdin().is_terminal() {
+                fail(
+                    "The host presents several certificates. Pass --fingerprint to choose one."
+                        .to_string(),
+                );
+            }
+            let labels: Vec<String> = chain
+                .iter()
+                .map(|presented| {
+                    format!(
+                        "{}  {}",
+                        presented.subject,
+                        trust::fingerprint(presented.cert.der()).dimmed()
+                    )
+                })
+                .collect();
+            crate::tui::enter_raw_mode();
+            // The last one is the CA nearest the root, which outlives the
+            // host's own certificate.
+            let selection = crate::tui::raw_select("Certificate to pin", &labels, chain.len() - 1);
+            crate::tui::exit_raw_mode();
+            match selection {
+                Some(index) => &chain[index],
+                None => crate::tui::exit_cancelled(),
+            }
+        }
+    };
+    let fingerprint = trust::fingerprint(chosen.cert.der());
+
+    // Make sure the host actually validates against it before relying on it.
+    let agent = crate::github::agent_with_roots(Some(ureq::tls::RootCerts::Specific(
+        std::sync::Arc::new(vec![chosen.cert.clone()]),
+    )));
+    if let Err(e) = agent.get(format!("https://{}/", host)).call() {
+        fail(format!(
+            "{} does not validate against that certificate: {}",
+            host, e
+        ));
+    }
+
+    let path = trust::cert_path(&host);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .unwrap_or_else(|e| fail(format!("Could not create {}: {}", dir.display(), e)));
+    }
+    std::fs::write(&path, &chosen.pem)
+        .unwrap_or_else(|e| fail(format!("Could not write {}: {}", path.display(), e)));
+    config
+        .pinned_certs
+        .insert(host.clone(), fingerprint.clone());
+    save_config(config);
+    trust::init(&config.pinned_certs);
+
+    println!(
+        "\n  {} Pinned {} for {}",
+        "✓".green().bold(),
+        chosen.subject.cyan(),
+        host.cyan()
+    );
+    println!("  {}", format!("SHA-256 {}", fingerprint).dimmed());
+    println!("\n  Let git trust it too, instead of turning off http.sslVerify:");
+    println!(
+        "    {}\n",
+        format!(
+            "git config --global http.https://{}/.sslCAInfo \"{}\"",
+            host,
+            path.display()
+        )
+        .cyan()
+    );
+}
+
+/// `gitas cert unpin`: go back to the usual trust store for `host`.
+pub fn unpin(config: &mut Config, host: String) {
+    let host = crate::host::normalize(&host);
+    if config.pinned_certs.remove(&host).is_none() {
+        fail(format!("No certificate is pinned for {}.", host));
+    }
+    save_config(config);
+    let path = trust::cert_path(&host);
+    std::fs::remove_file(&path).ok();
+    println!(
+        "\n  {} {} uses the system's trusted certificates again",
+        "✓".green().bold(),
+        host.cyan()
+    );
+    println!(
+        "  {}\n",
+        format!(
+            "If git points at {}, run: git config --global --unset http.https://{}/.sslCAInfo",
+            path.display(),
+            host
+        )
+        .dimmed()
+    );
+}
+
+/// `gitas cert list`: pinned hosts, flagging files that no longer match.
+pub fn list(config: &Config) {
+    if config.pinned_certs.is_empty() {
+        println!("\n  {}\n", "No pinned certificates.".dimmed());
+        return;
+    }
+    println!();
+    for (host, fingerprint) in &config.pinned_certs {
+        let path = trust::cert_path(host);
+        let intact = std::fs::read_to_string(&path).is_ok_and(|text| {
+            trust::parse_certs(&text)
+                .iter()
+                .any(|(_, cert)| trust::fingerprint(cert.der()) == *fingerprint)
+        });
+        let mark = if intact {
+            "✓".green().bold()
+        } else {
+            "✗".red().bold()
+        };
+        println!("  {} {}", mark, host.cyan());
+        println!("    {}", format!("SHA-256 {}", fingerprint).dimmed());
+        if !intact {
+            println!(
+                "    {} does not hold this certificate; API calls to {} fail until it's pinned again",
+                path.display(),
+                host
+            );
+        }
+    }
+    println!();
+}
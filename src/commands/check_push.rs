@@ -0,0 +1,41 @@
+use crate::models::{Config, save_config};
+use crate::push_check::{self, PushCheckMode};
+use crate::resolve::account_for_cwd;
+use colored::Colorize;
+
+pub fn run(
+    config: &mut Config,
+    account_id: Option<String>,
+    enable: Option<PushCheckMode>,
+    disable: bool,
+    args: Vec<String>,
+) {
+    if disable || enable.is_some() {
+        config.push_check = enable;
+        save_config(config);
+        match enable {
+            Some(mode) => println!(
+                "\n  {} Push check enabled ({}) for {}.\n",
+                "✓".green().bold(),
+                format!("{:?}", mode).to_lowercase().cyan(),
+                "gitas git push".cyan()
+            ),
+            None => println!("\n  {} Push check disabled.\n", "✓".green().bold()),
+        }
+        return;
+    }
+
+    let account = account_for_cwd(config, account_id, "  Check as");
+
+    let (revs, exclude) = push_check::push_range(&args);
+    let mode = config.push_check.unwrap_or(PushCheckMode::Block);
+    if !push_check::check(&account, &revs, &exclude, mode) {
+        std::process::exit(1);
+    }
+    println!(
+        "\n  {} Outgoing commits all belong to {} <{}>.\n",
+        "✓".green().bold(),
+        crate::utils::redact_name(&account.username).cyan(),
+        crate::utils::redact_email(&account.email)
+    );
+}
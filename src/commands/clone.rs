@@ -0,0 +1,304 @@
+use crate::models::{Account, Config, get_token, is_ephemeral, save_config};
+use crate::resolve::{account_for_url, resolve_default};
+use crate::utils::{
+    CREDENTIAL_HELPER, credential_env, format_account_label, git_credential_approve, is_http_url,
+    resolve_account,
+};
+use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub fn run(
+    config: &mut Config,
+    account_id: Option<String>,
+    pick: bool,
+    url: Option<String>,
+    directory: Option<String>,
+    git_args: Vec<String>,
+) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+
+    if url.is_none() && (crate::models::is_strict() || !std::io::stdin().is_terminal()) {
+        fail("No repository URL given, and there is no terminal to pick one.".to_string());
+    }
+
+    let owner = url
+        .as_deref()
+        .and_then(|url| account_for_url(&config.accounts, url));
+    let (account, via) = match (account_id, owner) {
+        (Some(id), _) => (resolve_account(config, Some(id), ""), "--account"),
+        (None, _) if pick => (resolve_account(config, None, "  Clone as"), "selected"),
+        (None, Some(account)) => (account.clone(), "repository owner"),
+        (None, None) => match resolve_default(config) {
+            Some(resolution) => (resolution.account.clone(), "default account"),
+            None => (resolve_account(config, None, "  Clone as"), "selected"),
+        },
+    };
+    let url = match url {
+        Some(url) => url,
+        None => pick_repository(&account).unwrap_or_else(|e| fail(e)),
+    };
+
+    if account.ssh_only
+        && let Err(e) = crate::ssh::check_key(&account)
+    {
+        fail(e);
+    }
+    if let Err(e) = crate::hardware::check_present(&account) {
+        fail(e);
+    }
+    for problem in crate::policy::violations(config, &account) {
+        println!("  {} {}", "⚠".yellow(), problem);
+    }
+
+    let http = is_http_url(&url);
+    let token = if account.ssh_only || !http {
+        None
+    } else {
+        get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+    };
+    if http
+        && token.is_none()
+        && !account.ssh_only
+        && !crate::models::is_strict()
+        && crate::warnings::enabled(config, Some(&account), crate::warnings::Warning::NoToken)
+    {
+        println!(
+            "  {} No token found for {}. Git may prompt for authentication.",
+            "⚠".yellow(),
+            crate::utils::redact_name(&account.username).cyan()
+        );
+    }
+
+    let directory = directory
+        .or_else(|| default_directory(&url))
+        .unwrap_or_else(|| fail(format!("Cannot tell a directory name from '{}'", url)));
+
+    let mut cmd = Command::new("git");
+    cmd.arg("-c").arg(format!("user.name={}", account.username));
+    cmd.arg("-c").arg(format!("user.email={}", account.email));
+    if let Some(ssh_key) = &account.ssh_key {
+        cmd.arg("-c").arg(format!(
+            "core.sshCommand={}",
+            crate::ssh::run_command(ssh_key)
+        ));
+    }
+    if crate::models::is_strict() {
+        for (key, value) in crate::utils::STRICT_GIT_CONFIG {
+            cmd.arg("-c").arg(format!("{}={}", key, value));
+        }
+        crate::utils::apply_strict_env(&mut cmd);
+    }
+    if let Some(token) = &token {
+        cmd.arg("-c").arg("credential.helper=");
+        cmd.arg("-c")
+            .arg(format!("credential.helper={}", CREDENTIAL_HELPER));
+        cmd.envs(credential_env(&account.username, token));
+    }
+    cmd.arg("clone").args(&git_args).arg(&url).arg(&directory);
+
+    println!(
+        "  {} git clone {} {}",
+        "\u{21b7}".dimmed(),
+        url,
+        format!("as {} ({})", format_account_label(&account), via).dimmed(),
+    );
+    println!();
+
+    let status = cmd.status().expect("Failed to execute git");
+    if !status.success() {
+        if let Some(token) = &token
+            && let Some(hint) = super::git::permission_hint_for_url(
+                &account,
+                token,
+                &url,
+                crate::permissions::Operation::Read,
+            )
+        {
+            eprintln!("\n  {} {}\n", "⚠".yellow(), hint);
+        }
+        if http && token.is_none() && !account.ssh_only {
+            super::git::strict_failure(&account);
+        }
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    let repo = Path::new(&directory);
+    if is_ephemeral() {
+        println!(
+            "\n  {} Cloned into {} {}\n",
+            "✓".green().bold(),
+            directory.cyan(),
+            "(ephemeral mode, repository config not written)".dimmed()
+        );
+        return;
+    }
+    let toplevel = repo
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&directory));
+    let email = plus_address(config, &account, &toplevel).unwrap_or_else(|| account.email.clone());
+    if let Err(e) = configure(
+        repo,
+        &account,
+        &email,
+        &url,
+        token.as_deref().map(String::as_str),
+    ) {
+        fail(format!("Cloned into {}, but {}", directory, e));
+    }
+
+    crate::audit::record(&crate::audit::Entry {
+        repo: Some(toplevel.display().to_string()),
+        remote: Some("origin".to_string()),
+        scope: Some("local".to_string()),
+        ..crate::audit::Entry::new(crate::audit::Event::Switch, &account)
+    });
+
+    println!(
+        "\n  {} Cloned into {} and set {} locally\n",
+        "✓".green().bold(),
+        directory.cyan(),
+        format_account_label(&account).cyan()
+    );
+}
+
+/// The account whose username owns the repository on the URL's host.
+/// Directory `git clone` would pick for `url`: its last path segment without `.git`.
+fn default_directory(url: &str) -> Option<String> {
+    let name = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// HTTPS URL of a repository picked from those the account's token can
+/// access, for `gitas clone` without a URL. Needs a terminal.
+fn pick_repository(account: &Account) -> Result<String, String> {
+    let token = Some(account)
+        .filter(|account| !account.ssh_only)
+        .and_then(|account| get_token(&account.username, account.alias.as_deref()))
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "{} has no token to list repositories with; give the URL to clone.",
+                format_account_label(account)
+            )
+        })?;
+
+    crate::tui::enter_raw_mode();
+    let lister = account.clone();
+    let listed = crate::tui::raw_with_loader("Listing repositories…", move || {
+        crate::provider::for_account(&lister).list_repos(&token)
+    })
+    .unwrap_or_else(|_| Err("Listing repositories failed".to_string()));
+    let repos = match listed {
+        Ok(repos) if repos.is_empty() => Err(format!(
+            "{} can't see any repositories.",
+            format_account_label(account)
+        )),
+        other => other,
+    };
+    let choice = repos.map(|repos| {
+        crate::tui::raw_select("Repository to clone", &repos, 0).map(|index| repos[index].clone())
+    });
+    crate::tui::exit_raw_mode();
+
+    let path = choice?.unwrap_or_else(|| crate::tui::exit_cancelled());
+    Ok(format!(
+        "https://{}/{}",
+        account.host.as_deref().unwrap_or("github.com"),
+        path
+    ))
+}
+
+/// A fresh plus address for the clone at `toplevel`, recorded on the
+/// account, when it has a template.
+fn plus_address(config: &mut Config, account: &Account, toplevel: &Path) -> Option<String> {
+    let id = crate::audit::account_id(account);
+    let saved = config
+        .accounts
+        .iter_mut()
+        .find(|a| crate::audit::account_id(a) == id)?;
+    match crate::plus::assign(saved, toplevel)? {
+        Ok(address) => {
+            save_config(config);
+            Some(address)
+        }
+        Err(e) => {
+            println!("  {} {}", "⚠".yellow(), e);
+            None
+        }
+    }
+}
+
+/// Write the account into the fresh repository's local config, the same keys
+/// a local switch sets, with `email` as the commit email.
+fn configure(
+    repo: &Path,
+    account: &Account,
+    email: &str,
+    url: &str,
+    token: Option<&str>,
+) -> Result<(), String> {
+    let host = account.host.as_deref().unwrap_or("github.com");
+    if let Some(token) = token {
+        for username in crate::provider::credential_usernames(account, Some(url)) {
+            git_credential_approve(&username, token, host, Some(url))?;
+        }
+        set_local(repo, "credential.useHttpPath", "true")?;
+    }
+    set_local(repo, "user.name", &account.username)?;
+    set_local(repo, "user.email", email)?;
+    if let Some(alias) = &account.alias {
+        set_local(repo, "gitas.alias", alias)?;
+    }
+    if let Some(ssh_key) = &account.ssh_key {
+        set_local(repo, "core.sshCommand", &crate::ssh::command(ssh_key))?;
+    }
+    if !account.ssh_only {
+        set_local(
+            repo,
+            &format!("credential.https://{}.username", host),
+            &account.username,
+        )?;
+    }
+    Ok(())
+}
+
+fn set_local(repo: &Path, key: &str, value: &str) -> Result<(), String> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["config", "--local", key, value])
+        .status()
+        .map_err(|e| format!("could not run git config: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("could not set {}", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_directory_matches_git() {
+        assert_eq!(
+            default_directory("https://github.com/octo/repo.git").as_deref(),
+            Some("repo")
+        );
+        assert_eq!(
+            default_directory("git@github.com:octo/repo/").as_deref(),
+            Some("repo")
+        );
+        assert_eq!(default_directory("host:repo").as_deref(), Some("repo"));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::confirm::{self, Action, Policy};
+use crate::models::{Config, save_config};
+use clap::ValueEnum;
+use colored::Colorize;
+
+pub fn list(config: &Config) {
+    println!();
+    for action in Action::value_variants() {
+        let policy = confirm::policy(config, *action);
+        println!(
+            "  {:<21} {:<9} {}{}",
+            action.name(),
+            policy.name().cyan(),
+            action
+                .to_possible_value()
+                .and_then(|v| v.get_help().map(|h| h.to_string()))
+                .unwrap_or_default()
+                .dimmed(),
+            if config.confirm.contains_key(action) {
+                String::new()
+            } else {
+                " (default)".dimmed().to_string()
+            }
+        );
+    }
+    println!(
+        "\n  {}\n",
+        "always: ask, refuse without a terminal · tty-only: ask only on a terminal · never: don't ask"
+            .dimmed()
+    );
+}
+
+pub fn set(config: &mut Config, action: Action, policy: Policy) {
+    config.confirm.insert(action, policy);
+    save_config(config);
+    println!(
+        "\n  {} '{}' now confirms: {}\n",
+        "✓".green().bold(),
+        action.name().cyan(),
+        policy.name()
+    );
+}
+
+/// Go back to the built-in policy for `action`.
+pub fn reset(config: &mut Config, action: Action) {
+    config.confirm.remove(&action);
+    save_config(config);
+    println!(
+        "\n  {} '{}' confirms by default again: {}\n",
+        "✓".green().bold(),
+        action.name().cyan(),
+        confirm::policy(config, action).name()
+    );
+}
@@ -0,0 +1,148 @@
+use crate::models::{Account, Config};
+use crate::resolve::{account_for_push, resolve_for_path};
+use std::io::Read;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// What git asks a credential helper about: the `key=value` lines it writes
+/// to stdin, up to a blank line.
+#[derive(Debug, Default, PartialEq)]
+struct Request {
+    protocol: String,
+    host: String,
+    /// Only sent with `credential.useHttpPath`.
+    path: Option<String>,
+    username: Option<String>,
+}
+
+impl Request {
+    fn parse(input: &str) -> Request {
+        let mut request = Request::default();
+        for line in input.lines().take_while(|line| !line.is_empty()) {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key {
+                "protocol" => request.protocol = value.to_string(),
+                "host" => request.host = value.to_string(),
+                "path" => request.path = Some(value.to_string()).filter(|p| !p.is_empty()),
+                "username" => request.username = Some(value.to_string()).filter(|u| !u.is_empty()),
+                _ => {}
+            }
+        }
+        request
+    }
+
+    fn url(&self) -> String {
+        match (&self.username, &self.path) {
+            (Some(user), Some(path)) => {
+                format!("{}://{}@{}/{}", self.protocol, user, self.host, path)
+            }
+            (None, Some(path)) => format!("{}://{}/{}", self.protocol, self.host, path),
+            (Some(user), None) => format!("{}://{}@{}", self.protocol, user, self.host),
+            (None, None) => format!("{}://{}", self.protocol, self.host),
+        }
+    }
+}
+
+/// Account whose token answers `request`: among the accounts on its host
+/// (and under the username git sent, if any), the repository's account, the
+/// owner of the path, or the only one there is. Never prompts.
+fn account_for<'a>(config: &'a Config, request: &Request, dir: &Path) -> Option<&'a Account> {
+    let url = request.url();
+    let fits = |account: &&Account| {
+        !account.ssh_only
+            && crate::host::matches(
+                account.host.as_deref().unwrap_or("github.com"),
+                &request.host,
+            )
+            && request.username.as_ref().is_none_or(|user| {
+                crate::provider::credential_usernames(account, Some(&url))
+                    .iter()
+                    .chain(crate::provider::credential_usernames(account, None).iter())
+                    .any(|name| name.eq_ignore_ascii_case(user))
+            })
+    };
+    let candidates: Vec<&Account> = config.accounts.iter().filter(fits).collect();
+    let repo = match &request.path {
+        Some(_) => account_for_push(config, dir, &url),
+        None => resolve_for_path(config, dir).map(|resolution| resolution.account),
+    };
+    if let Some(account) = repo.filter(fits) {
+        return Some(account);
+    }
+    match candidates.as_slice() {
+        [only] => Some(only),
+        _ => None,
+    }
+}
+
+/// `gitas credential <action>`, run by git as `credential.helper`. `get`
+/// answers with the token of the account for the repository or host;
+/// `store` and `erase` do nothing, since the keychain entries belong to
+/// gitas. Anything unanswerable prints nothing, and git moves on to its next
+/// helper or prompt.
+pub fn run(config: &Config, action: &str) {
+    let mut input = String::new();
+    if std::io::stdin().read_to_string(&mut input).is_err() || action != "get" {
+        return;
+    }
+    let request = Request::parse(&input);
+    // Tokens are never sent in the clear.
+    if request.protocol != "https" {
+        return;
+    }
+    let Some(account) = account_for(config, &request, Path::new(".")) else {
+        return;
+    };
+    let Some(token) = crate::token_agent::token(account).filter(|token| !token.is_empty()) else {
+        return;
+    };
+    let username = request.username.clone().unwrap_or_else(|| {
+        crate::provider::credential_usernames(account, Some(&request.url()))
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| account.username.clone())
+    });
+    let answer = Zeroizing::new(format!(
+        "username={}\npassword={}\n",
+        username,
+        token.as_str()
+    ));
+    print!("{}", answer.as_str());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_only_account_that_fits() {
+        let request =
+            Request::parse("protocol=https\nhost=gitlab.com\nusername=octo\n\nprotocol=ignored\n");
+        assert_eq!(request.host, "gitlab.com");
+        assert_eq!(request.url(), "https://octo@gitlab.com");
+
+        let account = |username: &str, host: Option<&str>| Account {
+            username: username.to_string(),
+            host: host.map(str::to_string),
+            ..Default::default()
+        };
+        let config = Config {
+            accounts: vec![
+                account("octo", None),
+                account("octo", Some("gitlab.com")),
+                account("cat", Some("gitlab.com")),
+            ],
+            ..Default::default()
+        };
+        let dir = std::env::temp_dir();
+        let found = account_for(&config, &request, &dir).unwrap();
+        assert_eq!(found.host.as_deref(), Some("gitlab.com"));
+        assert_eq!(found.username, "octo");
+
+        // Two accounts on the host and nothing to tell them apart.
+        let request = Request::parse("protocol=https\nhost=gitlab.com\n");
+        assert!(account_for(&config, &request, &dir).is_none());
+    }
+}
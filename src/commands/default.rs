@@ -0,0 +1,50 @@
+use crate::models::{Config, save_config};
+use crate::utils::{default_account, find_account_index, format_account_label};
+use colored::Colorize;
+
+pub fn run(config: &mut Config, account: Option<String>, clear: bool) {
+    if clear {
+        config.default_account = None;
+        save_config(config);
+        println!("\n  {} Default account cleared.\n", "✓".green().bold());
+        return;
+    }
+
+    let Some(id) = account else {
+        match default_account(config) {
+            Some(account) => println!(
+                "\n  {} {}\n",
+                "Default account:".dimmed(),
+                format_account_label(account).cyan()
+            ),
+            None => println!(
+                "\n  {} Set one with {}.\n",
+                "No default account.".dimmed(),
+                "gitas default <account>".cyan()
+            ),
+        }
+        return;
+    };
+
+    let Some(index) = find_account_index(config, &id) else {
+        eprintln!(
+            "\n  {} No account matching '{}'.\n",
+            "✗".red().bold(),
+            id.yellow()
+        );
+        std::process::exit(1);
+    };
+    let account = &config.accounts[index];
+    let label = format_account_label(account);
+    config.default_account = Some(crate::audit::account_id(account));
+    save_config(config);
+    println!(
+        "\n  {} {} is now the default account.",
+        "✓".green().bold(),
+        label.cyan()
+    );
+    println!(
+        "  {}\n",
+        "gitas git, exec and clone use it when no -a is given; --pick shows the picker.".dimmed()
+    );
+}
@@ -0,0 +1,404 @@
+use crate::models::{Account, Config};
+use crate::rules::Rule;
+use crate::tui::{enter_raw_mode, exit_raw_mode, raw_multi_select};
+use crate::utils::{format_account_label, redact_email};
+use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Another way of juggling identities that gitas should work alongside.
+#[derive(Debug, PartialEq)]
+enum Finding {
+    /// Git Credential Manager answers for credentials, with the usernames
+    /// it is told to use per URL.
+    Gcm { usernames: Vec<(String, String)> },
+    /// gh is logged in to `host` as several users.
+    Gh { host: String, users: Vec<String> },
+    /// An `includeIf` entry gitas didn't write, and the identity it sets.
+    Include {
+        rule: Rule,
+        name: Option<String>,
+        email: Option<String>,
+    },
+    /// An allowed `.envrc` that exports identity variables.
+    Direnv { file: PathBuf, vars: Vec<String> },
+}
+
+impl Finding {
+    fn title(&self) -> String {
+        match self {
+            Finding::Gcm { .. } => "Git Credential Manager".to_string(),
+            Finding::Gh { host, users } => {
+                format!("gh is logged in to {} as {}", host, users.join(", "))
+            }
+            Finding::Include { rule, name, email } => format!(
+                "includeIf gitdir:{} sets {}",
+                rule.pattern,
+                match (name, email) {
+                    (Some(name), Some(email)) => format!("{} <{}>", name, redact_email(email)),
+                    (Some(name), None) => name.clone(),
+                    (None, Some(email)) => format!("<{}>", redact_email(email)),
+                    (None, None) => "nothing".to_string(),
+                }
+            ),
+            Finding::Direnv { file, vars } => {
+                format!("{} exports {}", file.display(), vars.join(", "))
+            }
+        }
+    }
+
+    fn details(&self) -> Vec<String> {
+        match self {
+            Finding::Gcm { usernames } => usernames
+                .iter()
+                .map(|(url, user)| format!("credential.{}.username = {}", url, user))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// How gitas and this setup get along.
+    fn guidance(&self) -> &'static str {
+        match self {
+            Finding::Gcm { .. } => {
+                "`gitas install-helper` lets gitas answer first; GCM keeps answering for hosts without a gitas account. Per-URL usernames still pin which login GCM picks."
+            }
+            Finding::Gh { .. } => {
+                "gitas leaves gh's logins alone. With `gitas gh sync --enable`, switching also logs gh in as the account."
+            }
+            Finding::Include { .. } => {
+                "The rule keeps applying in its directories. Import the identity as an account, then `gitas rule add` can take the rule over."
+            }
+            Finding::Direnv { .. } => {
+                "These variables win over every identity gitas sets in that directory. gitas git drops them for its commands; plain git still uses them."
+            }
+        }
+    }
+
+    /// Account for an identity that can be imported.
+    fn account(&self) -> Option<Account> {
+        match self {
+            Finding::Include {
+                name: Some(name),
+                email: Some(email),
+                ..
+            } => Some(Account {
+                username: name.clone(),
+                email: email.clone(),
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn git_output(args: &[&str]) -> String {
+    Command::new("git")
+        .args(args)
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default()
+}
+
+fn gcm() -> Option<Finding> {
+    let helpers = git_output(&["config", "--get-regexp", r"^credential\.(.+\.)?helper$"]);
+    let uses_gcm = helpers
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .any(|(_, value)| value.contains("manager"));
+    if !uses_gcm {
+        return None;
+    }
+    let usernames = git_output(&[
+        "config",
+        "--global",
+        "--get-regexp",
+        r"^credential\..+\.username$",
+    ])
+    .lines()
+    .filter_map(|line| {
+        let (key, user) = line.split_once(' ')?;
+        let url = key.strip_prefix("credential.")?.strip_suffix(".username")?;
+        Some((url.to_string(), user.to_string()))
+    })
+    .collect();
+    Some(Finding::Gcm { usernames })
+}
+
+fn gh_hosts_file() -> Option<PathBuf> {
+    let dir = match std::env::var_os("GH_CONFIG_DIR").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => dirs::config_dir()?.join("GitHub CLI"),
+        None => match std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+            Some(dir) => PathBuf::from(dir).join("gh"),
+            None => dirs::home_dir()?.join(".config").join("gh"),
+        },
+    };
+    Some(dir.join("hosts.yml"))
+}
+
+/// Users gh is logged in as, per host, from its `hosts.yml`: the keys under
+/// each host's `users:`, or its single `user:` in files from before gh
+/// handled several accounts.
+fn parse_gh_hosts(text: &str) -> Vec<(String, Vec<String>)> {
+    let mut hosts: Vec<(String, Vec<String>)> = Vec::new();
+    // Indent of `users:`, and of the user names under it once seen.
+    let mut users_indent: Option<(usize, Option<usize>)> = None;
+    for line in text.lines() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        if indent == 0 {
+            users_indent = None;
+            if let Some(host) = content.strip_suffix(':') {
+                hosts.push((host.trim().to_string(), Vec::new()));
+            }
+            continue;
+        }
+        let Some((_, users)) = hosts.last_mut() else {
+            continue;
+        };
+        match users_indent {
+            Some((under, names)) if indent > under => {
+                let names = names.unwrap_or(indent);
+                if indent == names
+                    && let Some(user) = content.strip_suffix(':').map(str::trim)
+                    && !users.iter().any(|u| u == user)
+                {
+                    users.push(user.to_string());
+                }
+                users_indent = Some((under, Some(names)));
+                continue;
+            }
+            Some(_) => users_indent = None,
+            None => {}
+        }
+        if content == "users:" {
+            users_indent = Some((indent, None));
+        } else if let Some(user) = content.strip_prefix("user:").map(str::trim)
+            && !user.is_empty()
+            && !users.iter().any(|u| u == user)
+        {
+            users.push(user.to_string());
+        }
+    }
+    hosts
+}
+
+fn gh() -> Vec<Finding> {
+    let Some(text) = gh_hosts_file().and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    parse_gh_hosts(&text)
+        .into_iter()
+        .filter(|(_, users)| users.len() > 1)
+        .map(|(host, users)| Finding::Gh { host, users })
+        .collect()
+}
+
+/// An include path as git reads it from the global config.
+fn expand_home(path: &str) -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_default();
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => home.join(path),
+    }
+}
+
+fn includes() -> Vec<Finding> {
+    crate::rules::list()
+        .into_iter()
+        .filter(|rule| !crate::rules::is_managed(rule))
+        .filter_map(|rule| {
+            let file = expand_home(&rule.path);
+            let get = |key: &str| {
+                let value =
+                    git_output(&["config", "--file", &file.to_string_lossy(), "--get", key]);
+                Some(value.trim().to_string()).filter(|v| !v.is_empty())
+            };
+            let (name, email) = (get("user.name"), get("user.email"));
+            (name.is_some() || email.is_some()).then_some(Finding::Include { rule, name, email })
+        })
+        .collect()
+}
+
+/// Identity variables an `.envrc` sets.
+fn envrc_vars(text: &str) -> Vec<String> {
+    crate::utils::IDENTITY_ENV_VARS
+        .iter()
+        .filter(|var| {
+            text.lines().any(|line| {
+                let line = line.trim_start();
+                let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+                line.strip_prefix(**var)
+                    .is_some_and(|rest| rest.trim_start().starts_with('='))
+            })
+        })
+        .map(|var| var.to_string())
+        .collect()
+}
+
+/// `.envrc` files direnv was told to trust, found through its allow list.
+fn direnv() -> Vec<Finding> {
+    let data = match std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => match dirs::home_dir() {
+            Some(home) => home.join(".local").join("share"),
+            None => return Vec::new(),
+        },
+    };
+    let Ok(entries) = std::fs::read_dir(data.join("direnv").join("allow")) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .map(|text| PathBuf::from(text.trim()))
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let vars = envrc_vars(&std::fs::read_to_string(&file).ok()?);
+            (!vars.is_empty()).then_some(Finding::Direnv { file, vars })
+        })
+        .collect()
+}
+
+fn scan() -> Vec<Finding> {
+    let mut findings: Vec<Finding> = gcm().into_iter().collect();
+    findings.extend(gh());
+    findings.extend(includes());
+    findings.extend(direnv());
+    findings
+}
+
+fn show(findings: &[Finding]) {
+    for finding in findings {
+        println!("  {} {}", "●".cyan(), finding.title());
+        for line in finding.details() {
+            println!("    {}", line.dimmed());
+        }
+        println!("    {}\n", finding.guidance().dimmed());
+    }
+}
+
+/// Offer to add the identities found as accounts. Ones whose email is
+/// already managed are left out.
+fn offer_import(config: &mut Config, findings: &[Finding]) {
+    let accounts: Vec<Account> = findings
+        .iter()
+        .filter_map(Finding::account)
+        .filter(|account| {
+            !config
+                .accounts
+                .iter()
+                .any(|a| a.email.eq_ignore_ascii_case(&account.email))
+        })
+        .collect();
+    if accounts.is_empty() {
+        return;
+    }
+    if crate::models::is_strict() || !std::io::stdin().is_terminal() {
+        println!(
+            "  {}\n",
+            "Run in a terminal to import these identities as accounts.".dimmed()
+        );
+        return;
+    }
+    let labels: Vec<String> = accounts.iter().map(format_account_label).collect();
+    let checked: Vec<usize> = (0..labels.len()).collect();
+    enter_raw_mode();
+    let selection = raw_multi_select("Import as accounts", &labels, &checked);
+    exit_raw_mode();
+    let Some(selection) = selection else {
+        crate::tui::exit_cancelled();
+    };
+    if selection.is_empty() {
+        return;
+    }
+
+    println!();
+    for index in selection {
+        let account = accounts[index].clone();
+        let label = format_account_label(&account);
+        match super::edit::commit(config, None, account, super::edit::TokenChange::Keep) {
+            Ok(_) => println!(
+                "  {} {} {}",
+                "✓".green().bold(),
+                label.cyan(),
+                "(added)".dimmed()
+            ),
+            Err(problems) => println!("  {} {} {}", "✗".red().bold(), label, problems.join("; ")),
+        }
+    }
+    println!(
+        "\n  {}\n",
+        "Accounts were added without tokens; add them with `gitas token set`.".dimmed()
+    );
+}
+
+/// `gitas detect`: report other multi-account setups and how gitas fits
+/// next to them, and offer to import the identities they hold.
+pub fn run(config: &mut Config) {
+    let findings = scan();
+    if findings.is_empty() {
+        println!(
+            "\n  {}\n",
+            "No other multi-account setup found (GCM, gh, includeIf, direnv).".dimmed()
+        );
+        return;
+    }
+    println!();
+    show(&findings);
+    offer_import(config, &findings);
+}
+
+/// On the first run (no config file yet), say what other setups are in
+/// place before gitas changes anything. The config is saved afterwards so
+/// this happens once.
+pub fn first_run(config: &mut Config) {
+    let findings = scan();
+    if findings.is_empty() {
+        return;
+    }
+    println!(
+        "\n  {}\n",
+        "Welcome to gitas. It found identity setups it will work alongside:".bold()
+    );
+    show(&findings);
+    offer_import(config, &findings);
+    crate::models::save_config(config);
+    println!("  {}\n", "Run `gitas detect` to see this again.".dimmed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_gh_users_and_envrc_identities() {
+        let hosts = "github.com:\n    users:\n        alice:\n            oauth_token: x\n        bob:\n    git_protocol: https\n    user: alice\nghe.acme.com:\n    user: carol\n    oauth_token: y\n";
+        assert_eq!(
+            parse_gh_hosts(hosts),
+            [
+                (
+                    "github.com".to_string(),
+                    vec!["alice".to_string(), "bob".to_string()]
+                ),
+                ("ghe.acme.com".to_string(), vec!["carol".to_string()]),
+            ]
+        );
+
+        let envrc = "export GIT_AUTHOR_EMAIL=me@work.com\nGIT_COMMITTER_EMAIL = me@work.com\n# GIT_AUTHOR_NAME=x\nexport GIT_AUTHOR_NAMES=y\n";
+        assert_eq!(
+            envrc_vars(envrc),
+            ["GIT_AUTHOR_EMAIL", "GIT_COMMITTER_EMAIL"]
+        );
+    }
+}
@@ -0,0 +1,606 @@
+use crate::models::{Account, Config, delete_token, read_token, save_config, set_token};
+use crate::provider::ProviderKind;
+use crate::utils::find_account_index;
+use colored::Colorize;
+use std::io::BufRead;
+use zeroize::Zeroizing;
+
+/// Fields `gitas edit` can change. Empty values clear optional fields.
+#[derive(clap::Args, Default, PartialEq)]
+pub struct EditArgs {
+    /// New username
+    #[arg(long)]
+    username: Option<String>,
+    /// New commit email
+    #[arg(long)]
+    email: Option<String>,
+    /// Alias (empty to clear)
+    #[arg(long)]
+    alias: Option<String>,
+    /// Host (empty for github.com)
+    #[arg(long)]
+    host: Option<String>,
+    /// Forge type of the host; detected when --host changes and this is omitted
+    #[arg(long)]
+    provider: Option<crate::provider::ProviderKind>,
+    /// Path to the SSH private key (empty to clear)
+    #[arg(long)]
+    ssh_key: Option<String>,
+    /// Authenticate over SSH only
+    #[arg(long)]
+    ssh_only: Option<bool>,
+    /// Have ssh-agent ask before each use of the SSH key (needs an askpass program)
+    #[arg(long)]
+    ssh_confirm: Option<bool>,
+    /// GPG signing key ID (empty to clear)
+    #[arg(long)]
+    gpg_key: Option<String>,
+    /// Sign commits with the GPG key on switch (turns on commit.gpgsign)
+    #[arg(long)]
+    sign_with_gpg_key: Option<bool>,
+    /// Key to sign commits with on switch, e.g. an SSH public key path (empty to clear)
+    #[arg(long)]
+    signing_key: Option<String>,
+    /// Format of the signing key
+    #[arg(long)]
+    signing_format: Option<crate::signing::SigningFormat>,
+    /// Group label (empty to clear)
+    #[arg(long)]
+    group: Option<String>,
+    /// Comma-separated organizations or users whose repositories select this
+    /// account (empty to clear)
+    #[arg(long)]
+    orgs: Option<String>,
+    /// Timezone commits are stamped in, e.g. Europe/Berlin (empty for the machine's)
+    #[arg(long)]
+    timezone: Option<String>,
+    /// Author dates when gitas git amends or rebases
+    #[arg(long)]
+    author_date: Option<crate::dates::AuthorDate>,
+    /// Give each repository bound or cloned its own address from a template
+    /// ('{local}+{repo}@{domain}' when none is given; empty to stop)
+    #[arg(long, value_name = "TEMPLATE", num_args = 0..=1, default_missing_value = crate::plus::DEFAULT_TEMPLATE)]
+    plus_template: Option<String>,
+    /// Sync the token to ~/.npmrc on switch
+    #[arg(long)]
+    npm: Option<bool>,
+    /// Cargo registry whose credentials follow this account (empty to clear)
+    #[arg(long)]
+    cargo_registry: Option<String>,
+    /// Maven settings.xml server id whose credentials follow this account (empty to clear)
+    #[arg(long)]
+    maven_server: Option<String>,
+    /// Sync gpr.user/gpr.key in gradle.properties on switch
+    #[arg(long)]
+    gradle: Option<bool>,
+    /// Read a new token from the first line of stdin (empty removes it)
+    #[arg(long)]
+    token_stdin: bool,
+}
+
+pub fn run(config: &mut Config, id: String, args: EditArgs) {
+    let Some(index) = find_account_index(config, &id) else {
+        eprintln!(
+            "\n  {} No account matches '{}'.\n",
+            "✗".red().bold(),
+            id.yellow()
+        );
+        std::process::exit(1);
+    };
+
+    let original = config.accounts[index].clone();
+    let mut account = original.clone();
+    if let Some(username) = args.username.filter(|u| !u.is_empty()) {
+        account.username = username;
+    }
+    if let Some(email) = args.email.filter(|e| !e.is_empty()) {
+        account.email = email;
+    }
+    if let Some(alias) = args.alias {
+        account.alias = non_empty(alias);
+    }
+    if let Some(host) = args.host {
+        account.host = crate::host::for_account(&host);
+    }
+    if let Some(kind) = args.provider {
+        account.provider = Some(kind);
+    } else if account.host != original.host {
+        account.provider = match account.host.as_deref() {
+            None => None,
+            Some(host) => match crate::provider::detect(host) {
+                Some(kind) => Some(kind),
+                None => {
+                    eprintln!(
+                        "\n  {} Could not detect the forge at {}. Pass --provider.\n",
+                        "✗".red().bold(),
+                        host
+                    );
+                    std::process::exit(1);
+                }
+            },
+        };
+    }
+    account.provider = account
+        .provider
+        .filter(|kind| *kind != crate::provider::ProviderKind::Github);
+    if let Some(ssh_key) = args.ssh_key {
+        account.ssh_key = non_empty(ssh_key);
+    }
+    if let Some(ssh_only) = args.ssh_only {
+        account.ssh_only = ssh_only;
+    }
+    if let Some(ssh_confirm) = args.ssh_confirm {
+        account.ssh_confirm = ssh_confirm;
+    }
+    if let Some(gpg_key) = args.gpg_key {
+        account.gpg_key = non_empty(gpg_key);
+    }
+    if let Some(sign) = args.sign_with_gpg_key {
+        account.sign_with_gpg_key = sign;
+    }
+    if let Some(signing_key) = args.signing_key {
+        account.signing_key = non_empty(signing_key);
+    }
+    if let Some(format) = args.signing_format {
+        account.signing_format = Some(format);
+    }
+    if let Some(group) = args.group {
+        account.group = non_empty(group);
+    }
+    if let Some(orgs) = args.orgs {
+        account.orgs = orgs
+            .split(',')
+            .map(str::trim)
+            .filter(|org| !org.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if let Some(timezone) = args.timezone {
+        account.timezone = non_empty(timezone);
+    }
+    if let Some(author_date) = args.author_date {
+        account.author_date = Some(author_date).filter(|d| *d != crate::dates::AuthorDate::Keep);
+    }
+    if let Some(template) = args.plus_template {
+        account.plus_template = non_empty(template);
+    }
+
+    if let Some(npm) = args.npm {
+        account.packages.npm = npm;
+    }
+    if let Some(registry) = args.cargo_registry {
+        account.packages.cargo_registry = non_empty(registry);
+    }
+
+    if let Some(server) = args.maven_server {
+        account.packages.maven_server = non_empty(server);
+    }
+    if let Some(gradle) = args.gradle {
+        account.packages.gradle = gradle;
+    }
+
+    let token = args.token_stdin.then(read_token_stdin);
+    let change = match &token {
+        None => TokenChange::Keep,
+        Some(token) => TokenChange::from(token.as_deref().map(String::as_str)),
+    };
+
+    if let Err(problems) = commit(config, Some(index), account, change) {
+        eprintln!();
+        for problem in problems {
+            eprintln!("  {} {}", "✗".red().bold(), problem);
+        }
+        eprintln!("\n  {}\n", "Account not saved.".dimmed());
+        std::process::exit(1);
+    }
+
+    let account = &config.accounts[index];
+    println!(
+        "\n  {} Updated {}\n",
+        "✓".green().bold(),
+        crate::utils::format_account_label(account).cyan()
+    );
+}
+
+/// First line of stdin, or None when it's empty. Buffers are wiped on drop.
+pub(crate) fn read_token_stdin() -> Option<Zeroizing<String>> {
+    let mut line = Zeroizing::new(String::with_capacity(256));
+    if std::io::stdin().lock().read_line(&mut line).is_err() {
+        eprintln!(
+            "\n  {} Failed to read token from stdin.\n",
+            "✗".red().bold()
+        );
+        std::process::exit(1);
+    }
+    let token = line.trim();
+    (!token.is_empty()).then(|| Zeroizing::new(token.to_string()))
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+/// Reasons `account` can't be saved at `index` (or as a new account).
+pub(crate) fn check(config: &Config, index: Option<usize>, account: &Account) -> Vec<String> {
+    let mut problems: Vec<String> =
+        crate::utils::validate_identity(&account.username, &account.email)
+            .err()
+            .into_iter()
+            .collect();
+    if let Some(timezone) = &account.timezone
+        && let Err(e) = crate::dates::check_timezone(timezone)
+    {
+        problems.push(e);
+    }
+    if let Some(template) = &account.plus_template
+        && let Err(e) = crate::plus::render(template, &account.email, "repo")
+    {
+        problems.push(e);
+    }
+    problems.extend(crate::policy::violations(config, account));
+    let same = |other: &Account| other.username == account.username && other.alias == account.alias;
+    if config
+        .accounts
+        .iter()
+        .enumerate()
+        .any(|(i, other)| Some(i) != index && same(other))
+        || config.hidden.iter().any(|(_, other)| same(other))
+    {
+        problems.push(format!(
+            "Another account is already named {}",
+            crate::utils::format_account_label(account)
+        ));
+    }
+    problems
+}
+
+/// What saving an account does to its stored token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TokenChange<'a> {
+    /// Leave it as it is (moving it along when the account is renamed).
+    Keep,
+    Set(&'a str),
+    Delete,
+}
+
+impl<'a> From<Option<&'a str>> for TokenChange<'a> {
+    /// A token to store, or none at all.
+    fn from(token: Option<&'a str>) -> Self {
+        token.map_or(TokenChange::Delete, TokenChange::Set)
+    }
+}
+
+/// Validate and persist an edited account (or a new one when `index` is None),
+/// moving its keyring entry when the username or alias changed. Shared by the
+/// interactive editor, `gitas edit` and `gitas add` with flags.
+pub(crate) fn commit(
+    config: &mut Config,
+    index: Option<usize>,
+    mut account: Account,
+    token: TokenChange,
+) -> Result<usize, Vec<String>> {
+    // Accounts added under --profile belong to it.
+    if index.is_none() && account.profile.is_none() {
+        account.profile = crate::models::active_profile().map(str::to_string);
+    }
+    let problems = check(config, index, &account);
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    let original = index.map(|index| config.accounts[index].clone());
+    let renamed = original
+        .as_ref()
+        .filter(|o| o.username != account.username || o.alias != account.alias);
+    let previous = match &original {
+        Some(original) => read_token(&original.username, original.alias.as_deref()),
+        None => Ok(None),
+    };
+    // A keychain that can't be read must not pass for an empty one, or a
+    // renamed account would lose its token.
+    let previous = match previous {
+        Ok(previous) => previous,
+        Err(e) if renamed.is_some() && token == TokenChange::Keep => {
+            return Err(vec![format!("{}; the account was not renamed", e)]);
+        }
+        Err(_) => None,
+    };
+    if let Some(original) = renamed {
+        delete_token(&original.username, original.alias.as_deref());
+        let original_id = crate::audit::account_id(original);
+        config.retarget_account(&original_id, Some(&crate::audit::account_id(&account)));
+    }
+    // None leaves the stored token where it is.
+    let token = match token {
+        TokenChange::Keep if renamed.is_none() => None,
+        TokenChange::Keep => Some(previous.as_deref().map(String::as_str)),
+        TokenChange::Set(token) => Some(Some(token)),
+        TokenChange::Delete => Some(None),
+    };
+    match token {
+        Some(Some(token)) => set_token(&account.username, account.alias.as_deref(), token),
+        Some(None) => delete_token(&account.username, account.alias.as_deref()),
+        None => {}
+    }
+    if let Some(token) = token
+        && previous.as_deref().map(String::as_str) != token
+    {
+        crate::audit::record_token(&account, if token.is_some() { "stored" } else { "removed" });
+    }
+
+    let index = match index {
+        Some(index) => {
+            config.accounts[index] = account;
+            index
+        }
+        None => {
+            config.accounts.push(account);
+            config.accounts.len() - 1
+        }
+    };
+    save_config(config);
+    Ok(index)
+}
+
+/// Fields `gitas edit --all` can set and filter on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BatchField {
+    Host,
+    Group,
+    Provider,
+}
+
+/// Split `key=value` pairs for --set and --where.
+fn parse_pairs(pairs: &[String]) -> Result<Vec<(BatchField, String)>, String> {
+    pairs
+        .iter()
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("'{}' is not KEY=VALUE", pair))?;
+            let field = match key.trim() {
+                "host" => BatchField::Host,
+                "group" => BatchField::Group,
+                "provider" => BatchField::Provider,
+                other => {
+                    return Err(format!(
+                        "Unknown field '{}' (use host, group or provider)",
+                        other
+                    ));
+                }
+            };
+            Ok((field, value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn parse_provider(value: &str) -> Result<ProviderKind, String> {
+    <ProviderKind as clap::ValueEnum>::from_str(value, true).map_err(|_| {
+        format!(
+            "Unknown provider '{}' (github, gitlab, gitea, forgejo or azure)",
+            value
+        )
+    })
+}
+
+fn matches_filter(account: &Account, field: BatchField, value: &str) -> bool {
+    match field {
+        BatchField::Host => {
+            crate::host::matches(value, account.host.as_deref().unwrap_or("github.com"))
+        }
+        BatchField::Group => account.group.as_deref().unwrap_or("") == value,
+        BatchField::Provider => parse_provider(value)
+            .is_ok_and(|kind| account.provider.unwrap_or(ProviderKind::Github) == kind),
+    }
+}
+
+/// The same change applied to several accounts. Each field is `Some` when it
+/// changes; the inner value is what gets stored.
+#[derive(Debug, Default)]
+pub(crate) struct BatchChange {
+    pub host: Option<Option<String>>,
+    pub provider: Option<Option<ProviderKind>>,
+    pub group: Option<Option<String>>,
+}
+
+impl BatchChange {
+    fn apply(&self, account: &mut Account) {
+        if let Some(host) = &self.host {
+            account.host = host.clone();
+        }
+        if let Some(provider) = self.provider {
+            account.provider = provider.filter(|kind| *kind != ProviderKind::Github);
+        }
+        if let Some(group) = &self.group {
+            account.group = group.clone();
+        }
+    }
+
+    /// What changed between `before` and `after`, for the summary.
+    pub(crate) fn describe(before: &Account, after: &Account) -> String {
+        let show =
+            |value: Option<&str>, default: &'static str| value.unwrap_or(default).to_string();
+        let mut parts = Vec::new();
+        if before.host != after.host {
+            parts.push(format!(
+                "host {} → {}",
+                show(before.host.as_deref(), "github.com"),
+                show(after.host.as_deref(), "github.com")
+            ));
+        }
+        if before.provider != after.provider {
+            let label = |kind: Option<ProviderKind>| kind.unwrap_or(ProviderKind::Github).label();
+            parts.push(format!(
+                "provider {} → {}",
+                label(before.provider),
+                label(after.provider)
+            ));
+        }
+        if before.group != after.group {
+            parts.push(format!(
+                "group {} → {}",
+                show(before.group.as_deref(), "none"),
+                show(after.group.as_deref(), "none")
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Apply `change` to the accounts at `indices`. Every account is checked
+/// before any is saved, so the batch lands whole or not at all. Returns the
+/// changed accounts as they were before.
+pub(crate) fn commit_batch(
+    config: &mut Config,
+    indices: &[usize],
+    change: &BatchChange,
+) -> Result<Vec<(usize, Account)>, Vec<String>> {
+    let mut edits = Vec::new();
+    let mut problems = Vec::new();
+    for &index in indices {
+        let before = config.accounts[index].clone();
+        let mut after = before.clone();
+        change.apply(&mut after);
+        if BatchChange::describe(&before, &after).is_empty() {
+            continue;
+        }
+        problems.extend(
+            check(config, Some(index), &after)
+                .into_iter()
+                .map(|problem| {
+                    format!(
+                        "{}: {}",
+                        crate::utils::format_account_label(&after),
+                        problem
+                    )
+                }),
+        );
+        edits.push((index, before, after));
+    }
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    // Username and alias never change here, so keychain entries stay put.
+    let mut changed = Vec::new();
+    for (index, before, after) in edits {
+        config.accounts[index] = after;
+        changed.push((index, before));
+    }
+    save_config(config);
+    Ok(changed)
+}
+
+/// `gitas edit --all`: set fields on every account matching the filters.
+pub fn batch(config: &mut Config, args: EditArgs, set: Vec<String>, filter: Vec<String>) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+    if args != EditArgs::default() {
+        fail("With --all, change fields with --set KEY=VALUE.".to_string());
+    }
+
+    let set = parse_pairs(&set).unwrap_or_else(|e| fail(e));
+    let filter = parse_pairs(&filter).unwrap_or_else(|e| fail(e));
+
+    let mut change = BatchChange::default();
+    for (field, value) in &set {
+        match field {
+            BatchField::Host => change.host = Some(crate::host::for_account(value)),
+            BatchField::Group => change.group = Some(non_empty(value.clone())),
+            BatchField::Provider => {
+                change.provider = Some(Some(parse_provider(value).unwrap_or_else(|e| fail(e))))
+            }
+        }
+    }
+    if change.provider.is_none()
+        && let Some(host) = &change.host
+    {
+        change.provider = Some(host.as_deref().map(|host| {
+            crate::provider::detect(host).unwrap_or_else(|| {
+                fail(format!(
+                    "Could not detect the forge at {}. Pass --set provider=….",
+                    host
+                ))
+            })
+        }));
+    }
+
+    let indices: Vec<usize> = config
+        .accounts
+        .iter()
+        .enumerate()
+        .filter(|(_, account)| {
+            filter
+                .iter()
+                .all(|(field, value)| matches_filter(account, *field, value))
+        })
+        .map(|(index, _)| index)
+        .collect();
+    if indices.is_empty() {
+        fail("No accounts match.".to_string());
+    }
+
+    match commit_batch(config, &indices, &change) {
+        Ok(changed) if changed.is_empty() => println!(
+            "\n  {}\n",
+            "All matching accounts already have these values.".dimmed()
+        ),
+        Ok(changed) => {
+            println!();
+            for (index, before) in &changed {
+                let after = &config.accounts[*index];
+                println!(
+                    "  {} {} {}",
+                    "✓".green().bold(),
+                    crate::utils::format_account_label(after).cyan(),
+                    BatchChange::describe(before, after).dimmed()
+                );
+            }
+            println!("\n  Updated {} account(s).\n", changed.len());
+        }
+        Err(problems) => {
+            eprintln!();
+            for problem in problems {
+                eprintln!("  {} {}", "✗".red().bold(), problem);
+            }
+            eprintln!("\n  {}\n", "No accounts were changed.".dimmed());
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_and_applies_batch_changes() {
+        let account = Account {
+            username: "octo".to_string(),
+            host: Some("ghe.old.com".to_string()),
+            group: Some("work".to_string()),
+            ..Default::default()
+        };
+        let filter =
+            parse_pairs(&["group=work".to_string(), "host=*.old.com".to_string()]).unwrap();
+        assert!(filter.iter().all(|(f, v)| matches_filter(&account, *f, v)));
+        assert!(!matches_filter(&account, BatchField::Group, ""));
+        assert!(parse_pairs(&["name=x".to_string()]).is_err());
+
+        let change = BatchChange {
+            host: Some(None),
+            provider: Some(Some(ProviderKind::Github)),
+            ..Default::default()
+        };
+        let mut after = account.clone();
+        change.apply(&mut after);
+        assert_eq!(after.host, None);
+        assert_eq!(after.provider, None);
+        assert_eq!(
+            BatchChange::describe(&account, &after),
+            "host ghe.old.com → github.com"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use crate::utils::{IDENTITY_ENV_VARS, identity_env_overrides};
+use colored::Colorize;
+
+pub fn run(clear: bool) {
+    if clear {
+        // Plain output so it can be eval'd.
+        println!("{}", clear_snippet());
+        return;
+    }
+
+    let overrides = identity_env_overrides();
+    if overrides.is_empty() {
+        println!(
+            "\n  {} No git identity variables are set in the environment.\n",
+            "✓".green().bold()
+        );
+        return;
+    }
+
+    println!(
+        "\n  {} These variables override every git config scope, including gitas switches:\n",
+        "⚠".yellow().bold()
+    );
+    for (var, value) in &overrides {
+        println!("    {}={}", var.yellow(), value);
+    }
+    println!(
+        "\n  Unset them in this shell with {}\n  and remove them from your shell profile to make it permanent.\n",
+        clear_hint().cyan()
+    );
+}
+
+pub fn clear_hint() -> &'static str {
+    if is_powershell() {
+        "gitas env --clear | Invoke-Expression"
+    } else if is_fish() {
+        "gitas env --clear | source"
+    } else {
+        "eval \"$(gitas env --clear)\""
+    }
+}
+
+fn clear_snippet() -> String {
+    let lines: Vec<String> = IDENTITY_ENV_VARS
+        .iter()
+        .map(|var| {
+            if is_powershell() {
+                format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", var)
+            } else if is_fish() {
+                format!("set -e {}", var)
+            } else {
+                format!("unset {}", var)
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn is_fish() -> bool {
+    std::env::var("SHELL").is_ok_and(|shell| shell.ends_with("fish"))
+}
+
+fn is_powershell() -> bool {
+    cfg!(windows) && std::env::var_os("PSModulePath").is_some()
+}
@@ -0,0 +1,228 @@
+use crate::models::{Account, Config};
+use crate::resolve::account_or_default;
+use crate::warnings::{self, Warning};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub fn run(config: &mut Config, account_id: Option<String>, pick: bool, args: Vec<String>) {
+    let Some((program, program_args)) = args.split_first() else {
+        eprintln!(
+            "\n  {} No command provided. Usage: {}\n",
+            "✗".red().bold(),
+            "gitas exec <command> [args...]".cyan()
+        );
+        std::process::exit(1);
+    };
+
+    let account = account_or_default(config, account_id, pick, "  Run as");
+    if account.ssh_only
+        && let Err(e) = crate::ssh::check_key(&account)
+    {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+        std::process::exit(1);
+    }
+    if let Err(e) = crate::hardware::check_present(&account) {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+        std::process::exit(1);
+    }
+
+    let token = if account.ssh_only {
+        None
+    } else {
+        crate::token_agent::token(&account).filter(|token| !token.is_empty())
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(program_args);
+    apply_identity_env(&mut cmd, &account, token.as_deref().map(String::as_str));
+
+    // gh keeps its own login state; point it at a throwaway config dir that
+    // only knows about this account so the command runs as the right user.
+    let gh_config_dir = if is_gh(program) {
+        match token.as_deref() {
+            Some(token) => match write_gh_config_dir(&account, token) {
+                Ok(dir) => {
+                    apply_gh_env(&mut cmd, &account, &dir.path);
+                    Some(dir)
+                }
+                Err(e) => {
+                    eprintln!("  {} Failed to prepare gh config: {}", "✗".red().bold(), e);
+                    std::process::exit(1);
+                }
+            },
+            None if account.ssh_only => None,
+            None => {
+                if warnings::enabled(config, Some(&account), Warning::NoToken) {
+                    println!(
+                        "  {} No token found for {}. gh will use its own login.",
+                        "⚠".yellow(),
+                        crate::utils::redact_name(&account.username).cyan()
+                    );
+                }
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    println!(
+        "  {} {} {}",
+        "\u{21b7}".dimmed(),
+        args.join(" "),
+        format!(
+            "as {} <{}>",
+            crate::utils::redact_name(&account.username),
+            crate::utils::redact_email(&account.email)
+        )
+        .dimmed(),
+    );
+    println!();
+
+    let status = cmd.status();
+    // Before any exit below, which would skip the guard.
+    drop(gh_config_dir);
+
+    match status {
+        Ok(status) if !status.success() => std::process::exit(status.code().unwrap_or(1)),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!(
+                "\n  {} Failed to execute '{}': {}\n",
+                "✗".red().bold(),
+                program,
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Identity for git invoked anywhere below the child process, passed through
+/// the environment so it also reaches tools that shell out to git themselves.
+pub(crate) fn apply_identity_env(cmd: &mut Command, account: &Account, token: Option<&str>) {
+    let email = crate::plus::email_for(account, cmd.get_current_dir().unwrap_or(Path::new(".")));
+    cmd.env("GIT_AUTHOR_NAME", &account.username)
+        .env("GIT_AUTHOR_EMAIL", &email)
+        .env("GIT_COMMITTER_NAME", &account.username)
+        .env("GIT_COMMITTER_EMAIL", &email)
+        .envs(crate::dates::env(account));
+
+    if let Some(ssh_key) = &account.ssh_key {
+        cmd.env("GIT_SSH_COMMAND", crate::ssh::run_command(ssh_key));
+    }
+
+    // All entries go in one call: each call numbers from the inherited count.
+    let mut entries = Vec::new();
+    if let Some(token) = token {
+        entries.push(("credential.helper", ""));
+        entries.push(("credential.helper", crate::utils::CREDENTIAL_HELPER));
+        cmd.envs(crate::utils::credential_env(&account.username, token));
+    }
+    if crate::models::is_strict() {
+        entries.extend(crate::utils::STRICT_GIT_CONFIG);
+        crate::utils::apply_strict_env(cmd);
+    }
+    if !entries.is_empty() {
+        crate::utils::append_git_config_env(cmd, &entries);
+    }
+}
+
+fn apply_gh_env(cmd: &mut Command, account: &Account, config_dir: &Path) {
+    cmd.env("GH_HOST", account.host.as_deref().unwrap_or("github.com"))
+        .env("GH_CONFIG_DIR", config_dir)
+        // Tokens in the environment take precedence over hosts.yml in gh.
+        .env_remove("GH_TOKEN")
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_ENTERPRISE_TOKEN")
+        .env_remove("GITHUB_ENTERPRISE_TOKEN");
+}
+
+fn is_gh(program: &str) -> bool {
+    Path::new(program)
+        .file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("gh"))
+}
+
+fn gh_hosts_yml(account: &Account, token: &str) -> String {
+    let host = account.host.as_deref().unwrap_or("github.com");
+    format!(
+        "{host}:\n    user: {user}\n    oauth_token: {token}\n    git_protocol: https\n    users:\n        {user}:\n            oauth_token: {token}\n",
+        user = account.username,
+    )
+}
+
+/// Throwaway gh config directory holding the account's token, removed with
+/// everything in it when dropped.
+struct GhConfigDir {
+    path: PathBuf,
+}
+
+impl Drop for GhConfigDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.path).ok();
+    }
+}
+
+/// A fresh private directory with gh's `hosts.yml` for the account. Its
+/// name is random and creating it fails if it exists, so it can't be one
+/// another user prepared.
+fn write_gh_config_dir(account: &Account, token: &str) -> std::io::Result<GhConfigDir> {
+    use ring::rand::{SecureRandom, SystemRandom};
+    use std::io::Write;
+
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| std::io::Error::other("could not generate a directory name"))?;
+    let name: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let path = std::env::temp_dir().join(format!("gitas-gh-{}", name));
+
+    let mut builder = fs::DirBuilder::new();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+        builder.mode(0o700);
+        options.mode(0o600);
+    }
+    builder.create(&path)?;
+    let dir = GhConfigDir { path };
+
+    let hosts = zeroize::Zeroizing::new(gh_hosts_yml(account, token));
+    options
+        .open(dir.path.join("hosts.yml"))?
+        .write_all(hosts.as_bytes())?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_gh_binary_by_stem() {
+        assert!(is_gh("gh"));
+        assert!(is_gh("/usr/local/bin/gh"));
+        assert!(!is_gh("git"));
+        assert!(!is_gh("ghq"));
+    }
+
+    #[test]
+    fn writes_hosts_yml_for_account_host() {
+        let account = Account {
+            username: "octo".to_string(),
+            email: "octo@example.com".to_string(),
+            host: Some("ghe.corp.com".to_string()),
+            ..Default::default()
+        };
+
+        let yml = gh_hosts_yml(&account, "tok");
+        assert!(yml.starts_with("ghe.corp.com:\n"));
+        assert!(yml.contains("    user: octo\n"));
+        assert!(yml.contains("    oauth_token: tok\n"));
+    }
+}
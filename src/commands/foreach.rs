@@ -0,0 +1,217 @@
+use crate::models::{Account, Config, get_token};
+use crate::utils::{find_account, find_account_by_id, format_account_label};
+use colored::Colorize;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zeroize::Zeroizing;
+
+/// Repositories run at once without `--jobs`; fetches and pulls wait on the
+/// network, not the CPU.
+const DEFAULT_JOBS: usize = 8;
+
+/// A bound repository and the account it runs as.
+struct Job {
+    dir: PathBuf,
+    label: String,
+    account: Account,
+    token: Option<Zeroizing<String>>,
+}
+
+/// `gitas foreach`: run a command in every repository bound with `gitas use`
+/// (limited to a group's accounts, or to `-a`), several at a time, each with
+/// its account's identity and credentials. Output lines are prefixed with
+/// the repository; nothing can prompt, since prompts from parallel jobs
+/// would interleave.
+pub fn run(
+    config: &Config,
+    account_id: Option<String>,
+    group: Option<String>,
+    jobs: Option<usize>,
+    args: Vec<String>,
+) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+    if args.is_empty() {
+        fail(format!(
+            "No command provided. Usage: {}",
+            "gitas foreach [--group <name>] -- <command> [args...]".cyan()
+        ));
+    }
+    let only = account_id.map(|id| {
+        find_account(config, &id)
+            .map(crate::audit::account_id)
+            .unwrap_or_else(|| fail(format!("No account matching '{}'.", id)))
+    });
+    if let Some(group) = &group
+        && !config
+            .accounts
+            .iter()
+            .any(|a| a.group.as_deref() == Some(group.as_str()))
+    {
+        fail(format!("No account is in group '{}'.", group));
+    }
+
+    let mut queue = Vec::new();
+    for (dir, id) in &config.repos {
+        let Some(account) = find_account_by_id(config, id) else {
+            continue;
+        };
+        if only.as_ref().is_some_and(|only| only != id)
+            || group
+                .as_ref()
+                .is_some_and(|group| account.group.as_ref() != Some(group))
+        {
+            continue;
+        }
+        let dir = PathBuf::from(dir);
+        if !dir.is_dir() {
+            println!(
+                "  {} {} no longer exists, skipped",
+                "⚠".yellow(),
+                dir.display()
+            );
+            continue;
+        }
+        let ready = if account.ssh_only {
+            crate::ssh::check_key(account)
+        } else {
+            Ok(())
+        };
+        if let Err(e) = ready.and_then(|()| crate::hardware::check_present(account)) {
+            println!("  {} {}: {}, skipped", "⚠".yellow(), dir.display(), e);
+            continue;
+        }
+        let token = if account.ssh_only {
+            None
+        } else {
+            get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+        };
+        queue.push(Job {
+            label: label(&dir),
+            dir,
+            account: account.clone(),
+            token,
+        });
+    }
+    if queue.is_empty() {
+        println!(
+            "\n  {}\n",
+            "No bound repositories match. Bind one with `gitas use <account>` inside it.".dimmed()
+        );
+        return;
+    }
+
+    let width = queue.iter().map(|job| job.label.len()).max().unwrap_or(0);
+    let jobs = jobs.unwrap_or(DEFAULT_JOBS).clamp(1, queue.len());
+    println!(
+        "  {} {} in {} repositories {}\n",
+        "\u{21b7}".dimmed(),
+        args.join(" "),
+        queue.len(),
+        format!("({} at a time)", jobs).dimmed()
+    );
+
+    let next = AtomicUsize::new(0);
+    let output = Mutex::new(());
+    let failures = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                while let Some(job) = queue.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let prefix = format!("{:width$} │", job.label, width = width);
+                    if let Err(reason) = run_job(job, &args, &prefix, &output) {
+                        failures.lock().unwrap().push((job, reason));
+                    }
+                }
+            });
+        }
+    });
+
+    let failures = failures.into_inner().unwrap();
+    println!();
+    if failures.is_empty() {
+        println!(
+            "  {} Done in {} repositories\n",
+            "✓".green().bold(),
+            queue.len()
+        );
+        return;
+    }
+    for (job, reason) in &failures {
+        println!(
+            "  {} {} {}",
+            "✗".red().bold(),
+            job.dir.display(),
+            format!("as {}: {}", format_account_label(&job.account), reason).dimmed()
+        );
+    }
+    println!();
+    std::process::exit(1);
+}
+
+/// Repository name shown before its output.
+fn label(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+/// Run the command in one repository, copying its output line by line.
+fn run_job(job: &Job, args: &[String], prefix: &str, output: &Mutex<()>) -> Result<(), String> {
+    let mut cmd = Command::new(&args[0]);
+    cmd.args(&args[1..])
+        .current_dir(&job.dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env("GIT_TERMINAL_PROMPT", "0");
+    super::exec::apply_identity_env(
+        &mut cmd,
+        &job.account,
+        job.token.as_deref().map(String::as_str),
+    );
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("could not run {}: {}", args[0], e))?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    std::thread::scope(|scope| {
+        scope.spawn(|| stream(stdout, prefix, false, output));
+        stream(stderr, prefix, true, output);
+    });
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(match status.code() {
+            Some(code) => format!("exit {}", code),
+            None => "killed".to_string(),
+        })
+    }
+}
+
+fn stream(pipe: Option<impl Read>, prefix: &str, is_stderr: bool, output: &Mutex<()>) {
+    let Some(pipe) = pipe else {
+        return;
+    };
+    for line in BufReader::new(pipe).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        // git redraws progress with \r; keep only the final state.
+        let line = line.rsplit('\r').next().unwrap_or_default();
+        let _guard = output.lock().unwrap();
+        if is_stderr {
+            eprintln!("  {} {}", prefix.dimmed(), line);
+        } else {
+            println!("  {} {}", prefix.cyan(), line);
+        }
+    }
+}
@@ -0,0 +1,572 @@
+use crate::models::{Account, Config, get_token, save_config};
+use crate::rules;
+use crate::utils::{git_config_get, redact_id, redact_name};
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where an inconsistency was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Source {
+    Config,
+    Keychain,
+    Gitconfig,
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Source::Config => "accounts.json",
+            Source::Keychain => "keychain",
+            Source::Gitconfig => "git config",
+        }
+    }
+}
+
+/// How a finding is fixed.
+#[derive(Debug, Clone, PartialEq)]
+enum Repair {
+    /// Drop the default, bindings and owner mappings naming a missing account.
+    DropReferences(String),
+    /// Forget a binding whose repository is gone.
+    DropBinding(String),
+    Unpin(String),
+    /// Park a token the way `gitas remove` does, restorable for the grace period.
+    ParkToken(String, Option<String>),
+    /// Remove a managed `includeIf` rule (and the file it includes).
+    RemoveRule(String),
+    DeleteFile(PathBuf),
+    UnsetGlobal(String),
+}
+
+impl Repair {
+    fn describe(&self) -> String {
+        match self {
+            Repair::DropReferences(id) => {
+                format!("forget every reference to '{}'", redact_id(id))
+            }
+            Repair::DropBinding(dir) => format!("forget the binding of {}", dir),
+            Repair::Unpin(host) => format!("unpin {} (the system's certificates apply)", host),
+            Repair::ParkToken(username, alias) => format!(
+                "park the token of {} (gitas token gc --restore brings it back)",
+                redact_id(&account_id(username, alias.as_deref()))
+            ),
+            Repair::RemoveRule(pattern) => format!("remove the rule for {}", pattern),
+            Repair::DeleteFile(path) => format!("delete {}", path.display()),
+            Repair::UnsetGlobal(key) => format!("unset {} in the global git config", key),
+        }
+    }
+
+    fn apply(&self, config: &mut Config) -> Result<(), String> {
+        match self {
+            Repair::DropReferences(id) => config.retarget_account(id, None),
+            Repair::DropBinding(dir) => {
+                config.repos.remove(dir);
+            }
+            Repair::Unpin(host) => {
+                config.pinned_certs.remove(host);
+                std::fs::remove_file(crate::trust::cert_path(host)).ok();
+            }
+            Repair::ParkToken(username, alias) => {
+                let account = Account {
+                    username: username.clone(),
+                    alias: alias.clone(),
+                    ..Default::default()
+                };
+                let days = config
+                    .token_grace_days
+                    .unwrap_or(crate::tombstone::DEFAULT_GRACE_DAYS);
+                if !crate::tombstone::bury(&account, days) && days > 0 {
+                    return Err(format!("Could not park the token of {}", account.username));
+                }
+            }
+            Repair::RemoveRule(pattern) => {
+                rules::remove(pattern)?;
+            }
+            Repair::DeleteFile(path) => std::fs::remove_file(path)
+                .map_err(|e| format!("Could not delete {}: {}", path.display(), e))?,
+            Repair::UnsetGlobal(key) => {
+                let status = Command::new("git")
+                    .args(["config", "--global", "--unset-all", key])
+                    .status()
+                    .map_err(|e| format!("Could not run git: {}", e))?;
+                if !status.success() {
+                    return Err(format!("Could not unset {}", key));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Finding {
+    source: Source,
+    problem: String,
+    /// What to do by hand when there is no repair.
+    hint: Option<String>,
+    repair: Option<Repair>,
+}
+
+fn account_id(username: &str, alias: Option<&str>) -> String {
+    match alias {
+        Some(alias) => format!("{}:{}", username, alias),
+        None => username.to_string(),
+    }
+}
+
+/// Whether `id` names one of `accounts`.
+fn is_account(accounts: &[Account], id: &str) -> bool {
+    accounts
+        .iter()
+        .any(|account| crate::audit::account_id(account) == id)
+}
+
+fn split_id(id: &str) -> (String, Option<String>) {
+    match id.split_once(':') {
+        Some((username, alias)) => (username.to_string(), Some(alias.to_string())),
+        None => (id.to_string(), None),
+    }
+}
+
+/// References in the config to accounts that don't exist, and bindings of
+/// repositories that are gone (`exists` says which directories are there).
+fn check_config(config: &Config, exists: impl Fn(&Path) -> bool) -> Vec<Finding> {
+    let mut uses: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    if let Some(id) = &config.default_account {
+        uses.entry(id).or_default().push("the default".to_string());
+    }
+    for (dir, id) in &config.repos {
+        uses.entry(id)
+            .or_default()
+            .push(format!("bound to {}", dir));
+    }
+    for (owner, id) in &config.remote_owners {
+        uses.entry(id)
+            .or_default()
+            .push(format!("confirmed for {}", owner));
+    }
+    for (owner, id) in &config.declined_owners {
+        uses.entry(id)
+            .or_default()
+            .push(format!("declined for {}", owner));
+    }
+
+    let accounts = config.all_accounts();
+    let mut findings = Vec::new();
+    for (id, uses) in uses {
+        if !is_account(&accounts, id) {
+            findings.push(Finding {
+                source: Source::Config,
+                problem: format!(
+                    "'{}' no longer exists but is {}",
+                    redact_id(id),
+                    uses.join(", ")
+                ),
+                hint: None,
+                repair: Some(Repair::DropReferences(id.to_string())),
+            });
+        }
+    }
+    for dir in config.repos.keys() {
+        if !exists(Path::new(dir)) {
+            findings.push(Finding {
+                source: Source::Config,
+                problem: format!("bound repository {} no longer exists", dir),
+                hint: None,
+                repair: Some(Repair::DropBinding(dir.clone())),
+            });
+        }
+    }
+    for host in config.pinned_certs.keys() {
+        if let Err(e) = crate::trust::roots_for(host) {
+            findings.push(Finding {
+                source: Source::Config,
+                problem: e,
+                hint: Some(format!("gitas cert pin {}", host)),
+                repair: Some(Repair::Unpin(host.clone())),
+            });
+        }
+    }
+    findings
+}
+
+/// Accounts without their token, tokens SSH-only accounts never use, and
+/// tokens left behind by accounts that are gone but still referenced.
+fn check_keychain(config: &Config, orphans: &BTreeSet<String>) -> Vec<Finding> {
+    let accounts = config.all_accounts();
+    let mut findings = Vec::new();
+    for account in &accounts {
+        let id = crate::audit::account_id(account);
+        let token = get_token(&account.username, account.alias.as_deref())
+            .filter(|token| !token.is_empty());
+        match (account.ssh_only, token) {
+            (false, None) => findings.push(Finding {
+                source: Source::Keychain,
+                problem: format!("{} has no token, so git may prompt for one", redact_id(&id)),
+                hint: Some(format!("gitas token set {}", redact_id(&id))),
+                repair: None,
+            }),
+            (true, Some(_)) => findings.push(Finding {
+                source: Source::Keychain,
+                problem: format!("{} is SSH-only but a token is still stored", redact_id(&id)),
+                hint: None,
+                repair: Some(Repair::ParkToken(
+                    account.username.clone(),
+                    account.alias.clone(),
+                )),
+            }),
+            _ => {}
+        }
+    }
+    // Keychain entries don't carry the host, so a username that is an
+    // account elsewhere owns its token.
+    for id in orphans.iter().filter(|id| !is_account(&accounts, id)) {
+        let (username, alias) = split_id(id);
+        if get_token(&username, alias.as_deref()).is_some() {
+            findings.push(Finding {
+                source: Source::Keychain,
+                problem: format!(
+                    "a token is stored for '{}', which is not an account",
+                    redact_id(id)
+                ),
+                hint: None,
+                repair: Some(Repair::ParkToken(username, alias)),
+            });
+        }
+    }
+    findings
+}
+
+/// Records of `git config --global --null --get-regexp <pattern>` as
+/// (key, value).
+fn global_entries(pattern: &str) -> Vec<(String, String)> {
+    let Ok(output) = Command::new("git")
+        .args(["config", "--global", "--null", "--get-regexp", pattern])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter_map(|record| {
+            let (key, value) = record.split_once('\n')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Files in `dir` with `extension` that nothing points at.
+fn stray_files(dir: &Path, extension: &str, used: &BTreeSet<PathBuf>) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .filter(|path| !used.contains(path))
+        .collect()
+}
+
+/// Settings gitas wrote to the global git config and its own include and
+/// certificate files, checked against the accounts and pins. Ids of missing
+/// accounts they name are added to `orphans`.
+fn check_gitconfig(config: &Config, orphans: &mut BTreeSet<String>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let accounts = config.all_accounts();
+    let known = |id: &str| is_account(&accounts, id);
+
+    let all_rules = rules::list();
+    for rule in all_rules.iter().filter(|rule| rules::is_managed(rule)) {
+        let problem = if !Path::new(&rule.path).exists() {
+            Some(format!(
+                "rule {} includes missing {}",
+                rule.pattern, rule.path
+            ))
+        } else {
+            match rules::account_of(&rule.path) {
+                Some(id) if known(&id) => None,
+                Some(id) => {
+                    let problem = format!(
+                        "rule {} applies '{}', which is not an account",
+                        rule.pattern,
+                        redact_id(&id)
+                    );
+                    orphans.insert(id);
+                    Some(problem)
+                }
+                None => Some(format!(
+                    "rule {} includes {}, which names no account",
+                    rule.pattern, rule.path
+                )),
+            }
+        };
+        if let Some(problem) = problem {
+            findings.push(Finding {
+                source: Source::Gitconfig,
+                problem,
+                hint: None,
+                repair: Some(Repair::RemoveRule(rule.pattern.clone())),
+            });
+        }
+    }
+    let included: BTreeSet<PathBuf> = all_rules
+        .iter()
+        .map(|rule| PathBuf::from(&rule.path))
+        .collect();
+    for path in stray_files(&rules::dir(), "gitconfig", &included) {
+        findings.push(Finding {
+            source: Source::Gitconfig,
+            problem: format!("{} is not included by any rule", path.display()),
+            hint: None,
+            repair: Some(Repair::DeleteFile(path)),
+        });
+    }
+
+    let pinned: BTreeSet<PathBuf> = config
+        .pinned_certs
+        .keys()
+        .map(|host| crate::trust::cert_path(host))
+        .collect();
+    let certs_dir = crate::trust::cert_path("x")
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    for path in stray_files(&certs_dir, "pem", &pinned) {
+        findings.push(Finding {
+            source: Source::Config,
+            problem: format!("{} belongs to no pinned host", path.display()),
+            hint: None,
+            repair: Some(Repair::DeleteFile(path)),
+        });
+    }
+    for (key, value) in global_entries(r"^http\..*\.sslcainfo$") {
+        let path = PathBuf::from(&value);
+        if path.starts_with(&certs_dir) && !pinned.contains(&path) {
+            findings.push(Finding {
+                source: Source::Gitconfig,
+                problem: format!("{} points at {}, which is no longer pinned", key, value),
+                hint: None,
+                repair: Some(Repair::UnsetGlobal(key)),
+            });
+        }
+    }
+
+    // Rules and certificates above live next to this config. The global
+    // settings below may belong to the default config instead.
+    if crate::models::is_config_overridden() {
+        return findings;
+    }
+    for (key, username) in global_entries(r"^credential\..*\.username$") {
+        let Some(host) = key
+            .strip_prefix("credential.")
+            .and_then(|rest| rest.strip_suffix(".username"))
+            .map(|url| url.trim_start_matches("https://").trim_end_matches('/'))
+        else {
+            continue;
+        };
+        let on_host =
+            |a: &&Account| crate::host::matches(a.host.as_deref().unwrap_or("github.com"), host);
+        // Usernames on hosts without an account were set by someone else,
+        // and their tokens aren't gitas's to park.
+        let mut managed = accounts.iter().filter(on_host).peekable();
+        if managed.peek().is_none() {
+            continue;
+        }
+        if !managed.any(|a| a.username == username) {
+            findings.push(Finding {
+                source: Source::Gitconfig,
+                problem: format!(
+                    "{} is '{}', which is not an account on {}",
+                    key,
+                    redact_name(&username),
+                    host
+                ),
+                hint: None,
+                repair: Some(Repair::UnsetGlobal(key)),
+            });
+        }
+    }
+
+    if let Some(alias) = git_config_get("gitas.alias", "global") {
+        let name = git_config_get("user.name", "global").unwrap_or_default();
+        let id = account_id(&name, Some(&alias));
+        if !known(&id) {
+            orphans.insert(id.clone());
+            findings.push(Finding {
+                source: Source::Gitconfig,
+                problem: format!(
+                    "the global identity was switched to '{}', which is not an account",
+                    redact_id(&id)
+                ),
+                hint: Some("switch to another account with gitas".to_string()),
+                repair: Some(Repair::UnsetGlobal("gitas.alias".to_string())),
+            });
+        }
+    }
+    findings
+}
+
+/// `gitas fsck`: cross-check accounts.json, the keychain and the git config
+/// gitas writes, and offer to repair what doesn't line up. With `fix` every
+/// repair is applied without asking. Exits 1 while problems remain, so it
+/// can run unattended. With `--config` only what lives next to that config
+/// is checked.
+pub fn run(config: &mut Config, fix: bool) {
+    let mut findings = check_config(config, Path::is_dir);
+    let mut orphans: BTreeSet<String> = findings
+        .iter()
+        .filter_map(|finding| match &finding.repair {
+            Some(Repair::DropReferences(id)) => Some(id.clone()),
+            _ => None,
+        })
+        .collect();
+    let gitconfig = check_gitconfig(config, &mut orphans);
+    let keychain = if crate::models::is_ephemeral() {
+        Vec::new()
+    } else {
+        match crate::models::probe_keychain(&format!("gitas-fsck-{}", std::process::id())) {
+            Ok(()) => check_keychain(config, &orphans),
+            Err(e) => {
+                println!(
+                    "\n  {} {}",
+                    "○".dimmed(),
+                    format!("Keychain unavailable ({}); tokens not checked.", e).dimmed()
+                );
+                Vec::new()
+            }
+        }
+    };
+    findings.extend(keychain);
+    findings.extend(gitconfig);
+    findings.sort_by_key(|finding| finding.source);
+
+    if findings.is_empty() {
+        println!(
+            "\n  {} accounts.json, the keychain and git config agree.\n",
+            "✓".green().bold()
+        );
+        return;
+    }
+
+    println!();
+    for finding in &findings {
+        println!(
+            "  {} {} {}",
+            "✗".red().bold(),
+            format!("{}:", finding.source.label()).dimmed(),
+            finding.problem
+        );
+        if let Some(hint) = &finding.hint {
+            println!("      {}", hint.cyan());
+        }
+    }
+    println!();
+
+    let ask = !fix && !crate::models::is_strict() && std::io::stdin().is_terminal();
+    let repairs: Vec<&Repair> = findings.iter().filter_map(|f| f.repair.as_ref()).collect();
+    if !fix && !ask {
+        if !repairs.is_empty() {
+            println!(
+                "  {} Run {} to repair {} of them.\n",
+                "→".dimmed(),
+                "gitas fsck --fix".cyan(),
+                repairs.len()
+            );
+        }
+        std::process::exit(1);
+    }
+
+    let mut remaining = findings.len() - repairs.len();
+    let mut seen = Vec::new();
+    if ask {
+        crate::tui::enter_raw_mode();
+    }
+    for repair in repairs {
+        // One missing account can be behind several findings.
+        if seen.contains(&repair) {
+            continue;
+        }
+        seen.push(repair);
+        if ask {
+            match crate::tui::raw_confirm(&format!("Fix: {}?", repair.describe()), true) {
+                Some(true) => {}
+                Some(false) => {
+                    remaining += 1;
+                    continue;
+                }
+                None => crate::tui::exit_cancelled(),
+            }
+        }
+        match repair.apply(config) {
+            Ok(()) => {
+                crate::tui::raw_println(&format!("  {} {}", "✓".green().bold(), repair.describe()))
+            }
+            Err(e) => {
+                remaining += 1;
+                crate::tui::raw_println(&format!("  {} {}", "✗".red().bold(), e));
+            }
+        }
+    }
+    if ask {
+        crate::tui::exit_raw_mode();
+    }
+    save_config(config);
+    crate::trust::init(&config.pinned_certs);
+
+    println!();
+    if remaining > 0 {
+        println!("  {} {} problem(s) left.\n", "⚠".yellow(), remaining);
+        std::process::exit(1);
+    }
+    println!("  {} Everything is consistent again.\n", "✓".green().bold());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_references_to_missing_accounts() {
+        let mut config = Config {
+            default_account: Some("gone".to_string()),
+            ..Default::default()
+        };
+        config.accounts.push(Account {
+            username: "octo".to_string(),
+            alias: Some("work".to_string()),
+            ..Default::default()
+        });
+        config
+            .repos
+            .insert("/src/app".to_string(), "gone".to_string());
+        config
+            .repos
+            .insert("/src/lib".to_string(), "octo:work".to_string());
+        config
+            .remote_owners
+            .insert("github.com/acme".to_string(), "octo:work".to_string());
+
+        let findings = check_config(&config, |dir| dir == Path::new("/src/app"));
+        let repairs: Vec<_> = findings.iter().filter_map(|f| f.repair.clone()).collect();
+        assert_eq!(
+            repairs,
+            [
+                Repair::DropReferences("gone".to_string()),
+                Repair::DropBinding("/src/lib".to_string()),
+            ]
+        );
+        assert!(
+            findings[0]
+                .problem
+                .contains("the default, bound to /src/app")
+        );
+
+        assert_eq!(
+            split_id("octo:work"),
+            ("octo".to_string(), Some("work".to_string()))
+        );
+    }
+}
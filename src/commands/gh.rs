@@ -0,0 +1,93 @@
+use crate::models::{Account, Config, get_token, save_config};
+use crate::provider::ProviderKind;
+use crate::utils::{format_account_label, resolve_account};
+use colored::Colorize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// `gitas gh sync`: log gh into the account's host with the account's token,
+/// so gh acts as the same user without its own device flow. With `enable`
+/// or `disable`, turn doing this on every switch on or off instead.
+pub fn sync(config: &mut Config, account_id: Option<String>, enable: bool, disable: bool) {
+    if enable || disable {
+        config.gh_sync = enable;
+        save_config(config);
+        println!(
+            "\n  {} {}\n",
+            "✓".green().bold(),
+            if enable {
+                "Switching now logs gh in as the account too."
+            } else {
+                "Switching no longer touches gh."
+            }
+        );
+        return;
+    }
+
+    let account = resolve_account(config, account_id, "  Log gh in as");
+    let token = get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty());
+    let result = match token {
+        Some(token) => login(&account, &token),
+        None => Err(format!(
+            "{} has no token. Add one with `gitas token set`.",
+            format_account_label(&account)
+        )),
+    };
+    match result {
+        Ok(()) => println!(
+            "\n  {} gh is logged in to {} as {}\n",
+            "✓".green().bold(),
+            account.host.as_deref().unwrap_or("github.com"),
+            format_account_label(&account).cyan()
+        ),
+        Err(e) => {
+            eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Store `token` in gh's own credential store for the account's host with
+/// `gh auth login --with-token`, which also makes it gh's active account.
+pub fn login(account: &Account, token: &str) -> Result<(), String> {
+    if account
+        .provider
+        .is_some_and(|kind| kind != ProviderKind::Github)
+    {
+        return Err(format!(
+            "gh only works with GitHub; {} is not a GitHub host",
+            account.host.as_deref().unwrap_or("github.com")
+        ));
+    }
+    let host = account.host.as_deref().unwrap_or("github.com");
+    let mut child = Command::new("gh")
+        .args(["auth", "login", "--hostname", host, "--with-token"])
+        // gh refuses to store a login while a token in the environment wins.
+        .env_remove("GH_TOKEN")
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_ENTERPRISE_TOKEN")
+        .env_remove("GITHUB_ENTERPRISE_TOKEN")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run gh: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(token.as_bytes())
+            .map_err(|e| format!("Could not pass the token to gh: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("gh did not finish: {}", e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("gh auth login failed");
+    Err(format!("gh rejected the token: {}", reason.trim()))
+}
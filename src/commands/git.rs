@@ -1,9 +1,14 @@
-use crate::models::Config;
-use crate::utils::resolve_account;
+use crate::cred_once::{OneShot, TokenDelivery};
+use crate::models::{Account, Config};
+use crate::permissions::Operation;
+use crate::resolve::account_or_default;
+use crate::warnings::{self, Warning};
 use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::Path;
 use std::process::Command;
 
-pub fn run(config: &Config, account_id: Option<String>, args: Vec<String>) {
+pub fn run(config: &mut Config, account_id: Option<String>, pick: bool, args: Vec<String>) {
     if args.is_empty() {
         eprintln!(
             "\n  {} No git command provided. Usage: {}\n",
@@ -13,53 +18,310 @@ pub fn run(config: &Config, account_id: Option<String>, args: Vec<String>) {
         std::process::exit(1);
     }
 
-    let account = resolve_account(config, account_id, "  Run as");
+    let account = account_or_default(config, account_id, pick, "  Run as");
+    let reset_author = crate::authorship::reset_author(config, &account, &args);
+
+    if account.ssh_only
+        && let Err(e) = crate::ssh::check_key(&account)
+    {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+        std::process::exit(1);
+    }
+    if let Err(e) = crate::hardware::check_present(&account) {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+        std::process::exit(1);
+    }
+
+    for problem in crate::policy::violations(config, &account) {
+        println!("  {} {}", "⚠".yellow(), problem);
+    }
 
     // Build: git -c user.name=X -c user.email=Y <args...>
     let mut cmd = Command::new("git");
     cmd.arg("-c").arg(format!("user.name={}", account.username));
-    cmd.arg("-c").arg(format!("user.email={}", account.email));
+    cmd.arg("-c").arg(format!(
+        "user.email={}",
+        crate::plus::email_for(&account, Path::new("."))
+    ));
 
     if let Some(ssh_key) = &account.ssh_key {
         cmd.arg("-c").arg(format!(
             "core.sshCommand={}",
-            crate::utils::git_ssh_command(ssh_key)
+            crate::ssh::run_command(ssh_key)
         ));
+        if crate::utils::git_args_use_ssh_transport(&args) {
+            offer_agent(&account, ssh_key);
+        }
+    }
+    for (key, value) in crate::signing::settings(&account) {
+        cmd.arg("-c").arg(format!("{}={}", key, value));
+    }
+    if crate::models::is_strict() {
+        for (key, value) in crate::utils::STRICT_GIT_CONFIG {
+            cmd.arg("-c").arg(format!("{}={}", key, value));
+        }
+        crate::utils::apply_strict_env(&mut cmd);
     }
 
     // Inject inline credential helper if token is available
-    match crate::models::get_token(&account.username, account.alias.as_deref()) {
+    let token = if account.ssh_only {
+        None
+    } else {
+        crate::token_agent::token(&account)
+    };
+    // Kept until git exits: dropping it removes the socket.
+    let mut one_shot = None;
+    match &token {
         Some(token) if !token.is_empty() => {
             cmd.arg("-c").arg("credential.helper=");
-            cmd.arg("-c").arg(format!(
-                "credential.helper=!f() {{ echo \"username={}\"; echo \"password={}\"; }}; f",
-                account.username, token
-            ));
+            if config.token_delivery == Some(TokenDelivery::Once) {
+                match one_shot_helper(&account.username, token) {
+                    Ok((shot, helper)) => {
+                        cmd.arg("-c").arg(format!("credential.helper={}", helper));
+                        one_shot = Some(shot);
+                    }
+                    Err(e) => println!(
+                        "  {} {}; passing the token in the environment.",
+                        "⚠".yellow(),
+                        e
+                    ),
+                }
+            }
+            if one_shot.is_none() {
+                cmd.arg("-c").arg(format!(
+                    "credential.helper={}",
+                    crate::utils::CREDENTIAL_HELPER
+                ));
+                cmd.envs(crate::utils::credential_env(&account.username, token));
+            }
         }
+        _ if account.ssh_only => {}
         _ => {
-            if account.ssh_key.is_none() || crate::utils::git_args_use_http_transport(&args) {
+            if (account.ssh_key.is_none() || crate::utils::git_args_use_http_transport(&args))
+                && !crate::models::is_strict()
+                && warnings::enabled(config, Some(&account), Warning::NoToken)
+            {
                 println!(
                     "  {} No token found for {}. Git may prompt for authentication.",
                     "⚠".yellow(),
-                    account.username.cyan()
+                    crate::utils::redact_name(&account.username).cyan()
                 );
             }
         }
     }
 
+    let mut args = args;
+    if reset_author && let Some(index) = crate::utils::git_subcommand_index(&args) {
+        args.insert(index + 1, "--reset-author".to_string());
+    }
+    if let Some((index, flag)) = crate::dates::reset_flag(&account, &args) {
+        args.insert(index, flag.to_string());
+    }
     cmd.args(&args);
+    cmd.envs(crate::dates::env(&account));
+
+    // These would silently win over the -c identity above.
+    let overrides = crate::utils::identity_env_overrides();
+    if !overrides.is_empty() {
+        let names: Vec<&str> = overrides.iter().map(|(var, _)| *var).collect();
+        if warnings::enabled(config, Some(&account), Warning::EnvOverride) {
+            println!(
+                "  {} Ignoring {} from the environment for this command.",
+                "⚠".yellow(),
+                names.join(", ")
+            );
+        }
+        for var in names {
+            cmd.env_remove(var);
+        }
+    }
+
+    if let Some(mode) = config.push_check
+        && let Some(index) = crate::utils::git_subcommand_index(&args)
+        && args[index] == "push"
+    {
+        let (revs, exclude) = crate::push_check::push_range(&args[index + 1..]);
+        if !crate::push_check::check(&account, &revs, &exclude, mode) {
+            std::process::exit(1);
+        }
+    }
 
     println!(
         "  {} git {} {}",
         "\u{21b7}".dimmed(),
         args.join(" "),
-        format!("as {} <{}>", account.username, account.email).dimmed(),
+        format!(
+            "as {} <{}>",
+            crate::utils::redact_name(&account.username),
+            crate::utils::redact_email(&account.email)
+        )
+        .dimmed(),
     );
     println!();
 
     let status = cmd.status().expect("Failed to execute git");
+    drop(one_shot);
 
     if !status.success() {
+        if let Some(token) = token.as_deref().filter(|t| !t.is_empty())
+            && let Some(hint) = permission_hint(&account, token, &args)
+        {
+            eprintln!("\n  {} {}\n", "⚠".yellow(), hint);
+        }
+        if token.as_deref().is_none_or(|t| t.is_empty())
+            && !account.ssh_only
+            && crate::utils::git_args_use_http_transport(&args)
+        {
+            strict_failure(&account);
+        }
         std::process::exit(status.code().unwrap_or(1));
     }
+
+    if let Some(index) = crate::utils::git_subcommand_index(&args)
+        && args[index] == "push"
+    {
+        record_push(&account, &args[index + 1..]);
+    }
+}
+
+/// A key with a passphrase that ssh-agent doesn't hold makes ssh stop to ask
+/// for it mid-push, or fail with "Permission denied (publickey)" where it
+/// can't ask. Offer to load it first, or say why the push is about to fail.
+fn offer_agent(account: &Account, key: &str) {
+    if !crate::agent::needs_passphrase(key) || crate::agent::is_loaded(key) {
+        return;
+    }
+    let agent = crate::agent::detect() == Some(crate::agent::Agent::OpenSsh);
+    let interactive = !crate::models::is_strict() && std::io::stdin().is_terminal();
+    if !interactive {
+        eprintln!(
+            "  {} {} needs its passphrase and ssh can't ask for it here. {} with {}.",
+            "⚠".yellow(),
+            key.cyan(),
+            if agent {
+                "Load it first"
+            } else {
+                "Start ssh-agent and load it"
+            },
+            format!("ssh-add {}", key).cyan()
+        );
+        return;
+    }
+    // Without an agent ssh asks for the passphrase itself.
+    if !agent {
+        return;
+    }
+    let prompt = format!("{} is not in ssh-agent. Load it now?", key.cyan());
+    crate::tui::enter_raw_mode();
+    let answer = crate::tui::raw_confirm(&prompt, true);
+    crate::tui::exit_raw_mode();
+    match answer {
+        Some(true) => match crate::agent::add(account, key) {
+            Ok(()) => println!("  {} Added {} to ssh-agent", "✓".green().bold(), key),
+            Err(e) => println!("  {} {}", "⚠".yellow(), e),
+        },
+        Some(false) => {}
+        None => crate::tui::exit_cancelled(),
+    }
+}
+
+/// Serve the token on a single-use socket, with the helper that reads it.
+fn one_shot_helper(username: &str, token: &str) -> Result<(OneShot, String), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Cannot locate the gitas executable: {}", e))?;
+    let shot = OneShot::serve(username, token)?;
+    let helper = shot.helper(&exe.to_string_lossy());
+    Ok((shot, helper))
+}
+
+/// In strict mode git could not ask for the credentials it was missing, so
+/// say which account lacks a token and how to store one.
+pub(crate) fn strict_failure(account: &crate::models::Account) {
+    if crate::models::is_strict() {
+        let id = crate::audit::account_id(account);
+        eprintln!(
+            "\n  {} {} has no token and strict mode keeps git from prompting. Store one with {}.\n",
+            "✗".red().bold(),
+            id.cyan(),
+            format!("gitas token set -a {}", id).cyan()
+        );
+    }
+}
+
+/// After a failed push or fetch over HTTPS, ask the forge whether the token
+/// could reach the repository and name the permission it lacks.
+fn permission_hint(
+    account: &crate::models::Account,
+    token: &str,
+    args: &[String],
+) -> Option<String> {
+    let index = crate::utils::git_subcommand_index(args)?;
+    let rest = &args[index + 1..];
+    let (op, remote) = match args[index].as_str() {
+        "push" => (Operation::Write, crate::push_check::push_target(rest).0),
+        "fetch" | "pull" | "ls-remote" => (
+            Operation::Read,
+            rest.iter()
+                .find(|arg| !arg.starts_with('-'))
+                .cloned()
+                .or_else(|| {
+                    Some(crate::utils::default_remote_at(std::path::Path::new("."))?.name)
+                })?,
+        ),
+        _ => return None,
+    };
+    let url = if crate::utils::is_http_url(&remote) {
+        remote
+    } else {
+        crate::utils::get_remotes()
+            .into_iter()
+            .find(|r| r.name == remote)?
+            .url
+    };
+    permission_hint_for_url(account, token, &url, op)
+}
+
+/// Diagnose access to `url` when it's an HTTPS remote on the account's host,
+/// the only case where gitas supplied the token.
+pub(crate) fn permission_hint_for_url(
+    account: &crate::models::Account,
+    token: &str,
+    url: &str,
+    op: Operation,
+) -> Option<String> {
+    if !crate::utils::is_http_url(url) {
+        return None;
+    }
+    let (host, repo) = crate::utils::parse_remote_url(url)?;
+    if !crate::host::matches(account.host.as_deref().unwrap_or("github.com"), &host) {
+        return None;
+    }
+    let provider = crate::provider::for_account(account);
+    crate::permissions::diagnose_git(provider.as_ref(), token, &repo, op)
+}
+
+/// Confirm who pushed what, and keep it in the history log.
+fn record_push(account: &crate::models::Account, push_args: &[String]) {
+    let (remote, refspecs) = crate::push_check::push_target(push_args);
+
+    println!();
+    println!(
+        "  {} Pushed {} to {} as {} <{}>",
+        "✓".green().bold(),
+        if refspecs.is_empty() {
+            "(default refs)".to_string()
+        } else {
+            refspecs.join(", ")
+        }
+        .cyan(),
+        remote.cyan(),
+        crate::utils::redact_name(&account.username).cyan(),
+        crate::utils::redact_email(&account.email)
+    );
+
+    crate::audit::record(&crate::audit::Entry {
+        remote: Some(remote),
+        refspecs,
+        ..crate::audit::Entry::new(crate::audit::Event::Push, account)
+    });
 }
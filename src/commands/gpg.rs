@@ -0,0 +1,63 @@
+use crate::models::{Config, get_token};
+use crate::provider::ProviderKind;
+use crate::utils::{format_account_label, resolve_account};
+use colored::Colorize;
+use std::process::Command;
+
+/// `gitas gpg upload`: export the account's GPG public key and register it
+/// on GitHub, so the commits it signs show as Verified.
+pub fn upload(config: &Config, account_id: Option<String>) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+
+    let account = resolve_account(config, account_id, "  Upload GPG key of");
+    let label = format_account_label(&account);
+    if account
+        .provider
+        .is_some_and(|kind| kind != ProviderKind::Github)
+    {
+        fail(format!(
+            "GPG key upload is only supported on GitHub, not {}.",
+            account.host.as_deref().unwrap_or("github.com")
+        ));
+    }
+    let key_id = crate::signing::openpgp_key(&account).unwrap_or_else(|| {
+        fail(format!(
+            "{} doesn't sign with a GPG key. Set one with `gitas edit --gpg-key`.",
+            label
+        ))
+    });
+    let key_id = key_id.as_str();
+
+    let armored = export_public_key(key_id).unwrap_or_else(|e| fail(e));
+    let token = get_token(&account.username, account.alias.as_deref())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| fail("No token stored to upload the key with.".to_string()));
+    let host = account.host.as_deref().unwrap_or("github.com");
+    let name = format!("gitas ({})", label);
+    match crate::github::upload_gpg_key(host, &token, &name, &armored) {
+        Ok(()) => println!(
+            "\n  {} Added GPG key {} to {} as \"{}\"\n",
+            "✓".green().bold(),
+            key_id.cyan(),
+            host,
+            name
+        ),
+        Err(e) => fail(format!("Could not upload the key: {}", e)),
+    }
+}
+
+/// ASCII-armored public key for `key_id` from the local keyring.
+fn export_public_key(key_id: &str) -> Result<String, String> {
+    let output = Command::new("gpg")
+        .args(["--batch", "--armor", "--export", key_id])
+        .output()
+        .map_err(|e| format!("Could not run gpg: {}", e))?;
+    let armored = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || armored.is_empty() {
+        return Err(format!("GPG key {} is not in your keyring", key_id));
+    }
+    Ok(armored)
+}
@@ -0,0 +1,90 @@
+use crate::models::{Config, save_config};
+use crate::policy::{self, GroupPolicy};
+use crate::utils::format_account_label;
+use colored::Colorize;
+
+pub fn set(config: &mut Config, name: String, email_domain: Option<String>, host: Option<String>) {
+    let policy = config.groups.entry(name.clone()).or_default();
+    if email_domain.is_some() {
+        policy.email_domain = email_domain.filter(|d| !d.is_empty());
+    }
+    if host.is_some() {
+        policy.host = host
+            .map(|h| crate::host::normalize(&h))
+            .filter(|h| !h.is_empty());
+    }
+    save_config(config);
+    println!("\n  {} Group '{}' saved.", "✓".green().bold(), name.cyan());
+    print_members(config, &name);
+    println!();
+}
+
+pub fn remove(config: &mut Config, name: String) {
+    if config.groups.remove(&name).is_none() {
+        eprintln!(
+            "\n  {} No group named '{}'.\n",
+            "✗".red().bold(),
+            name.yellow()
+        );
+        std::process::exit(1);
+    }
+    save_config(config);
+    println!(
+        "\n  {} Group '{}' policy removed. Accounts keep their group label.\n",
+        "✓".green().bold(),
+        name.cyan()
+    );
+}
+
+pub fn list(config: &Config) {
+    if config.groups.is_empty() {
+        println!("\n  {}\n", "No groups defined.".dimmed());
+        println!(
+            "  Run {} to add one.\n",
+            "gitas group set <name> --email-domain <domain>"
+                .cyan()
+                .bold()
+        );
+        return;
+    }
+
+    println!();
+    for (name, policy) in &config.groups {
+        println!("  {}  {}", name.bold(), describe(policy).dimmed());
+        print_members(config, name);
+        println!();
+    }
+}
+
+fn describe(policy: &GroupPolicy) -> String {
+    let mut parts = Vec::new();
+    if let Some(domain) = &policy.email_domain {
+        parts.push(format!("email @{}", domain));
+    }
+    if let Some(host) = &policy.host {
+        parts.push(format!("host {}", host));
+    }
+    if parts.is_empty() {
+        "no restrictions".to_string()
+    } else {
+        parts.join(" · ")
+    }
+}
+
+fn print_members(config: &Config, group: &str) {
+    for account in config
+        .accounts
+        .iter()
+        .filter(|a| a.group.as_deref() == Some(group))
+    {
+        let problems = policy::violations(config, account);
+        if problems.is_empty() {
+            println!("    {} {}", "✔".green(), format_account_label(account));
+        } else {
+            println!("    {} {}", "✗".red(), format_account_label(account));
+            for problem in problems {
+                println!("      {}", problem.yellow());
+            }
+        }
+    }
+}
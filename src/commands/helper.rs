@@ -0,0 +1,276 @@
+use colored::Colorize;
+use std::process::Command;
+
+/// Set while gitas' helper is installed in a scope, so uninstalling knows
+/// to restore what was there before.
+const INSTALLED_KEY: &str = "gitas.helperInstalled";
+/// The scope's `credential.helper` values from before the install, in order.
+const SAVED_KEY: &str = "gitas.savedHelper";
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+fn scope_name(local: bool) -> &'static str {
+    if local { "local" } else { "global" }
+}
+
+/// Run `git config --<scope> <args>`.
+fn git_config(scope: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("config")
+        .arg(format!("--{}", scope))
+        .args(args)
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+    // 1 is a key --get-all didn't find, 5 one --unset-all didn't: both
+    // mean there was nothing there.
+    match output.status.code() {
+        Some(0 | 1 | 5) => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    }
+}
+
+fn get_all(scope: &str, key: &str) -> Result<Vec<String>, String> {
+    Ok(git_config(scope, &["--get-all", key])?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Replace every value of `key` in `scope` with `values`, in order.
+fn set_all(scope: &str, key: &str, values: &[String]) -> Result<(), String> {
+    git_config(scope, &["--unset-all", key])?;
+    for value in values {
+        git_config(scope, &["--add", key, value])?;
+    }
+    Ok(())
+}
+
+/// `credential.helper` values in effect, by scope, in the order git asks
+/// them. Scopes after `scope` are left out.
+fn helpers_through(scope: &str) -> Result<Vec<(String, String)>, String> {
+    let output = Command::new("git")
+        .args(["config", "--show-scope", "--get-all", "credential.helper"])
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+    let order = ["system", "global", "local"];
+    let limit = order.iter().position(|s| *s == scope).unwrap_or(0);
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(from, _)| {
+            order
+                .iter()
+                .position(|s| s == from)
+                .is_some_and(|i| i <= limit)
+        })
+        .map(|(from, value)| (from.to_string(), value.to_string()))
+        .collect())
+}
+
+/// Helpers git ends up asking: an empty value clears the ones before it.
+fn effective<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut helpers = Vec::new();
+    for value in values {
+        if value.is_empty() {
+            helpers.clear();
+        } else {
+            helpers.push(value);
+        }
+    }
+    helpers
+}
+
+/// Whether a `credential.helper` value runs `gitas credential`.
+fn is_gitas_helper(value: &str) -> bool {
+    value.starts_with('!') && value.contains("gitas") && value.trim_end().ends_with(" credential")
+}
+
+/// What `scope` should hold so gitas answers first and every helper in
+/// effect before still runs after it. Helpers from a scope git reads
+/// earlier can only follow gitas if they're cleared and listed again here.
+fn plan(entries: &[(String, String)], scope: &str, gitas: &str) -> Vec<String> {
+    let earlier = effective(
+        entries
+            .iter()
+            .filter(|(from, _)| from != scope)
+            .map(|(_, value)| value.as_str()),
+    );
+    let keep = |values: Vec<&str>| {
+        values
+            .into_iter()
+            .filter(|value| !is_gitas_helper(value))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    if earlier.iter().all(|value| is_gitas_helper(value)) {
+        let own = entries
+            .iter()
+            .filter(|(from, _)| from == scope)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !is_gitas_helper(value))
+            .collect();
+        let mut values = vec![gitas.to_string()];
+        values.extend(keep(own));
+        return values;
+    }
+    let mut values = vec![String::new(), gitas.to_string()];
+    values.extend(keep(effective(
+        entries.iter().map(|(_, value)| value.as_str()),
+    )));
+    values
+}
+
+/// Put back the values saved by the install in `scope`. False when gitas
+/// didn't install there.
+fn restore(scope: &str) -> Result<bool, String> {
+    if git_config(scope, &["--get", INSTALLED_KEY])?.trim() != "true" {
+        return Ok(false);
+    }
+    let saved = get_all(scope, SAVED_KEY)?;
+    set_all(scope, "credential.helper", &saved)?;
+    git_config(scope, &["--unset-all", SAVED_KEY])?;
+    git_config(scope, &["--unset-all", INSTALLED_KEY])?;
+    Ok(true)
+}
+
+/// URL-specific helpers, which git asks alongside the general ones.
+fn url_helpers() -> Vec<String> {
+    let output = Command::new("git")
+        .args(["config", "--get-regexp", r"^credential\..+\.helper$"])
+        .output();
+    output
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `gitas install-helper`: make `gitas credential` the first credential
+/// helper git asks, in the global config or the repository's. The helpers
+/// that were in effect keep answering after it, for hosts gitas has no
+/// account on, and `uninstall-helper` puts the old values back.
+pub fn install(local: bool) {
+    let scope = scope_name(local);
+    let exe = std::env::current_exe()
+        .unwrap_or_else(|e| fail(format!("Cannot locate the gitas executable: {}", e)));
+    let gitas = format!(
+        "!{} credential",
+        crate::utils::shell_quote(&exe.to_string_lossy().replace('\\', "/"))
+    );
+
+    // Installing again (say, after gitas moved) starts from the original.
+    restore(scope).unwrap_or_else(|e| fail(e));
+    let saved = get_all(scope, "credential.helper").unwrap_or_else(|e| fail(e));
+    let entries = helpers_through(scope).unwrap_or_else(|e| fail(e));
+    let values = plan(&entries, scope, &gitas);
+
+    let result = set_all(scope, SAVED_KEY, &saved)
+        .and_then(|()| git_config(scope, &[INSTALLED_KEY, "true"]).map(|_| ()))
+        .and_then(|()| set_all(scope, "credential.helper", &values));
+    if let Err(e) = result {
+        restore(scope).ok();
+        fail(format!("Could not update the {} git config: {}", scope, e));
+    }
+
+    println!(
+        "\n  {} git asks gitas for credentials first ({} config)",
+        "✓".green().bold(),
+        scope
+    );
+    let after = effective(values.iter().map(String::as_str))
+        .into_iter()
+        .filter(|value| !is_gitas_helper(value))
+        .collect::<Vec<_>>();
+    if !after.is_empty() {
+        println!(
+            "  {}",
+            format!("Then, as before: {}", after.join(", ")).dimmed()
+        );
+    }
+    for line in url_helpers() {
+        println!(
+            "  {} {} also answers for its URL and may be asked before gitas",
+            "⚠".yellow(),
+            line
+        );
+    }
+    println!("  {}\n", "Undo with `gitas uninstall-helper`.".dimmed());
+}
+
+/// `gitas uninstall-helper`: restore the credential helpers from before
+/// `install-helper`, or drop a `gitas credential` entry added by hand.
+pub fn uninstall(local: bool) {
+    let scope = scope_name(local);
+    let restored = restore(scope).unwrap_or_else(|e| fail(e));
+    if !restored {
+        let current = get_all(scope, "credential.helper").unwrap_or_else(|e| fail(e));
+        if !current.iter().any(|value| is_gitas_helper(value)) {
+            println!(
+                "\n  {}\n",
+                format!("gitas is not a credential helper in the {} config.", scope).dimmed()
+            );
+            return;
+        }
+        let rest: Vec<String> = current
+            .into_iter()
+            .filter(|value| !is_gitas_helper(value))
+            .collect();
+        set_all(scope, "credential.helper", &rest).unwrap_or_else(|e| fail(e));
+    }
+
+    let now = get_all(scope, "credential.helper").unwrap_or_default();
+    println!(
+        "\n  {} Removed gitas from credential.helper ({} config)",
+        "✓".green().bold(),
+        scope
+    );
+    println!(
+        "  {}\n",
+        if now.is_empty() {
+            "No credential helper is set there now.".to_string()
+        } else {
+            format!("credential.helper is back to: {}", now.join(", "))
+        }
+        .dimmed()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter()
+            .map(|(scope, value)| (scope.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn puts_gitas_first_and_keeps_the_rest() {
+        let gitas = "!\"/usr/bin/gitas\" credential";
+        assert!(is_gitas_helper(gitas));
+        assert!(!is_gitas_helper("osxkeychain"));
+
+        // Only this scope has helpers: gitas goes in front of them.
+        let own = entries(&[("global", "osxkeychain")]);
+        assert_eq!(plan(&own, "global", gitas), [gitas, "osxkeychain"]);
+
+        // A system helper would be asked first, so it's cleared and re-listed.
+        let system = entries(&[("system", "manager"), ("global", "store")]);
+        assert_eq!(
+            plan(&system, "global", gitas),
+            ["", gitas, "manager", "store"]
+        );
+
+        // Helpers already cleared by a reset stay cleared.
+        let reset = entries(&[("system", "manager"), ("global", ""), ("global", "cache")]);
+        assert_eq!(plan(&reset, "global", gitas), ["", gitas, "cache"]);
+        assert_eq!(plan(&[], "local", gitas), [gitas]);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::audit::{self, Event};
+use crate::models::Config;
+use crate::utils::{find_account, redact_email, redact_id};
+use colored::Colorize;
+
+pub fn run(config: &Config, account_id: Option<String>, pushes: bool, limit: usize) {
+    let account = account_id.map(|id| match find_account(config, &id) {
+        Some(account) => audit::account_id(account),
+        // Accounts may have been removed since; match the raw id.
+        None => id,
+    });
+
+    let entries: Vec<_> = audit::entries()
+        .into_iter()
+        .rev()
+        .filter(|e| !pushes || e.event == Event::Push)
+        .filter(|e| account.as_ref().is_none_or(|a| &e.account == a))
+        .take(limit)
+        .collect();
+
+    if entries.is_empty() {
+        println!("\n  {}\n", "No history recorded yet.".dimmed());
+        return;
+    }
+
+    println!();
+    for entry in &entries {
+        let time = audit::format_time(entry.time);
+        match entry.event {
+            Event::Push => {
+                println!(
+                    "  {}  {}  {} <{}>",
+                    time.dimmed(),
+                    "push".bold(),
+                    redact_id(&entry.account).cyan(),
+                    redact_email(&entry.email)
+                );
+                println!(
+                    "    {} {} {}",
+                    entry.refspecs.join(", "),
+                    "→".dimmed(),
+                    entry.remote.as_deref().unwrap_or("?")
+                );
+                if let Some(repo) = &entry.repo {
+                    println!("    {}", repo.dimmed());
+                }
+            }
+            Event::Switch => {
+                println!(
+                    "  {}  {}  {} <{}> {}",
+                    time.dimmed(),
+                    "switch".bold(),
+                    redact_id(&entry.account).cyan(),
+                    redact_email(&entry.email),
+                    entry.scope.as_deref().unwrap_or("").dimmed()
+                );
+                if let Some(repo) = entry
+                    .repo
+                    .as_ref()
+                    .filter(|_| entry.scope.as_deref() == Some("local"))
+                {
+                    println!("    {}", repo.dimmed());
+                }
+            }
+            Event::Guard => {
+                println!(
+                    "  {}  {}  {} <{}>",
+                    time.dimmed(),
+                    "guard".yellow().bold(),
+                    redact_id(&entry.account).cyan(),
+                    redact_email(&entry.email)
+                );
+                if let Some(detail) = &entry.detail {
+                    println!("    {}", detail);
+                }
+                if let Some(repo) = &entry.repo {
+                    println!("    {}", repo.dimmed());
+                }
+            }
+            Event::Token => {
+                println!(
+                    "  {}  {}  {} {}",
+                    time.dimmed(),
+                    "token".bold(),
+                    redact_id(&entry.account).cyan(),
+                    entry.detail.as_deref().unwrap_or("").dimmed()
+                );
+            }
+        }
+    }
+    println!();
+}
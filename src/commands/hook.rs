@@ -0,0 +1,151 @@
+use crate::confirm::{self, Action, Decision};
+use crate::hooks::{self, PrePush};
+use crate::models::Config;
+use crate::push_check::{self, PushCheckMode};
+use crate::resolve::account_for_push;
+use crate::utils::format_account_label;
+use colored::Colorize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+fn repo_dir(path: Option<String>) -> PathBuf {
+    PathBuf::from(path.unwrap_or_else(|| ".".to_string()))
+}
+
+/// `gitas hook install`: add the pre-push check to a repository, chaining
+/// into the hook or hook manager already there.
+pub fn install(config: &Config, path: Option<String>) {
+    let exe = std::env::current_exe()
+        .unwrap_or_else(|e| fail(format!("Cannot locate the gitas executable: {}", e)));
+    let dir = repo_dir(path);
+    match confirm::decide(config, Action::HookInstall) {
+        Decision::Proceed => {}
+        Decision::Ask => {
+            let prompt = format!(
+                "Install the pre-push check in {}?",
+                dir.canonicalize()
+                    .unwrap_or_else(|_| dir.clone())
+                    .display()
+                    .to_string()
+                    .cyan()
+            );
+            crate::tui::enter_raw_mode();
+            let confirmed = crate::tui::raw_confirm(&prompt, true);
+            crate::tui::exit_raw_mode();
+            if confirmed != Some(true) {
+                println!("\n  {}\n", "Cancelled.".dimmed());
+                return;
+            }
+        }
+        Decision::Refuse => fail(format!(
+            "Refusing to install the hook without a terminal to confirm. Run {} to allow it.",
+            "gitas confirm set hook-install tty-only".cyan()
+        )),
+    }
+    match hooks::install_pre_push(&dir, &exe.to_string_lossy()) {
+        Ok(installed) => {
+            let path = installed.path().display().to_string();
+            match &installed {
+                PrePush::Hook(_) => {
+                    println!("\n  {} Installed {}", "✓".green().bold(), path.cyan())
+                }
+                PrePush::Chained { original, .. } => {
+                    println!("\n  {} Installed {}", "✓".green().bold(), path.cyan());
+                    println!(
+                        "  {}",
+                        format!(
+                            "The hook that was there runs after the check, from {}.",
+                            original.display()
+                        )
+                        .dimmed()
+                    );
+                }
+                PrePush::Manager { note, .. } => {
+                    println!(
+                        "\n  {} Added the check to {}",
+                        "✓".green().bold(),
+                        path.cyan()
+                    );
+                    if let Some(note) = note {
+                        println!("  {} {}", "⚠".yellow(), note);
+                    }
+                }
+            }
+            println!(
+                "  {}\n",
+                format!(
+                    "Pushes now stop on commits by other accounts' emails. Set {}=1 to push anyway.",
+                    push_check::SKIP_ENV
+                )
+                .dimmed()
+            );
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// `gitas hook uninstall`: remove the pre-push check and restore what it
+/// chained into.
+pub fn uninstall(path: Option<String>) {
+    match hooks::uninstall_pre_push(&repo_dir(path)) {
+        Ok(Some(PrePush::Hook(hook))) => println!(
+            "\n  {} Removed {}\n",
+            "✓".green().bold(),
+            hook.display().to_string().cyan()
+        ),
+        Ok(Some(PrePush::Chained { hook, .. })) => println!(
+            "\n  {} Put the original {} back\n",
+            "✓".green().bold(),
+            hook.display().to_string().cyan()
+        ),
+        Ok(Some(PrePush::Manager { file, .. })) => println!(
+            "\n  {} Removed the check from {}\n",
+            "✓".green().bold(),
+            file.display().to_string().cyan()
+        ),
+        Ok(None) => println!("\n  {}\n", "No gitas pre-push hook installed.".dimmed()),
+        Err(e) => fail(e),
+    }
+}
+
+/// `gitas hook pre-push`, run by git with the remote's name and URL and the
+/// ref updates on stdin. Blocks the push when an outgoing commit's author or
+/// committer email doesn't belong to the repository's account. Never prompts:
+/// stdin belongs to git.
+pub fn pre_push(config: &Config, remote: &str, url: &str) {
+    let mut input = String::new();
+    if std::io::stdin().read_to_string(&mut input).is_err() {
+        return;
+    }
+    let updates = hooks::parse_updates(&input);
+    let dir = Path::new(".");
+    let (revs, exclude) = hooks::push_range(&updates, remote, |sha| hooks::has_commit(dir, sha));
+    if revs.is_empty() {
+        return;
+    }
+
+    let Some(account) = account_for_push(config, dir, url) else {
+        eprintln!(
+            "  {} {}",
+            "⚠".yellow(),
+            format!(
+                "gitas: no account for this repository, so the push to {} is not checked.",
+                remote
+            )
+            .dimmed()
+        );
+        return;
+    };
+    if !push_check::check(account, &revs, &exclude, PushCheckMode::Block) {
+        eprintln!(
+            "  {}\n",
+            format!("Checked against {}.", format_account_label(account)).dimmed()
+        );
+        std::process::exit(1);
+    }
+}
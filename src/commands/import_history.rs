@@ -0,0 +1,439 @@
+use crate::models::{Account, Config};
+use crate::tui::{enter_raw_mode, exit_raw_mode, raw_multi_select};
+use crate::utils::{format_account_label, git_config_get};
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Identities beyond this many are counted but not offered.
+const MAX_OFFERED: usize = 30;
+
+/// One name/email pair as it appears in commit authors.
+#[derive(Debug, Clone, PartialEq)]
+struct Author {
+    name: String,
+    email: String,
+    commits: usize,
+    /// Unix time of the newest commit.
+    last: i64,
+}
+
+/// Authors that are probably the same person: they share an email, a name,
+/// or the login in a noreply address.
+#[derive(Debug, Default)]
+struct Identity {
+    authors: Vec<Author>,
+}
+
+impl Identity {
+    fn commits(&self) -> usize {
+        self.authors.iter().map(|a| a.commits).sum()
+    }
+
+    /// Distinct emails, most recently used first.
+    fn emails(&self) -> Vec<&str> {
+        let mut latest: BTreeMap<String, (i64, &str)> = BTreeMap::new();
+        for author in &self.authors {
+            let entry = latest
+                .entry(author.email.to_lowercase())
+                .or_insert((author.last, &author.email));
+            if author.last > entry.0 {
+                *entry = (author.last, &author.email);
+            }
+        }
+        let mut emails: Vec<(i64, &str)> = latest.into_values().collect();
+        emails.sort_by_key(|(last, _)| std::cmp::Reverse(*last));
+        emails.into_iter().map(|(_, email)| email).collect()
+    }
+
+    /// The login from a noreply address, else the name used on most commits.
+    fn username(&self) -> &str {
+        if let Some((_, login)) = self.authors.iter().find_map(|a| noreply_login(&a.email)) {
+            return login;
+        }
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for author in &self.authors {
+            *counts.entry(&author.name).or_default() += author.commits;
+        }
+        counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(name, _)| name)
+            .unwrap_or_default()
+    }
+
+    /// Forge host named by a noreply address, when it isn't github.com.
+    fn host(&self) -> Option<String> {
+        self.authors
+            .iter()
+            .find_map(|a| noreply_login(&a.email))
+            .map(|(host, _)| host)
+            .filter(|host| host != "github.com")
+    }
+}
+
+/// Host and login of a forge noreply address: GitHub's `id+login@users.noreply.<host>`
+/// (`noreply.<host>` on Enterprise Server), GitLab's `id-login@users.noreply.<host>`
+/// or Gitea's `login@noreply.<host>`.
+fn noreply_login(email: &str) -> Option<(String, &str)> {
+    let (local, domain) = email.rsplit_once('@')?;
+    let domain = domain.to_lowercase();
+    let host = domain
+        .strip_prefix("users.noreply.")
+        .or_else(|| domain.strip_prefix("noreply."))?
+        .to_string();
+    let login = match local.split_once('+') {
+        Some((id, login)) if id.bytes().all(|b| b.is_ascii_digit()) => login,
+        _ => match local.split_once('-') {
+            Some((id, login)) if id.bytes().all(|b| b.is_ascii_digit()) => login,
+            _ => local,
+        },
+    };
+    (!login.is_empty()).then_some((host, login))
+}
+
+fn is_bot(author: &Author) -> bool {
+    author.name.ends_with("[bot]") || author.email.contains("[bot]@")
+}
+
+/// Group authors into identities by shared email, name or noreply login.
+fn cluster(authors: Vec<Author>) -> Vec<Identity> {
+    let mut parent: Vec<usize> = (0..authors.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for (i, author) in authors.iter().enumerate() {
+        let mut keys = vec![
+            format!("email:{}", author.email.to_lowercase()),
+            format!("name:{}", author.name.trim().to_lowercase()),
+        ];
+        if let Some((_, login)) = noreply_login(&author.email) {
+            keys.push(format!("name:{}", login.to_lowercase()));
+        }
+        for key in keys {
+            match seen.get(&key) {
+                Some(&other) => {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, other));
+                    parent[a] = b;
+                }
+                None => {
+                    seen.insert(key, i);
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Identity> = BTreeMap::new();
+    for (i, author) in authors.into_iter().enumerate() {
+        let r = root(&mut parent, i);
+        groups.entry(r).or_default().authors.push(author);
+    }
+    let mut identities: Vec<Identity> = groups.into_values().collect();
+    identities.sort_by_key(|identity| std::cmp::Reverse(identity.commits()));
+    identities
+}
+
+/// Repositories to scan for `path`: the repository itself, or the
+/// repositories directly inside it.
+fn repositories(path: &Path) -> Vec<PathBuf> {
+    if path.join(".git").exists() {
+        return vec![path.to_path_buf()];
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Vec::new();
+    };
+    let mut repos: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join(".git").exists())
+        .collect();
+    repos.sort();
+    repos
+}
+
+/// Commit authors of every branch in `repo`, with `.mailmap` applied.
+fn authors(repo: &Path) -> Result<Vec<Author>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "--all", "--format=%aN%x1f%aE%x1f%at"])
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_log(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Lines of `name\x1femail\x1ftime`, counted per name and email.
+fn parse_log(text: &str) -> Vec<Author> {
+    let mut authors: BTreeMap<(String, String), Author> = BTreeMap::new();
+    for line in text.lines() {
+        let mut fields = line.split('\x1f');
+        let (Some(name), Some(email), Some(time)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if name.is_empty() || email.is_empty() {
+            continue;
+        }
+        let time = time.parse().unwrap_or(0);
+        let author = authors
+            .entry((name.to_string(), email.to_lowercase()))
+            .or_insert_with(|| Author {
+                name: name.to_string(),
+                email: email.to_string(),
+                commits: 0,
+                last: time,
+            });
+        author.commits += 1;
+        author.last = author.last.max(time);
+    }
+    authors.into_values().collect()
+}
+
+fn merge(all: &mut Vec<Author>, found: Vec<Author>) {
+    for author in found {
+        match all
+            .iter_mut()
+            .find(|a| a.name == author.name && a.email.eq_ignore_ascii_case(&author.email))
+        {
+            Some(existing) => {
+                existing.commits += author.commits;
+                existing.last = existing.last.max(author.last);
+            }
+            None => all.push(author),
+        }
+    }
+}
+
+/// `gitas import history`: find every identity that authored commits in the
+/// given repositories and offer to manage the ones that are yours, without
+/// tokens. Identities with the global git identity or a managed account's
+/// name or email are preselected.
+pub fn run(config: &mut Config, paths: Vec<String>) {
+    let paths = if paths.is_empty() {
+        vec![".".to_string()]
+    } else {
+        paths
+    };
+    let mut all = Vec::new();
+    let mut scanned = 0;
+    for path in &paths {
+        let repos = repositories(Path::new(path));
+        if repos.is_empty() {
+            println!("  {} {} has no git repositories", "⚠".yellow(), path);
+        }
+        for repo in repos {
+            match authors(&repo) {
+                Ok(found) => {
+                    scanned += 1;
+                    merge(&mut all, found);
+                }
+                Err(e) => println!("  {} {}: {}", "⚠".yellow(), repo.display(), e),
+            }
+        }
+    }
+    all.retain(|author| !is_bot(author));
+    if all.is_empty() {
+        println!("\n  {}\n", "No commit authors found.".dimmed());
+        return;
+    }
+
+    let managed_emails: BTreeSet<String> = config
+        .accounts
+        .iter()
+        .map(|a| a.email.to_lowercase())
+        .collect();
+    let managed_names: BTreeSet<String> =
+        config.accounts.iter().map(|a| a.username.clone()).collect();
+    let managed = |email: &str| managed_emails.contains(&email.to_lowercase());
+    let global_email = git_config_get("user.email", "--global");
+    let global_name = git_config_get("user.name", "--global");
+    let is_mine = |identity: &Identity| {
+        identity.authors.iter().any(|author| {
+            managed(&author.email)
+                || global_email
+                    .as_deref()
+                    .is_some_and(|email| author.email.eq_ignore_ascii_case(email))
+                || global_name.as_deref() == Some(author.name.as_str())
+                || managed_names.contains(&author.name)
+        })
+    };
+
+    let identities = cluster(all);
+    let total = identities.len();
+    let mut offered: Vec<(Identity, bool)> = identities
+        .into_iter()
+        .filter(|identity| !identity.emails().iter().all(|email| managed(email)))
+        .map(|identity| {
+            let mine = is_mine(&identity);
+            (identity, mine)
+        })
+        .collect();
+    // Yours first; `cluster` already sorted by commits.
+    offered.sort_by_key(|(_, mine)| !mine);
+    let hidden = offered.len().saturating_sub(MAX_OFFERED);
+    offered.truncate(MAX_OFFERED);
+
+    println!(
+        "\n  {} {} in {} {}\n",
+        total,
+        if total == 1 { "identity" } else { "identities" },
+        scanned,
+        if scanned == 1 {
+            "repository"
+        } else {
+            "repositories"
+        }
+    );
+    if offered.is_empty() {
+        println!(
+            "  {}\n",
+            "Every identity found is already managed.".dimmed()
+        );
+        return;
+    }
+    let labels: Vec<String> = offered
+        .iter()
+        .map(|(identity, _)| {
+            let emails: Vec<String> = identity
+                .emails()
+                .iter()
+                .map(|email| {
+                    if managed(email) {
+                        format!("<{}> {}", email, "(managed)".dimmed())
+                    } else {
+                        format!("<{}>", email)
+                    }
+                })
+                .collect();
+            format!(
+                "{} {}  {}",
+                identity.username(),
+                emails.join(", "),
+                match identity.commits() {
+                    1 => "1 commit".to_string(),
+                    n => format!("{} commits", n),
+                }
+                .dimmed()
+            )
+        })
+        .collect();
+    if hidden > 0 {
+        println!(
+            "  {}\n",
+            format!("{} identities with fewer commits are not shown.", hidden).dimmed()
+        );
+    }
+
+    if crate::models::is_strict() || !std::io::stdin().is_terminal() {
+        for label in &labels {
+            println!("  {} {}", "○".dimmed(), label);
+        }
+        println!(
+            "\n  {}\n",
+            "Run in a terminal to choose which to add.".dimmed()
+        );
+        return;
+    }
+
+    let checked: Vec<usize> = offered
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, mine))| *mine)
+        .map(|(i, _)| i)
+        .collect();
+    enter_raw_mode();
+    let selection = raw_multi_select("Add as accounts", &labels, &checked);
+    exit_raw_mode();
+    let Some(selection) = selection else {
+        crate::tui::exit_cancelled();
+    };
+
+    println!();
+    let mut failed = false;
+    for index in selection {
+        let identity = &offered[index].0;
+        // The newest address the account doesn't already manage.
+        let Some(email) = identity.emails().into_iter().find(|email| !managed(email)) else {
+            continue;
+        };
+        let account = Account {
+            username: identity.username().to_string(),
+            email: email.to_string(),
+            host: identity
+                .host()
+                .as_deref()
+                .and_then(crate::host::for_account),
+            ..Default::default()
+        };
+        let label = format_account_label(&account);
+        match super::edit::commit(config, None, account, super::edit::TokenChange::Keep) {
+            Ok(_) => println!(
+                "  {} {} {}",
+                "✓".green().bold(),
+                label.cyan(),
+                "(added)".dimmed()
+            ),
+            Err(problems) => {
+                failed = true;
+                println!("  {} {} {}", "✗".red().bold(), label, problems.join("; "));
+            }
+        }
+    }
+    println!(
+        "\n  {}\n",
+        "Accounts were added without tokens; add them with `gitas token set`.".dimmed()
+    );
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clusters_authors_by_email_name_and_noreply_login() {
+        let authors = parse_log(
+            "Octo Cat\x1focto@work.com\x1f100\n\
+             Octo Cat\x1focto@home.org\x1f300\n\
+             octo\x1f1+octo@users.noreply.github.com\x1f200\n\
+             octo\x1fOCTO@home.org\x1f250\n\
+             Hub\x1fhub@x.com\x1f50\n\
+             Hub\x1fhub@x.com\x1f60\n",
+        );
+        let identities = cluster(authors);
+        assert_eq!(identities.len(), 2);
+        let octo = &identities[0];
+        assert_eq!(octo.commits(), 4);
+        assert_eq!(octo.username(), "octo");
+        assert_eq!(
+            octo.emails(),
+            [
+                "octo@home.org",
+                "1+octo@users.noreply.github.com",
+                "octo@work.com"
+            ]
+        );
+        assert_eq!(identities[1].commits(), 2);
+        assert_eq!(
+            noreply_login("7-lab@users.noreply.gitlab.com"),
+            Some(("gitlab.com".to_string(), "lab"))
+        );
+        assert_eq!(
+            noreply_login("tea@noreply.codeberg.org"),
+            Some(("codeberg.org".to_string(), "tea"))
+        );
+    }
+}
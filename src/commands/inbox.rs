@@ -0,0 +1,62 @@
+use crate::models::{Config, get_token};
+use crate::utils::resolve_account;
+use colored::Colorize;
+
+pub fn run(config: &Config, account_id: Option<String>) {
+    let account = resolve_account(config, account_id, "  Show inbox of");
+    let Some(token) = get_token(&account.username, account.alias.as_deref())
+        .filter(|token| !token.is_empty() && !account.ssh_only)
+    else {
+        eprintln!(
+            "\n  {} {} has no token; notifications need API access.\n",
+            "✗".red().bold(),
+            account.username.cyan()
+        );
+        std::process::exit(1);
+    };
+
+    let notifications = match crate::provider::for_account(&account).fetch_notifications(&token) {
+        Ok(notifications) => notifications,
+        Err(e) => {
+            eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    };
+
+    println!();
+    if notifications.is_empty() {
+        println!(
+            "  {} No unread notifications for {}.\n",
+            "✓".green().bold(),
+            account.username.cyan()
+        );
+        return;
+    }
+
+    println!(
+        "  {} unread for {}\n",
+        notifications.len().to_string().bold(),
+        account.username.cyan()
+    );
+    let repo_width = notifications
+        .iter()
+        .map(|n| n.repo.len())
+        .max()
+        .unwrap_or(0);
+    for notification in &notifications {
+        let reason = notification.reason.replace('_', " ");
+        let reason = if notification.reason == "review_requested" {
+            reason.yellow().bold()
+        } else {
+            reason.dimmed()
+        };
+        println!(
+            "  {:<rw$}  {}  {}",
+            notification.repo.dimmed(),
+            notification.title,
+            reason,
+            rw = repo_width
+        );
+    }
+    println!();
+}
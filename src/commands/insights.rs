@@ -0,0 +1,172 @@
+use crate::audit::{self, Entry, Event};
+use crate::models::Config;
+use crate::utils::redact_id;
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Default)]
+struct AccountUsage {
+    pushes: usize,
+    switches: usize,
+    guards: usize,
+    last_used: Option<u64>,
+}
+
+#[derive(Default)]
+struct RepoUsage {
+    pushes: usize,
+    switches: usize,
+    accounts: BTreeSet<String>,
+}
+
+/// Usage summary computed purely from the local history log.
+pub fn run(config: &Config, days: Option<u64>) {
+    let since = days.map(|d| crate::cache::now_secs().saturating_sub(d * 86_400));
+    let entries: Vec<Entry> = audit::entries()
+        .into_iter()
+        .filter(|e| since.is_none_or(|since| e.time >= since))
+        // Token changes say nothing about which account is used where.
+        .filter(|e| e.event != Event::Token)
+        .collect();
+
+    let mut accounts: BTreeMap<String, AccountUsage> = BTreeMap::new();
+    let mut repos: BTreeMap<String, RepoUsage> = BTreeMap::new();
+    for entry in &entries {
+        let usage = accounts.entry(entry.account.clone()).or_default();
+        match entry.event {
+            Event::Push => usage.pushes += 1,
+            Event::Switch => usage.switches += 1,
+            Event::Guard => usage.guards += 1,
+            Event::Token => {}
+        }
+        if entry.event != Event::Guard {
+            usage.last_used = usage.last_used.max(Some(entry.time));
+        }
+
+        // Global switches aren't tied to the repository they happened in.
+        let repo_scoped = entry.event == Event::Push || entry.scope.as_deref() == Some("local");
+        if let Some(repo) = entry.repo.as_ref().filter(|_| repo_scoped) {
+            let usage = repos.entry(repo.clone()).or_default();
+            match entry.event {
+                Event::Push => usage.pushes += 1,
+                _ => usage.switches += 1,
+            }
+            usage.accounts.insert(entry.account.clone());
+        }
+    }
+
+    let period = match days {
+        Some(d) => format!("last {} days", d),
+        None => "all recorded history".to_string(),
+    };
+    println!(
+        "\n  {} {}\n",
+        "Insights".bold(),
+        format!("({})", period).dimmed()
+    );
+
+    if entries.is_empty() {
+        println!("  {}\n", "No history recorded yet.".dimmed());
+    } else {
+        print_accounts(&accounts);
+        print_repos(&repos);
+        print_guards(&entries);
+    }
+    print_unused(config, &accounts);
+}
+
+fn print_accounts(accounts: &BTreeMap<String, AccountUsage>) {
+    let mut ranked: Vec<_> = accounts.iter().collect();
+    ranked.sort_by_key(|(_, u)| std::cmp::Reverse(u.pushes + u.switches));
+    let width = ranked
+        .iter()
+        .map(|(id, _)| redact_id(id).len())
+        .max()
+        .unwrap_or(0);
+
+    println!("  {}", "Most used accounts".bold());
+    for (id, usage) in ranked {
+        println!(
+            "    {:<w$}  {:>4} pushes  {:>4} switches  {}",
+            redact_id(id).cyan(),
+            usage.pushes,
+            usage.switches,
+            usage
+                .last_used
+                .map(|t| format!("last {}", audit::format_time(t)))
+                .unwrap_or_default()
+                .dimmed(),
+            w = width
+        );
+    }
+    println!();
+}
+
+fn print_repos(repos: &BTreeMap<String, RepoUsage>) {
+    if repos.is_empty() {
+        return;
+    }
+    let mut ranked: Vec<_> = repos.iter().collect();
+    ranked.sort_by_key(|(_, u)| std::cmp::Reverse(u.switches + u.pushes));
+
+    println!("  {}", "Repositories".bold());
+    for (repo, usage) in ranked.into_iter().take(10) {
+        let accounts: Vec<String> = usage.accounts.iter().map(|id| redact_id(id)).collect();
+        let mixed = if accounts.len() > 1 {
+            format!("  {}", "(several identities)".yellow())
+        } else {
+            String::new()
+        };
+        println!(
+            "    {}  {} switches, {} pushes as {}{}",
+            repo,
+            usage.switches,
+            usage.pushes,
+            accounts.join(", ").cyan(),
+            mixed
+        );
+    }
+    println!();
+}
+
+fn print_guards(entries: &[Entry]) {
+    let guards: Vec<&Entry> = entries.iter().filter(|e| e.event == Event::Guard).collect();
+    println!(
+        "  {} {}",
+        "Identity mistakes caught:".bold(),
+        if guards.is_empty() {
+            "none".green().to_string()
+        } else {
+            guards.len().to_string().yellow().to_string()
+        }
+    );
+    for entry in guards.iter().rev().take(5) {
+        println!(
+            "    {}  {}  {}",
+            audit::format_time(entry.time).dimmed(),
+            redact_id(&entry.account).cyan(),
+            entry.detail.as_deref().unwrap_or_default()
+        );
+    }
+    println!();
+}
+
+fn print_unused(config: &Config, accounts: &BTreeMap<String, AccountUsage>) {
+    let unused: Vec<String> = config
+        .accounts
+        .iter()
+        .map(audit::account_id)
+        .filter(|id| accounts.get(id).is_none_or(|u| u.last_used.is_none()))
+        .collect();
+    if unused.is_empty() {
+        return;
+    }
+    println!("  {}", "Not used in this period".bold());
+    for id in unused {
+        println!("    {}", redact_id(&id).dimmed());
+    }
+    println!(
+        "\n  Remove accounts you no longer need from the list view ({}).\n",
+        "gitas".cyan()
+    );
+}
@@ -1,9 +1,14 @@
-use crate::models::{Config, save_config};
+use crate::models::Config;
+use crate::plugins::{self, HookPoint};
 use crate::tui::{
-    raw_confirm, raw_input, raw_password, raw_select, raw_show_status, raw_with_loader,
-    truncate_rendered_line,
+    raw_confirm, raw_input, raw_multi_select, raw_password, raw_select, raw_show_status,
+    raw_with_loader, truncate_rendered_line,
 };
-use crate::utils::{git_config_get, git_config_set, git_config_unset, git_credential_approve};
+use crate::utils::{
+    git_config_get, git_config_set, git_config_unset, git_credential_approve, redact_email,
+    redact_name,
+};
+use crate::warnings::{self, Warning};
 use colored::Colorize;
 use crossterm::{
     cursor,
@@ -19,25 +24,104 @@ pub fn run(config: &mut Config) {
     state.run_loop();
 }
 
+/// One line of the list the cursor can rest on.
+#[derive(Debug, Clone, PartialEq)]
+enum Row {
+    /// Section header for the accounts on one host.
+    Host(String),
+    Account(usize),
+    Unmanaged(usize),
+}
+
 struct ListState<'a> {
     config: &'a mut Config,
     git: GitIdentity,
+    /// Position in `rows()`.
     cursor: usize,
     last_rendered_lines: usize,
     unmanaged: Vec<(String, String, String)>,
+    /// Host sections folded to their header.
+    collapsed: std::collections::BTreeSet<String>,
 }
 
 impl<'a> ListState<'a> {
     fn new(config: &'a mut Config) -> Self {
-        let git = GitIdentity::fetch();
+        let git = GitIdentity::fetch(&config.accounts);
         let unmanaged = Self::compute_unmanaged(&git, config);
-        Self {
+        let mut state = Self {
             config,
             git,
             cursor: 0,
             last_rendered_lines: 0,
             unmanaged,
+            collapsed: Default::default(),
+        };
+        // Start on the account that owns the default remote, otherwise the
+        // first account rather than a section header.
+        let owner = crate::utils::default_remote_at(std::path::Path::new(".")).and_then(|remote| {
+            let owner = crate::resolve::account_for_url(&state.config.accounts, &remote.url)?;
+            state
+                .config
+                .accounts
+                .iter()
+                .position(|a| std::ptr::eq(a, owner))
+        });
+        let rows = state.rows();
+        state.cursor = owner
+            .and_then(|index| rows.iter().position(|row| *row == Row::Account(index)))
+            .or_else(|| rows.iter().position(|row| matches!(row, Row::Account(_))))
+            .unwrap_or(0);
+        state
+    }
+
+    /// Accounts grouped under a header per host once more than one host is
+    /// in use, then identities gitas doesn't manage.
+    fn rows(&self) -> Vec<Row> {
+        let groups = host_groups(&self.config.accounts);
+        let mut rows = Vec::new();
+        if groups.len() > 1 {
+            for (host, indices) in groups {
+                let collapsed = self.collapsed.contains(&host);
+                rows.push(Row::Host(host));
+                if !collapsed {
+                    rows.extend(indices.into_iter().map(Row::Account));
+                }
+            }
+        } else {
+            rows.extend((0..self.config.accounts.len()).map(Row::Account));
+        }
+        rows.extend((0..self.unmanaged.len()).map(Row::Unmanaged));
+        rows
+    }
+
+    fn selected_account(&self) -> Option<usize> {
+        match self.rows().get(self.cursor) {
+            Some(Row::Account(index)) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Fold (`collapse`) or unfold the section under the cursor, keeping the
+    /// cursor on its header.
+    fn fold_section(&mut self, collapse: bool) {
+        let rows = self.rows();
+        let host = match rows.get(self.cursor) {
+            Some(Row::Host(host)) => host.clone(),
+            Some(Row::Account(index)) if rows.iter().any(|r| matches!(r, Row::Host(_))) => {
+                host_key(&self.config.accounts[*index])
+            }
+            _ => return,
+        };
+        if collapse {
+            self.collapsed.insert(host.clone());
+        } else {
+            self.collapsed.remove(&host);
         }
+        self.cursor = self
+            .rows()
+            .iter()
+            .position(|row| *row == Row::Host(host.clone()))
+            .unwrap_or(0);
     }
 
     fn run_loop(&mut self) {
@@ -70,6 +154,24 @@ impl<'a> ListState<'a> {
                     self.move_cursor(1);
                     self.render();
                 }
+                KeyCode::Enter | KeyCode::Char(' ')
+                    if matches!(self.rows().get(self.cursor), Some(Row::Host(_))) =>
+                {
+                    let rows = self.rows();
+                    if let Some(Row::Host(host)) = rows.get(self.cursor) {
+                        let collapse = !self.collapsed.contains(host);
+                        self.fold_section(collapse);
+                    }
+                    self.render();
+                }
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.fold_section(true);
+                    self.render();
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.fold_section(false);
+                    self.render();
+                }
                 KeyCode::Enter => {
                     if self.handle_switch() {
                         self.refresh_git();
@@ -88,6 +190,20 @@ impl<'a> ListState<'a> {
                     }
                     self.render();
                 }
+                KeyCode::Char('b') => {
+                    if self.handle_batch_edit() {
+                        self.refresh_git();
+                    }
+                    self.render();
+                }
+                KeyCode::Char('p') => {
+                    crate::models::set_redacted(!crate::models::is_redacted());
+                    self.render();
+                }
+                KeyCode::Char('d') => {
+                    self.handle_diff();
+                    self.render();
+                }
                 KeyCode::Char('q') | KeyCode::Esc => {
                     break;
                 }
@@ -96,6 +212,10 @@ impl<'a> ListState<'a> {
                 }
                 _ => {}
             }
+            // Ctrl-C in any nested prompt ends the whole session.
+            if crate::tui::interrupted() {
+                break;
+            }
         }
 
         // Cleanup on exit
@@ -141,13 +261,12 @@ impl<'a> ListState<'a> {
     }
 
     fn refresh_git(&mut self) {
-        self.git = GitIdentity::fetch();
+        self.git = GitIdentity::fetch(&self.config.accounts);
         self.unmanaged = Self::compute_unmanaged(&self.git, self.config);
     }
 
     fn move_cursor(&mut self, delta: isize) {
-        let unmanaged_len = self.unmanaged.len();
-        let total_len = (self.config.accounts.len() + unmanaged_len) as isize;
+        let total_len = self.rows().len() as isize;
 
         if total_len == 0 {
             self.cursor = 0;
@@ -208,10 +327,11 @@ impl<'a> ListState<'a> {
 
     fn compute_unmanaged(git: &GitIdentity, config: &Config) -> Vec<(String, String, String)> {
         let mut unmanaged = Vec::new();
+        // Accounts of other profiles are still gitas's.
+        let accounts = config.all_accounts();
 
         if let (Some(name), Some(email)) = (&git.global_name, &git.global_email)
-            && !config
-                .accounts
+            && !accounts
                 .iter()
                 .any(|a| &a.username == name && &a.email == email)
         {
@@ -219,8 +339,7 @@ impl<'a> ListState<'a> {
         }
 
         if let (Some(name), Some(email)) = (&git.local_name, &git.local_email) {
-            let is_known = config
-                .accounts
+            let is_known = accounts
                 .iter()
                 .any(|a| &a.username == name && &a.email == email);
             let is_already_listed = unmanaged.iter().any(|(n, e, _)| n == name && e == email);
@@ -237,14 +356,37 @@ impl<'a> ListState<'a> {
         frame.push(String::new());
         const VERSION: &str = env!("CARGO_PKG_VERSION");
         frame.push(format!(
-            "  {} {} {}",
+            "  {} {} {}{}",
             "GITAS".bold(),
             "(Git Account Switch)".dimmed(),
-            format!("v{}", VERSION).dimmed()
+            format!("v{}", VERSION).dimmed(),
+            if crate::models::is_ephemeral() {
+                format!(" {}", "ephemeral".magenta().bold())
+            } else {
+                String::new()
+            }
         ));
+        if crate::models::is_redacted() {
+            frame[1].push_str(&format!(" {}", "redacted".blue().bold()));
+        }
+        if let Some(profile) = crate::models::active_profile() {
+            frame[1].push_str(&format!(
+                " {}",
+                format!("profile {}", profile).cyan().bold()
+            ));
+        }
+        let fold = if host_groups(&self.config.accounts).len() > 1 {
+            " · ←→ fold"
+        } else {
+            ""
+        };
         frame.push(format!(
             "  {}",
-            "↑↓ select · Enter switch · e edit · Backspace remove · q quit".dimmed()
+            format!(
+                "↑↓ select{} · Enter switch · d diff · e edit · b batch edit · Backspace remove · p privacy · q quit",
+                fold
+            )
+            .dimmed()
         ));
         frame.push(String::new());
 
@@ -253,7 +395,7 @@ impl<'a> ListState<'a> {
         let max_width = (term_cols as usize).saturating_sub(4); // buffer
 
         let name_len_fn = |name: &str, alias: Option<&String>| -> usize {
-            name.len() + alias.map(|a| a.len() + 1).unwrap_or(0)
+            redact_name(name).len() + alias.map(|a| a.len() + 1).unwrap_or(0)
         };
 
         let max_name_len = self
@@ -261,7 +403,7 @@ impl<'a> ListState<'a> {
             .accounts
             .iter()
             .map(|a| name_len_fn(&a.username, a.alias.as_ref()))
-            .chain(unmanaged.iter().map(|(n, _, _)| n.len()))
+            .chain(unmanaged.iter().map(|(n, _, _)| redact_name(n).len()))
             .max()
             .unwrap_or(0);
 
@@ -272,8 +414,8 @@ impl<'a> ListState<'a> {
             .config
             .accounts
             .iter()
-            .map(|a| a.email.len() + 2) // <email>
-            .chain(unmanaged.iter().map(|(_, e, _)| e.len() + 2))
+            .map(|a| redact_email(&a.email).len() + 2) // <email>
+            .chain(unmanaged.iter().map(|(_, e, _)| redact_email(e).len() + 2))
             .max()
             .unwrap_or(0);
 
@@ -293,28 +435,71 @@ impl<'a> ListState<'a> {
         let safe_sep_len = min(sep_len, max_width);
         frame.push(format!("  {}", "─".repeat(safe_sep_len).dimmed()));
 
+        if let Some(line) = self.format_env_line(name_width, email_width) {
+            frame.push(line);
+        }
+
         // List Accounts
+        let local = self.git.matches(&self.config.accounts, true);
+        let global = self.git.matches(&self.config.accounts, false);
         if self.config.accounts.is_empty() && unmanaged.is_empty() {
             frame.push(format!("  {}", "No accounts found.".italic().dimmed()));
         } else {
-            for (i, account) in self.config.accounts.iter().enumerate() {
-                frame.push(self.format_account_line(i, account, name_width, email_width));
-            }
-
-            // Unmanaged
-            let accounts_len = self.config.accounts.len();
-            for (i, unmanaged_acc) in unmanaged.iter().enumerate() {
-                frame.push(self.format_unmanaged_line(
-                    i,
-                    accounts_len,
-                    unmanaged_acc,
-                    name_width,
-                    email_width,
-                ));
+            let groups = host_groups(&self.config.accounts);
+            for (position, row) in self.rows().into_iter().enumerate() {
+                let is_current = position == self.cursor;
+                match row {
+                    Row::Host(host) => {
+                        let order = groups.iter().position(|(h, _)| *h == host).unwrap_or(0);
+                        let count = groups.get(order).map_or(0, |(_, indices)| indices.len());
+                        frame.push(format_host_line(
+                            &host,
+                            order,
+                            count,
+                            self.collapsed.contains(&host),
+                            is_current,
+                        ));
+                    }
+                    Row::Account(index) => frame.push(self.format_account_line(
+                        is_current,
+                        index,
+                        (&local, &global),
+                        name_width,
+                        email_width,
+                    )),
+                    Row::Unmanaged(index) => frame.push(self.format_unmanaged_line(
+                        is_current,
+                        &unmanaged[index],
+                        name_width,
+                        email_width,
+                    )),
+                }
             }
         }
 
         frame.push(format!("  {}", "─".repeat(safe_sep_len).dimmed()));
+        for (scope, matches) in [("local", &local), ("global", &global)] {
+            if matches.len() > 1 {
+                frame.push(format!(
+                    "  {} {}",
+                    "◐".yellow(),
+                    format!(
+                        "The {} identity fits {} accounts; switch to one to record which.",
+                        scope,
+                        matches.len()
+                    )
+                    .dimmed()
+                ));
+            }
+        }
+        if !self.git.env.is_empty() {
+            frame.push(format!(
+                "  {} {} {}",
+                "⚠".yellow(),
+                "Environment variables override every switch. Clear them with".dimmed(),
+                super::env::clear_hint().cyan()
+            ));
+        }
         frame.push(String::new());
 
         // Leave the rightmost cell untouched to avoid the terminal's automatic
@@ -327,23 +512,23 @@ impl<'a> ListState<'a> {
             .collect()
     }
 
+    /// `matches` holds the accounts the local and global identities could
+    /// belong to; a row that shares its identity with another is marked as
+    /// ambiguous instead of current.
     fn format_account_line(
         &self,
+        is_current: bool,
         index: usize,
-        account: &crate::models::Account,
+        matches: (&[usize], &[usize]),
         name_width: usize,
         email_width: usize,
     ) -> String {
-        let is_current = index == self.cursor;
-
-        let is_global = self.git.global_name.as_deref() == Some(&account.username)
-            && self.git.global_email.as_deref() == Some(&account.email)
-            && self.git.global_alias.as_deref() == account.alias.as_deref();
-
-        let is_local = self.git.has_local()
-            && self.git.local_name.as_deref() == Some(&account.username)
-            && self.git.local_email.as_deref() == Some(&account.email)
-            && self.git.local_alias.as_deref() == account.alias.as_deref();
+        let account = &self.config.accounts[index];
+        let (local, global) = matches;
+        let is_local = local.len() == 1 && local[0] == index;
+        let is_global = global.len() == 1 && global[0] == index;
+        let ambiguous = (local.len() > 1 && local.contains(&index))
+            || (!is_local && global.len() > 1 && global.contains(&index));
 
         let pointer = if is_current {
             ">".yellow().bold().to_string()
@@ -353,6 +538,8 @@ impl<'a> ListState<'a> {
 
         let marker = if is_local {
             "●".green().bold()
+        } else if ambiguous {
+            "◐".yellow().bold()
         } else if is_global {
             "●".cyan().bold()
         } else {
@@ -365,27 +552,41 @@ impl<'a> ListState<'a> {
             .as_ref()
             .map(|a| format!(":{}", a).dimmed().to_string())
             .unwrap_or_default();
+        let username = redact_name(&account.username);
         let display_name = match (is_local, is_global) {
-            (true, _) => format!("{}{}", account.username.green().bold(), alias_part),
-            (_, true) => format!("{}{}", account.username.cyan().bold(), alias_part),
-            _ => format!("{}{}", account.username.white(), alias_part),
+            (true, _) => format!("{}{}", username.green().bold(), alias_part),
+            (_, true) => format!("{}{}", username.cyan().bold(), alias_part),
+            _ => format!("{}{}", username.white(), alias_part),
         };
 
         // Padding logic
         let raw_name_len =
-            account.username.len() + account.alias.as_ref().map(|a| a.len() + 1).unwrap_or(0);
+            username.len() + account.alias.as_ref().map(|a| a.len() + 1).unwrap_or(0);
         let name_pad = " ".repeat(name_width.saturating_sub(raw_name_len));
 
-        let email_str = format!("<{}>", account.email);
+        let email_str = format!("<{}>", redact_email(&account.email));
         let email_pad = " ".repeat(email_width.saturating_sub(email_str.len()));
 
-        let scope_str = if is_local {
+        let mut scope_str = if is_local {
             "local".green().to_string()
+        } else if ambiguous {
+            let scope = if local.contains(&index) {
+                "local"
+            } else {
+                "global"
+            };
+            format!("{}?", scope).yellow().to_string()
         } else if is_global {
             "global".cyan().to_string()
         } else {
             String::new()
         };
+        if self.config.default_account.as_deref() == Some(&crate::audit::account_id(account)) {
+            if !scope_str.is_empty() {
+                scope_str.push(' ');
+            }
+            scope_str.push_str(&"★ default".yellow().to_string());
+        }
 
         format!(
             "{} {} {}{}  {}{}  {}",
@@ -399,16 +600,49 @@ impl<'a> ListState<'a> {
         )
     }
 
+    /// Non-selectable row for an identity forced through environment variables.
+    fn format_env_line(&self, name_width: usize, email_width: usize) -> Option<String> {
+        if self.git.env.is_empty() {
+            return None;
+        }
+        let lookup = |vars: [&str; 2]| {
+            vars.iter().find_map(|var| {
+                self.git
+                    .env
+                    .iter()
+                    .find(|(name, _)| name == var)
+                    .map(|(_, value)| value.clone())
+            })
+        };
+        let name =
+            redact_name(&lookup(["GIT_AUTHOR_NAME", "GIT_COMMITTER_NAME"]).unwrap_or_default());
+        let email =
+            redact_email(&lookup(["GIT_AUTHOR_EMAIL", "GIT_COMMITTER_EMAIL"]).unwrap_or_default());
+
+        let name_pad = " ".repeat(name_width.saturating_sub(name.len()));
+        let email_str = format!("<{}>", email);
+        let email_pad = " ".repeat(email_width.saturating_sub(email_str.len()));
+
+        Some(format!(
+            "  {} {}{}  {}{}  {}",
+            "●".magenta().bold(),
+            name.magenta(),
+            name_pad,
+            email_str.dimmed(),
+            email_pad,
+            "environment".magenta()
+        ))
+    }
+
     fn format_unmanaged_line(
         &self,
-        index: usize,
-        accounts_len: usize,
+        is_selected: bool,
         unmanaged: &(String, String, String),
         name_width: usize,
         email_width: usize,
     ) -> String {
         let (name, email, scope) = unmanaged;
-        let is_selected = (accounts_len + index) == self.cursor;
+        let (name, email) = (redact_name(name), redact_email(email));
         let pointer = if is_selected {
             ">".yellow().bold().to_string()
         } else {
@@ -432,12 +666,32 @@ impl<'a> ListState<'a> {
         )
     }
 
+    /// Switching, editing and removing all persist state, which --ephemeral forbids.
+    fn refuse_ephemeral(&self) -> bool {
+        if !crate::models::is_ephemeral() {
+            return false;
+        }
+        raw_show_status(
+            &[format!(
+                "  {} Ephemeral mode saves nothing. Use {} or {} to act as an account.",
+                "⚠".yellow(),
+                "gitas git".cyan(),
+                "gitas exec".cyan()
+            )],
+            true,
+        );
+        true
+    }
+
     fn handle_switch(&mut self) -> bool {
-        if self.config.accounts.is_empty() || self.cursor >= self.config.accounts.len() {
+        if self.refuse_ephemeral() {
             return false;
         }
+        let Some(index) = self.selected_account() else {
+            return false;
+        };
 
-        let account = self.config.accounts[self.cursor].clone();
+        let account = self.config.accounts[index].clone();
         let local_label = match crate::utils::git_toplevel() {
             Some(path) => format!("local {}", format!("({})", path).dimmed()),
             None => "local".to_string(),
@@ -449,38 +703,146 @@ impl<'a> ListState<'a> {
             "Cancel".dimmed().to_string(),
         ];
 
-        let prompt = format!("Switch to '{}'. Apply to", account.username.cyan());
-        let Some(selection) = raw_select(&prompt, &items, 0) else {
-            return false;
-        };
+        let prompt = format!(
+            "Switch to '{}'. Apply to",
+            redact_name(&account.username).cyan()
+        );
+        // Esc in a later prompt comes back to the scope choice.
+        loop {
+            let Some(selection) = raw_select(&prompt, &items, 0) else {
+                return false;
+            };
 
-        if selection != 0 && selection != 1 {
-            return false;
+            if selection != 0 && selection != 1 {
+                return false;
+            }
+
+            let scope = if selection == 0 { "global" } else { "local" };
+            if scope == "global" {
+                match self.confirm_global_overwrite() {
+                    Some(true) => {}
+                    Some(false) => return false,
+                    None => continue,
+                }
+            }
+            if let Some(switched) = self.apply_switch(&account, scope) {
+                return switched;
+            }
         }
+    }
+
+    /// A hand-configured global identity would be lost on a global switch; make
+    /// the user choose between adopting it as an account and replacing it,
+    /// unless the `global-switch` policy is `never`. `None` when the user
+    /// backed out with Esc.
+    fn confirm_global_overwrite(&mut self) -> Option<bool> {
+        let ask = crate::confirm::policy(self.config, crate::confirm::Action::GlobalSwitch)
+            != crate::confirm::Policy::Never;
+        if !ask {
+            return Some(true);
+        }
+        let Some((name, email, _)) = self
+            .unmanaged
+            .iter()
+            .find(|(_, _, scope)| scope == "global")
+            .cloned()
+        else {
+            return Some(true);
+        };
 
-        let scope = if selection == 0 { "global" } else { "local" };
-        self.apply_switch(&account, scope)
+        let items = vec![
+            "Adopt it as an account, then switch".to_string(),
+            "Replace it".to_string(),
+            "Cancel".dimmed().to_string(),
+        ];
+        let prompt = format!(
+            "Global identity '{} <{}>' isn't managed by gitas",
+            redact_name(&name).yellow(),
+            redact_email(&email)
+        );
+        match raw_select(&prompt, &items, 0) {
+            Some(0) => {
+                let adopted = crate::models::Account {
+                    username: name,
+                    email,
+                    ..Default::default()
+                };
+                match super::edit::commit(
+                    self.config,
+                    None,
+                    adopted,
+                    super::edit::TokenChange::Keep,
+                ) {
+                    Ok(_) => {
+                        self.unmanaged = Self::compute_unmanaged(&self.git, self.config);
+                        Some(true)
+                    }
+                    Err(problems) => {
+                        let mut lines: Vec<String> = problems
+                            .iter()
+                            .map(|p| format!("  {} {}", "✗".red(), p))
+                            .collect();
+                        lines.push(format!("{}   Switch cancelled", "✗".red()));
+                        raw_show_status(&lines, true);
+                        Some(false)
+                    }
+                }
+            }
+            Some(1) => Some(true),
+            Some(_) => Some(false),
+            None => None,
+        }
     }
 
-    fn apply_switch(&self, account: &crate::models::Account, scope: &str) -> bool {
+    /// Write the account into `scope`. `None` when the user backed out of a
+    /// prompt before anything changed.
+    fn apply_switch(&self, account: &crate::models::Account, scope: &str) -> Option<bool> {
         let mut status_lines = Vec::new();
         let mut has_status_issue = false;
 
         let host = account.host.as_deref().unwrap_or("github.com");
         let mut target_url = None;
-        let token = crate::models::get_token(&account.username, account.alias.as_deref())
-            .filter(|token| !token.is_empty());
+        let token = if account.ssh_only {
+            None
+        } else {
+            crate::models::get_token(&account.username, account.alias.as_deref())
+                .filter(|token| !token.is_empty())
+        };
 
         // Check authentication BEFORE making any git config changes
+        if account.ssh_only
+            && let Err(e) = crate::ssh::check_key(account)
+        {
+            status_lines.push(format!("  {} {}", "⚠".yellow(), e));
+            status_lines.push(String::new());
+            status_lines.push(format!(
+                "{}   Aborted switch due to SSH key problem",
+                "✗".red()
+            ));
+            raw_show_status(&status_lines, true);
+            return Some(false);
+        }
+        if let Err(e) = crate::hardware::check_present(account) {
+            status_lines.push(format!("  {} {}", "⚠".yellow(), e));
+            status_lines.push(String::new());
+            status_lines.push(format!(
+                "{}   Aborted switch: hardware key not available",
+                "✗".red()
+            ));
+            raw_show_status(&status_lines, true);
+            return Some(false);
+        }
         if token.is_some() {
             if scope == "local" {
                 let remotes = crate::utils::get_http_remotes();
                 if remotes.is_empty() {
-                    status_lines.push(format!(
-                        "  {} No HTTP remotes found. Token authentication may fail.",
-                        "⚠".yellow()
-                    ));
-                    has_status_issue = true;
+                    if warnings::enabled(self.config, Some(account), Warning::NoHttpRemote) {
+                        has_status_issue = true;
+                        status_lines.push(format!(
+                            "  {} No HTTP remotes found. Token authentication may fail.",
+                            "⚠".yellow()
+                        ));
+                    }
                 } else if remotes.len() == 1 {
                     target_url = Some(remotes[0].url.clone());
                 } else {
@@ -491,128 +853,335 @@ impl<'a> ListState<'a> {
                     if let Some(selection) = raw_select("Select remote for token auth", &items, 0) {
                         target_url = Some(remotes[selection].url.clone());
                     } else {
-                        return false;
+                        return None;
                     }
                 }
             }
 
-            if let Some(warning) = crate::utils::check_credential_helper() {
+            if warnings::enabled(self.config, Some(account), Warning::CredentialHelper)
+                && let Some(warning) = crate::utils::check_credential_helper()
+            {
                 has_status_issue = true;
                 status_lines.push(warning);
             }
-        } else if account.ssh_key.is_none()
-            || (scope == "local" && crate::utils::has_http_remotes())
+        } else if !account.ssh_only
+            && (account.ssh_key.is_none() || (scope == "local" && crate::utils::has_http_remotes()))
+            && warnings::enabled(self.config, Some(account), Warning::NoToken)
         {
             has_status_issue = true;
             status_lines.push(format!(
                 "  {} No token found for {}. Git may prompt for authentication.",
                 "⚠".yellow(),
-                account.username.cyan()
+                redact_name(&account.username).cyan()
+            ));
+        }
+
+        for problem in crate::policy::violations(self.config, account) {
+            has_status_issue = true;
+            status_lines.push(format!("  {} {}", "⚠".yellow(), problem));
+        }
+
+        if let Err(e) = plugins::run(
+            &self.config.hooks,
+            HookPoint::PreSwitch,
+            account,
+            Some(scope),
+        ) {
+            status_lines.push(format!("  {} {}", "⚠".yellow(), e));
+            status_lines.push(String::new());
+            status_lines.push(format!(
+                "{}   Switch cancelled by pre_switch hook",
+                "✗".red()
             ));
+            raw_show_status(&status_lines, true);
+            return Some(false);
         }
 
+        let hook_account = account.clone();
+        let switch_entry = crate::audit::Entry {
+            scope: Some(scope.to_string()),
+            ..crate::audit::Entry::new(crate::audit::Event::Switch, account)
+        };
         let account = account.clone();
         let scope = scope.to_string();
         let host = host.to_string();
-        let status_username = account.username.clone();
+        let status_username = redact_name(&account.username);
         let status_scope = scope.clone();
-        let managed_ssh_commands: Vec<String> = self
-            .config
-            .accounts
-            .iter()
-            .filter_map(|account| account.ssh_key.as_deref())
-            .map(crate::utils::git_ssh_command)
-            .collect();
-        let loader_message = format!("Setting account '{}'…", account.username);
+        let accounts = self.config.all_accounts();
+        let loader_message = format!("Setting account '{}'…", redact_name(&account.username));
 
-        let worker_result = raw_with_loader(&loader_message, move || -> Result<(), String> {
+        let worker_result = raw_with_loader(&loader_message, move || -> Result<_, String> {
+            let mut warnings = Vec::new();
+            let has_token = token.is_some();
             if let Some(token) = token {
-                git_credential_approve(&account.username, &token, &host, target_url.as_deref())?;
+                if let Some(warning) = crate::utils::check_token_owner(&account, &token) {
+                    warnings.push(warning);
+                }
+                for username in
+                    crate::provider::credential_usernames(&account, target_url.as_deref())
+                {
+                    git_credential_approve(&username, &token, &host, target_url.as_deref())?;
+                }
 
                 if scope == "local" && target_url.is_some() {
                     git_config_set("credential.useHttpPath", "true", "local");
                 }
-            }
-
-            // Apply all configuration changes now that auth has succeeded.
-            git_config_set("user.name", &account.username, &scope);
-            git_config_set("user.email", &account.email, &scope);
 
-            if let Some(alias) = &account.alias {
-                git_config_set("gitas.alias", alias, &scope);
-            } else {
-                git_config_unset("gitas.alias", &scope);
+                match crate::registry::sync(&account, &token) {
+                    Ok(updated) => warnings.extend(
+                        updated
+                            .into_iter()
+                            .map(|file| format!("  {} Updated {}", "✓".green(), file)),
+                    ),
+                    Err(e) => warnings.push(format!(
+                        "  {} Package registry sync failed: {}",
+                        "⚠".yellow(),
+                        e
+                    )),
+                }
             }
 
-            if let Some(ssh_key) = &account.ssh_key {
-                git_config_set(
-                    "core.sshCommand",
-                    &crate::utils::git_ssh_command(ssh_key),
-                    &scope,
-                );
-            } else if git_config_get("core.sshCommand", &scope)
-                .is_some_and(|current| managed_ssh_commands.contains(&current))
+            // Apply all configuration changes now that auth has succeeded.
+            for (key, change) in switch_plan(&account, &accounts, |key| git_config_get(key, &scope))
             {
-                git_config_unset("core.sshCommand", &scope);
+                match change {
+                    Some(value) => git_config_set(&key, &value, &scope),
+                    None => git_config_unset(&key, &scope),
+                }
             }
 
-            let cred_key = format!("credential.https://{}.username", host);
-            git_config_set(&cred_key, &account.username, &scope);
-            Ok(())
+            // Ask git itself which credentials it would now use, so the result
+            // reflects every helper in the chain rather than what gitas wrote.
+            let mut confirmed = None;
+            if has_token {
+                let fill_url = if scope == "local" {
+                    target_url.as_deref()
+                } else {
+                    None
+                };
+                match crate::utils::git_credential_fill(&host, fill_url) {
+                    Some(user)
+                        if crate::provider::credential_usernames(&account, fill_url)
+                            .iter()
+                            .any(|name| name.eq_ignore_ascii_case(&user)) =>
+                    {
+                        confirmed = Some(user)
+                    }
+                    Some(user) => warnings.push(format!(
+                        "  {} Credential helpers return '{}' for {}, not '{}'.",
+                        "⚠".yellow(),
+                        redact_name(&user).yellow(),
+                        host,
+                        redact_name(&account.username).cyan()
+                    )),
+                    None => warnings.push(format!(
+                        "  {} No credential helper returns credentials for {}.",
+                        "⚠".yellow(),
+                        host
+                    )),
+                }
+            }
+            Ok((warnings, confirmed))
         });
 
         let switch_result = worker_result.unwrap_or_else(|_| {
             Err("Account setting failed because the background operation panicked.".to_string())
         });
 
-        if let Err(error) = switch_result {
-            status_lines.push(format!("  {} {}", "⚠".yellow(), error));
-            status_lines.push(String::new());
-            status_lines.push(format!(
-                "{}   Aborted switch due to authentication failure",
-                "✗".red()
-            ));
-            raw_show_status(&status_lines, true);
-            return false;
+        let confirmed = match switch_result {
+            Ok((warnings, confirmed)) => {
+                has_status_issue |= !warnings.is_empty();
+                status_lines.extend(warnings);
+                confirmed
+            }
+            Err(error) => {
+                status_lines.push(format!("  {} {}", "⚠".yellow(), error));
+                status_lines.push(String::new());
+                status_lines.push(format!(
+                    "{}   Aborted switch due to authentication failure",
+                    "✗".red()
+                ));
+                raw_show_status(&status_lines, true);
+                return Some(false);
+            }
+        };
+
+        if self.config.ssh_agent && hook_account.ssh_key.is_some() {
+            // ssh-add may need the terminal to ask for the key's passphrase.
+            terminal::disable_raw_mode().ok();
+            let result = crate::agent::sync(&self.config.all_accounts(), &hook_account);
+            terminal::enable_raw_mode().ok();
+            match result {
+                Ok(notes) => status_lines.extend(
+                    notes
+                        .into_iter()
+                        .map(|note| format!("  {} {}", "✓".green(), note)),
+                ),
+                Err(e) => {
+                    has_status_issue = true;
+                    status_lines.push(format!("  {} {}", "⚠".yellow(), e));
+                }
+            }
+        }
+
+        if self.config.gh_sync
+            && !hook_account.ssh_only
+            && let Some(token) =
+                crate::models::get_token(&hook_account.username, hook_account.alias.as_deref())
+                    .filter(|t| !t.is_empty())
+        {
+            match super::gh::login(&hook_account, &token) {
+                Ok(()) => status_lines.push(format!("  {} gh is logged in too", "✓".green())),
+                Err(e) => {
+                    has_status_issue = true;
+                    status_lines.push(format!("  {} {}", "⚠".yellow(), e));
+                }
+            }
         }
 
         if !status_lines.is_empty() {
             status_lines.push(String::new());
         }
+        crate::audit::record(&switch_entry);
+        if let Err(e) = plugins::run(
+            &self.config.hooks,
+            HookPoint::PostSwitch,
+            &hook_account,
+            Some(&status_scope),
+        ) {
+            has_status_issue = true;
+            status_lines.push(format!("  {} {}", "⚠".yellow(), e));
+        }
+
         status_lines.push(format!(
-            "{}   Switched to '{}' ({})",
+            "{}   Switched to '{}' ({}){}",
             "✔".green(),
             status_username.cyan(),
-            status_scope.green()
+            status_scope.green(),
+            match confirmed {
+                Some(user) => format!(", git credential fill returns '{}'", redact_name(&user))
+                    .dimmed()
+                    .to_string(),
+                None => String::new(),
+            }
         ));
 
         raw_show_status(&status_lines, has_status_issue);
-        true
+        Some(true)
     }
 
-    fn handle_delete(&mut self) -> bool {
-        if self.config.accounts.is_empty() {
-            return false;
+    /// Show what switching to the selected account would change in the
+    /// repository (or the global config outside one), without changing it.
+    fn handle_diff(&self) {
+        let Some(account) = self
+            .selected_account()
+            .map(|index| &self.config.accounts[index])
+        else {
+            return;
+        };
+        let toplevel = crate::utils::git_toplevel();
+        let scope = if toplevel.is_some() {
+            "local"
+        } else {
+            "global"
+        };
+
+        // The value in the scope a switch writes, else the one git uses now.
+        let current = |key: &str| match git_config_get(key, scope) {
+            Some(value) => Some((value, None)),
+            None => git_config_get(key, "").map(|value| (value, Some("inherited"))),
+        };
+        let show = |value: &str, origin: Option<&str>| match origin {
+            Some(origin) => format!("{} {}", value, format!("({})", origin).dimmed()),
+            None => value.to_string(),
+        };
+        let redacted = |key: &str, value: &str| {
+            if key == "user.email" {
+                redact_email(value)
+            } else if key == "user.name" || key.ends_with(".username") {
+                redact_name(value)
+            } else {
+                value.to_string()
+            }
+        };
+
+        let mut lines = vec![format!(
+            "  Switching to '{}' ({}) would set:",
+            redact_name(&account.username).cyan(),
+            match &toplevel {
+                Some(path) => format!("local {}", path),
+                None => "global".to_string(),
+            }
+        )];
+        let mut changes = 0;
+        for (key, change) in switch_plan(account, &self.config.all_accounts(), |key| {
+            git_config_get(key, scope)
+        }) {
+            let before = current(&key);
+            match (&before, &change) {
+                (Some((old, None)), Some(new)) if old == new => {
+                    lines.push(format!(
+                        "    {} {}",
+                        key.dimmed(),
+                        redacted(&key, new).dimmed()
+                    ));
+                }
+                (_, Some(new)) => {
+                    changes += 1;
+                    if let Some((old, origin)) = &before {
+                        lines.push(format!(
+                            "  {} {} {}",
+                            "-".red(),
+                            key,
+                            show(&redacted(&key, old), *origin).red()
+                        ));
+                    }
+                    lines.push(format!(
+                        "  {} {} {}",
+                        "+".green(),
+                        key,
+                        redacted(&key, new).green()
+                    ));
+                }
+                (Some((old, None)), None) => {
+                    changes += 1;
+                    lines.push(format!(
+                        "  {} {} {}",
+                        "-".red(),
+                        key,
+                        redacted(&key, old).red()
+                    ));
+                }
+                (_, None) => {}
+            }
+        }
+
+        if changes == 0 {
+            lines.push(format!(
+                "  {}",
+                "Already matches; nothing would change.".dimmed()
+            ));
         }
+        raw_show_status(&lines, true);
+    }
 
-        // Prevent accessing unmanaged accounts
-        if self.cursor >= self.config.accounts.len() {
+    fn handle_delete(&mut self) -> bool {
+        if self.refuse_ephemeral() {
             return false;
         }
+        // Section headers and unmanaged identities can't be removed.
+        let Some(index) = self.selected_account() else {
+            return false;
+        };
 
-        let account = &self.config.accounts[self.cursor];
-        let prompt = format!("Remove account '{}'?", account.username.yellow());
+        let prompt = super::remove::confirm_prompt(self.config, index);
+        let ask = crate::confirm::policy(self.config, crate::confirm::Action::Delete)
+            != crate::confirm::Policy::Never;
 
-        if let Some(true) = raw_confirm(&prompt, false) {
-            let username = account.username.clone();
-            let alias = account.alias.clone();
-            crate::models::delete_token(&username, alias.as_deref());
-            self.config.accounts.remove(self.cursor);
-            save_config(self.config);
+        if !ask || raw_confirm(&prompt, false) == Some(true) {
+            super::remove::remove_account(self.config, index);
 
-            if self.cursor >= self.config.accounts.len() && self.cursor > 0 {
-                self.cursor -= 1;
-            }
+            self.cursor = self.cursor.min(self.rows().len().saturating_sub(1));
             true
         } else {
             false
@@ -620,57 +1189,240 @@ impl<'a> ListState<'a> {
     }
 
     fn handle_edit(&mut self) -> bool {
-        if self.config.accounts.is_empty() || self.cursor >= self.config.accounts.len() {
+        if self.refuse_ephemeral() {
             return false;
         }
-        self.apply_edit()
+        let Some(index) = self.selected_account() else {
+            return false;
+        };
+        self.apply_edit(index)
     }
 
-    fn apply_edit(&mut self) -> bool {
-        let mut temp_account = self.config.accounts[self.cursor].clone();
-        let original_username = temp_account.username.clone();
-        let original_alias = temp_account.alias.clone();
+    /// Set the host or group of several accounts at once. Esc at any step
+    /// goes back to the one before.
+    fn handle_batch_edit(&mut self) -> bool {
+        if self.refuse_ephemeral() || self.config.accounts.is_empty() {
+            return false;
+        }
+        let labels: Vec<String> = self
+            .config
+            .accounts
+            .iter()
+            .map(crate::utils::format_account_label)
+            .collect();
+        let mut checked: Vec<usize> = vec![self.selected_account().unwrap_or(0)];
+
+        'accounts: loop {
+            let Some(selection) = raw_multi_select("Accounts to edit", &labels, &checked) else {
+                return false;
+            };
+            checked = selection;
+            if checked.is_empty() {
+                continue;
+            }
 
-        let mut current_token =
-            crate::models::get_token(&original_username, original_alias.as_deref());
+            let fields = ["Host".to_string(), "Group".to_string()];
+            loop {
+                let Some(field) = raw_select("Field to set", &fields, 0) else {
+                    continue 'accounts;
+                };
+                let mut change = super::edit::BatchChange::default();
+                if field == 0 {
+                    let Some(host) = crate::utils::choose_host(self.config, "New Host", None)
+                    else {
+                        continue;
+                    };
+                    let provider = match &host {
+                        Some(host) => super::add::detect_provider(host),
+                        None => Some(crate::provider::ProviderKind::Github),
+                    };
+                    let Some(kind) = provider else {
+                        continue;
+                    };
+                    change.host = Some(host);
+                    change.provider = Some(Some(kind));
+                } else if self.config.groups.is_empty() {
+                    let Some(group) = raw_input("New Group", "") else {
+                        continue;
+                    };
+                    change.group = Some((!group.is_empty()).then_some(group));
+                } else {
+                    let mut groups = vec!["None".to_string()];
+                    groups.extend(self.config.groups.keys().cloned());
+                    let Some(selection) = raw_select("Group", &groups, 0) else {
+                        continue;
+                    };
+                    change.group = Some((selection > 0).then(|| groups[selection].clone()));
+                }
 
-        loop {
-            let fields = [
-                format!("{:<15} {}", "Username:".dimmed(), temp_account.username),
-                format!("{:<15} {}", "Email:".dimmed(), temp_account.email),
-                format!(
-                    "{:<15} {}",
-                    "Alias:".dimmed(),
-                    temp_account.alias.as_deref().unwrap_or("none")
-                ),
-                format!(
-                    "{:<15} {}",
-                    "Host:".dimmed(),
-                    temp_account.host.as_deref().unwrap_or("github.com")
-                ),
-                format!(
-                    "{:<15} {}",
-                    "Token:".dimmed(),
-                    if current_token.is_some() {
-                        "*******"
-                    } else {
-                        "none"
+                let prompt = format!("Apply to {} account(s)?", checked.len());
+                if raw_confirm(&prompt, true) != Some(true) {
+                    continue;
+                }
+                return match super::edit::commit_batch(self.config, &checked, &change) {
+                    Ok(changed) if changed.is_empty() => {
+                        raw_show_status(
+                            &[format!(
+                                "  {}",
+                                "The selected accounts already have this value.".dimmed()
+                            )],
+                            false,
+                        );
+                        false
                     }
-                ),
-                format!(
-                    "{:<15} {}",
-                    "SSH Key:".dimmed(),
-                    temp_account.ssh_key.as_deref().unwrap_or("none")
-                ),
-                "Save Changes".green().to_string(),
-                "Cancel".dimmed().to_string(),
+                    Ok(changed) => {
+                        let mut lines = vec![format!(
+                            "  {} Updated {} account(s)",
+                            "✓".green().bold(),
+                            changed.len()
+                        )];
+                        for (index, before) in &changed {
+                            let after = &self.config.accounts[*index];
+                            lines.push(format!(
+                                "    {} {}",
+                                crate::utils::format_account_label(after).cyan(),
+                                super::edit::BatchChange::describe(before, after).dimmed()
+                            ));
+                        }
+                        raw_show_status(&lines, false);
+                        true
+                    }
+                    Err(problems) => {
+                        let mut lines: Vec<String> = problems
+                            .iter()
+                            .map(|p| format!("  {} {}", "✗".red(), p))
+                            .collect();
+                        lines.push(format!("  {}", "No accounts were changed.".dimmed()));
+                        raw_show_status(&lines, true);
+                        false
+                    }
+                };
+            }
+        }
+    }
+
+    fn apply_edit(&mut self, index: usize) -> bool {
+        let original = self.config.accounts[index].clone();
+        let mut temp_account = original.clone();
+
+        let original_token =
+            crate::models::get_token(&original.username, original.alias.as_deref());
+        let mut current_token = original_token.clone();
+
+        loop {
+            let values = [
+                redact_name(&temp_account.username),
+                redact_email(&temp_account.email),
+                temp_account
+                    .alias
+                    .clone()
+                    .unwrap_or_else(|| "none".to_string()),
+                temp_account
+                    .host
+                    .clone()
+                    .unwrap_or_else(|| "github.com".to_string()),
+                if current_token.is_some() {
+                    "*******".to_string()
+                } else {
+                    "none".to_string()
+                },
+                temp_account
+                    .ssh_key
+                    .clone()
+                    .unwrap_or_else(|| "none".to_string()),
+                temp_account
+                    .gpg_key
+                    .clone()
+                    .unwrap_or_else(|| "none".to_string()),
+                if temp_account.ssh_only { "yes" } else { "no" }.to_string(),
+                temp_account
+                    .group
+                    .clone()
+                    .unwrap_or_else(|| "none".to_string()),
+                describe_packages(&temp_account.packages),
             ];
+            let changed = changed_fields(&original, &temp_account, current_token != original_token);
 
-            let items: Vec<String> = fields.iter().map(|s| s.to_string()).collect();
-            let Some(selection) = raw_select("Edit Account", &items, 0) else {
-                return false;
+            let mut items: Vec<String> = EDIT_FIELDS
+                .iter()
+                .zip(&values)
+                .enumerate()
+                .map(|(index, (label, value))| {
+                    let line = format!("{:<15} {}", format!("{}:", label).dimmed(), value);
+                    if changed.contains(&index) {
+                        format!("{} {}", line, "*".yellow().bold())
+                    } else {
+                        line
+                    }
+                })
+                .collect();
+            let revert_index = (!changed.is_empty()).then(|| {
+                items.push("Revert Field".yellow().to_string());
+                items.len() - 1
+            });
+            let save_index = items.len();
+            items.push("Save Changes".green().to_string());
+            items.push("Cancel".dimmed().to_string());
+
+            let selection = raw_select("Edit Account", &items, 0);
+            let Some(selection) = selection.filter(|&i| i != save_index + 1) else {
+                // Esc or Cancel: make sure edits, like a long token paste,
+                // aren't thrown away by a stray keypress.
+                if changed.is_empty() || crate::tui::interrupted() {
+                    return false;
+                }
+                let prompt = format!("Discard changes to {} field(s)?", changed.len());
+                if raw_confirm(&prompt, false) == Some(true) {
+                    return false;
+                }
+                continue;
             };
 
+            if Some(selection) == revert_index {
+                let mut names: Vec<String> = changed
+                    .iter()
+                    .map(|&index| EDIT_FIELDS[index].to_string())
+                    .collect();
+                names.push("All fields".to_string());
+                if let Some(choice) = raw_select("Revert", &names, 0) {
+                    let fields = match changed.get(choice) {
+                        Some(&index) => vec![index],
+                        None => changed.clone(),
+                    };
+                    for index in fields {
+                        if index == TOKEN_FIELD {
+                            current_token = original_token.clone();
+                        } else {
+                            revert_field(&mut temp_account, &original, index);
+                        }
+                    }
+                }
+                continue;
+            }
+            if selection == save_index {
+                let result = super::edit::commit(
+                    self.config,
+                    Some(index),
+                    temp_account.clone(),
+                    if current_token == original_token {
+                        super::edit::TokenChange::Keep
+                    } else {
+                        current_token.as_deref().map(String::as_str).into()
+                    },
+                );
+                match result {
+                    Ok(_) => return true,
+                    Err(problems) => {
+                        let lines: Vec<String> = problems
+                            .iter()
+                            .map(|p| format!("  {} {}", "✗".red(), p))
+                            .collect();
+                        raw_show_status(&lines, true);
+                    }
+                }
+                continue;
+            }
+
             match selection {
                 0 => {
                     if let Some(val) =
@@ -680,9 +1432,22 @@ impl<'a> ListState<'a> {
                     }
                 }
                 1 => {
-                    if let Some(val) =
+                    let emails = match current_token.clone() {
+                        Some(token) if !temp_account.ssh_only => {
+                            let provider = crate::provider::for_account(&temp_account);
+                            raw_with_loader("Fetching emails…", move || {
+                                provider.fetch_emails(&token).unwrap_or_default()
+                            })
+                            .unwrap_or_default()
+                        }
+                        _ => Vec::new(),
+                    };
+                    let selected = if emails.is_empty() {
                         raw_input("New Email", &temp_account.email).filter(|v| !v.is_empty())
-                    {
+                    } else {
+                        crate::utils::choose_email(&emails, &temp_account.email)
+                    };
+                    if let Some(val) = selected {
                         temp_account.email = val;
                     }
                 }
@@ -694,15 +1459,23 @@ impl<'a> ListState<'a> {
                     }
                 }
                 3 => {
-                    if let Some(val) = raw_input(
+                    // Esc on the forge type goes back to the host list.
+                    while let Some(host) = crate::utils::choose_host(
+                        self.config,
                         "New Host",
-                        temp_account.host.as_deref().unwrap_or("github.com"),
-                    ) {
-                        temp_account.host = if val == "github.com" || val.is_empty() {
-                            None
-                        } else {
-                            Some(val)
+                        temp_account.host.as_deref(),
+                    ) && host != temp_account.host
+                    {
+                        let provider = match &host {
+                            Some(host) => super::add::detect_provider(host),
+                            None => Some(crate::provider::ProviderKind::Github),
                         };
+                        if let Some(kind) = provider {
+                            temp_account.host = host;
+                            temp_account.provider =
+                                Some(kind).filter(|k| *k != crate::provider::ProviderKind::Github);
+                            break;
+                        }
                     }
                 }
                 4 => {
@@ -712,89 +1485,528 @@ impl<'a> ListState<'a> {
                 }
                 5 => {
                     let (display_items, paths, default_idx) =
-                        crate::utils::scan_ssh_keys(&temp_account.username, &temp_account.email);
-                    if let Some(selection) =
+                        crate::ssh::scan_keys(&temp_account.username, &temp_account.email);
+                    // Esc on the manual path goes back to the key list.
+                    while let Some(selection) =
                         raw_select("Keys in ~/.ssh", &display_items, default_idx)
                     {
                         if selection < paths.len() {
                             temp_account.ssh_key =
                                 Some(paths[selection].to_string_lossy().to_string());
                         } else if selection == paths.len() {
-                            let manual = raw_input(
+                            let Some(manual) = raw_input(
                                 "New SSH Key Path",
                                 temp_account.ssh_key.as_deref().unwrap_or(""),
-                            )
-                            .unwrap_or_default();
+                            ) else {
+                                continue;
+                            };
                             temp_account.ssh_key = if manual.is_empty() {
                                 None
                             } else {
                                 Some(manual)
                             };
                         }
+                        break;
                     }
                 }
                 6 => {
-                    if original_username != temp_account.username
-                        || original_alias != temp_account.alias
-                    {
-                        crate::models::delete_token(&original_username, original_alias.as_deref());
+                    if let Some(val) = raw_input(
+                        "New GPG Key ID",
+                        temp_account.gpg_key.as_deref().unwrap_or(""),
+                    ) {
+                        temp_account.gpg_key = if val.is_empty() { None } else { Some(val) };
                     }
-                    if let Some(t) = &current_token {
-                        crate::models::set_token(
-                            &temp_account.username,
-                            temp_account.alias.as_deref(),
-                            t,
-                        );
+                }
+                7 => {
+                    temp_account.ssh_only = !temp_account.ssh_only;
+                }
+                8 => {
+                    if self.config.groups.is_empty() {
+                        if let Some(val) =
+                            raw_input("New Group", temp_account.group.as_deref().unwrap_or(""))
+                        {
+                            temp_account.group = if val.is_empty() { None } else { Some(val) };
+                        }
                     } else {
-                        crate::models::delete_token(
-                            &temp_account.username,
-                            temp_account.alias.as_deref(),
-                        );
+                        let mut groups = vec!["None".to_string()];
+                        groups.extend(self.config.groups.keys().cloned());
+                        let current = temp_account
+                            .group
+                            .as_ref()
+                            .and_then(|g| groups.iter().position(|item| item == g))
+                            .unwrap_or(0);
+                        if let Some(selection) = raw_select("Group", &groups, current) {
+                            temp_account.group = (selection > 0).then(|| groups[selection].clone());
+                        }
                     }
-
-                    self.config.accounts[self.cursor] = temp_account;
-                    save_config(self.config);
-                    return true;
                 }
-                7 => return false,
+                9 => {
+                    edit_packages(&mut temp_account.packages);
+                }
                 _ => {}
             }
         }
     }
 }
 
+/// Rows of the account edit form, in order.
+const EDIT_FIELDS: [&str; 10] = [
+    "Username", "Email", "Alias", "Host", "Token", "SSH Key", "GPG Key", "SSH Only", "Group",
+    "Packages",
+];
+const TOKEN_FIELD: usize = 4;
+
+/// Indices into [`EDIT_FIELDS`] of the fields `edited` changed.
+fn changed_fields(
+    original: &crate::models::Account,
+    edited: &crate::models::Account,
+    token_changed: bool,
+) -> Vec<usize> {
+    [
+        original.username != edited.username,
+        original.email != edited.email,
+        original.alias != edited.alias,
+        original.host != edited.host || original.provider != edited.provider,
+        token_changed,
+        original.ssh_key != edited.ssh_key,
+        original.gpg_key != edited.gpg_key,
+        original.ssh_only != edited.ssh_only,
+        original.group != edited.group,
+        original.packages != edited.packages,
+    ]
+    .iter()
+    .enumerate()
+    .filter(|(_, changed)| **changed)
+    .map(|(index, _)| index)
+    .collect()
+}
+
+/// Put one field of the edit form back to its saved value. The token lives
+/// outside the account and is reverted by the caller.
+fn revert_field(
+    edited: &mut crate::models::Account,
+    original: &crate::models::Account,
+    index: usize,
+) {
+    match index {
+        0 => edited.username = original.username.clone(),
+        1 => edited.email = original.email.clone(),
+        2 => edited.alias = original.alias.clone(),
+        3 => {
+            edited.host = original.host.clone();
+            edited.provider = original.provider;
+        }
+        5 => edited.ssh_key = original.ssh_key.clone(),
+        6 => edited.gpg_key = original.gpg_key.clone(),
+        7 => edited.ssh_only = original.ssh_only,
+        8 => edited.group = original.group.clone(),
+        9 => edited.packages = original.packages.clone(),
+        _ => {}
+    }
+}
+
+fn describe_packages(sync: &crate::registry::PackageSync) -> String {
+    let mut parts = Vec::new();
+    if sync.npm {
+        parts.push("npm".to_string());
+    }
+    if let Some(name) = &sync.cargo_registry {
+        parts.push(format!("cargo:{}", name));
+    }
+    if let Some(id) = &sync.maven_server {
+        parts.push(format!("maven:{}", id));
+    }
+    if sync.gradle {
+        parts.push("gradle".to_string());
+    }
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Toggle which package registry configs follow the account.
+fn edit_packages(sync: &mut crate::registry::PackageSync) {
+    loop {
+        let items = vec![
+            format!(
+                "{:<15} {}",
+                "npm:".dimmed(),
+                if sync.npm { "sync ~/.npmrc" } else { "off" }
+            ),
+            format!(
+                "{:<15} {}",
+                "Cargo:".dimmed(),
+                sync.cargo_registry.as_deref().unwrap_or("off")
+            ),
+            format!(
+                "{:<15} {}",
+                "Maven:".dimmed(),
+                sync.maven_server.as_deref().unwrap_or("off")
+            ),
+            format!(
+                "{:<15} {}",
+                "Gradle:".dimmed(),
+                if sync.gradle {
+                    "sync gpr.user/gpr.key"
+                } else {
+                    "off"
+                }
+            ),
+            "Done".green().to_string(),
+        ];
+        match raw_select("Package registries", &items, 0) {
+            Some(0) => sync.npm = !sync.npm,
+            Some(1) => {
+                if let Some(name) = raw_input(
+                    "Cargo registry name (empty for off)",
+                    sync.cargo_registry.as_deref().unwrap_or(""),
+                ) {
+                    sync.cargo_registry = (!name.is_empty()).then_some(name);
+                }
+            }
+            Some(2) => {
+                if let Some(id) = raw_input(
+                    "Maven server id (empty for off)",
+                    sync.maven_server.as_deref().unwrap_or("github"),
+                ) {
+                    sync.maven_server = (!id.is_empty()).then_some(id);
+                }
+            }
+            Some(3) => sync.gradle = !sync.gradle,
+            _ => return,
+        }
+    }
+}
+
 // ─── Git identity ───────────────────────────────────────────────────────────
 
 struct GitIdentity {
     global_name: Option<String>,
     global_email: Option<String>,
     global_alias: Option<String>,
+    /// `(host, username)` from `credential.https://<host>.username`.
+    global_credentials: Vec<(String, String)>,
     local_name: Option<String>,
     local_email: Option<String>,
     local_alias: Option<String>,
+    local_credentials: Vec<(String, String)>,
+    /// `GIT_AUTHOR_*`/`GIT_COMMITTER_*` overrides, which beat every config scope.
+    env: Vec<(&'static str, String)>,
 }
 
 impl GitIdentity {
-    fn fetch() -> Self {
+    fn fetch(accounts: &[crate::models::Account]) -> Self {
+        let mut hosts: Vec<String> = accounts.iter().map(host_key).collect();
+        hosts.sort();
+        hosts.dedup();
+        let credentials = |scope: &str| {
+            hosts
+                .iter()
+                .filter_map(|host| {
+                    git_config_get(&format!("credential.https://{}.username", host), scope)
+                        .map(|user| (host.clone(), user))
+                })
+                .collect()
+        };
         Self {
             global_name: git_config_get("user.name", "global"),
             global_email: git_config_get("user.email", "global"),
             global_alias: git_config_get("gitas.alias", "global"),
+            global_credentials: credentials("global"),
             local_name: git_config_get("user.name", "local"),
             local_email: git_config_get("user.email", "local"),
             local_alias: git_config_get("gitas.alias", "local"),
+            local_credentials: credentials("local"),
+            env: crate::utils::identity_env_overrides(),
         }
     }
 
     fn has_local(&self) -> bool {
         self.local_name.is_some() || self.local_email.is_some()
     }
+
+    /// Accounts the local (or global) identity could belong to; more than one
+    /// when accounts share a name and email that nothing else tells apart.
+    fn matches(&self, accounts: &[crate::models::Account], local: bool) -> Vec<usize> {
+        let (name, email, alias, credentials) = if local {
+            if !self.has_local() {
+                return Vec::new();
+            }
+            (
+                &self.local_name,
+                &self.local_email,
+                &self.local_alias,
+                &self.local_credentials,
+            )
+        } else {
+            (
+                &self.global_name,
+                &self.global_email,
+                &self.global_alias,
+                &self.global_credentials,
+            )
+        };
+        match (name, email) {
+            (Some(name), Some(email)) => {
+                identity_matches(accounts, name, email, alias.as_deref(), credentials)
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Indices of the accounts with this name and email, narrowed by the
+/// `gitas.alias` a switch wrote and then by the credential username for
+/// each account's host. Each step only applies when something is left.
+fn identity_matches(
+    accounts: &[crate::models::Account],
+    name: &str,
+    email: &str,
+    alias: Option<&str>,
+    credentials: &[(String, String)],
+) -> Vec<usize> {
+    let mut candidates: Vec<usize> = accounts
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.username == name && a.email == email)
+        .map(|(index, _)| index)
+        .collect();
+    let narrow = |candidates: &mut Vec<usize>, keep: &dyn Fn(&crate::models::Account) -> bool| {
+        let kept: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&index| keep(&accounts[index]))
+            .collect();
+        if !kept.is_empty() {
+            *candidates = kept;
+        }
+    };
+    if candidates.len() > 1 {
+        narrow(&mut candidates, &|a| a.alias.as_deref() == alias);
+    }
+    if candidates.len() > 1 {
+        narrow(&mut candidates, &|a| {
+            let host = host_key(a);
+            credentials
+                .iter()
+                .any(|(h, user)| *h == host && user.eq_ignore_ascii_case(&a.username))
+        });
+    }
+    candidates
+}
+
+/// Host of an account as its section is keyed.
+fn host_key(account: &crate::models::Account) -> String {
+    account
+        .host
+        .as_deref()
+        .unwrap_or("github.com")
+        .to_lowercase()
+}
+
+/// Account indices per host, hosts in the order they first appear.
+fn host_groups(accounts: &[crate::models::Account]) -> Vec<(String, Vec<usize>)> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, account) in accounts.iter().enumerate() {
+        let host = host_key(account);
+        match groups.iter_mut().find(|(h, _)| *h == host) {
+            Some((_, indices)) => indices.push(index),
+            None => groups.push((host, vec![index])),
+        }
+    }
+    groups
+}
+
+/// Section header: a fold marker, the host in its section color and the
+/// number of accounts on it.
+fn format_host_line(
+    host: &str,
+    order: usize,
+    count: usize,
+    collapsed: bool,
+    is_current: bool,
+) -> String {
+    let pointer = if is_current {
+        ">".yellow().bold().to_string()
+    } else {
+        " ".to_string()
+    };
+    let name = match order % 4 {
+        0 => host.blue(),
+        1 => host.magenta(),
+        2 => host.green(),
+        _ => host.cyan(),
+    };
+    format!(
+        "{} {} {} {}",
+        pointer,
+        if collapsed { "▸" } else { "▾" }.dimmed(),
+        name.bold(),
+        format!("({})", count).dimmed()
+    )
+}
+
+/// Config a switch writes (`Some`) or removes (`None`), in order. An SSH
+/// command is only removed when gitas wrote it for some account.
+pub(crate) fn switch_plan(
+    account: &crate::models::Account,
+    accounts: &[crate::models::Account],
+    current: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, Option<String>)> {
+    // A plus address the repository was given stays.
+    let email = current("user.email")
+        .filter(|email| crate::plus::owns(account, email))
+        .unwrap_or_else(|| account.email.clone());
+    let mut plan = vec![
+        ("user.name".to_string(), Some(account.username.clone())),
+        ("user.email".to_string(), Some(email)),
+        ("gitas.alias".to_string(), account.alias.clone()),
+    ];
+    if let Some(ssh_key) = &account.ssh_key {
+        plan.push((
+            "core.sshCommand".to_string(),
+            Some(crate::ssh::command(ssh_key)),
+        ));
+    } else if current("core.sshCommand").is_some_and(|current| {
+        accounts
+            .iter()
+            .filter_map(|a| a.ssh_key.as_deref())
+            .any(|key| crate::ssh::command(key) == current)
+    }) {
+        plan.push(("core.sshCommand".to_string(), None));
+    }
+    let signing = crate::signing::settings(account);
+    if !signing.is_empty() {
+        plan.extend(signing.into_iter().map(|(key, value)| (key, Some(value))));
+    } else if current("user.signingkey")
+        .is_some_and(|key| crate::signing::is_managed(accounts, &key))
+    {
+        plan.extend(crate::signing::KEYS.map(|key| (key.to_string(), None)));
+    }
+    // SSH-only accounts authenticate through core.sshCommand alone.
+    if !account.ssh_only {
+        let host = account.host.as_deref().unwrap_or("github.com");
+        plan.push((
+            format!("credential.https://{}.username", host),
+            Some(account.username.clone()),
+        ));
+    }
+    plan
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{changed_fields, host_groups, identity_matches, revert_field, switch_plan};
+    use crate::models::Account;
     use crate::tui::{truncate_rendered_line, visible_line_width};
 
+    #[test]
+    fn tracks_and_reverts_edited_fields() {
+        let original = Account {
+            username: "octo".to_string(),
+            email: "o@x.com".to_string(),
+            ..Default::default()
+        };
+        let mut edited = original.clone();
+        edited.email = "new@x.com".to_string();
+        edited.host = Some("ghe.corp".to_string());
+        assert_eq!(changed_fields(&original, &edited, true), [1, 3, 4]);
+
+        revert_field(&mut edited, &original, 3);
+        assert_eq!(changed_fields(&original, &edited, false), [1]);
+    }
+
+    #[test]
+    fn groups_accounts_by_host_in_first_seen_order() {
+        let on = |host: Option<&str>| Account {
+            host: host.map(str::to_string),
+            ..Default::default()
+        };
+        let accounts = [
+            on(Some("gitlab.com")),
+            on(None),
+            on(Some("GitLab.com")),
+            on(Some("github.com")),
+        ];
+        assert_eq!(
+            host_groups(&accounts),
+            [
+                ("gitlab.com".to_string(), vec![0, 2]),
+                ("github.com".to_string(), vec![1, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn narrows_shared_identities_by_alias_then_credential() {
+        let account = |alias: Option<&str>, host: Option<&str>| Account {
+            username: "octo".to_string(),
+            email: "o@x.com".to_string(),
+            alias: alias.map(str::to_string),
+            host: host.map(str::to_string),
+            ..Default::default()
+        };
+        let accounts = [
+            account(Some("work"), None),
+            account(Some("bot"), Some("ghe.corp")),
+        ];
+        assert_eq!(
+            identity_matches(&accounts, "octo", "o@x.com", None, &[]),
+            [0, 1]
+        );
+        assert_eq!(
+            identity_matches(&accounts, "octo", "o@x.com", Some("bot"), &[]),
+            [1]
+        );
+        let credentials = [("github.com".to_string(), "octo".to_string())];
+        assert_eq!(
+            identity_matches(&accounts, "octo", "o@x.com", None, &credentials),
+            [0]
+        );
+        assert!(identity_matches(&accounts, "octo", "other@x.com", None, &[]).is_empty());
+    }
+
+    #[test]
+    fn switch_plan_only_drops_settings_gitas_wrote() {
+        let account = Account {
+            username: "octo".to_string(),
+            email: "o@x.com".to_string(),
+            host: Some("ghe.corp".to_string()),
+            ..Default::default()
+        };
+        let other = Account {
+            ssh_key: Some("/k".to_string()),
+            signing_key: Some("/k.pub".to_string()),
+            ..Default::default()
+        };
+        let accounts = [account.clone(), other];
+        let managed = |key: &str| match key {
+            "core.sshCommand" => Some(crate::ssh::command("/k")),
+            "user.signingkey" => Some("/k.pub".to_string()),
+            _ => None,
+        };
+        let plan = switch_plan(&account, &accounts, managed);
+        assert!(plan.contains(&("core.sshCommand".to_string(), None)));
+        assert!(plan.contains(&("commit.gpgsign".to_string(), None)));
+        assert!(plan.contains(&("gitas.alias".to_string(), None)));
+        assert_eq!(
+            plan.last().unwrap(),
+            &(
+                "credential.https://ghe.corp.username".to_string(),
+                Some("octo".to_string())
+            )
+        );
+        let plan = switch_plan(&account, &accounts, |key| match key {
+            "core.sshCommand" => Some("ssh -v".to_string()),
+            "user.signingkey" => Some("ABCD1234".to_string()),
+            _ => None,
+        });
+        assert!(!plan.iter().any(|(key, _)| key == "core.sshCommand"));
+        assert!(!plan.iter().any(|(key, _)| key == "commit.gpgsign"));
+    }
+
     #[test]
     fn truncates_rows_before_the_terminal_wrap_column() {
         let line = truncate_rendered_line("0123456789", 6);
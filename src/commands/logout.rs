@@ -0,0 +1,110 @@
+use crate::models::{Account, Config, delete_token, get_token};
+use crate::utils::{format_account_label, git_credential_reject, resolve_account};
+use colored::Colorize;
+
+/// `gitas logout`: forget an account's token everywhere gitas put it, and
+/// with `revoke` invalidate it at the forge too. The account itself stays.
+pub fn run(config: &Config, account_id: Option<String>, revoke: bool) {
+    let account = resolve_account(config, account_id, "  Log out");
+    let label = format_account_label(&account);
+    let host = account.host.as_deref().unwrap_or("github.com");
+    if account.ssh_only {
+        println!(
+            "\n  {}\n",
+            format!("{} is SSH-only and has no token to log out.", label).dimmed()
+        );
+        return;
+    }
+
+    let token = get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty());
+    println!();
+    if token.is_some() {
+        delete_token(&account.username, account.alias.as_deref());
+        crate::audit::record_token(&account, "removed");
+        println!(
+            "  {} Deleted the token from the keychain",
+            "✓".green().bold()
+        );
+    } else {
+        println!("  {}", "No token in the keychain".dimmed());
+    }
+
+    match crate::provider::credential_usernames(&account, None)
+        .iter()
+        .try_for_each(|username| git_credential_reject(username, host))
+    {
+        Ok(()) => println!(
+            "  {} Cleared git's stored credentials for {}",
+            "✓".green().bold(),
+            host
+        ),
+        Err(e) => println!("  {} {}", "⚠".yellow(), e),
+    }
+
+    if revoke {
+        match &token {
+            Some(token) if host == "github.com" => match crate::github::revoke_token(token) {
+                Ok(()) => {
+                    crate::audit::record_token(&account, "revoked");
+                    println!("  {} Revoked the token on GitHub", "✓".green().bold())
+                }
+                Err(e) => {
+                    println!("  {} {}", "⚠".yellow(), e);
+                    open_settings(&account, token);
+                }
+            },
+            Some(token) => open_settings(&account, token),
+            None => println!(
+                "  {}",
+                "Nothing to revoke: gitas had no token for this account".dimmed()
+            ),
+        }
+    }
+
+    println!(
+        "\n  {} is logged out. {}\n",
+        label.cyan(),
+        "Add a token again with `gitas edit` or `gitas token paste`.".dimmed()
+    );
+}
+
+/// Send the user to the page where the token can be revoked by hand.
+fn open_settings(account: &Account, token: &str) {
+    let url = settings_url(account, token);
+    println!("  {} Revoke it here: {}", "→".cyan(), url.underline());
+    crate::browser::open_url(&url);
+}
+
+/// Forge page listing the tokens (or, for gitas' own OAuth login, the app
+/// authorization) that `token` would appear on.
+fn settings_url(account: &Account, token: &str) -> String {
+    crate::provider::for_account(account).token_settings_url(token, &account.orgs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderKind;
+
+    #[test]
+    fn settings_url_follows_token_kind_and_forge() {
+        let github = Account::default();
+        assert_eq!(
+            settings_url(&github, "gho_abc"),
+            "https://github.com/settings/applications"
+        );
+        assert_eq!(
+            settings_url(&github, "ghp_abc"),
+            "https://github.com/settings/tokens"
+        );
+        let gitlab = Account {
+            host: Some("gitlab.example.com".to_string()),
+            provider: Some(ProviderKind::Gitlab),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings_url(&gitlab, "glpat-abc"),
+            "https://gitlab.example.com/-/user_settings/personal_access_tokens"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use crate::migrate::{CONFIG_VERSION, extras};
+use crate::models::{config_path, parse_config, save_config};
+use colored::Colorize;
+use std::fs;
+
+pub fn run(dry_run: bool) {
+    let path = config_path();
+    let Ok(data) = fs::read_to_string(path) else {
+        println!(
+            "\n  {}\n",
+            "No config file yet; nothing to migrate.".dimmed()
+        );
+        return;
+    };
+    let (config, changes) = match parse_config(&data) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!(
+                "\n  {} Could not parse {}: {}\n",
+                "✗".red().bold(),
+                path.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    println!();
+    if config.version > CONFIG_VERSION {
+        println!(
+            "  {} Config is version {}, newer than this gitas ({}). Leaving it as is.",
+            "⚠".yellow(),
+            config.version,
+            CONFIG_VERSION
+        );
+    } else if changes.is_empty() {
+        println!(
+            "  {} Config is up to date (version {}).",
+            "✓".green().bold(),
+            CONFIG_VERSION
+        );
+    } else {
+        println!("  {}", "Changes:".bold());
+        for change in &changes {
+            println!(
+                "    {} {}  {}",
+                "~".yellow(),
+                change.path,
+                change.description.dimmed()
+            );
+        }
+    }
+
+    let extras = extras(&config);
+    if !extras.is_empty() {
+        println!("\n  {}", "Unknown fields (kept as is):".bold());
+        for field in &extras {
+            println!("    {} {}", "=".dimmed(), field);
+        }
+    }
+
+    if changes.is_empty() {
+        println!();
+        return;
+    }
+    if dry_run {
+        println!("\n  Run {} to apply.\n", "gitas migrate".cyan().bold());
+        return;
+    }
+
+    let backup = path.with_extension("json.bak");
+    if let Err(e) = fs::write(&backup, &data) {
+        eprintln!(
+            "\n  {} Could not write backup {}: {}\n",
+            "✗".red().bold(),
+            backup.display(),
+            e
+        );
+        std::process::exit(1);
+    }
+    save_config(&config);
+    println!(
+        "\n  {} Migrated to version {}. Previous config saved to {}.\n",
+        "✓".green().bold(),
+        CONFIG_VERSION,
+        backup.display().to_string().dimmed()
+    );
+}
@@ -1,3 +1,51 @@
 pub mod add;
+pub mod audit;
+pub mod backup;
+pub mod bind;
+pub mod cert;
+pub mod check_push;
+pub mod clone;
+pub mod confirm;
+pub mod credential;
+pub mod default;
+pub mod detect;
+pub mod edit;
+pub mod env;
+pub mod exec;
+pub mod foreach;
+pub mod fsck;
+pub mod gh;
 pub mod git;
+pub mod gpg;
+pub mod group;
+pub mod helper;
+pub mod history;
+pub mod hook;
+pub mod import_history;
+pub mod inbox;
+pub mod insights;
 pub mod list;
+pub mod logout;
+pub mod migrate;
+pub mod notify;
+pub mod open;
+pub mod packages;
+pub mod pr;
+pub mod preserve_author;
+pub mod profile;
+pub mod provision;
+pub mod refresh;
+pub mod remote;
+pub mod remove;
+pub mod resolve;
+pub mod rule;
+pub mod sane;
+pub mod schema;
+pub mod selftest;
+pub mod ssh;
+pub mod status;
+pub mod token;
+pub mod version;
+pub mod warnings;
+pub mod whoami;
+pub mod worktrees;
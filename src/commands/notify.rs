@@ -0,0 +1,54 @@
+use crate::models::{Config, save_config};
+use crate::notify::{self, NotifyEvent};
+use clap::ValueEnum;
+use colored::Colorize;
+
+/// `gitas notify`: which events raise a desktop notification.
+pub fn list(config: &Config) {
+    println!();
+    for event in NotifyEvent::value_variants() {
+        let on = config.notifications.contains(event);
+        println!(
+            "  {} {:<8} {}",
+            if on { "●".green() } else { "○".dimmed() },
+            event.name(),
+            event
+                .to_possible_value()
+                .and_then(|v| v.get_help().map(|h| h.to_string()))
+                .unwrap_or_default()
+                .dimmed()
+        );
+    }
+    println!(
+        "\n  {}\n",
+        "Useful when switches and pushes happen outside the account list.".dimmed()
+    );
+}
+
+/// Turn notifications for an event on or off.
+pub fn set(config: &mut Config, event: NotifyEvent, on: bool) {
+    if on {
+        config.notifications.insert(event);
+    } else {
+        config.notifications.remove(&event);
+    }
+    save_config(config);
+    println!(
+        "\n  {} Notifications on '{}' {}.\n",
+        "✓".green().bold(),
+        event.name().cyan(),
+        if on { "enabled" } else { "disabled" }
+    );
+}
+
+/// `gitas notify test`: show a notification now, to check the platform's
+/// notifier works.
+pub fn test() {
+    match notify::send("gitas", "Notifications are working.") {
+        Ok(()) => println!("\n  {} Sent a test notification.\n", "✓".green().bold()),
+        Err(e) => {
+            eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
+}
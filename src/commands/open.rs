@@ -0,0 +1,45 @@
+use crate::utils::{choose_remote, get_remotes, remote_web_url};
+use colored::Colorize;
+
+pub fn run(remote: Option<String>) {
+    let remotes = get_remotes();
+    let selected = match &remote {
+        Some(name) => remotes.iter().find(|r| &r.name == name).cloned(),
+        None => choose_remote("Open which remote?"),
+    };
+
+    let Some(selected) = selected else {
+        match remote {
+            Some(name) => eprintln!(
+                "\n  {} No remote named '{}'.\n",
+                "✗".red().bold(),
+                name.yellow()
+            ),
+            None if remotes.is_empty() => {
+                eprintln!("\n  {} This repository has no remotes.\n", "✗".red().bold())
+            }
+            None => eprintln!(
+                "\n  {} Several remotes and none is origin. Name one: {}\n",
+                "✗".red().bold(),
+                remotes
+                    .iter()
+                    .map(|r| r.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+        std::process::exit(1);
+    };
+
+    let Some(url) = remote_web_url(&selected.url) else {
+        eprintln!(
+            "\n  {} Can't derive a web URL from '{}'.\n",
+            "✗".red().bold(),
+            selected.url
+        );
+        std::process::exit(1);
+    };
+
+    println!("  {} {}", "\u{21b7}".dimmed(), url);
+    crate::browser::open_url(&url);
+}
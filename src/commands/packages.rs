@@ -0,0 +1,77 @@
+use crate::models::{Config, get_token};
+use crate::registry;
+use crate::utils::resolve_account;
+use colored::Colorize;
+
+/// Write (or with `dry_run`, preview) the account's package registry credentials.
+pub fn sync(config: &Config, account_id: Option<String>, dry_run: bool) {
+    let account = resolve_account(config, account_id, "  Sync packages for");
+    if account.packages.is_empty() {
+        println!(
+            "\n  {} has no package registries enabled. Turn them on with {}.\n",
+            account.username.cyan(),
+            "gitas edit <account> --npm true / --cargo-registry / --maven-server / --gradle true"
+                .cyan()
+        );
+        return;
+    }
+    let Some(token) =
+        get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+    else {
+        eprintln!(
+            "\n  {} {} has no token to sync.\n",
+            "✗".red().bold(),
+            account.username.cyan()
+        );
+        std::process::exit(1);
+    };
+
+    let edits = match registry::plan(&account, &token) {
+        Ok(edits) => edits,
+        Err(e) => {
+            eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    };
+
+    println!();
+    for edit in &edits {
+        if edit.before == edit.after {
+            println!(
+                "  {} {} {}",
+                "○".dimmed(),
+                edit.label,
+                "(up to date)".dimmed()
+            );
+            continue;
+        }
+        if dry_run {
+            println!("  {} {}", "~".yellow(), edit.path.display());
+            for line in registry::diff(&edit.before, &edit.after) {
+                let line = if line.starts_with('+') {
+                    line.green()
+                } else {
+                    line.red()
+                };
+                println!("    {}", line);
+            }
+        }
+    }
+
+    if dry_run {
+        println!("\n  {}\n", "Dry run: nothing was written.".dimmed());
+        return;
+    }
+    match registry::sync(&account, &token) {
+        Ok(updated) => {
+            for label in updated {
+                println!("  {} Updated {}", "✓".green().bold(), label);
+            }
+            println!();
+        }
+        Err(e) => {
+            eprintln!("  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
+}
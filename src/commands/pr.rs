@@ -0,0 +1,122 @@
+use crate::models::{Config, get_token};
+use crate::resolve::account_for_cwd;
+use crate::utils::{choose_remote, get_remotes, parse_remote_url};
+use colored::{ColoredString, Colorize};
+
+pub fn status(config: &mut Config, account_id: Option<String>) {
+    let Some(branch) = crate::utils::git_current_branch() else {
+        fail("Not on a branch.");
+    };
+    let Some(remote) = choose_remote("Which remote's pull request?") else {
+        if get_remotes().is_empty() {
+            fail("This repository has no remotes.");
+        }
+        fail("Several remotes and none is origin; check out a branch that tracks one.");
+    };
+    let Some((_, repo)) = parse_remote_url(&remote.url) else {
+        fail(&format!("Can't tell the repository from '{}'.", remote.url));
+    };
+
+    let account = account_for_cwd(config, account_id, "  Look up as");
+    let Some(token) = get_token(&account.username, account.alias.as_deref())
+        .filter(|token| !token.is_empty() && !account.ssh_only)
+    else {
+        fail(&format!(
+            "{} has no token; pull request status needs API access.",
+            account.username
+        ));
+    };
+
+    // The branch's push remote may be a fork; its owner is the head's.
+    let (push_remote, _) = crate::push_check::push_target(&[]);
+    let head_owner = get_remotes()
+        .into_iter()
+        .find(|r| r.name == push_remote)
+        .and_then(|r| parse_remote_url(&r.url))
+        .map(|(_, path)| path)
+        .unwrap_or_else(|| repo.clone());
+    let head_owner = head_owner.split('/').next().unwrap_or_default();
+
+    let provider = crate::provider::for_account(&account);
+    let pull = match provider.pull_request_status(&token, &repo, head_owner, &branch) {
+        Ok(Some(pull)) => pull,
+        Ok(None) => {
+            println!(
+                "\n  {} No open pull request for {} in {}.\n",
+                "○".dimmed(),
+                branch.cyan(),
+                repo
+            );
+            return;
+        }
+        Err(e) => fail(&e),
+    };
+
+    println!();
+    println!(
+        "  {} {}{}",
+        format!("#{}", pull.number).bold(),
+        pull.title,
+        if pull.draft {
+            " (draft)".dimmed().to_string()
+        } else {
+            String::new()
+        }
+    );
+    println!("  {}", pull.url.dimmed());
+    println!();
+
+    if pull.reviews.is_empty() {
+        println!("  {:<9} {}", "Reviews:".dimmed(), "none yet".dimmed());
+    } else {
+        println!("  {}", "Reviews:".dimmed());
+        for (login, state) in &pull.reviews {
+            println!("    {} {}", review_marker(state), login);
+        }
+    }
+
+    if pull.checks.is_empty() {
+        println!("  {:<9} {}", "Checks:".dimmed(), "none reported".dimmed());
+    } else {
+        println!("  {}", "Checks:".dimmed());
+        for (name, outcome) in &pull.checks {
+            println!(
+                "    {} {} {}",
+                check_marker(outcome),
+                name,
+                outcome.dimmed()
+            );
+        }
+    }
+    println!(
+        "\n  {}\n",
+        format!(
+            "as {} <{}>",
+            crate::utils::redact_name(&account.username),
+            crate::utils::redact_email(&account.email)
+        )
+        .dimmed()
+    );
+}
+
+fn review_marker(state: &str) -> ColoredString {
+    match state {
+        "APPROVED" => "✔ approved".green(),
+        "CHANGES_REQUESTED" => "✗ changes requested".red(),
+        "DISMISSED" => "○ dismissed".dimmed(),
+        other => other.to_lowercase().yellow(),
+    }
+}
+
+fn check_marker(outcome: &str) -> ColoredString {
+    match outcome {
+        "success" | "neutral" | "skipped" => "✔".green(),
+        "failure" | "error" | "timed_out" | "cancelled" | "action_required" => "✗".red(),
+        _ => "●".yellow(),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
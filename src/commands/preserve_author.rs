@@ -0,0 +1,25 @@
+use crate::authorship::PreserveAuthor;
+use crate::models::{Config, save_config};
+use colored::Colorize;
+
+/// `gitas preserve-author [mode]`: show or change what happens when a commit
+/// being amended or continued belongs to someone else.
+pub fn run(config: &mut Config, mode: Option<PreserveAuthor>) {
+    let name = |mode: PreserveAuthor| format!("{:?}", mode).to_lowercase();
+    let Some(mode) = mode else {
+        let current = config.preserve_author.unwrap_or_default();
+        println!(
+            "\n  Preserve author: {} {}\n",
+            name(current).cyan(),
+            "(ask, always or never)".dimmed()
+        );
+        return;
+    };
+    config.preserve_author = (mode != PreserveAuthor::default()).then_some(mode);
+    save_config(config);
+    println!(
+        "\n  {} Preserve author set to {}.\n",
+        "✓".green().bold(),
+        name(mode).cyan()
+    );
+}
@@ -0,0 +1,159 @@
+use crate::models::{Account, Config, save_config};
+use crate::utils::{find_account_index, format_account_label};
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+/// Accounts by profile; those without one are left out.
+fn profiles(config: &Config) -> BTreeMap<&str, Vec<&Account>> {
+    let mut profiles: BTreeMap<&str, Vec<&Account>> = BTreeMap::new();
+    for account in &config.accounts {
+        if let Some(profile) = &account.profile {
+            profiles.entry(profile).or_default().push(account);
+        }
+    }
+    profiles
+}
+
+/// `gitas profile`: each profile with its accounts, marking `active`.
+pub fn list(config: &Config, active: Option<&str>) {
+    let profiles = profiles(config);
+    if profiles.is_empty() {
+        println!("\n  {}\n", "No profiles yet.".dimmed());
+        println!(
+            "  Run {} to start one.\n",
+            "gitas profile add <name> <account>...".cyan().bold()
+        );
+        return;
+    }
+
+    println!();
+    for (name, accounts) in &profiles {
+        let marker = if Some(*name) == active {
+            " (active)".cyan().to_string()
+        } else {
+            String::new()
+        };
+        println!("  {}{}", name.bold(), marker);
+        for account in accounts {
+            println!("    {}", format_account_label(account));
+        }
+        println!();
+    }
+    let loose: Vec<&Account> = config
+        .accounts
+        .iter()
+        .filter(|a| a.profile.is_none())
+        .collect();
+    if !loose.is_empty() {
+        println!("  {}", "No profile".dimmed());
+        for account in loose {
+            println!("    {}", format_account_label(account).dimmed());
+        }
+        println!();
+    }
+    if let Some(active) = active
+        && !profiles.contains_key(active)
+    {
+        println!(
+            "  {} No account is in profile '{}' yet.\n",
+            "⚠".yellow(),
+            active
+        );
+    }
+}
+
+fn index_of(config: &Config, id: &str) -> usize {
+    find_account_index(config, id)
+        .unwrap_or_else(|| fail(format!("No account matching '{}'.", id.yellow())))
+}
+
+/// Put accounts in `profile`, taking them out of any other.
+pub fn add(config: &mut Config, profile: String, accounts: Vec<String>) {
+    let profile = profile.trim().to_string();
+    if profile.is_empty() {
+        fail("A profile needs a name.".to_string());
+    }
+    let indices: Vec<usize> = accounts.iter().map(|id| index_of(config, id)).collect();
+    for &index in &indices {
+        config.accounts[index].profile = Some(profile.clone());
+    }
+    save_config(config);
+    println!();
+    for index in indices {
+        println!(
+            "  {} {} is in profile '{}'",
+            "✓".green().bold(),
+            format_account_label(&config.accounts[index]).cyan(),
+            profile
+        );
+    }
+    println!(
+        "\n  {}\n",
+        format!("Use them alone with `gitas --profile {}`.", profile).dimmed()
+    );
+}
+
+/// Take accounts out of `profile`, or every account when none are named.
+/// The accounts themselves stay.
+pub fn remove(config: &mut Config, profile: String, accounts: Vec<String>) {
+    let named: Vec<usize> = if accounts.is_empty() {
+        (0..config.accounts.len()).collect()
+    } else {
+        accounts.iter().map(|id| index_of(config, id)).collect()
+    };
+    let indices: Vec<usize> = named
+        .into_iter()
+        .filter(|&i| config.accounts[i].profile.as_deref() == Some(profile.as_str()))
+        .collect();
+    if indices.is_empty() {
+        fail(format!(
+            "No account to take out of profile '{}'.",
+            profile.yellow()
+        ));
+    }
+    for &index in &indices {
+        config.accounts[index].profile = None;
+    }
+    save_config(config);
+    println!();
+    for index in indices {
+        println!(
+            "  {} {} left profile '{}'",
+            "✓".green().bold(),
+            format_account_label(&config.accounts[index]).cyan(),
+            profile
+        );
+    }
+    println!();
+}
+
+pub fn rename(config: &mut Config, from: String, to: String) {
+    let to = to.trim().to_string();
+    if to.is_empty() {
+        fail("A profile needs a name.".to_string());
+    }
+    let mut moved = 0;
+    for account in &mut config.accounts {
+        if account.profile.as_deref() == Some(from.as_str()) {
+            account.profile = Some(to.clone());
+            moved += 1;
+        }
+    }
+    if moved == 0 {
+        fail(format!("No profile named '{}'.", from.yellow()));
+    }
+    save_config(config);
+    println!(
+        "\n  {} Profile '{}' is now '{}' ({} {}).\n",
+        "✓".green().bold(),
+        from,
+        to.cyan(),
+        moved,
+        if moved == 1 { "account" } else { "accounts" }
+    );
+}
@@ -0,0 +1,347 @@
+use crate::models::{Config, save_config};
+use crate::provider::{self, ProviderKind};
+use crate::utils::{find_account, format_account_label};
+use colored::Colorize;
+use serde::Deserialize;
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+/// A machine's gitas setup, read by `gitas provision`:
+///
+/// ```toml
+/// default = "octo:work"
+///
+/// [[pins]]
+/// host = "ghe.corp.com"
+/// fingerprint = "32:B7:09:...:9E:2D"
+///
+/// [[accounts]]
+/// username = "octo"
+/// alias = "work"
+/// email = "octo@corp.com"
+/// host = "ghe.corp.com"
+/// timezone = "America/New_York"
+/// token_env = "GHE_TOKEN"
+/// rules = ["~/work/"]
+///
+/// [hooks]
+/// pre_push = ["~/work/app"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    /// Account made the default, as username, alias or username:alias.
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    pins: Vec<ManifestPin>,
+    #[serde(default)]
+    accounts: Vec<ManifestAccount>,
+    #[serde(default)]
+    hooks: ManifestHooks,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestAccount {
+    username: String,
+    email: String,
+    alias: Option<String>,
+    host: Option<String>,
+    provider: Option<ProviderKind>,
+    // Left out, these keep what an existing account has; an empty string
+    // (or list) clears them.
+    ssh_key: Option<String>,
+    ssh_only: Option<bool>,
+    ssh_confirm: Option<bool>,
+    signing_key: Option<String>,
+    gpg_key: Option<String>,
+    group: Option<String>,
+    orgs: Option<Vec<String>>,
+    /// Timezone commits are stamped in, e.g. "Europe/Berlin".
+    timezone: Option<String>,
+    author_date: Option<crate::dates::AuthorDate>,
+    /// Environment variable holding the token.
+    token_env: Option<String>,
+    /// File holding the token, e.g. a mounted secret.
+    token_file: Option<String>,
+    /// Directories whose repositories use this account (`gitas rule add`).
+    #[serde(default)]
+    rules: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestPin {
+    host: String,
+    /// Required: provisioning never asks which certificate to trust.
+    fingerprint: String,
+    /// PEM file with the certificate, instead of what the host presents.
+    file: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestHooks {
+    /// Repositories that get the pre-push hook (`gitas hook install`).
+    #[serde(default)]
+    pre_push: Vec<String>,
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+/// `~/` at the start of a path, as a shell would expand it.
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The account's token from its environment variable or file; None when the
+/// manifest names neither.
+fn read_token(account: &ManifestAccount) -> Result<Option<Zeroizing<String>>, String> {
+    let label = match &account.alias {
+        Some(alias) => format!("{} ({})", account.username, alias),
+        None => account.username.clone(),
+    };
+    let token = match (&account.token_env, &account.token_file) {
+        (Some(_), Some(_)) => {
+            return Err(format!("{}: set token_env or token_file, not both", label));
+        }
+        (Some(var), None) => Zeroizing::new(
+            std::env::var(var)
+                .map_err(|_| format!("{}: environment variable {} is not set", label, var))?,
+        ),
+        (None, Some(file)) => Zeroizing::new(
+            std::fs::read_to_string(expand_home(file))
+                .map_err(|e| format!("{}: could not read {}: {}", label, file, e))?,
+        ),
+        (None, None) => return Ok(None),
+    };
+    let token = Zeroizing::new(token.trim().to_string());
+    if token.is_empty() {
+        return Err(format!("{}: the token is empty", label));
+    }
+    Ok(Some(token))
+}
+
+/// `gitas provision`: set up accounts, certificate pins, directory rules and
+/// pre-push hooks from a manifest, so build machines and new laptops get the
+/// same setup from one command. Accounts already present are updated in
+/// place; without a token source an account keeps its stored token.
+pub fn run(config: &mut Config, path: &str) {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| fail(format!("Could not read {}: {}", path, e)));
+    let manifest: Manifest = toml::from_str(&text)
+        .unwrap_or_else(|e| fail(format!("{} is not a valid manifest: {}", path, e)));
+
+    // Every secret must be there before anything changes.
+    let mut tokens = Vec::new();
+    let mut problems = Vec::new();
+    for account in &manifest.accounts {
+        match read_token(account) {
+            Ok(token) => tokens.push(token),
+            Err(problem) => problems.push(problem),
+        }
+    }
+    if !problems.is_empty() {
+        eprintln!();
+        for problem in problems {
+            eprintln!("  {} {}", "✗".red().bold(), problem);
+        }
+        fail("Nothing was provisioned.".to_string());
+    }
+
+    // Pins first: detecting a pinned host's forge already needs them.
+    for pin in &manifest.pins {
+        let host = crate::host::normalize(&pin.host);
+        let current = crate::trust::normalize_fingerprint(&pin.fingerprint);
+        if current.is_some()
+            && config.pinned_certs.get(&host) == current.as_ref()
+            && crate::trust::roots_for(&host).is_ok()
+        {
+            println!("  {} {} already pinned", "·".dimmed(), host.cyan());
+            continue;
+        }
+        super::cert::pin(
+            config,
+            host,
+            pin.file
+                .as_deref()
+                .map(|file| expand_home(file).to_string_lossy().to_string()),
+            Some(pin.fingerprint.clone()),
+        );
+    }
+
+    let mut provisioned = Vec::new();
+    for (entry, token) in manifest.accounts.iter().zip(tokens) {
+        let existing = config.accounts.iter().position(|a| {
+            a.username == entry.username && a.alias.as_deref() == entry.alias.as_deref()
+        });
+        let host = entry.host.as_deref().and_then(crate::host::for_account);
+        let provider = match (&host, entry.provider) {
+            (None, _) => None,
+            (Some(_), Some(kind)) => Some(kind),
+            (Some(host), None) => match existing
+                .map(|index| &config.accounts[index])
+                .filter(|a| a.host.as_deref() == Some(host.as_str()))
+                .and_then(|a| a.provider)
+                .or_else(|| provider::detect(host))
+            {
+                Some(kind) => Some(kind),
+                None => fail(format!(
+                    "Could not detect the forge at {}. Set provider for {} in the manifest.",
+                    host, entry.username
+                )),
+            },
+        }
+        .filter(|kind| *kind != ProviderKind::Github);
+
+        // Fields the manifest leaves out (and those it doesn't cover, like
+        // packages and warnings) survive.
+        let mut account = existing
+            .map(|index| config.accounts[index].clone())
+            .unwrap_or_default();
+        let set = |field: &mut Option<String>, value: &Option<String>| {
+            if let Some(value) = value {
+                *field = Some(value.clone()).filter(|v| !v.is_empty());
+            }
+        };
+        account.username = entry.username.clone();
+        account.email = entry.email.clone();
+        account.alias = entry.alias.clone().filter(|a| !a.is_empty());
+        account.host = host;
+        account.provider = provider;
+        set(&mut account.ssh_key, &entry.ssh_key);
+        if let Some(ssh_only) = entry.ssh_only {
+            account.ssh_only = ssh_only;
+        }
+        if let Some(ssh_confirm) = entry.ssh_confirm {
+            account.ssh_confirm = ssh_confirm;
+        }
+        set(&mut account.signing_key, &entry.signing_key);
+        set(&mut account.gpg_key, &entry.gpg_key);
+        set(&mut account.group, &entry.group);
+        if let Some(orgs) = &entry.orgs {
+            account.orgs = orgs.clone();
+        }
+        set(&mut account.timezone, &entry.timezone);
+        if let Some(author_date) = entry.author_date {
+            account.author_date =
+                Some(author_date).filter(|d| *d != crate::dates::AuthorDate::Keep);
+        }
+
+        let change = match &token {
+            Some(token) => super::edit::TokenChange::Set(token),
+            None => super::edit::TokenChange::Keep,
+        };
+        let label = format_account_label(&account);
+        let index =
+            super::edit::commit(config, existing, account, change).unwrap_or_else(|problems| {
+                eprintln!();
+                for problem in problems {
+                    eprintln!("  {} {}: {}", "✗".red().bold(), label, problem);
+                }
+                fail("Provisioning stopped; the accounts above it were saved.".to_string())
+            });
+        println!(
+            "  {} {} {}",
+            "✓".green().bold(),
+            if existing.is_some() {
+                "Updated"
+            } else {
+                "Added"
+            },
+            label.cyan()
+        );
+        provisioned.push(index);
+    }
+
+    if let Some(id) = &manifest.default {
+        let Some(account) = find_account(config, id) else {
+            fail(format!("default: no account matching '{}'.", id));
+        };
+        let id = crate::audit::account_id(account);
+        println!("  {} Default account {}", "✓".green().bold(), id.cyan());
+        config.default_account = Some(id);
+        save_config(config);
+    }
+
+    for (entry, index) in manifest.accounts.iter().zip(&provisioned) {
+        let account = &config.accounts[*index];
+        for dir in &entry.rules {
+            let pattern = super::rule::pattern_for(dir);
+            if let Err(e) = crate::rules::add(&pattern, account) {
+                fail(e);
+            }
+            println!(
+                "  {} Repositories under {} use {}",
+                "✓".green().bold(),
+                pattern.cyan(),
+                format_account_label(account)
+            );
+        }
+    }
+
+    if !manifest.hooks.pre_push.is_empty() {
+        let exe = std::env::current_exe()
+            .unwrap_or_else(|e| fail(format!("Cannot locate the gitas executable: {}", e)));
+        for repo in &manifest.hooks.pre_push {
+            // Repositories may not be cloned yet on a fresh machine.
+            match crate::hooks::install_pre_push(&expand_home(repo), &exe.to_string_lossy()) {
+                Ok(hook) => println!(
+                    "  {} Pre-push check in {}",
+                    "✓".green().bold(),
+                    hook.path().display().to_string().cyan()
+                ),
+                Err(e) => println!("  {} {}, skipped", "⚠".yellow(), e),
+            }
+        }
+    }
+
+    println!(
+        "\n  {} Provisioned from {}\n",
+        "✓".green().bold(),
+        path.cyan()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_manifest_and_rejects_unknown_keys() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+            default = "work"
+
+            [[accounts]]
+            username = "octo"
+            alias = "work"
+            email = "octo@corp.com"
+            provider = "gitlab"
+            token_file = "/run/secrets/gitlab"
+            rules = ["~/work/"]
+
+            [hooks]
+            pre_push = ["~/work/app"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.default.as_deref(), Some("work"));
+        assert_eq!(manifest.accounts[0].provider, Some(ProviderKind::Gitlab));
+        assert_eq!(manifest.accounts[0].rules, ["~/work/"]);
+        assert_eq!(manifest.hooks.pre_push.len(), 1);
+        assert!(manifest.pins.is_empty());
+
+        let typo = toml::from_str::<Manifest>("[[accounts]]\nusername = \"a\"\nemial = \"a@b\"\n");
+        assert!(typo.is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use super::token::{CheckResult, check_all, jobs_for};
+use crate::models::Config;
+use crate::utils::format_account_label;
+use colored::Colorize;
+
+/// Tokens expiring within this many days are reported even when `quiet`.
+const EXPIRY_WARNING_DAYS: u64 = 7;
+
+/// Whether a token expiring at `expires` (GitHub's
+/// `github-authentication-token-expiration`, e.g. `2026-10-25 12:00:00 UTC`)
+/// runs out within [`EXPIRY_WARNING_DAYS`] of `now`. A date that can't be
+/// read counts as soon.
+fn expires_soon(expires: &str, now: u64) -> bool {
+    expires
+        .get(..10)
+        .and_then(crate::audit::parse_date)
+        .is_none_or(|day| day <= now + EXPIRY_WARNING_DAYS * 86_400)
+}
+
+/// Non-interactive maintenance meant for cron or a systemd timer: revalidate
+/// tokens (refreshing the cached logins as a side effect) and rotate the
+/// history log. Prints only problems and tokens close to expiry when `quiet`.
+pub fn run(config: &Config, quiet: bool) {
+    let jobs = jobs_for(config.accounts.clone());
+    let results = check_all(&jobs);
+    let now = crate::cache::now_secs();
+
+    let mut failed = false;
+    let mut valid = 0;
+    for (job, result) in jobs.iter().zip(&results) {
+        let label = format_account_label(&job.account);
+        match result {
+            CheckResult::Valid(info) => {
+                valid += 1;
+                if !info.login.eq_ignore_ascii_case(&job.account.username) {
+                    failed = true;
+                    println!(
+                        "  {} {}: token belongs to {}",
+                        "✗".red(),
+                        label,
+                        info.login.yellow()
+                    );
+                } else if let Some(expires) = &info.expires_at {
+                    if expires_soon(expires, now) {
+                        println!("  {} {}: token expires {}", "⚠".yellow(), label, expires);
+                    } else if !quiet {
+                        println!(
+                            "  {}",
+                            format!("{}: token expires {}", label, expires).dimmed()
+                        );
+                    }
+                }
+            }
+            CheckResult::Invalid(e) => {
+                failed = true;
+                println!("  {} {}: {}", "✗".red(), label, e);
+            }
+            CheckResult::RateLimited => {
+                println!(
+                    "  {} {}: skipped, rate limit exhausted for {}",
+                    "⚠".yellow(),
+                    label,
+                    job.host
+                );
+            }
+            CheckResult::Missing | CheckResult::SshOnly => {}
+        }
+    }
+
+    let rotated = crate::audit::rotate();
+    if !quiet {
+        println!(
+            "  {} {} token(s) valid{}",
+            "✓".green().bold(),
+            valid,
+            if rotated { ", history log rotated" } else { "" }
+        );
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// How often the installed timer runs `gitas refresh`.
+const TIMER_INTERVAL_HOURS: u32 = 6;
+
+/// Install (or remove) a user-level scheduled job running `gitas refresh --quiet`.
+pub fn install_timer(uninstall: bool) {
+    let result = if uninstall {
+        timer::uninstall()
+    } else {
+        std::env::current_exe()
+            .map_err(|e| format!("Cannot locate the gitas executable: {}", e))
+            .and_then(|exe| timer::install(&exe.to_string_lossy()))
+    };
+
+    match result {
+        Ok(description) => println!("\n  {} {}\n", "✓".green().bold(), description),
+        Err(e) => {
+            eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_checked(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod timer {
+    use super::{TIMER_INTERVAL_HOURS, run_checked};
+    use std::fs;
+    use std::path::PathBuf;
+
+    const UNIT: &str = "gitas-refresh";
+
+    fn unit_dir() -> Result<PathBuf, String> {
+        dirs::config_dir()
+            .map(|dir| dir.join("systemd").join("user"))
+            .ok_or_else(|| "Cannot locate the systemd user unit directory".to_string())
+    }
+
+    pub fn install(exe: &str) -> Result<String, String> {
+        let dir = unit_dir()?;
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let service = format!(
+            "[Unit]\nDescription=Refresh gitas tokens and caches\n\n\
+             [Service]\nType=oneshot\nExecStart=\"{}\" refresh --quiet\n",
+            // systemd expands % specifiers even inside quotes.
+            exe.replace('%', "%%")
+        );
+        let timer = format!(
+            "[Unit]\nDescription=Run gitas refresh periodically\n\n\
+             [Timer]\nOnBootSec=5min\nOnUnitActiveSec={}h\nPersistent=true\n\n\
+             [Install]\nWantedBy=timers.target\n",
+            TIMER_INTERVAL_HOURS
+        );
+        fs::write(dir.join(format!("{}.service", UNIT)), service).map_err(|e| e.to_string())?;
+        fs::write(dir.join(format!("{}.timer", UNIT)), timer).map_err(|e| e.to_string())?;
+
+        run_checked("systemctl", &["--user", "daemon-reload"])?;
+        run_checked(
+            "systemctl",
+            &["--user", "enable", "--now", &format!("{}.timer", UNIT)],
+        )?;
+        Ok(format!(
+            "Installed systemd user timer {}.timer (every {}h)",
+            UNIT, TIMER_INTERVAL_HOURS
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        let dir = unit_dir()?;
+        // The timer may already be gone; removing the files is what matters.
+        let _ = run_checked(
+            "systemctl",
+            &["--user", "disable", "--now", &format!("{}.timer", UNIT)],
+        );
+        for ext in ["timer", "service"] {
+            let _ = fs::remove_file(dir.join(format!("{}.{}", UNIT, ext)));
+        }
+        let _ = run_checked("systemctl", &["--user", "daemon-reload"]);
+        Ok(format!("Removed systemd user timer {}.timer", UNIT))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod timer {
+    use super::{TIMER_INTERVAL_HOURS, run_checked};
+    use std::fs;
+    use std::path::PathBuf;
+
+    const LABEL: &str = "io.github.letmutex.gitas.refresh";
+
+    fn plist_path() -> Result<PathBuf, String> {
+        dirs::home_dir()
+            .map(|home| {
+                home.join("Library")
+                    .join("LaunchAgents")
+                    .join(format!("{}.plist", LABEL))
+            })
+            .ok_or_else(|| "Cannot locate ~/Library/LaunchAgents".to_string())
+    }
+
+    fn xml_escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    pub fn install(exe: &str) -> Result<String, String> {
+        let path = plist_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>refresh</string>
+        <string>--quiet</string>
+    </array>
+    <key>StartInterval</key>
+    <integer>{}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            LABEL,
+            xml_escape(exe),
+            TIMER_INTERVAL_HOURS * 3600
+        );
+        fs::write(&path, plist).map_err(|e| e.to_string())?;
+        let path = path.to_string_lossy();
+        let _ = run_checked("launchctl", &["unload", "-w", &path]);
+        run_checked("launchctl", &["load", "-w", &path])?;
+        Ok(format!(
+            "Installed launchd agent {} (every {}h)",
+            LABEL, TIMER_INTERVAL_HOURS
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        let path = plist_path()?;
+        let _ = run_checked("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+        let _ = fs::remove_file(&path);
+        Ok(format!("Removed launchd agent {}", LABEL))
+    }
+}
+
+#[cfg(windows)]
+mod timer {
+    use super::{TIMER_INTERVAL_HOURS, run_checked};
+
+    const TASK: &str = "gitas-refresh";
+
+    pub fn install(exe: &str) -> Result<String, String> {
+        let command = format!("\"{}\" refresh --quiet", exe);
+        let interval = TIMER_INTERVAL_HOURS.to_string();
+        run_checked(
+            "schtasks",
+            &[
+                "/Create", "/F", "/SC", "HOURLY", "/MO", &interval, "/TN", TASK, "/TR", &command,
+            ],
+        )?;
+        Ok(format!(
+            "Installed scheduled task {} (every {}h)",
+            TASK, TIMER_INTERVAL_HOURS
+        ))
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        run_checked("schtasks", &["/Delete", "/F", "/TN", TASK])?;
+        Ok(format!("Removed scheduled task {}", TASK))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod timer {
+    pub fn install(_exe: &str) -> Result<String, String> {
+        Err("No supported scheduler on this platform; run `gitas refresh --quiet` from cron".into())
+    }
+
+    pub fn uninstall() -> Result<String, String> {
+        install("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_only_about_tokens_close_to_expiry() {
+        let now = crate::audit::parse_date("2026-10-18").unwrap();
+        assert!(expires_soon("2026-10-20 09:00:00 UTC", now));
+        assert!(expires_soon("2026-10-25 00:00:00 +0000", now));
+        assert!(!expires_soon("2026-11-30 09:00:00 UTC", now));
+        assert!(expires_soon("soon", now));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::models::Config;
+use crate::resolve::account_for_cwd;
+use crate::utils::{format_account_label, get_remotes, git_toplevel};
+use colored::Colorize;
+use std::process::Command;
+
+/// `gitas remote fix`: point the repository's SSH remotes for the account's
+/// host at its `Host` alias from `gitas ssh config`, so ssh picks its key.
+/// Remotes already on another account's alias for that host move too.
+pub fn fix(config: &mut Config, account_id: Option<String>, dry_run: bool) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+    if git_toplevel().is_none() {
+        fail("Not inside a git repository.".to_string());
+    }
+
+    let account = account_for_cwd(config, account_id, "  Fix remotes for");
+    if account.ssh_key.is_none() {
+        fail(format!(
+            "{} has no SSH key to alias. Create one with `gitas ssh keygen`.",
+            format_account_label(&account)
+        ));
+    }
+    let host = account.host.as_deref().unwrap_or("github.com");
+    let alias = crate::ssh::host_alias(&account);
+    let mut hosts = vec![host.to_string()];
+    hosts.extend(
+        config
+            .accounts
+            .iter()
+            .filter(|a| a.ssh_key.is_some() && a.host.as_deref().unwrap_or("github.com") == host)
+            .map(crate::ssh::host_alias),
+    );
+
+    println!();
+    let mut changed = 0;
+    for remote in get_remotes() {
+        let Some(url) = aliased_url(&remote.url, &hosts, &alias) else {
+            continue;
+        };
+        changed += 1;
+        println!(
+            "  {:<10} {} {} {}",
+            remote.name,
+            remote.url.dimmed(),
+            "→".cyan(),
+            url
+        );
+        if dry_run {
+            continue;
+        }
+        let status = Command::new("git")
+            .args(["remote", "set-url", &remote.name, &url])
+            .status();
+        if !status.is_ok_and(|s| s.success()) {
+            fail(format!("Could not update remote '{}'.", remote.name));
+        }
+    }
+
+    if changed == 0 {
+        println!(
+            "  {}\n",
+            format!("No SSH remotes on {} to point at {}.", host, alias).dimmed()
+        );
+        return;
+    }
+    if dry_run {
+        println!("\n  {}\n", "Dry run: no remote was changed.".dimmed());
+        return;
+    }
+    println!(
+        "\n  {} {} remote(s) now use {}",
+        "✓".green().bold(),
+        changed,
+        alias.cyan()
+    );
+    let aliased = crate::ssh::config_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .is_some_and(|contents| contents.contains(&format!("Host {}\n", alias)));
+    if !aliased {
+        println!(
+            "  {} {} is not in ~/.ssh/config yet. Add it with {}.",
+            "⚠".yellow(),
+            alias,
+            "gitas ssh config".cyan()
+        );
+    }
+    println!();
+}
+
+/// `url` moved to `alias` when it is an SSH URL on one of `hosts`;
+/// `None` for other URLs and those already on `alias`.
+fn aliased_url(url: &str, hosts: &[String], alias: &str) -> Option<String> {
+    let on_host = |host: &str| host != alias && hosts.iter().any(|h| h == host);
+    if let Some(rest) = url.strip_prefix("ssh://") {
+        let (authority, path) = rest.split_once('/')?;
+        let (user, host_port) = match authority.rsplit_once('@') {
+            Some((user, host_port)) => (Some(user), host_port),
+            None => (None, authority),
+        };
+        let (host, port) = match host_port.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_port, None),
+        };
+        if !on_host(host) {
+            return None;
+        }
+        return Some(format!(
+            "ssh://{}{}{}/{}",
+            user.map(|u| format!("{}@", u)).unwrap_or_default(),
+            alias,
+            port.map(|p| format!(":{}", p)).unwrap_or_default(),
+            path
+        ));
+    }
+    if url.contains("://") {
+        return None;
+    }
+    let (authority, path) = url.split_once(':')?;
+    let (user, host) = match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, authority),
+    };
+    // `C:/path` and other local paths have no user and a one-letter "host".
+    if user.is_none() && host.len() < 2 || !on_host(host) {
+        return None;
+    }
+    Some(format!(
+        "{}{}:{}",
+        user.map(|u| format!("{}@", u)).unwrap_or_default(),
+        alias,
+        path
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::aliased_url;
+
+    #[test]
+    fn moves_ssh_remotes_to_the_alias() {
+        let hosts = ["github.com".to_string(), "github.com-home".to_string()];
+        let alias = "github.com-work";
+        assert_eq!(
+            aliased_url("git@github.com:org/repo.git", &hosts, alias).as_deref(),
+            Some("git@github.com-work:org/repo.git")
+        );
+        assert_eq!(
+            aliased_url("git@github.com-home:org/repo.git", &hosts, alias).as_deref(),
+            Some("git@github.com-work:org/repo.git")
+        );
+        assert_eq!(
+            aliased_url("ssh://git@github.com:22/org/repo", &hosts, alias).as_deref(),
+            Some("ssh://git@github.com-work:22/org/repo")
+        );
+        assert_eq!(
+            aliased_url("git@github.com-work:org/repo.git", &hosts, alias),
+            None
+        );
+        assert_eq!(
+            aliased_url("https://github.com/org/repo.git", &hosts, alias),
+            None
+        );
+        assert_eq!(
+            aliased_url("git@gitlab.com:org/repo.git", &hosts, alias),
+            None
+        );
+    }
+}
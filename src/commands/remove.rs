@@ -0,0 +1,101 @@
+use crate::confirm::{self, Action, Decision};
+use crate::models::{Config, save_config};
+use crate::tui::{enter_raw_mode, exit_raw_mode, raw_confirm};
+use crate::utils::{find_account_index, format_account_label};
+use colored::Colorize;
+
+pub fn run(config: &mut Config, id: String, yes: bool) {
+    let Some(index) = find_account_index(config, &id) else {
+        eprintln!(
+            "\n  {} No account matching '{}'.\n",
+            "✗".red().bold(),
+            id.yellow()
+        );
+        std::process::exit(1);
+    };
+    let label = format_account_label(&config.accounts[index]);
+
+    let decision = if yes {
+        Decision::Proceed
+    } else {
+        confirm::decide(config, Action::Delete)
+    };
+    if decision == Decision::Refuse {
+        eprintln!(
+            "\n  {} Refusing to remove {} without a terminal to confirm. Pass {}.\n",
+            "✗".red().bold(),
+            label,
+            "--yes".cyan()
+        );
+        std::process::exit(1);
+    }
+    if decision == Decision::Ask {
+        enter_raw_mode();
+        let confirmed = raw_confirm(&confirm_prompt(config, index), false);
+        exit_raw_mode();
+        if confirmed != Some(true) {
+            println!("\n  {}\n", "Cancelled.".dimmed());
+            if crate::tui::interrupted() {
+                std::process::exit(130);
+            }
+            return;
+        }
+    }
+
+    let kept = remove_account(config, index);
+    println!("\n  {} Removed {}.", "✓".green().bold(), label.cyan());
+    if kept {
+        println!(
+            "  {}",
+            format!(
+                "Token kept for {} days; `gitas token gc --restore {}` brings it back.",
+                grace_days(config),
+                id
+            )
+            .dimmed()
+        );
+    }
+    println!();
+}
+
+fn grace_days(config: &Config) -> u64 {
+    config
+        .token_grace_days
+        .unwrap_or(crate::tombstone::DEFAULT_GRACE_DAYS)
+}
+
+/// Question asked before removing the account at `index`.
+pub(crate) fn confirm_prompt(config: &Config, index: usize) -> String {
+    let account = &config.accounts[index];
+    let days = grace_days(config);
+    if days > 0 && !account.ssh_only {
+        format!(
+            "Remove account '{}'? Its token stays recoverable for {} days.",
+            account.username.yellow(),
+            days
+        )
+    } else {
+        format!("Remove account '{}'?", account.username.yellow())
+    }
+}
+
+/// Drop the account at `index`, parking its token for the grace period.
+/// Returns whether a token was kept.
+pub(crate) fn remove_account(config: &mut Config, index: usize) -> bool {
+    let account = config.accounts.remove(index);
+    // The keychain copy is parked below; git's own helpers would otherwise
+    // keep offering the token for the host.
+    if !account.ssh_only {
+        for username in crate::provider::credential_usernames(&account, None) {
+            crate::utils::git_credential_reject(
+                &username,
+                account.host.as_deref().unwrap_or("github.com"),
+            )
+            .ok();
+        }
+    }
+    config.retarget_account(&crate::audit::account_id(&account), None);
+    let kept = crate::tombstone::bury(&account, grace_days(config));
+    save_config(config);
+    kept
+}
@@ -0,0 +1,53 @@
+use crate::models::{Config, get_token};
+use crate::resolve::{resolve_identifier, resolve_or_default};
+use crate::utils::{redact_email, redact_name};
+use serde_json::json;
+use std::path::PathBuf;
+
+pub fn run(config: &Config, target: Option<String>) {
+    let path = match &target {
+        Some(t) => PathBuf::from(t),
+        None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+    };
+
+    let resolution = match &target {
+        Some(t) if !path.is_dir() => resolve_identifier(config, t),
+        _ => resolve_or_default(config, &path),
+    };
+
+    let output = match &resolution {
+        Some(resolution) => {
+            let account = resolution.account;
+            let has_token = !account.ssh_only
+                && get_token(&account.username, account.alias.as_deref())
+                    .is_some_and(|t| !t.is_empty());
+            json!({
+                "account": {
+                    "username": redact_name(&account.username),
+                    "email": redact_email(&account.email),
+                    "alias": account.alias,
+                    "host": account.host.as_deref().unwrap_or("github.com"),
+                    "ssh_key": account.ssh_key,
+                    "ssh_only": account.ssh_only,
+                    "has_token": has_token,
+                },
+                "source": resolution.source,
+                "path": path.is_dir().then(|| path.display().to_string()),
+            })
+        }
+        None => json!({
+            "account": null,
+            "source": null,
+            "path": path.is_dir().then(|| path.display().to_string()),
+        }),
+    };
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).expect("Could not serialize resolution")
+    );
+
+    if resolution.is_none() {
+        std::process::exit(1);
+    }
+}
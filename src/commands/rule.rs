@@ -0,0 +1,140 @@
+use crate::models::Config;
+use crate::rules::{self, Rule};
+use crate::utils::{format_account_label, resolve_account};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+fn home() -> String {
+    dirs::home_dir()
+        .map(|home| home.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default()
+}
+
+/// `gitdir:` pattern for a directory the user typed: absolute, with forward
+/// slashes and a trailing `/` so it covers every repository below it. A
+/// leading `~/` is kept, so the rule follows the home directory.
+pub(crate) fn pattern_for(dir: &str) -> String {
+    let mut pattern = if dir == "~" || dir.starts_with("~/") {
+        dir.to_string()
+    } else {
+        let path = Path::new(dir);
+        let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| {
+            std::env::current_dir()
+                .map(|cwd| cwd.join(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        });
+        let absolute = absolute.to_string_lossy().replace('\\', "/");
+        // canonicalize gives `\\?\C:\...` on Windows.
+        absolute.trim_start_matches("//?/").to_string()
+    };
+    if !pattern.ends_with('/') {
+        pattern.push('/');
+    }
+    pattern
+}
+
+/// `gitas rule add`: use the account in every repository under `dir`.
+pub fn add(config: &Config, dir: &str, account_id: Option<String>) {
+    let account = resolve_account(config, account_id, "  Use in this directory");
+    let pattern = pattern_for(dir);
+    if let Err(e) = rules::add(&pattern, &account) {
+        fail(e);
+    }
+    println!(
+        "\n  {} Repositories under {} now use {}\n",
+        "✓".green().bold(),
+        pattern.cyan(),
+        format_account_label(&account).cyan()
+    );
+}
+
+/// `gitas rule list`: every directory rule in the global git config.
+pub fn list(config: &Config) {
+    let rules = rules::list();
+    if rules.is_empty() {
+        println!("\n  {}\n", "No directory rules.".dimmed());
+        println!(
+            "  Add one with {}\n",
+            "gitas rule add <dir> <account>".cyan().bold()
+        );
+        return;
+    }
+    println!();
+    for rule in &rules {
+        println!("  {}  →  {}", rule.pattern.cyan(), describe(config, rule));
+    }
+    println!();
+}
+
+/// Account a rule applies, or the file it includes when gitas didn't write it.
+fn describe(config: &Config, rule: &Rule) -> String {
+    let id = rules::is_managed(rule)
+        .then(|| rules::account_of(&rule.path))
+        .flatten();
+    match id {
+        Some(id) => match config
+            .accounts
+            .iter()
+            .find(|a| crate::audit::account_id(a) == id)
+        {
+            Some(account) => format_account_label(account),
+            None => format!("{} {}", id, "(account removed)".yellow()),
+        },
+        None => format!("{} {}", rule.path, "(not managed by gitas)".dimmed()),
+    }
+}
+
+/// `gitas rule remove`: drop the rule for `dir`.
+pub fn remove(dir: &str) {
+    let pattern = pattern_for(dir);
+    match rules::remove(&pattern) {
+        Ok(0) => fail(format!("No gitas rule for {}.", pattern)),
+        Ok(_) => println!(
+            "\n  {} Removed the rule for {}\n",
+            "✓".green().bold(),
+            pattern.cyan()
+        ),
+        Err(e) => fail(e),
+    }
+}
+
+/// `gitas rule test`: which rule, and so which account, applies at `path`.
+pub fn test(config: &Config, path: Option<String>) {
+    let path = PathBuf::from(path.unwrap_or_else(|| ".".to_string()));
+    let Ok(path) = std::fs::canonicalize(&path) else {
+        fail(format!("{} does not exist.", path.display()));
+    };
+    // Rules match git's directory; outside a repository, test the one a new
+    // repository here would get.
+    let gitdir = Command::new("git")
+        .args(["rev-parse", "--absolute-git-dir"])
+        .current_dir(&path)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| path.join(".git").to_string_lossy().to_string());
+
+    let rules = rules::list();
+    println!();
+    match rules::applying(&rules, &gitdir, &home()) {
+        Some(rule) => println!(
+            "  {}  →  {}  {}",
+            path.display(),
+            describe(config, rule).cyan(),
+            format!("(rule {})", rule.pattern).dimmed()
+        ),
+        None => println!(
+            "  {}  →  {}",
+            path.display(),
+            "no rule; the global identity applies".dimmed()
+        ),
+    }
+    println!();
+}
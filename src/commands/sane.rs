@@ -0,0 +1,37 @@
+use colored::Colorize;
+use crossterm::{cursor, event, execute, style, terminal};
+use std::io::stdout;
+
+/// `gitas sane`: put the terminal back into a usable state after gitas or
+/// another full-screen program died without restoring it. Every step runs
+/// whether or not it looks needed.
+pub fn run() {
+    let mut out = stdout();
+    execute!(
+        out,
+        event::DisableMouseCapture,
+        event::DisableBracketedPaste,
+        event::DisableFocusChange,
+        terminal::LeaveAlternateScreen,
+        style::SetAttribute(style::Attribute::Reset),
+        style::ResetColor,
+        cursor::Show,
+    )
+    .ok();
+
+    // crossterm only undoes raw mode it entered itself, so on Unix ask stty
+    // to reset the line discipline the crashed program left behind.
+    #[cfg(unix)]
+    {
+        let tty = std::fs::File::open("/dev/tty").map(std::process::Stdio::from);
+        let mut stty = std::process::Command::new("stty");
+        stty.arg("sane");
+        if let Ok(tty) = tty {
+            stty.stdin(tty);
+        }
+        stty.status().ok();
+    }
+    terminal::disable_raw_mode().ok();
+
+    println!("  {} Terminal restored", "✓".green().bold());
+}
@@ -0,0 +1,203 @@
+use colored::Colorize;
+use serde_json::{Value, json};
+
+/// Version of the document `gitas schema --json` prints; bumped when its
+/// shape changes, not when commands are added.
+const SCHEMA_VERSION: u64 = 1;
+
+/// `gitas schema`: every subcommand and flag, read from the clap definition
+/// so it can't drift from the parser, plus the JSON schema of each command
+/// that prints JSON.
+pub fn run(cli: clap::Command, as_json: bool) {
+    let root = describe(&cli, &[]);
+    if as_json {
+        let document = json!({
+            "schema_version": SCHEMA_VERSION,
+            "version": env!("CARGO_PKG_VERSION"),
+            "command": root,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&document).expect("Could not serialize schema")
+        );
+        return;
+    }
+
+    println!();
+    print_tree(&root);
+    println!(
+        "\n  {}\n",
+        "Machine-readable form, with output schemas: gitas schema --json".dimmed()
+    );
+}
+
+fn describe(command: &clap::Command, parent: &[&str]) -> Value {
+    let mut path = parent.to_vec();
+    path.push(command.get_name());
+    let args: Vec<Value> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .map(describe_arg)
+        .collect();
+    let subcommands: Vec<Value> = command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| describe(sub, &path))
+        .collect();
+
+    let mut value = json!({
+        "name": command.get_name(),
+        "path": path.join(" "),
+        "about": command.get_about().map(|about| about.to_string()),
+        "args": args,
+        "subcommands": subcommands,
+    });
+    if let Some(output) = output_schema(&path[1..]) {
+        value["output"] = output;
+    }
+    value
+}
+
+fn describe_arg(arg: &clap::Arg) -> Value {
+    let takes_value = arg.get_action().takes_values();
+    let possible_values: Vec<String> = if takes_value {
+        arg.get_possible_values()
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    json!({
+        "id": arg.get_id().as_str(),
+        "long": arg.get_long(),
+        "short": arg.get_short().map(String::from),
+        "positional": arg.is_positional(),
+        "help": arg.get_help().map(|help| help.to_string()),
+        "required": arg.is_required_set(),
+        "global": arg.is_global_set(),
+        "takes_value": takes_value,
+        "multiple": takes_value
+            && arg.get_num_args().is_some_and(|range| range.max_values() > 1),
+        "value_name": arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map(|name| name.to_string()),
+        "possible_values": possible_values,
+        "default": arg
+            .get_default_values()
+            .first()
+            .map(|value| value.to_string_lossy().to_string()),
+    })
+}
+
+/// JSON printed by the command at `path` (below `gitas`), and the flag that
+/// selects it when JSON is not the default.
+fn output_schema(path: &[&str]) -> Option<Value> {
+    let nullable_string = json!({ "type": ["string", "null"] });
+    match path {
+        ["resolve"] => Some(json!({
+            "schema": {
+                "type": "object",
+                "required": ["account", "source", "path"],
+                "properties": {
+                    "account": {
+                        "type": ["object", "null"],
+                        "properties": {
+                            "username": { "type": "string" },
+                            "email": { "type": "string" },
+                            "alias": nullable_string,
+                            "host": { "type": "string" },
+                            "ssh_key": nullable_string,
+                            "ssh_only": { "type": "boolean" },
+                            "has_token": { "type": "boolean" },
+                        },
+                    },
+                    "source": {
+                        "type": ["string", "null"],
+                        "enum": ["identifier", "binding", "repo-identity", "remote-owner", "default", null],
+                    },
+                    "path": nullable_string,
+                },
+            },
+        })),
+        ["audit", "export"] => Some(json!({
+            "when": "--format json",
+            "schema": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["time", "timestamp", "event", "account", "email"],
+                    "properties": {
+                        "time": { "type": "integer", "description": "Unix seconds" },
+                        "timestamp": { "type": "string", "format": "date-time" },
+                        "event": { "enum": ["push", "switch", "guard", "token"] },
+                        "account": { "type": "string" },
+                        "email": { "type": "string" },
+                        "repo": { "type": "string" },
+                        "remote": { "type": "string" },
+                        "refspecs": { "type": "array", "items": { "type": "string" } },
+                        "scope": { "type": "string" },
+                        "detail": { "type": "string" },
+                    },
+                },
+            },
+        })),
+        _ => None,
+    }
+}
+
+fn print_tree(command: &Value) {
+    let flags: Vec<String> = command["args"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|arg| arg["global"] != true || command["path"] == "gitas")
+        .map(|arg| match arg["long"].as_str() {
+            Some(long) => format!("--{}", long),
+            None => format!(
+                "<{}>",
+                arg["value_name"]
+                    .as_str()
+                    .unwrap_or_else(|| arg["id"].as_str().unwrap_or_default())
+                    .to_uppercase()
+            ),
+        })
+        .collect();
+    let path = command["path"].as_str().unwrap_or_default().cyan();
+    if flags.is_empty() {
+        println!("  {}", path);
+    } else {
+        println!("  {}  {}", path, flags.join(" ").dimmed());
+    }
+    for sub in command["subcommands"].as_array().into_iter().flatten() {
+        print_tree(sub);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::describe;
+
+    #[test]
+    fn describes_subcommands_flags_and_outputs() {
+        let cli = clap::Command::new("gitas").subcommand(
+            clap::Command::new("resolve")
+                .about("Print the account for a path as JSON")
+                .arg(clap::Arg::new("target"))
+                .arg(
+                    clap::Arg::new("format")
+                        .long("format")
+                        .value_parser(["csv", "json"]),
+                ),
+        );
+        let schema = describe(&cli, &[]);
+        let resolve = &schema["subcommands"][0];
+        assert_eq!(resolve["path"], "gitas resolve");
+        assert_eq!(resolve["args"][0]["positional"], true);
+        assert_eq!(resolve["args"][1]["possible_values"][1], "json");
+        assert_eq!(resolve["output"]["schema"]["type"], "object");
+        assert!(schema.get("output").is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use crate::models::{Account, Config, parse_config};
+use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Outcome of one check: a short detail on success, the reason on failure,
+/// or `Skip` when the check can't run here.
+enum Outcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+/// `gitas selftest`: exercise the pieces an install depends on without
+/// touching the user's config, keychain entries or repositories.
+/// `parse` checks argument parsing against the real CLI definition.
+pub fn run(parse: fn(&[&str]) -> Result<(), String>) {
+    let scratch = std::env::temp_dir().join(format!("gitas-selftest-{}", std::process::id()));
+    let checks: Vec<(&str, Outcome)> = vec![
+        ("Argument parsing", check_parsing(parse)),
+        ("Config round-trip", check_config(&scratch)),
+        ("Keychain", check_keychain()),
+        ("Terminal", check_terminal()),
+        ("Git", check_git(&scratch)),
+    ];
+    std::fs::remove_dir_all(&scratch).ok();
+
+    println!();
+    let width = checks.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut failed = 0;
+    for (name, outcome) in &checks {
+        let (marker, detail) = match outcome {
+            Outcome::Pass(detail) => ("✓".green().bold(), detail.dimmed()),
+            Outcome::Fail(reason) => {
+                failed += 1;
+                ("✗".red().bold(), reason.red())
+            }
+            Outcome::Skip(reason) => ("○".dimmed(), reason.dimmed()),
+        };
+        println!("  {} {:<width$}  {}", marker, name, detail, width = width);
+    }
+    println!();
+
+    if failed > 0 {
+        eprintln!("  {} {} check(s) failed.\n", "✗".red().bold(), failed);
+        std::process::exit(1);
+    }
+    println!(
+        "  {} gitas {} works on this system.\n",
+        "✓".green().bold(),
+        env!("CARGO_PKG_VERSION")
+    );
+}
+
+fn check_parsing(parse: fn(&[&str]) -> Result<(), String>) -> Outcome {
+    let valid: [&[&str]; 3] = [
+        &["gitas", "whoami"],
+        &["gitas", "-a", "work", "git", "--", "status", "-s"],
+        &["gitas", "edit", "--all", "--set", "group=work"],
+    ];
+    for args in valid {
+        if let Err(e) = parse(args) {
+            return Outcome::Fail(format!("`{}` was rejected: {}", args.join(" "), e.trim()));
+        }
+    }
+    if parse(&["gitas", "--no-such-flag"]).is_ok() {
+        return Outcome::Fail("an unknown flag was accepted".to_string());
+    }
+    Outcome::Pass(format!("{} invocations", valid.len() + 1))
+}
+
+fn check_config(scratch: &Path) -> Outcome {
+    let mut config = Config {
+        default_account: Some("selftest:alias".to_string()),
+        ..Default::default()
+    };
+    config.accounts.push(Account {
+        username: "selftest".to_string(),
+        email: "selftest@example.com".to_string(),
+        alias: Some("alias".to_string()),
+        host: Some("git.example.com".to_string()),
+        ..Default::default()
+    });
+    config
+        .extra
+        .insert("fromNewerVersion".to_string(), serde_json::json!([1, 2]));
+
+    let path = scratch.join("accounts.json");
+    let result = std::fs::create_dir_all(scratch)
+        .and_then(|_| {
+            let data = serde_json::to_string_pretty(&config).map_err(std::io::Error::other)?;
+            std::fs::write(&path, data)
+        })
+        .and_then(|_| std::fs::read_to_string(&path));
+    let data = match result {
+        Ok(data) => data,
+        Err(e) => return Outcome::Fail(format!("{}: {}", scratch.display(), e)),
+    };
+    let loaded = match parse_config(&data) {
+        Ok((loaded, _)) => loaded,
+        Err(e) => return Outcome::Fail(format!("could not parse what was written: {}", e)),
+    };
+
+    let same = |a: &Config, b: &Config| {
+        serde_json::to_value(a)
+            .ok()
+            .zip(serde_json::to_value(b).ok())
+            .is_some_and(|(a, b)| {
+                // `version` is stamped by the migration on load.
+                let strip = |mut v: serde_json::Value| {
+                    v.as_object_mut().map(|o| o.remove("version"));
+                    v
+                };
+                strip(a) == strip(b)
+            })
+    };
+    if same(&config, &loaded) {
+        Outcome::Pass(path.display().to_string())
+    } else {
+        Outcome::Fail("the config read back differs from what was written".to_string())
+    }
+}
+
+fn check_keychain() -> Outcome {
+    if crate::models::is_ephemeral() {
+        return Outcome::Skip("skipped in ephemeral mode".to_string());
+    }
+    let key = format!("gitas-selftest-{}", std::process::id());
+    match crate::models::probe_keychain(&key) {
+        Ok(()) => Outcome::Pass("write, read and delete".to_string()),
+        Err(e) => Outcome::Fail(format!("keychain unavailable: {}", e)),
+    }
+}
+
+/// Report what the terminal supports. Nothing here is fatal: gitas falls
+/// back to plain output when colors or raw mode are unavailable.
+fn check_terminal() -> Outcome {
+    let tty = std::io::stdout().is_terminal();
+    let colors = colored::control::SHOULD_COLORIZE.should_colorize();
+    let size = crossterm::terminal::size()
+        .ok()
+        .filter(|&(columns, _)| columns > 0)
+        .map(|(columns, rows)| format!("{}x{}", columns, rows));
+    Outcome::Pass(format!(
+        "{}, colors {}, {}",
+        if tty { "tty" } else { "not a tty" },
+        if colors { "on" } else { "off" },
+        size.unwrap_or_else(|| "size unknown".to_string())
+    ))
+}
+
+fn check_git(scratch: &Path) -> Outcome {
+    let repo: PathBuf = scratch.join("repo");
+    if let Err(e) = std::fs::create_dir_all(&repo) {
+        return Outcome::Fail(format!("{}: {}", repo.display(), e));
+    }
+    let git = |args: &[&str]| -> Result<String, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&repo)
+            .args(args)
+            // Keep the user's global config and hooks out of the test.
+            .env(
+                "GIT_CONFIG_GLOBAL",
+                if cfg!(windows) { "NUL" } else { "/dev/null" },
+            )
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .output()
+            .map_err(|e| format!("could not run git: {}", e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            Err(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    };
+
+    let result = (|| {
+        let version = git(&["--version"])?;
+        git(&["init", "-q"])?;
+        git(&["config", "--local", "user.name", "gitas selftest"])?;
+        git(&["config", "--local", "user.email", "selftest@example.com"])?;
+        git(&[
+            "commit",
+            "-q",
+            "--allow-empty",
+            "--no-gpg-sign",
+            "-m",
+            "selftest",
+        ])?;
+        let author = git(&["log", "-1", "--format=%an <%ae>"])?;
+        if author != "gitas selftest <selftest@example.com>" {
+            return Err(format!("commit authored as '{}'", author));
+        }
+        Ok(version.trim_start_matches("git version ").to_string())
+    })();
+    match result {
+        Ok(version) => Outcome::Pass(format!("git {}, commit in a temp repository", version)),
+        Err(e) => Outcome::Fail(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trip_passes() {
+        let scratch =
+            std::env::temp_dir().join(format!("gitas-selftest-test-{}", std::process::id()));
+        let outcome = check_config(&scratch);
+        std::fs::remove_dir_all(&scratch).ok();
+        assert!(matches!(outcome, Outcome::Pass(_)));
+    }
+}
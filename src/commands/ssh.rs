@@ -0,0 +1,317 @@
+use crate::models::{Account, Config, get_token, save_config};
+use crate::utils::{find_account_index, format_account_label, resolve_account};
+use colored::Colorize;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// `gitas ssh test`: connect with each account's key and check the forge
+/// greets it as that account. Exits 1 when any key fails or logs in as
+/// someone else.
+pub fn test(config: &Config, account_id: Option<String>, all: bool) {
+    let accounts: Vec<Account> = if all {
+        config
+            .accounts
+            .iter()
+            .filter(|a| a.ssh_key.is_some())
+            .cloned()
+            .collect()
+    } else {
+        vec![resolve_account(config, account_id, "  Test SSH key of")]
+    };
+    if accounts.is_empty() {
+        println!("\n  {}\n", "No accounts have an SSH key.".dimmed());
+        return;
+    }
+
+    println!();
+    let mut failed = false;
+    for account in &accounts {
+        let label = format_account_label(account);
+        let result =
+            crate::ssh::check_key(account).and_then(|_| crate::ssh::authenticated_as(account));
+        match result {
+            Ok(login) if login.eq_ignore_ascii_case(&account.username) => println!(
+                "  {} {}  {}",
+                "✓".green().bold(),
+                label,
+                format!("authenticates as {}", login).dimmed()
+            ),
+            Ok(login) => {
+                failed = true;
+                println!(
+                    "  {} {}  {} {}",
+                    "✗".red().bold(),
+                    label,
+                    "authenticates as".dimmed(),
+                    login.yellow()
+                );
+            }
+            Err(e) => {
+                failed = true;
+                println!("  {} {}  {}", "✗".red().bold(), label, e);
+            }
+        }
+    }
+    println!();
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// `gitas ssh keygen`: create an ed25519 key named after the account, point
+/// the account at it and print (or upload) the public half.
+pub fn keygen(config: &mut Config, account_id: Option<String>, no_passphrase: bool, upload: bool) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+
+    let account = resolve_account(config, account_id, "  Generate SSH key for");
+    let index = find_account_index(config, &crate::audit::account_id(&account))
+        .unwrap_or_else(|| fail("The account disappeared from the config.".to_string()));
+    let label = format_account_label(&account);
+
+    let path = key_path(&account)
+        .unwrap_or_else(|| fail("Could not determine the home directory.".to_string()));
+    let public_path = path.with_extension("pub");
+    if path.exists() || public_path.exists() {
+        fail(format!(
+            "{} already exists. Point the account at it with `gitas edit --ssh-key`.",
+            path.display()
+        ));
+    }
+    if let Some(dir) = path.parent()
+        && let Err(e) = create_ssh_dir(dir)
+    {
+        fail(format!("Could not create {}: {}", dir.display(), e));
+    }
+
+    let mut cmd = Command::new("ssh-keygen");
+    cmd.args(["-q", "-t", "ed25519", "-C", &account.email, "-f"])
+        .arg(&path);
+    if no_passphrase {
+        cmd.args(["-N", ""]);
+    }
+    let status = cmd
+        .status()
+        .unwrap_or_else(|e| fail(format!("Could not run ssh-keygen: {}", e)));
+    if !status.success() {
+        fail("ssh-keygen failed; no key was saved to the account.".to_string());
+    }
+
+    let public_key = std::fs::read_to_string(&public_path)
+        .unwrap_or_else(|e| fail(format!("Could not read {}: {}", public_path.display(), e)));
+    let public_key = public_key.trim();
+
+    let previous = config.accounts[index]
+        .ssh_key
+        .replace(path.to_string_lossy().to_string());
+    save_config(config);
+
+    println!(
+        "\n  {} Generated {} for {}",
+        "✓".green().bold(),
+        path.display().to_string().cyan(),
+        label.cyan()
+    );
+    if let Some(previous) = previous {
+        println!(
+            "  {}",
+            format!("Replaces {} on the account.", previous).dimmed()
+        );
+    }
+
+    if upload {
+        let token = get_token(&account.username, account.alias.as_deref())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| fail("No token stored to upload the key with.".to_string()));
+        let provider = crate::provider::for_account(&account);
+        let title = format!("gitas ({})", label);
+        match provider.upload_ssh_key(&token, &title, public_key) {
+            Ok(()) => println!(
+                "  {} Added the key to {} as \"{}\"\n",
+                "✓".green().bold(),
+                provider.host(),
+                title
+            ),
+            Err(e) => fail(format!("Could not upload the key: {}", e)),
+        }
+        return;
+    }
+
+    println!(
+        "\n  {} Add this public key to {}:\n",
+        "→".cyan(),
+        account.host.as_deref().unwrap_or("github.com")
+    );
+    println!("{}\n", public_key);
+}
+
+/// `gitas ssh agent`: make the account's key the only managed key in the
+/// agent, or with `enable`/`disable` turn doing so on every switch on or off.
+pub fn agent(config: &mut Config, account_id: Option<String>, enable: bool, disable: bool) {
+    if enable || disable {
+        config.ssh_agent = enable;
+        save_config(config);
+        println!(
+            "\n  {} {}\n",
+            "✓".green().bold(),
+            if enable {
+                "Switching now loads the account's key into the SSH agent."
+            } else {
+                "Switching no longer touches the SSH agent."
+            }
+        );
+        return;
+    }
+
+    let account = resolve_account(config, account_id, "  Load SSH key of");
+    match crate::agent::sync(&config.all_accounts(), &account) {
+        Ok(notes) => {
+            println!();
+            for note in notes {
+                println!("  {} {}", "✓".green().bold(), note);
+            }
+            println!();
+        }
+        Err(e) => {
+            eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `gitas ssh config`: write (or with `remove`, drop) the block of `Host`
+/// aliases in `~/.ssh/config`. Once written, gitas keeps it in step with
+/// the accounts whenever it saves them.
+pub fn config(config: &Config, remove: bool) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+    let path = crate::ssh::config_path()
+        .unwrap_or_else(|| fail("Could not determine the home directory.".to_string()));
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => fail(format!("Could not read {}: {}", path.display(), e)),
+    };
+
+    let block = if remove {
+        String::new()
+    } else {
+        crate::ssh::hosts_block(&config.all_accounts())
+    };
+    if remove && !crate::ssh::has_block(&contents) {
+        println!(
+            "\n  {}\n",
+            format!("{} has no gitas block.", path.display()).dimmed()
+        );
+        return;
+    }
+    if !remove && block.is_empty() {
+        println!("\n  {}\n", "No accounts have an SSH key.".dimmed());
+        return;
+    }
+
+    let updated = crate::ssh::replace_block(&contents, &block);
+    if updated != contents {
+        if let Some(dir) = path.parent()
+            && let Err(e) = create_ssh_dir(dir)
+        {
+            fail(format!("Could not create {}: {}", dir.display(), e));
+        }
+        if let Err(e) = crate::ssh::write_config(&path, &updated) {
+            fail(format!("Could not write {}: {}", path.display(), e));
+        }
+    }
+
+    if remove {
+        println!(
+            "\n  {} Removed the gitas block from {}\n",
+            "✓".green().bold(),
+            path.display()
+        );
+        return;
+    }
+    println!(
+        "\n  {} {} {}\n",
+        "✓".green().bold(),
+        if updated == contents {
+            "Up to date:"
+        } else {
+            "Updated"
+        },
+        path.display()
+    );
+    for account in config.accounts.iter().filter(|a| a.ssh_key.is_some()) {
+        println!(
+            "  {}  {}",
+            crate::ssh::host_alias(account).cyan(),
+            format_account_label(account).dimmed()
+        );
+    }
+    if let Some(account) = config.accounts.iter().find(|a| a.ssh_key.is_some()) {
+        println!(
+            "\n  {}\n",
+            format!(
+                "Clone with git@{}:owner/repo.git",
+                crate::ssh::host_alias(account)
+            )
+            .dimmed()
+        );
+    }
+}
+
+/// `~/.ssh/gitas_<username>[_<alias>]`, keeping only characters safe in a
+/// file name.
+fn key_path(account: &Account) -> Option<PathBuf> {
+    let id = crate::audit::account_id(account);
+    let name: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(
+        dirs::home_dir()?
+            .join(".ssh")
+            .join(format!("gitas_{}", name)),
+    )
+}
+
+/// `~/.ssh` must not be readable by others or ssh refuses the keys in it.
+fn create_ssh_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_follow_the_account() {
+        let account = Account {
+            username: "octo".to_string(),
+            alias: Some("work/ci".to_string()),
+            ..Default::default()
+        };
+        let path = key_path(&account).unwrap();
+        assert_eq!(path.file_name().unwrap(), "gitas_octo_work_ci");
+        assert_eq!(path.parent().unwrap().file_name().unwrap(), ".ssh");
+    }
+}
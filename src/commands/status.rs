@@ -0,0 +1,158 @@
+use crate::models::{Account, Config};
+use crate::resolve::resolve_for_path;
+use crate::utils::{
+    format_account_label, get_remotes, git_config_get, git_toplevel, is_http_url, parse_remote_url,
+    redact_email, redact_name,
+};
+use colored::Colorize;
+use std::path::Path;
+use std::process::Command;
+
+const KEYS: [&str; 3] = ["user.name", "user.email", "gitas.alias"];
+
+pub fn run(config: &Config) {
+    let Some(toplevel) = git_toplevel() else {
+        eprintln!("\n  {} Not inside a git repository.\n", "✗".red().bold());
+        std::process::exit(1);
+    };
+
+    println!();
+    println!("  {:<20} {}", "Repository".dimmed(), toplevel.bold());
+
+    println!(
+        "\n  {:<20} {:<28} {}",
+        "Identity".bold(),
+        "local".dimmed(),
+        "global".dimmed()
+    );
+    for key in KEYS {
+        let shown = |value: String| match key {
+            "user.name" => redact_name(&value),
+            "user.email" => redact_email(&value),
+            _ => value,
+        };
+        let local = git_config_get(key, "local").map(shown);
+        let global = git_config_get(key, "global").map(shown);
+        // Pad before styling so escape codes don't skew the column.
+        let local = format!("{:<28}", local.as_deref().unwrap_or("-"));
+        println!(
+            "    {:<18} {} {}",
+            key,
+            if local.starts_with("- ") {
+                local.dimmed().to_string()
+            } else {
+                local
+            },
+            show(global.as_deref())
+        );
+    }
+    let account = resolve_for_path(config, Path::new(".")).map(|r| r.account);
+    println!(
+        "    {:<18} {}",
+        "account",
+        match account {
+            Some(account) => format_account_label(account).cyan().to_string(),
+            None => "not managed by gitas".yellow().to_string(),
+        }
+    );
+
+    println!("\n  {}", "Authentication".bold());
+    println!(
+        "    {:<18} {}",
+        "credential.helper",
+        show(git_config_get("credential.helper", "effective").as_deref())
+    );
+    let ssh_command = git_config_get("core.sshCommand", "effective");
+    println!(
+        "    {:<18} {}",
+        "core.sshCommand",
+        show(ssh_command.as_deref())
+    );
+
+    let remotes = get_remotes();
+    println!("\n  {}", "Remotes".bold());
+    if remotes.is_empty() {
+        println!("    {}", "none".dimmed());
+    }
+    for remote in &remotes {
+        println!("    {:<18} {}", remote.name, remote.url);
+        if parse_remote_url(&remote.url).is_none() {
+            println!(
+                "    {:<18} {}",
+                "",
+                "(local path, no authentication)".dimmed()
+            );
+            continue;
+        }
+        let (via, account) = if is_http_url(&remote.url) {
+            http_account(config, &remote.url)
+        } else {
+            ssh_account(config, ssh_command.as_deref())
+        };
+        println!(
+            "    {:<18} {} {}",
+            "",
+            match account {
+                Some(account) => format!("→ {}", format_account_label(account).cyan()),
+                None => format!("→ {}", "no managed account".yellow()),
+            },
+            format!("({})", via).dimmed()
+        );
+    }
+    println!();
+}
+
+fn show(value: Option<&str>) -> String {
+    value
+        .map(str::to_string)
+        .unwrap_or_else(|| "-".dimmed().to_string())
+}
+
+/// Account an HTTPS remote authenticates as: the user in the URL, else the
+/// most specific `credential.<url>.username`.
+fn http_account<'a>(config: &'a Config, url: &str) -> (String, Option<&'a Account>) {
+    let host = parse_remote_url(url)
+        .map(|(host, _)| host)
+        .unwrap_or_default();
+    let url_user = url
+        .split_once("://")
+        .and_then(|(_, rest)| rest.split('/').next())
+        .and_then(|authority| authority.rsplit_once('@'))
+        .map(|(user, _)| user.split(':').next().unwrap_or(user).to_string());
+
+    let (via, username) = match url_user {
+        Some(user) => ("user in URL".to_string(), Some(user)),
+        None => match credential_username(url) {
+            Some(user) => ("credential.username".to_string(), Some(user)),
+            None => ("no credential username".to_string(), None),
+        },
+    };
+    let account = username.and_then(|username| {
+        config.accounts.iter().find(|a| {
+            a.username.eq_ignore_ascii_case(&username)
+                && crate::host::matches(a.host.as_deref().unwrap_or("github.com"), &host)
+        })
+    });
+    (via, account)
+}
+
+/// Account whose SSH key core.sshCommand points at.
+fn ssh_account<'a>(config: &'a Config, ssh_command: Option<&str>) -> (String, Option<&'a Account>) {
+    let Some(command) = ssh_command else {
+        return ("ssh default keys".to_string(), None);
+    };
+    let account = config
+        .accounts
+        .iter()
+        .find(|a| a.ssh_key.as_deref().map(crate::ssh::command).as_deref() == Some(command));
+    ("core.sshCommand".to_string(), account)
+}
+
+fn credential_username(url: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--get-urlmatch", "credential.username", url])
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
@@ -0,0 +1,599 @@
+use crate::cred_once::TokenDelivery;
+use crate::github::TokenInfo;
+use crate::models::{Account, Config, delete_token, get_token, make_key, set_token};
+use crate::provider;
+use crate::token_agent;
+use crate::utils::{format_account_label, resolve_account};
+use colored::Colorize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use zeroize::Zeroizing;
+
+/// Upper bound on concurrent API requests across all hosts.
+const MAX_CONCURRENCY: usize = 8;
+/// Upper bound on concurrent API requests against a single host.
+const PER_HOST_CONCURRENCY: usize = 2;
+
+pub fn check(config: &Config, account_id: Option<String>, all: bool) {
+    let accounts: Vec<Account> = if all {
+        config.accounts.clone()
+    } else {
+        vec![resolve_account(config, account_id, "  Check token of")]
+    };
+
+    if accounts.is_empty() {
+        println!("\n  {}\n", "No accounts configured.".dimmed());
+        return;
+    }
+
+    let jobs = jobs_for(accounts);
+    let results = check_all(&jobs);
+    print_table(&jobs, &results);
+
+    // A token that logs in as someone else fails too: pushes would be
+    // attributed to the wrong user.
+    let failed = jobs.iter().zip(&results).any(|(job, result)| match result {
+        CheckResult::Valid(info) => !info.login.eq_ignore_ascii_case(&job.account.username),
+        CheckResult::Invalid(_) | CheckResult::RateLimited => true,
+        CheckResult::Missing | CheckResult::SshOnly => false,
+    });
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Store the token on the clipboard for an account, then clear the clipboard so
+/// it never has to pass through a shell command line.
+pub fn paste(config: &Config, account_id: Option<String>) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+
+    let account = resolve_account(config, account_id, "  Paste token for");
+    if account.ssh_only {
+        fail(format!(
+            "{} is SSH-only and doesn't use a token.",
+            format_account_label(&account)
+        ));
+    }
+
+    let mut clipboard =
+        arboard::Clipboard::new().unwrap_or_else(|e| fail(format!("Clipboard unavailable: {}", e)));
+    let text = Zeroizing::new(
+        clipboard
+            .get_text()
+            .unwrap_or_else(|e| fail(format!("Could not read the clipboard: {}", e))),
+    );
+    let token = text.trim();
+    if token.is_empty() || token.contains(char::is_whitespace) {
+        fail("The clipboard doesn't hold a token.".to_string());
+    }
+
+    set_token(&account.username, account.alias.as_deref(), token);
+    crate::audit::record_token(&account, "stored");
+    let cleared = clipboard.clear().is_ok();
+
+    println!(
+        "\n  {} Stored token for {}.",
+        "✓".green().bold(),
+        format_account_label(&account).cyan()
+    );
+    if cleared {
+        println!("  {}", "Clipboard cleared.".dimmed());
+    } else {
+        println!("  {} Could not clear the clipboard.", "⚠".yellow());
+    }
+    if let Some(warning) = crate::utils::check_token_owner(&account, token) {
+        println!("{}", warning);
+    }
+    println!();
+}
+
+/// Store a token for an account, read from stdin with `stdin` (for rotation
+/// scripts) or typed at a hidden prompt.
+pub fn set(config: &Config, account_id: Option<String>, stdin: bool) {
+    let fail = |message: String| -> ! {
+        eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+        std::process::exit(1);
+    };
+
+    let account = resolve_account(config, account_id, "  Set token for");
+    let label = format_account_label(&account);
+    if account.ssh_only {
+        fail(format!("{} is SSH-only and doesn't use a token.", label));
+    }
+
+    let token = if stdin {
+        super::edit::read_token_stdin()
+    } else {
+        crate::tui::enter_raw_mode();
+        let typed = crate::tui::raw_password(&format!("Token for {}", label));
+        crate::tui::exit_raw_mode();
+        match typed {
+            Some(typed) => Some(typed).filter(|t| !t.is_empty()),
+            None => crate::tui::exit_cancelled(),
+        }
+    };
+    let Some(token) = token else {
+        fail("No token given.".to_string());
+    };
+    if token.contains(char::is_whitespace) {
+        fail("A token can't contain whitespace.".to_string());
+    }
+
+    set_token(&account.username, account.alias.as_deref(), &token);
+    crate::audit::record_token(&account, "stored");
+    println!(
+        "\n  {} Stored token for {}.",
+        "✓".green().bold(),
+        label.cyan()
+    );
+    if let Some(warning) = crate::utils::check_token_owner(&account, &token) {
+        println!("{}", warning);
+    }
+    println!();
+}
+
+/// Report whether an account has a token. `show` prints the token itself,
+/// alone on stdout, so scripts can capture it.
+pub fn get(config: &Config, account_id: Option<String>, show: bool) {
+    let account = resolve_account(config, account_id, "  Get token of");
+    let label = format_account_label(&account);
+    let Some(token) =
+        get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+    else {
+        eprintln!("\n  {} No token stored for {}.\n", "✗".red().bold(), label);
+        std::process::exit(1);
+    };
+    if show {
+        println!("{}", token.as_str());
+    } else {
+        println!(
+            "\n  {} {}  {}\n",
+            label.cyan(),
+            mask(&token),
+            "(--show prints it)".dimmed()
+        );
+    }
+}
+
+/// Delete an account's token from the keychain, keeping the account.
+pub fn delete(config: &Config, account_id: Option<String>) {
+    let account = resolve_account(config, account_id, "  Delete token of");
+    let label = format_account_label(&account);
+    if get_token(&account.username, account.alias.as_deref()).is_none() {
+        println!(
+            "\n  {}\n",
+            format!("No token stored for {}.", label).dimmed()
+        );
+        return;
+    }
+    delete_token(&account.username, account.alias.as_deref());
+    crate::audit::record_token(&account, "removed");
+    println!(
+        "\n  {} Deleted the token of {}. {}\n",
+        "✓".green().bold(),
+        label.cyan(),
+        "git's credential helpers may still hold a copy; `gitas logout` clears those too.".dimmed()
+    );
+}
+
+/// The token's prefix and last four characters, enough to tell tokens apart.
+fn mask(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    // Forge prefixes like `ghp_` or `glpat-` say what kind of token it is.
+    let prefix: String = chars
+        .iter()
+        .take(7)
+        .position(|c| *c == '_' || *c == '-')
+        .map(|end| chars[..=end].iter().collect())
+        .unwrap_or_default();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+/// `gitas token delivery [mode]`: show or change how `gitas git` hands
+/// tokens to git.
+pub fn delivery(config: &mut Config, mode: Option<TokenDelivery>) {
+    let Some(mode) = mode else {
+        println!(
+            "\n  Token delivery: {} {}\n",
+            config.token_delivery.unwrap_or_default().name().cyan(),
+            "(env or once)".dimmed()
+        );
+        return;
+    };
+    if mode == TokenDelivery::Once && cfg!(not(unix)) {
+        eprintln!(
+            "\n  {} One-shot delivery needs Unix sockets; tokens stay in the environment here.\n",
+            "✗".red().bold()
+        );
+        std::process::exit(1);
+    }
+    config.token_delivery = (mode != TokenDelivery::default()).then_some(mode);
+    crate::models::save_config(config);
+    println!(
+        "\n  {} Token delivery set to {}.",
+        "✓".green().bold(),
+        mode.name().cyan()
+    );
+    if mode == TokenDelivery::Once {
+        println!(
+            "  {}",
+            "Each gitas git run answers one credential request; a second one in the same run gets nothing."
+                .dimmed()
+        );
+    }
+    println!();
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+/// `gitas token agent start`: hand every account's token to a background
+/// agent for the TTL. A running agent is replaced, so it picks up new tokens.
+pub fn agent_start(config: &Config, ttl: Option<String>) {
+    let ttl = match ttl {
+        Some(ttl) => token_agent::parse_ttl(&ttl).unwrap_or_else(|e| fail(e)),
+        None => config.agent_ttl.unwrap_or(token_agent::DEFAULT_TTL),
+    };
+    let replaced = token_agent::stop();
+    match token_agent::start(&config.accounts, ttl) {
+        Ok(count) => {
+            println!(
+                "\n  {} {} {} token{} for {}",
+                "✓".green().bold(),
+                if replaced {
+                    "Restarted the agent with"
+                } else {
+                    "Agent holds"
+                },
+                count,
+                if count == 1 { "" } else { "s" },
+                token_agent::format_ttl(ttl).cyan()
+            );
+            println!(
+                "  {}\n",
+                "gitas git and gitas credential read them from memory. Stop with `gitas token agent stop`."
+                    .dimmed()
+            );
+        }
+        Err(e) => fail(e),
+    }
+}
+
+pub fn agent_stop() {
+    if token_agent::stop() {
+        println!(
+            "\n  {} Stopped the agent; its tokens are wiped\n",
+            "✓".green().bold()
+        );
+    } else {
+        println!("\n  {}\n", "No token agent is running.".dimmed());
+    }
+}
+
+pub fn agent_status() {
+    match token_agent::status() {
+        Some((count, left)) => println!(
+            "\n  {} Agent holds {} token{}, {} left {}\n",
+            "●".green(),
+            count,
+            if count == 1 { "" } else { "s" },
+            token_agent::format_ttl(left).cyan(),
+            format!("({})", token_agent::socket_path().display()).dimmed()
+        ),
+        None => println!("\n  {}\n", "No token agent is running.".dimmed()),
+    }
+}
+
+/// `gitas token agent ttl [DURATION]`: show or set the default TTL.
+pub fn agent_ttl(config: &mut Config, duration: Option<String>) {
+    let Some(duration) = duration else {
+        println!(
+            "\n  Token agent TTL: {}\n",
+            token_agent::format_ttl(config.agent_ttl.unwrap_or(token_agent::DEFAULT_TTL)).cyan()
+        );
+        return;
+    };
+    let ttl = token_agent::parse_ttl(&duration).unwrap_or_else(|e| fail(e));
+    config.agent_ttl = (ttl != token_agent::DEFAULT_TTL).then_some(ttl);
+    crate::models::save_config(config);
+    println!(
+        "\n  {} Token agent TTL set to {}.\n",
+        "✓".green().bold(),
+        token_agent::format_ttl(ttl).cyan()
+    );
+}
+
+/// Show parked tokens of removed accounts, delete expired ones, or restore one.
+pub fn gc(all: bool, restore: Option<String>) {
+    if let Some(id) = restore {
+        match crate::tombstone::restore(&id) {
+            Ok(tombstone) => println!(
+                "\n  {} Restored token for {}. Re-add the account to use it.\n",
+                "✓".green().bold(),
+                tombstone.label().cyan()
+            ),
+            Err(e) => {
+                eprintln!("\n  {} {}\n", "✗".red().bold(), e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let (kept, deleted) = crate::tombstone::gc(all);
+    println!();
+    for tombstone in &deleted {
+        println!(
+            "  {} Deleted token of removed account {}",
+            "✓".green().bold(),
+            tombstone.label()
+        );
+    }
+    if kept.is_empty() {
+        if deleted.is_empty() {
+            println!("  {}", "No tokens from removed accounts.".dimmed());
+        }
+        println!();
+        return;
+    }
+
+    if !deleted.is_empty() {
+        println!();
+    }
+    for tombstone in &kept {
+        let removed = crate::audit::format_time(tombstone.removed_at);
+        println!(
+            "  {} {}  {}",
+            "○".dimmed(),
+            tombstone.label().bold(),
+            format!(
+                "removed {}, {} day(s) left",
+                removed,
+                crate::tombstone::days_left(tombstone)
+            )
+            .dimmed()
+        );
+    }
+    println!(
+        "\n  Restore one with {}.\n",
+        "gitas token gc --restore <account>".cyan()
+    );
+}
+
+pub(crate) struct CheckJob {
+    pub account: Account,
+    pub host: String,
+    token: Option<Zeroizing<String>>,
+}
+
+pub(crate) fn jobs_for(accounts: Vec<Account>) -> Vec<CheckJob> {
+    accounts
+        .into_iter()
+        .map(|account| CheckJob {
+            token: get_token(&account.username, account.alias.as_deref())
+                .filter(|token| !token.is_empty()),
+            host: account
+                .host
+                .clone()
+                .unwrap_or_else(|| "github.com".to_string()),
+            account,
+        })
+        .collect()
+}
+
+pub(crate) enum CheckResult {
+    Valid(TokenInfo),
+    Invalid(String),
+    Missing,
+    SshOnly,
+    RateLimited,
+}
+
+#[derive(Default)]
+struct Scheduler {
+    pending: VecDeque<usize>,
+    in_flight: HashMap<String, usize>,
+    /// Hosts that reported an exhausted rate limit; remaining jobs are skipped.
+    limited: HashSet<String>,
+}
+
+enum Next {
+    Run(usize),
+    Skip(usize),
+    Wait,
+    Done,
+}
+
+impl Scheduler {
+    fn next(&mut self, jobs: &[CheckJob]) -> Next {
+        if self.pending.is_empty() {
+            return Next::Done;
+        }
+        for pos in 0..self.pending.len() {
+            let index = self.pending[pos];
+            let host = &jobs[index].host;
+            if self.limited.contains(host) {
+                self.pending.remove(pos);
+                return Next::Skip(index);
+            }
+            let in_flight = self.in_flight.entry(host.clone()).or_default();
+            if *in_flight < PER_HOST_CONCURRENCY {
+                *in_flight += 1;
+                self.pending.remove(pos);
+                return Next::Run(index);
+            }
+        }
+        Next::Wait
+    }
+}
+
+/// Validate tokens with bounded concurrency, limiting parallel requests per host
+/// and backing off from hosts whose rate limit runs out mid-batch.
+pub(crate) fn check_all(jobs: &[CheckJob]) -> Vec<CheckResult> {
+    let mut results: Vec<Option<CheckResult>> = Vec::with_capacity(jobs.len());
+    let mut scheduler = Scheduler::default();
+
+    for (index, job) in jobs.iter().enumerate() {
+        if job.account.ssh_only {
+            results.push(Some(CheckResult::SshOnly));
+        } else if job.token.is_none() {
+            results.push(Some(CheckResult::Missing));
+        } else {
+            results.push(None);
+            scheduler.pending.push_back(index);
+        }
+    }
+
+    let workers = MAX_CONCURRENCY.min(scheduler.pending.len());
+    let state = (Mutex::new(scheduler), Condvar::new());
+    let results = Mutex::new(results);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let (lock, condvar) = &state;
+                loop {
+                    let index = {
+                        let mut scheduler = lock.lock().unwrap();
+                        loop {
+                            match scheduler.next(jobs) {
+                                Next::Run(index) => break index,
+                                Next::Skip(index) => {
+                                    let mut results = results.lock().unwrap();
+                                    results[index] = Some(CheckResult::RateLimited);
+                                }
+                                Next::Wait => scheduler = condvar.wait(scheduler).unwrap(),
+                                Next::Done => return,
+                            }
+                        }
+                    };
+
+                    let job = &jobs[index];
+                    let result = match validate(job) {
+                        Ok(info) => CheckResult::Valid(info),
+                        Err(e) => CheckResult::Invalid(e),
+                    };
+
+                    let exhausted = match &result {
+                        CheckResult::Valid(info) => info.rate_remaining == Some(0),
+                        CheckResult::Invalid(e) => e.starts_with("Rate limited"),
+                        _ => false,
+                    };
+                    results.lock().unwrap()[index] = Some(result);
+
+                    let mut scheduler = lock.lock().unwrap();
+                    if let Some(in_flight) = scheduler.in_flight.get_mut(&job.host) {
+                        *in_flight -= 1;
+                    }
+                    if exhausted {
+                        scheduler.limited.insert(job.host.clone());
+                    }
+                    condvar.notify_all();
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|result| result.unwrap_or(CheckResult::RateLimited))
+        .collect()
+}
+
+fn validate(job: &CheckJob) -> Result<TokenInfo, String> {
+    let token = job.token.as_deref().map_or("", String::as_str);
+    let info = provider::for_account(&job.account).check_token(token)?;
+    let key = make_key(&job.account.username, job.account.alias.as_deref());
+    crate::cache::store_token_login(&key, token, &info.login);
+    Ok(info)
+}
+
+fn print_table(jobs: &[CheckJob], results: &[CheckResult]) {
+    let labels: Vec<String> = jobs
+        .iter()
+        .map(|job| format_account_label(&job.account))
+        .collect();
+    let label_width = labels.iter().map(|l| l.len()).max().unwrap_or(0);
+    let host_width = jobs
+        .iter()
+        .map(|job| job.host.len())
+        .max()
+        .unwrap_or(0)
+        .max("Host".len());
+
+    println!();
+    println!(
+        "    {:<lw$}  {:<hw$}  {:<8}  {}",
+        "Account".dimmed(),
+        "Host".dimmed(),
+        "Status".dimmed(),
+        "Details".dimmed(),
+        lw = label_width,
+        hw = host_width
+    );
+    println!("  {}", "─".repeat(label_width + host_width + 40).dimmed());
+
+    for ((job, label), result) in jobs.iter().zip(&labels).zip(results) {
+        let (marker, status, details) = match result {
+            CheckResult::Valid(info) => {
+                let mut details = vec![format!("login {}", info.login.cyan())];
+                if !info.login.eq_ignore_ascii_case(&job.account.username) {
+                    details.push(format!("{}", "(MISMATCH: not this account)".red().bold()));
+                }
+                details.push(format!(
+                    "scopes: {}",
+                    info.scopes.as_deref().unwrap_or("n/a")
+                ));
+                details.push(format!(
+                    "expires: {}",
+                    info.expires_at.as_deref().unwrap_or("never")
+                ));
+                ("✔".green(), "valid".green(), details.join("  "))
+            }
+            CheckResult::Invalid(e) => ("✗".red(), "invalid".red(), e.clone()),
+            CheckResult::Missing => ("○".dimmed(), "missing".dimmed(), String::new()),
+            CheckResult::SshOnly => ("○".dimmed(), "ssh-only".dimmed(), String::new()),
+            CheckResult::RateLimited => (
+                "⚠".yellow(),
+                "skipped".yellow(),
+                "Rate limit exhausted for host".to_string(),
+            ),
+        };
+        println!(
+            "  {} {:<lw$}  {:<hw$}  {:<8}  {}",
+            marker,
+            label,
+            job.host,
+            status,
+            details,
+            lw = label_width,
+            hw = host_width
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_all_but_prefix_and_tail() {
+        assert_eq!(mask("ghp_abcdefghijklmnop1234"), "ghp_…1234");
+        assert_eq!(mask("glpat-abcdefghijklmn9876"), "glpat-…9876");
+        assert_eq!(mask("ABCDEFGHIJKLMNOP"), "…MNOP");
+        assert_eq!(mask("short"), "*****");
+    }
+}
@@ -0,0 +1,55 @@
+use colored::Colorize;
+use std::process::Command;
+
+pub fn run(verbose: bool) {
+    println!("gitas {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return;
+    }
+
+    let git = Command::new("git")
+        .arg("--version")
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "not found".to_string());
+    let path = |dir: Option<std::path::PathBuf>| {
+        dir.map(|d| d.join("gitas").display().to_string())
+            .unwrap_or_else(|| "unavailable".to_string())
+    };
+
+    let rows = [
+        ("commit", env!("GITAS_GIT_COMMIT").to_string()),
+        ("target", env!("GITAS_TARGET").to_string()),
+        ("profile", env!("GITAS_PROFILE").to_string()),
+        ("keyring", keyring_backend().to_string()),
+        ("git", git),
+        ("config", crate::models::config_path().display().to_string()),
+        ("cache", path(dirs::cache_dir())),
+        ("data", path(dirs::data_local_dir())),
+        (
+            "mode",
+            if crate::models::is_ephemeral() {
+                "ephemeral".to_string()
+            } else {
+                "normal".to_string()
+            },
+        ),
+    ];
+    for (label, value) in rows {
+        println!("  {:<8} {}", label.dimmed(), value);
+    }
+}
+
+/// Keychain backend compiled in for this platform.
+fn keyring_backend() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "apple-native (Keychain)"
+    } else if cfg!(target_os = "windows") {
+        "windows-native (Credential Manager)"
+    } else if cfg!(target_os = "linux") {
+        "linux-native (kernel keyutils)"
+    } else {
+        "mock (tokens are not persisted)"
+    }
+}
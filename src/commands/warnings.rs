@@ -0,0 +1,73 @@
+use crate::models::{Config, save_config};
+use crate::utils::{find_account_index, format_account_label};
+use crate::warnings::{self, Warning};
+use clap::ValueEnum;
+use colored::Colorize;
+
+pub fn list(config: &Config, account_id: Option<String>) {
+    let account = account_id.map(|id| &config.accounts[account_index(config, &id)]);
+
+    println!();
+    if let Some(account) = account {
+        println!("  {}\n", format_account_label(account).bold());
+    }
+    for warning in Warning::value_variants() {
+        let on = warnings::enabled(config, account, *warning);
+        let overridden = account.is_some_and(|a| a.warnings.contains_key(warning));
+        println!(
+            "  {} {:<18} {}{}",
+            if on { "●".green() } else { "○".dimmed() },
+            warning.name(),
+            warning
+                .to_possible_value()
+                .and_then(|v| v.get_help().map(|h| h.to_string()))
+                .unwrap_or_default()
+                .dimmed(),
+            if overridden {
+                " (account setting)".cyan().to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+    println!();
+}
+
+/// Turn a warning on or off, for one account when `account_id` is given.
+pub fn set(config: &mut Config, account_id: Option<String>, warning: Warning, on: bool) {
+    let scope = match account_id {
+        Some(id) => {
+            let index = account_index(config, &id);
+            config.accounts[index].warnings.insert(warning, on);
+            format_account_label(&config.accounts[index])
+        }
+        None => {
+            // Enabled is the default, so only the disabled state needs storing.
+            if on {
+                config.warnings.remove(&warning);
+            } else {
+                config.warnings.insert(warning, false);
+            }
+            "all accounts".to_string()
+        }
+    };
+    save_config(config);
+    println!(
+        "\n  {} Warning '{}' {} for {}.\n",
+        "✓".green().bold(),
+        warning.name().cyan(),
+        if on { "enabled" } else { "disabled" },
+        scope
+    );
+}
+
+fn account_index(config: &Config, id: &str) -> usize {
+    find_account_index(config, id).unwrap_or_else(|| {
+        eprintln!(
+            "\n  {} No account matching '{}'.\n",
+            "✗".red().bold(),
+            id.yellow()
+        );
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,89 @@
+use crate::models::{Config, get_token};
+use crate::resolve::resolve_for_path;
+use crate::utils::{
+    format_account_label, git_config_get, identity_env_overrides, redact_email, redact_name,
+};
+use colored::Colorize;
+use std::path::Path;
+
+pub fn run(config: &Config) {
+    let name = lookup("user.name");
+    let email = lookup("user.email");
+
+    println!();
+    let name = name.map(|(value, scope)| (redact_name(&value), scope));
+    let email = email.map(|(value, scope)| (redact_email(&value), scope));
+    for (label, value) in [("Name:", &name), ("Email:", &email)] {
+        match value {
+            Some((value, scope)) => println!(
+                "  {:<9} {} {}",
+                label.dimmed(),
+                value.bold(),
+                format!("({})", scope).dimmed()
+            ),
+            None => println!("  {:<9} {}", label.dimmed(), "not set".yellow()),
+        }
+    }
+
+    let resolution = resolve_for_path(config, Path::new("."));
+    match &resolution {
+        Some(resolution) => {
+            let account = resolution.account;
+            println!(
+                "  {:<9} {}",
+                "Account:".dimmed(),
+                format_account_label(account).cyan()
+            );
+            if let Some(repo) = git_config_get("user.email", "effective")
+                .and_then(|email| account.plus_addresses.get(&email.to_lowercase()))
+            {
+                println!(
+                    "  {:<9} {}",
+                    "Plus:".dimmed(),
+                    format!("address made for {}", repo).dimmed()
+                );
+            }
+            let token = if account.ssh_only {
+                "ssh-only".dimmed()
+            } else if get_token(&account.username, account.alias.as_deref())
+                .is_some_and(|t| !t.is_empty())
+            {
+                "✓ in keychain".green()
+            } else {
+                "✗ missing".red()
+            };
+            println!("  {:<9} {}", "Token:".dimmed(), token);
+        }
+        None => println!(
+            "  {:<9} {}",
+            "Account:".dimmed(),
+            "not managed by gitas".yellow()
+        ),
+    }
+
+    let overrides = identity_env_overrides();
+    if !overrides.is_empty() {
+        let names: Vec<&str> = overrides.iter().map(|(var, _)| *var).collect();
+        println!(
+            "\n  {} {} set in the environment and will override this identity.",
+            "⚠".yellow(),
+            names.join(", ")
+        );
+    }
+    println!();
+
+    if resolution.is_none() {
+        std::process::exit(1);
+    }
+}
+
+/// Effective value of `key` and the scope it comes from.
+fn lookup(key: &str) -> Option<(String, &'static str)> {
+    if let Some(value) = git_config_get(key, "local") {
+        return Some((value, "local"));
+    }
+    if let Some(value) = git_config_get(key, "global") {
+        return Some((value, "global"));
+    }
+    git_config_get(key, "effective").map(|value| (value, "system"))
+}
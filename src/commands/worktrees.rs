@@ -0,0 +1,305 @@
+use crate::models::{Config, get_token, is_ephemeral};
+use crate::resolve::resolve_for_path;
+use crate::utils::{format_account_label, git_credential_approve, redact_email, redact_name};
+use colored::Colorize;
+use std::path::Path;
+use std::process::Command;
+
+struct Worktree {
+    path: String,
+    /// Short branch name; `None` when HEAD is detached.
+    branch: Option<String>,
+    bare: bool,
+}
+
+/// `gitas worktrees`: every worktree of the current repository with the
+/// identity git uses there and the account that matches it.
+pub fn list(config: &Config) {
+    let worktrees = worktrees().unwrap_or_else(|e| fail(e));
+    let width = worktrees.iter().map(|w| w.path.len()).max().unwrap_or(0);
+
+    println!();
+    for worktree in worktrees.iter().filter(|w| !w.bare) {
+        let dir = Path::new(&worktree.path);
+        let branch = worktree.branch.as_deref().unwrap_or("(detached)");
+        println!(
+            "  {:<width$}  {}",
+            worktree.path.bold(),
+            branch.cyan(),
+            width = width
+        );
+        let name = scoped_get(dir, "user.name");
+        let email = scoped_get(dir, "user.email");
+        let identity = match (&name, &email) {
+            (Some((name, scope)), Some((email, _))) => format!(
+                "{} <{}> {}",
+                redact_name(name),
+                redact_email(email),
+                format!("({})", scope).dimmed()
+            ),
+            _ => "no identity".yellow().to_string(),
+        };
+        let account = match resolve_for_path(config, dir) {
+            Some(resolution) => format!("→ {}", format_account_label(resolution.account).cyan()),
+            None => format!("→ {}", "no managed account".yellow()),
+        };
+        println!("  {:<width$}  {}  {}", "", identity, account, width = width);
+    }
+    println!(
+        "\n  {}\n",
+        "Give one worktree its own account with `gitas worktrees --set <path> -a <account>`."
+            .dimmed()
+    );
+}
+
+/// `gitas worktrees --set`: write the account's identity into the worktree's
+/// own config (`config.worktree`), leaving the other worktrees alone.
+pub fn set(config: &Config, account_id: Option<String>, path: &str) {
+    let worktree = find(path);
+    if is_ephemeral() {
+        fail("Ephemeral mode writes no git config.".to_string());
+    }
+    let account = crate::utils::resolve_account(config, account_id, "  Apply to worktree");
+    enable_worktree_config(&worktree.path).unwrap_or_else(|e| fail(e));
+
+    let dir = Path::new(&worktree.path);
+    for (key, value) in super::list::switch_plan(&account, &config.all_accounts(), |key| {
+        worktree_get(dir, key)
+    }) {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(dir).args(["config", "--worktree"]);
+        match &value {
+            Some(value) => cmd.args([key.as_str(), value.as_str()]),
+            None => cmd.args(["--unset", key.as_str()]),
+        };
+        let ok = cmd.output().is_ok_and(|o| o.status.success());
+        if !ok && value.is_some() {
+            fail(format!("Could not set {} in {}", key, worktree.path));
+        }
+    }
+
+    if !account.ssh_only
+        && let Some(token) =
+            get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+    {
+        let host = account.host.as_deref().unwrap_or("github.com");
+        if let Err(e) = git_credential_approve(&account.username, &token, host, None) {
+            println!("  {} {}", "⚠".yellow(), e);
+        }
+    }
+
+    let entry = crate::audit::Entry {
+        repo: Some(worktree.path.clone()),
+        scope: Some("worktree".to_string()),
+        ..crate::audit::Entry::new(crate::audit::Event::Switch, &account)
+    };
+    crate::audit::record(&entry);
+    println!(
+        "\n  {} {} now uses {}\n",
+        "✓".green().bold(),
+        worktree.path,
+        format_account_label(&account).cyan()
+    );
+}
+
+/// `gitas worktrees --unset`: drop the worktree's own identity so it follows
+/// the repository again.
+pub fn unset(config: &Config, path: &str) {
+    let worktree = find(path);
+    if is_ephemeral() {
+        fail("Ephemeral mode writes no git config.".to_string());
+    }
+    let dir = Path::new(&worktree.path);
+    let accounts = config.all_accounts();
+    let mut keys: Vec<String> = ["user.name", "user.email", "gitas.alias"]
+        .map(str::to_string)
+        .to_vec();
+    let managed_ssh = worktree_get(dir, "core.sshCommand").is_some_and(|current| {
+        accounts
+            .iter()
+            .filter_map(|a| a.ssh_key.as_deref())
+            .any(|key| crate::ssh::command(key) == current)
+    });
+    if managed_ssh {
+        keys.push("core.sshCommand".to_string());
+    }
+    if worktree_get(dir, "user.signingkey")
+        .is_some_and(|key| crate::signing::is_managed(&accounts, &key))
+    {
+        keys.extend(crate::signing::KEYS.map(str::to_string));
+    }
+    for account in &accounts {
+        let host = account.host.as_deref().unwrap_or("github.com");
+        let key = format!("credential.https://{}.username", host);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    for key in &keys {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["config", "--worktree", "--unset", key.as_str()])
+            .output()
+            .ok();
+    }
+    println!(
+        "\n  {} {} follows the repository's identity again\n",
+        "✓".green().bold(),
+        worktree.path
+    );
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("\n  {} {}\n", "✗".red().bold(), message);
+    std::process::exit(1);
+}
+
+/// The worktree at (or containing) `path`.
+fn find(path: &str) -> Worktree {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+    let mut worktrees = worktrees().unwrap_or_else(|e| fail(e));
+    worktrees.sort_by_key(|w| std::cmp::Reverse(w.path.len()));
+    worktrees
+        .into_iter()
+        .filter(|w| !w.bare)
+        .find(|w| std::fs::canonicalize(&w.path).is_ok_and(|root| target.starts_with(&root)))
+        .unwrap_or_else(|| fail(format!("{} is not a worktree of this repository", path)))
+}
+
+fn worktrees() -> Result<Vec<Worktree>, String> {
+    let output = Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err("Not inside a git repository.".to_string());
+    }
+    Ok(parse_worktrees(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Records of `git worktree list --porcelain`, separated by blank lines.
+fn parse_worktrees(text: &str) -> Vec<Worktree> {
+    let mut worktrees: Vec<Worktree> = Vec::new();
+    for line in text.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push(Worktree {
+                path: path.to_string(),
+                branch: None,
+                bare: false,
+            });
+        } else if let Some(worktree) = worktrees.last_mut() {
+            if let Some(branch) = line.strip_prefix("branch ") {
+                worktree.branch = Some(branch.trim_start_matches("refs/heads/").to_string());
+            } else if line == "bare" {
+                worktree.bare = true;
+            }
+        }
+    }
+    worktrees
+}
+
+/// `git -C path <args>`, Some(stdout) when it succeeds.
+fn git_in(path: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(["-C", path])
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Per-worktree config needs `extensions.worktreeConfig`; without it
+/// `--worktree` writes would land in the shared config. As git asks, a true
+/// `core.bare` and any `core.worktree` then move into the main worktree's
+/// `config.worktree`, or every linked worktree would inherit them.
+fn enable_worktree_config(path: &str) -> Result<(), String> {
+    if git_in(
+        path,
+        &["config", "--local", "--bool", "extensions.worktreeConfig"],
+    )
+    .as_deref()
+        == Some("true")
+    {
+        return Ok(());
+    }
+    let bare =
+        git_in(path, &["config", "--local", "--bool", "core.bare"]).filter(|bare| bare == "true");
+    let worktree = git_in(path, &["config", "--local", "core.worktree"]);
+    let moves: Vec<(&str, String)> = [("core.bare", bare), ("core.worktree", worktree)]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect();
+    if !moves.is_empty() {
+        let common = git_in(
+            path,
+            &["rev-parse", "--path-format=absolute", "--git-common-dir"],
+        )
+        .ok_or("Could not find the repository's git directory")?;
+        let file = Path::new(&common).join("config.worktree");
+        let file = file.to_string_lossy();
+        for (key, value) in &moves {
+            git_in(path, &["config", "--file", &file, key, value])
+                .ok_or_else(|| format!("Could not move {} to {}", key, file))?;
+        }
+    }
+    git_in(
+        path,
+        &["config", "--local", "extensions.worktreeConfig", "true"],
+    )
+    .ok_or("Could not enable extensions.worktreeConfig")?;
+    for (key, _) in &moves {
+        git_in(path, &["config", "--local", "--unset", key])
+            .ok_or_else(|| format!("Could not remove {} from the shared config", key))?;
+    }
+    Ok(())
+}
+
+fn worktree_get(dir: &Path, key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["config", "--worktree", "--get", key])
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+/// Effective value of `key` in `dir` and the scope it comes from (`worktree`,
+/// `local`, `global`, ...).
+fn scoped_get(dir: &Path, key: &str) -> Option<(String, String)> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["config", "--show-scope", "--get", key])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (scope, value) = text.trim_end().split_once('\t')?;
+    Some((value.to_string(), scope.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_worktrees;
+
+    #[test]
+    fn parses_porcelain_records() {
+        let text = "worktree /src/app\nHEAD 1111\nbranch refs/heads/main\n\n\
+                    worktree /src/app-oss\nHEAD 2222\ndetached\n\n\
+                    worktree /src/bare.git\nbare\n";
+        let worktrees = parse_worktrees(text);
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[1].path, "/src/app-oss");
+        assert!(worktrees[1].branch.is_none());
+        assert!(worktrees[2].bare);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::models::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+
+/// Actions gitas can ask about before going ahead.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Removing an account
+    Delete,
+    /// A global switch replacing an identity gitas doesn't manage
+    GlobalSwitch,
+    /// `gitas add` replacing an account and its stored token
+    CredentialOverwrite,
+    /// Installing the pre-push hook
+    HookInstall,
+}
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Delete => "delete",
+            Action::GlobalSwitch => "global-switch",
+            Action::CredentialOverwrite => "credential-overwrite",
+            Action::HookInstall => "hook-install",
+        }
+    }
+
+    /// What gitas does when nothing is configured.
+    fn default_policy(self) -> Policy {
+        match self {
+            Action::HookInstall => Policy::Never,
+            _ => Policy::Always,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Policy {
+    /// Ask, and refuse when there is no terminal to ask on
+    Always,
+    /// Never ask
+    Never,
+    /// Ask on a terminal, go ahead in scripts
+    TtyOnly,
+}
+
+impl Policy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Always => "always",
+            Policy::Never => "never",
+            Policy::TtyOnly => "tty-only",
+        }
+    }
+}
+
+/// Policies set with `gitas confirm set`; missing actions use their default.
+pub type ConfirmSettings = BTreeMap<Action, Policy>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Decision {
+    Ask,
+    Proceed,
+    /// Confirmation is required and nobody can give it.
+    Refuse,
+}
+
+pub fn policy(config: &Config, action: Action) -> Policy {
+    config
+        .confirm
+        .get(&action)
+        .copied()
+        .unwrap_or(action.default_policy())
+}
+
+/// Whether to ask before `action`. Strict mode counts as having no terminal.
+pub fn decide(config: &Config, action: Action) -> Decision {
+    let interactive = std::io::stdin().is_terminal() && !crate::models::is_strict();
+    decide_for(policy(config, action), interactive)
+}
+
+fn decide_for(policy: Policy, interactive: bool) -> Decision {
+    match (policy, interactive) {
+        (Policy::Never, _) => Decision::Proceed,
+        (_, true) => Decision::Ask,
+        (Policy::TtyOnly, false) => Decision::Proceed,
+        (Policy::Always, false) => Decision::Refuse,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_decide_by_terminal() {
+        assert_eq!(decide_for(Policy::Always, true), Decision::Ask);
+        assert_eq!(decide_for(Policy::Always, false), Decision::Refuse);
+        assert_eq!(decide_for(Policy::TtyOnly, true), Decision::Ask);
+        assert_eq!(decide_for(Policy::TtyOnly, false), Decision::Proceed);
+        assert_eq!(decide_for(Policy::Never, true), Decision::Proceed);
+
+        let mut config = Config::default();
+        assert_eq!(policy(&config, Action::Delete), Policy::Always);
+        assert_eq!(policy(&config, Action::HookInstall), Policy::Never);
+        config.confirm.insert(Action::Delete, Policy::TtyOnly);
+        assert_eq!(policy(&config, Action::Delete), Policy::TtyOnly);
+
+        let json = serde_json::to_string(&config.confirm).unwrap();
+        assert_eq!(json, r#"{"delete":"tty-only"}"#);
+    }
+}
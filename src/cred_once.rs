@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+/// How `gitas git` hands the account's token to git's credential helper.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenDelivery {
+    /// Environment variables read by an inline shell helper
+    #[default]
+    Env,
+    /// A private socket that gives the token out once (Unix only)
+    Once,
+}
+
+impl TokenDelivery {
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenDelivery::Env => "env",
+            TokenDelivery::Once => "once",
+        }
+    }
+}
+
+/// Directory holding the socket for `id`, readable only by this user.
+fn socket_dir(id: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("gitas-cred-{}", id))
+}
+
+/// Ids are hex so they can't name a path outside the temp directory.
+fn is_id(id: &str) -> bool {
+    id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// A token waiting on a private socket for the first `gitas _cred-once` to
+/// ask for it. The socket closes after that answer, and its directory goes
+/// when this is dropped, so the token never lands in argv, the environment
+/// or a file.
+pub struct OneShot {
+    id: String,
+}
+
+impl OneShot {
+    #[cfg(unix)]
+    pub fn serve(username: &str, token: &str) -> Result<OneShot, String> {
+        use ring::rand::{SecureRandom, SystemRandom};
+        use std::io::Write;
+        use std::os::unix::fs::DirBuilderExt;
+        use std::os::unix::net::UnixListener;
+
+        let mut bytes = [0u8; 16];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| "Could not generate a socket id".to_string())?;
+        let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let dir = socket_dir(&id);
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        let path = dir.join("socket");
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| format!("Could not listen on {}: {}", path.display(), e))?;
+
+        let answer = Zeroizing::new(format!("username={}\npassword={}\n", username, token));
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                stream.write_all(answer.as_bytes()).ok();
+            }
+            // Later asks find no socket and get nothing.
+            std::fs::remove_file(&path).ok();
+        });
+        Ok(OneShot { id })
+    }
+
+    #[cfg(not(unix))]
+    pub fn serve(_username: &str, _token: &str) -> Result<OneShot, String> {
+        Err("One-shot token delivery needs Unix sockets".to_string())
+    }
+
+    /// `credential.helper` value that asks this socket.
+    pub fn helper(&self, exe: &str) -> String {
+        format!(
+            "!{} _cred-once {}",
+            crate::utils::shell_quote(&exe.replace('\\', "/")),
+            self.id
+        )
+    }
+}
+
+impl Drop for OneShot {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(socket_dir(&self.id)).ok();
+    }
+}
+
+/// `gitas _cred-once <id> get`, run by git: copy what the socket answers to
+/// stdout. Other actions, unknown ids and spent sockets print nothing, which
+/// git reads as "no credentials here".
+pub fn fetch(id: &str, action: Option<&str>) {
+    if action != Some("get") || !is_id(id) {
+        return;
+    }
+    #[cfg(unix)]
+    {
+        use std::io::Read;
+        let path = socket_dir(id).join("socket");
+        let Ok(mut stream) = std::os::unix::net::UnixStream::connect(path) else {
+            return;
+        };
+        let mut answer = Zeroizing::new(String::new());
+        if stream.read_to_string(&mut answer).is_ok() {
+            print!("{}", answer.as_str());
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_exactly_once() {
+        let shot = OneShot::serve("octo", "tok").unwrap();
+        assert!(
+            shot.helper("/usr/bin/gitas")
+                .ends_with(&format!(" _cred-once {}", shot.id))
+        );
+
+        let ask = || {
+            use std::io::Read;
+            let mut stream =
+                std::os::unix::net::UnixStream::connect(socket_dir(&shot.id).join("socket"))
+                    .ok()?;
+            let mut answer = String::new();
+            stream.read_to_string(&mut answer).ok()?;
+            Some(answer)
+        };
+        assert_eq!(ask().as_deref(), Some("username=octo\npassword=tok\n"));
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(ask(), None);
+
+        let dir = socket_dir(&shot.id);
+        drop(shot);
+        assert!(!dir.exists());
+        assert!(!is_id("../../etc"));
+    }
+}
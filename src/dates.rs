@@ -0,0 +1,119 @@
+use crate::models::Account;
+use crate::utils::git_subcommand_index;
+use serde::{Deserialize, Serialize};
+
+/// Author dates of commits `gitas git` recreates (amend, rebase).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorDate {
+    /// Keep the original author date, as git does
+    #[default]
+    Keep,
+    /// Restamp it with the time of the rewrite, in the account's timezone
+    Now,
+}
+
+/// Check a timezone name before it is stored. `UTC` always works; other
+/// names must exist in the zoneinfo database when there is one to look at.
+pub fn check_timezone(name: &str) -> Result<(), String> {
+    let invalid = || {
+        format!(
+            "'{}' is not a timezone name like Europe/Berlin or UTC",
+            name
+        )
+    };
+    if name.is_empty()
+        || name.starts_with('/')
+        || name.split('/').any(|part| part.is_empty() || part == "..")
+    {
+        return Err(invalid());
+    }
+    if name == "UTC" {
+        return Ok(());
+    }
+    let zoneinfo = std::env::var_os("TZDIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| "/usr/share/zoneinfo".into());
+    if zoneinfo.is_dir() && !zoneinfo.join(name).is_file() {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Environment git stamps commit dates from: `TZ` for accounts with a
+/// timezone, so their commits carry its offset instead of the machine's.
+pub fn env(account: &Account) -> Vec<(&'static str, String)> {
+    account
+        .timezone
+        .iter()
+        .map(|timezone| ("TZ", timezone.clone()))
+        .collect()
+}
+
+/// Flag that makes a rewrite take a fresh author date under `AuthorDate::Now`,
+/// with where to insert it. Git reads the author of a recreated commit from
+/// the original, so `GIT_AUTHOR_DATE` alone would be ignored.
+pub fn reset_flag(account: &Account, args: &[String]) -> Option<(usize, &'static str)> {
+    if account.author_date.unwrap_or_default() != AuthorDate::Now {
+        return None;
+    }
+    let index = git_subcommand_index(args)?;
+    let rest = &args[index + 1..];
+    let has = |flag: &str| {
+        rest.iter()
+            .any(|a| a == flag || a.starts_with(&format!("{flag}=")))
+    };
+    let flag = match args[index].as_str() {
+        "commit" if has("--amend") && !has("--date") && !has("--reset-author") => "--date=now",
+        "rebase"
+            if !["--continue", "--skip", "--abort", "--quit", "--edit-todo"]
+                .iter()
+                .any(|flag| has(flag))
+                && !has("--reset-author-date")
+                && !has("--ignore-date") =>
+        {
+            "--reset-author-date"
+        }
+        _ => return None,
+    };
+    Some((index + 1, flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn restamps_rewrites_only_when_asked() {
+        let mut account = Account {
+            timezone: Some("Asia/Tokyo".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(env(&account), [("TZ", "Asia/Tokyo".to_string())]);
+        assert_eq!(reset_flag(&account, &args("commit --amend")), None);
+
+        account.author_date = Some(AuthorDate::Now);
+        assert_eq!(
+            reset_flag(&account, &args("-C repo commit --amend --no-edit")),
+            Some((3, "--date=now"))
+        );
+        assert_eq!(
+            reset_flag(&account, &args("commit --amend --date=yesterday")),
+            None
+        );
+        assert_eq!(reset_flag(&account, &args("commit -m x")), None);
+        assert_eq!(
+            reset_flag(&account, &args("rebase -i main")),
+            Some((1, "--reset-author-date"))
+        );
+        assert_eq!(reset_flag(&account, &args("rebase --continue")), None);
+
+        assert!(check_timezone("UTC").is_ok());
+        assert!(check_timezone("../etc/passwd").is_err());
+        assert!(check_timezone("").is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::cache::PendingLogin;
+use crate::provider::{LoginResult, RemoteEmail};
 use colored::Colorize;
 use serde::Deserialize;
 use std::thread;
@@ -9,12 +11,13 @@ struct DeviceCodeResponse {
     user_code: String,
     verification_uri: String,
     interval: u64,
-    // expires_in: u64, // unused but part of response
+    expires_in: u64,
 }
 
 #[derive(Deserialize)]
 struct TokenResponse {
     access_token: Option<String>,
+    scope: Option<String>,
     error: Option<String>,
 }
 
@@ -29,24 +32,239 @@ struct UserResponse {
 struct EmailResponse {
     email: String,
     primary: bool,
+    #[serde(default)]
+    verified: bool,
 }
 
-pub fn login() -> Option<(String, String, Option<String>, String)> {
-    let client_id = "Ov23likbcGeD5f41YHUr";
+pub(crate) fn agent() -> ureq::Agent {
+    agent_with_roots(None)
+}
+
+/// Agent for API calls to `host`, trusting only the certificate pinned for
+/// it with `gitas cert pin` when there is one.
+pub(crate) fn agent_for(host: &str) -> Result<ureq::Agent, String> {
+    Ok(agent_with_roots(crate::trust::roots_for(host)?))
+}
 
-    let config = ureq::config::Config::builder()
+pub(crate) fn agent_with_roots(roots: Option<ureq::tls::RootCerts>) -> ureq::Agent {
+    let mut config = ureq::config::Config::builder()
         .user_agent("gitas-cli")
         .http_status_as_error(false)
-        .build();
-    let agent = ureq::Agent::new_with_config(config);
+        // Switches wait on API lookups; never hang the TUI on a dead host.
+        .timeout_global(Some(Duration::from_secs(20)));
+    if let Some(roots) = roots {
+        config = config.tls_config(ureq::tls::TlsConfig::builder().root_certs(roots).build());
+    }
+    ureq::Agent::new_with_config(config.build())
+}
+
+/// Longest wait gitas will sit through on its own before giving up on a request.
+const MAX_AUTO_WAIT: Duration = Duration::from_secs(90);
+
+/// How long the server asked us to back off, for primary and secondary
+/// (abuse) rate limits alike. `None` when the response isn't a rate limit.
+pub(crate) fn rate_limit_wait(r: &ureq::http::Response<ureq::Body>) -> Option<Duration> {
+    let status = r.status().as_u16();
+    if status != 403 && status != 429 {
+        return None;
+    }
+    let header = |name: &str| {
+        r.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+
+    if let Some(seconds) = header("retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header("x-ratelimit-remaining") == Some(0) {
+        let reset = header("x-ratelimit-reset").unwrap_or(0);
+        let now = crate::cache::now_secs();
+        return Some(Duration::from_secs(reset.saturating_sub(now).max(1)));
+    }
+    // Secondary limits without Retry-After: GitHub recommends waiting a minute.
+    (status == 429).then(|| Duration::from_secs(60))
+}
+
+pub(crate) fn rate_limit_error(host: &str, wait: Duration) -> String {
+    format!(
+        "Rate limited by {}; retry in {}s",
+        host,
+        wait.as_secs().max(1)
+    )
+}
+
+/// Sleep through a rate-limit window while showing the remaining time.
+fn wait_with_countdown(wait: Duration) {
+    use std::io::Write;
+    let mut remaining = wait.as_secs().max(1);
+    while remaining > 0 {
+        print!(
+            "\r  {} Rate limited by GitHub. Retrying in {}s… ",
+            "⏳".yellow(),
+            remaining
+        );
+        std::io::stdout().flush().ok();
+        thread::sleep(Duration::from_secs(1));
+        remaining -= 1;
+    }
+    print!("\r{}\r", " ".repeat(60));
+    std::io::stdout().flush().ok();
+}
+
+/// REST API root for a host; GitHub Enterprise Server serves it under /api/v3.
+pub fn api_base(host: &str) -> String {
+    if host == "github.com" {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", host)
+    }
+}
+
+/// Domain of GitHub's private commit emails on `host`: github.com and GHE.com
+/// use `users.noreply.<host>`, GitHub Enterprise Server `noreply.<host>`.
+pub fn noreply_domain(host: &str) -> String {
+    let host = host.to_lowercase();
+    if host == "github.com" || host.ends_with(".ghe.com") {
+        format!("users.noreply.{}", host)
+    } else {
+        format!("noreply.{}", host)
+    }
+}
+
+/// Whether `email` is a GitHub noreply address on `host`.
+pub fn is_noreply(host: &str, email: &str) -> bool {
+    email
+        .rsplit_once('@')
+        .is_some_and(|(_, domain)| domain.eq_ignore_ascii_case(&noreply_domain(host)))
+}
+
+/// The noreply address of the user with `id` and `login` on `host`.
+pub fn noreply_email(host: &str, id: u64, login: &str) -> String {
+    format!("{}+{}@{}", id, login, noreply_domain(host))
+}
+
+/// What `GET /user` reveals about a token.
+pub struct TokenInfo {
+    pub login: String,
+    /// Classic OAuth/PAT scopes; fine-grained tokens don't report any.
+    pub scopes: Option<String>,
+    pub expires_at: Option<String>,
+    /// Remaining core API requests for this token, when the host reports it.
+    pub rate_remaining: Option<u64>,
+}
+
+pub fn check_token(host: &str, token: &str) -> Result<TokenInfo, String> {
+    let res = agent_for(host)?
+        .get(format!("{}/user", api_base(host)))
+        .header("Accept", "application/vnd.github+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .call();
+
+    let Ok(mut r) = res else {
+        return Err(format!("Failed to connect to {}", host));
+    };
+
+    if let Some(wait) = rate_limit_wait(&r) {
+        return Err(rate_limit_error(host, wait));
+    }
+
+    let header = |name: &str| {
+        r.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let scopes = header("x-oauth-scopes");
+    let expires_at = header("github-authentication-token-expiration");
+    let rate_remaining = header("x-ratelimit-remaining").and_then(|v| v.parse().ok());
+
+    match r.status().as_u16() {
+        200 => {}
+        401 => return Err("Token is invalid or revoked".to_string()),
+        code => return Err(format!("Unexpected response from {} ({})", host, code)),
+    }
+
+    let Ok(user) = r.body_mut().read_json::<UserResponse>() else {
+        return Err("Failed to parse user info".to_string());
+    };
+
+    Ok(TokenInfo {
+        login: user.login,
+        scopes,
+        expires_at,
+        rate_remaining,
+    })
+}
+
+/// Revoke a github.com token of any kind (OAuth, classic or fine-grained PAT)
+/// through the credential revocation API, which needs no authentication.
+pub fn revoke_token(token: &str) -> Result<(), String> {
+    let res = agent()
+        .post("https://api.github.com/credentials/revoke")
+        .header("Accept", "application/vnd.github+json")
+        .send_json(serde_json::json!({ "credentials": [token] }));
+    let Ok(r) = res else {
+        return Err("Failed to connect to github.com".to_string());
+    };
+    if let Some(wait) = rate_limit_wait(&r) {
+        return Err(rate_limit_error("github.com", wait));
+    }
+    match r.status().as_u16() {
+        200..=299 => Ok(()),
+        code => Err(format!("GitHub refused the revocation ({})", code)),
+    }
+}
+
+/// Register an ASCII-armored GPG public key with the token's user
+/// (`POST /user/gpg_keys`), so commits it signs show as Verified.
+pub fn upload_gpg_key(host: &str, token: &str, name: &str, armored: &str) -> Result<(), String> {
+    let res = agent_for(host)?
+        .post(format!("{}/user/gpg_keys", api_base(host)))
+        .header("Accept", "application/vnd.github+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .send_json(serde_json::json!({ "name": name, "armored_public_key": armored }));
+    let Ok(r) = res else {
+        return Err(format!("Failed to connect to {}", host));
+    };
+    if let Some(wait) = rate_limit_wait(&r) {
+        return Err(rate_limit_error(host, wait));
+    }
+    match r.status().as_u16() {
+        200..=299 => Ok(()),
+        401 => Err("Token is invalid or revoked".to_string()),
+        403 | 404 => Err(format!(
+            "Token lacks the required permissions. {}",
+            crate::permissions::advice(
+                crate::provider::ProviderKind::Github,
+                crate::permissions::Operation::GpgKeys
+            )
+        )),
+        422 => {
+            Err("GitHub rejected the key (already added, or not a valid public key)".to_string())
+        }
+        code => Err(format!("Unexpected response from {} ({})", host, code)),
+    }
+}
+
+const CLIENT_ID: &str = "Ov23likbcGeD5f41YHUr";
+
+pub fn login() -> Option<LoginResult> {
+    let agent = agent();
 
     // Step 1: Request device code
     let res = agent
         .post("https://github.com/login/device/code")
         .header("Accept", "application/json")
         .send_form([
-            ("client_id", client_id),
-            ("scope", "read:user user:email repo workflow"),
+            ("client_id", CLIENT_ID),
+            // Key scopes let `gitas ssh keygen` and `gitas gpg upload` register keys.
+            (
+                "scope",
+                "read:user user:email repo workflow write:public_key write:gpg_key",
+            ),
         ]);
 
     let Ok(mut r) = res else {
@@ -64,54 +282,121 @@ pub fn login() -> Option<(String, String, Option<String>, String)> {
         return None;
     };
 
+    // Persist before polling so an interrupted login can pick up where it left off.
+    let pending = PendingLogin {
+        device_code: device_res.device_code,
+        user_code: device_res.user_code,
+        verification_uri: device_res.verification_uri,
+        interval: device_res.interval,
+        expires_at: crate::cache::now_secs() + device_res.expires_in,
+    };
+    crate::cache::store_pending_login(Some(&pending));
+
     println!();
-    println!(
-        "  Please visit: {}",
-        device_res.verification_uri.cyan().bold()
-    );
-    println!("  And enter code: {}", device_res.user_code.green().bold());
+    println!("  Please visit: {}", pending.verification_uri.cyan().bold());
+    println!("  And enter code: {}", pending.user_code.green().bold());
     println!();
 
     // Give user a moment to see the code before opening the browser
     thread::sleep(Duration::from_secs(1));
 
-    if open::that(&device_res.verification_uri).is_err() {
-        println!("  (Failed to open browser automatically)");
+    crate::browser::open_url(&pending.verification_uri);
+
+    poll_for_token(&agent, &pending)
+}
+
+/// Continue polling a device login that was interrupted before it completed.
+pub fn resume_login() -> Option<LoginResult> {
+    let Some(pending) = crate::cache::pending_login() else {
+        println!(
+            "  {} Run {} to start a new login.",
+            "No interrupted login to resume.".dimmed(),
+            "gitas add".cyan()
+        );
+        return None;
+    };
+
+    if crate::cache::now_secs() >= pending.expires_at {
+        crate::cache::store_pending_login(None);
+        println!(
+            "  {} Run {} to start over.",
+            "The previous login code has expired.".yellow(),
+            "gitas add".cyan()
+        );
+        return None;
     }
 
+    println!();
+    println!(
+        "  Resuming login. If you haven't yet, visit: {}",
+        pending.verification_uri.cyan().bold()
+    );
+    println!("  And enter code: {}", pending.user_code.green().bold());
+    println!();
+
+    poll_for_token(&agent(), &pending)
+}
+
+fn poll_for_token(agent: &ureq::Agent, pending: &PendingLogin) -> Option<LoginResult> {
     // Step 2: Poll for token
     println!("  Waiting for authentication...");
-    let interval = Duration::from_secs(device_res.interval + 1);
+    let mut interval = Duration::from_secs(pending.interval + 1);
 
     loop {
         thread::sleep(interval);
 
+        if crate::cache::now_secs() >= pending.expires_at {
+            crate::cache::store_pending_login(None);
+            println!("  {}", "The login code expired. Run gitas add again.".red());
+            return None;
+        }
+
         let token_res = agent
             .post("https://github.com/login/oauth/access_token")
             .header("Accept", "application/json")
             .send_form([
-                ("client_id", client_id),
-                ("device_code", device_res.device_code.as_str()),
+                ("client_id", CLIENT_ID),
+                ("device_code", pending.device_code.as_str()),
                 ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
             ]);
 
         let Ok(mut r) = token_res else { continue };
+        if let Some(wait) = rate_limit_wait(&r) {
+            wait_with_countdown(wait.min(MAX_AUTO_WAIT));
+            continue;
+        }
         let Ok(json) = r.body_mut().read_json::<TokenResponse>() else {
             continue;
         };
 
         if let Some(token) = json.access_token {
-            // Success! Fetch user info
-            let user_res = agent
-                .get("https://api.github.com/user")
-                .header("Authorization", format!("Bearer {}", token))
-                .call();
+            crate::cache::store_pending_login(None);
+            // Success! Fetch user info, sitting out a short rate-limit window once.
+            let fetch_user = || {
+                agent
+                    .get("https://api.github.com/user")
+                    .header("Authorization", format!("Bearer {}", token))
+                    .call()
+            };
+            let mut user_res = fetch_user();
+            if let Ok(ur) = &user_res
+                && let Some(wait) = rate_limit_wait(ur)
+                && wait <= MAX_AUTO_WAIT
+            {
+                wait_with_countdown(wait);
+                user_res = fetch_user();
+            }
 
             let Ok(mut ur) = user_res else {
                 println!("  {}", "Failed to fetch user info.".red());
                 return None;
             };
 
+            if let Some(wait) = rate_limit_wait(&ur) {
+                println!("  {}", rate_limit_error("api.github.com", wait).red());
+                return None;
+            }
+
             if !ur.status().is_success() {
                 println!("  {}", "Failed to fetch user info.".red());
                 return None;
@@ -128,33 +413,75 @@ pub fn login() -> Option<(String, String, Option<String>, String)> {
                 .header("Authorization", format!("Bearer {}", token))
                 .call();
 
-            let email = if let Ok(mut er) = emails_res
+            let emails = if let Ok(mut er) = emails_res
                 && er.status().is_success()
                 && let Ok(emails) = er.body_mut().read_json::<Vec<EmailResponse>>()
             {
-                // 1. Try to find a noreply address
-                // 2. Fallback to primary address
-                // 3. Fallback to the first one found
                 emails
-                    .iter()
-                    .find(|e| e.email.contains("noreply.github.com"))
-                    .or_else(|| emails.iter().find(|e| e.primary))
-                    .or_else(|| emails.first())
-                    .map(|e| e.email.clone())
-                    .unwrap_or_else(|| user.email.unwrap_or_default())
             } else {
-                user.email.unwrap_or_default()
+                Vec::new()
             };
 
-            return Some((user.login, email, user.name, token));
+            // 1. Try to find a noreply address
+            // 2. Fallback to primary address
+            // 3. Fallback to the first one found
+            let email = emails
+                .iter()
+                .find(|e| is_noreply("github.com", &e.email))
+                .or_else(|| emails.iter().find(|e| e.primary))
+                .or_else(|| emails.first())
+                .map(|e| e.email.clone())
+                .or(user.email)
+                .unwrap_or_default();
+
+            return Some(LoginResult {
+                login: user.login,
+                name: user.name,
+                email,
+                emails: emails
+                    .into_iter()
+                    .map(|e| RemoteEmail {
+                        email: e.email,
+                        primary: e.primary,
+                        verified: e.verified,
+                    })
+                    .collect(),
+                scopes: json.scope.filter(|s| !s.is_empty()),
+                token,
+            });
         }
 
-        if let Some(error) = json.error
-            && error != "authorization_pending"
-            && error != "slow_down"
-        {
-            println!("  Error: {}", error.red());
-            return None;
+        match json.error.as_deref() {
+            Some("authorization_pending") | None => {}
+            // RFC 8628: each slow_down adds five seconds to the polling interval.
+            Some("slow_down") => interval += Duration::from_secs(5),
+            Some(error) => {
+                crate::cache::store_pending_login(None);
+                println!("  Error: {}", error.red());
+                return None;
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noreply_addresses_follow_the_host() {
+        assert_eq!(
+            noreply_email("github.com", 1, "octo"),
+            "1+octo@users.noreply.github.com"
+        );
+        assert_eq!(
+            noreply_email("GHE.corp", 1, "octo"),
+            "1+octo@noreply.ghe.corp"
+        );
+        assert!(is_noreply(
+            "acme.ghe.com",
+            "1+octo@users.noreply.acme.ghe.com"
+        ));
+        assert!(!is_noreply("ghe.corp", "1+octo@users.noreply.github.com"));
+    }
+}
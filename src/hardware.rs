@@ -0,0 +1,108 @@
+use crate::models::Account;
+use std::path::Path;
+use std::process::Command;
+
+/// Whether an SSH key is a FIDO2 security-key key (`ed25519-sk`/`ecdsa-sk`).
+pub fn is_sk_key(private_key: &str) -> bool {
+    let public = format!("{}.pub", private_key);
+    std::fs::read_to_string(&public)
+        .ok()
+        .and_then(|data| data.split_whitespace().next().map(str::to_string))
+        .is_some_and(|kind| kind.starts_with("sk-"))
+}
+
+/// Make sure any hardware the account's keys live on is plugged in, so a
+/// switch doesn't claim success and then fail at the first push or commit.
+pub fn check_present(account: &Account) -> Result<(), String> {
+    if let Some(ssh_key) = account.ssh_key.as_deref()
+        && is_sk_key(ssh_key)
+        && fido_device_present() == Some(false)
+    {
+        return Err(format!(
+            "Security key for {} is not plugged in. Insert it and try again.",
+            Path::new(ssh_key)
+                .file_name()
+                .map_or(ssh_key.into(), |name| name.to_string_lossy())
+        ));
+    }
+
+    if let Some(key_id) = account.gpg_key.as_deref() {
+        match gpg_key_on_card(key_id) {
+            None => return Err(format!("GPG key {} is not in your keyring", key_id)),
+            Some(true) if !gpg_card_present() => {
+                return Err(format!(
+                    "GPG key {} lives on a smartcard that is not inserted. Insert it and try again.",
+                    key_id
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// `Some(present)` when we can tell, `None` when no detection method works here.
+fn fido_device_present() -> Option<bool> {
+    if let Ok(output) = Command::new("fido2-token").arg("-L").output() {
+        return Some(output.status.success() && !output.stdout.trim_ascii().is_empty());
+    }
+    fido_hidraw_present()
+}
+
+/// Look for a HID device advertising the FIDO usage page (0xF1D0).
+#[cfg(target_os = "linux")]
+fn fido_hidraw_present() -> Option<bool> {
+    let entries = std::fs::read_dir("/sys/class/hidraw").ok()?;
+    Some(entries.flatten().any(|entry| {
+        std::fs::read(entry.path().join("device/report_descriptor"))
+            .is_ok_and(|desc| desc.windows(3).any(|w| w == [0x06, 0xD0, 0xF1]))
+    }))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn fido_hidraw_present() -> Option<bool> {
+    None
+}
+
+/// `Some(true)` when the secret key is a stub pointing at a smartcard,
+/// `Some(false)` when it's on disk, `None` when there's no such secret key.
+fn gpg_key_on_card(key_id: &str) -> Option<bool> {
+    let output = Command::new("gpg")
+        .args(["--batch", "--with-colons", "--list-secret-keys", key_id])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let listing = String::from_utf8_lossy(&output.stdout);
+    Some(secret_keys_on_card(&listing))
+}
+
+/// Field 15 of `sec`/`ssb` records holds the card serial number for card keys.
+fn secret_keys_on_card(listing: &str) -> bool {
+    listing
+        .lines()
+        .filter(|line| line.starts_with("sec:") || line.starts_with("ssb:"))
+        .filter_map(|line| line.split(':').nth(14))
+        .any(|serial| !serial.is_empty() && serial != "+" && serial != "#")
+}
+
+fn gpg_card_present() -> bool {
+    Command::new("gpg")
+        .args(["--batch", "--card-status"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_card_stubs_in_gpg_listing() {
+        let on_card = "sec:u:255:22:AAAA:1:::u:::scESC:::D2760001240100000006:::ed25519:::0:\n";
+        let on_disk = "sec:u:255:22:AAAA:1:::u:::scESC:::+:::ed25519:::0:\n";
+        assert!(secret_keys_on_card(on_card));
+        assert!(!secret_keys_on_card(on_disk));
+    }
+}
@@ -0,0 +1,488 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// First line after the shebang of every hook gitas writes, so it never
+/// touches a hook someone else installed.
+const MARKER: &str = "# Managed by gitas";
+
+/// All zeros: the side of a ref update that doesn't exist (a new branch on
+/// the remote, or a deletion locally). SHA-256 repositories use 64.
+fn is_zero(sha: &str) -> bool {
+    !sha.is_empty() && sha.chars().all(|c| c == '0')
+}
+
+/// One line git writes to a pre-push hook's stdin:
+/// `<local ref> <local sha> <remote ref> <remote sha>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefUpdate {
+    pub local_ref: String,
+    pub local_sha: String,
+    pub remote_ref: String,
+    pub remote_sha: String,
+}
+
+impl RefUpdate {
+    /// Deleting the remote ref sends no commits.
+    pub fn is_delete(&self) -> bool {
+        is_zero(&self.local_sha)
+    }
+
+    /// The remote doesn't have the ref yet.
+    pub fn is_new(&self) -> bool {
+        is_zero(&self.remote_sha)
+    }
+}
+
+/// Parse a pre-push hook's stdin, skipping lines that aren't ref updates.
+pub fn parse_updates(input: &str) -> Vec<RefUpdate> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let update = RefUpdate {
+                local_ref: fields.next()?.to_string(),
+                local_sha: fields.next()?.to_string(),
+                remote_ref: fields.next()?.to_string(),
+                remote_sha: fields.next()?.to_string(),
+            };
+            fields.next().is_none().then_some(update)
+        })
+        .collect()
+}
+
+/// Revisions to scan and what to exclude, for `push_check::commits_in_range`:
+/// every pushed tip, minus what the remote already had. A new ref, or a
+/// remote tip that isn't in the local repository (`known` says which are),
+/// falls back to everything under the remote's tracking refs.
+pub fn push_range(
+    updates: &[RefUpdate],
+    remote: &str,
+    known: impl Fn(&str) -> bool,
+) -> (Vec<String>, Vec<String>) {
+    let mut revs = Vec::new();
+    let mut exclude = Vec::new();
+    let mut tracking = false;
+    for update in updates.iter().filter(|u| !u.is_delete()) {
+        if !revs.contains(&update.local_sha) {
+            revs.push(update.local_sha.clone());
+        }
+        if !update.is_new() && known(&update.remote_sha) {
+            if !exclude.contains(&update.remote_sha) {
+                exclude.push(update.remote_sha.clone());
+            }
+        } else {
+            tracking = true;
+        }
+    }
+    if tracking {
+        // Pushing straight to a URL has no tracking refs of its own.
+        exclude.push(if remote.contains('/') || remote.contains(':') {
+            "--remotes".to_string()
+        } else {
+            format!("--remotes={}", remote)
+        });
+    }
+    (revs, exclude)
+}
+
+/// Whether `sha` names a commit in the repository at `dir`.
+pub fn has_commit(dir: &Path, sha: &str) -> bool {
+    Command::new("git")
+        .args(["cat-file", "-e", &format!("{}^{{commit}}", sha)])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// Hooks directory of the repository at `dir`, honouring `core.hooksPath`.
+pub fn hooks_dir(dir: &Path) -> Result<PathBuf, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} does not exist", dir.display()));
+    }
+    let output = Command::new("git")
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "hooks"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("{} is not inside a git repository", dir.display()));
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+/// Whether the hook at `path` was written by gitas.
+pub fn is_managed(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .is_ok_and(|script| script.lines().nth(1).is_some_and(|l| l.starts_with(MARKER)))
+}
+
+/// Lines around what gitas adds to a file it shares with someone else.
+const BEGIN: &str = "# >>> Managed by gitas";
+const END: &str = "# <<< gitas";
+
+/// Where the pre-push check lives in a repository.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrePush {
+    /// Gitas' own hook script.
+    Hook(PathBuf),
+    /// Gitas' hook, running the hook that was there before it, which was
+    /// moved aside to `original`.
+    Chained { hook: PathBuf, original: PathBuf },
+    /// An entry in a hook manager's file. `note` says what is left to do.
+    Manager { file: PathBuf, note: Option<String> },
+}
+
+impl PrePush {
+    /// The file gitas wrote.
+    pub fn path(&self) -> &Path {
+        match self {
+            PrePush::Hook(path) | PrePush::Chained { hook: path, .. } => path,
+            PrePush::Manager { file, .. } => file,
+        }
+    }
+}
+
+/// Hook managers that own the hooks directory and would overwrite a hook
+/// written there, so gitas adds itself to their files instead.
+enum Manager {
+    /// `core.hooksPath` under `.husky`: scripts live in `.husky/<hook>`.
+    Husky(PathBuf),
+    /// A lefthook config: gitas' command goes in the personal
+    /// `lefthook-local.yml`, which lefthook merges into it.
+    Lefthook { root: PathBuf, local: PathBuf },
+}
+
+impl Manager {
+    fn file(&self) -> &Path {
+        match self {
+            Manager::Husky(file) => file,
+            Manager::Lefthook { local, .. } => local,
+        }
+    }
+
+    fn block(&self, exe: &str) -> String {
+        let about = "checks outgoing commit authors. Remove with `gitas hook uninstall`.";
+        match self {
+            Manager::Husky(_) => format!(
+                "{}: {}\n'{}' hook pre-push \"$@\"\n{}\n",
+                BEGIN,
+                about,
+                exe.replace('\'', r"'\''"),
+                END
+            ),
+            Manager::Lefthook { .. } => format!(
+                "{}: {}\npre-push:\n  commands:\n    gitas:\n      use_stdin: true\n      \
+                 run: \"'{}' hook pre-push {{1}} {{2}}\"\n{}\n",
+                BEGIN,
+                about,
+                exe.replace('\'', r"'\''")
+                    .replace('\\', r"\\")
+                    .replace('"', "\\\""),
+                END
+            ),
+        }
+    }
+}
+
+/// Top of the working tree at `dir`.
+fn toplevel(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+fn manager(dir: &Path, hooks: &Path) -> Option<Manager> {
+    let root = toplevel(dir)?;
+    if hooks
+        .strip_prefix(&root)
+        .is_ok_and(|rel| rel.starts_with(".husky"))
+    {
+        return Some(Manager::Husky(root.join(".husky").join("pre-push")));
+    }
+    ["lefthook", ".lefthook"].into_iter().find_map(|name| {
+        ["yml", "yaml"].into_iter().find_map(|ext| {
+            root.join(format!("{}.{}", name, ext))
+                .is_file()
+                .then(|| Manager::Lefthook {
+                    local: root.join(format!("{}-local.{}", name, ext)),
+                    root: root.clone(),
+                })
+        })
+    })
+}
+
+/// `text` with gitas' block appended, or replacing the one already there.
+fn add_block(text: &str, block: &str) -> String {
+    let mut text = remove_block(text).unwrap_or_else(|| text.to_string());
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text + block
+}
+
+/// `text` without gitas' block; None when it has none.
+fn remove_block(text: &str) -> Option<String> {
+    let start = text.find(BEGIN)?;
+    let end = text[start..]
+        .find(END)
+        .map_or(text.len(), |i| start + i + END.len());
+    let end = if text[end..].starts_with('\n') {
+        end + 1
+    } else {
+        end
+    };
+    Some(format!("{}{}", &text[..start], &text[end..]))
+}
+
+/// Whether a YAML file configures `pre-push` outside gitas' block.
+fn configures_pre_push(yaml: &str) -> bool {
+    remove_block(yaml)
+        .as_deref()
+        .unwrap_or(yaml)
+        .lines()
+        .any(|line| line.starts_with("pre-push:"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Could not make {} executable: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+/// Whether git tracks `file` in the repository around it.
+fn is_tracked(file: &Path) -> bool {
+    let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+        return false;
+    };
+    Command::new("git")
+        .args(["ls-files", "--error-unmatch", "--"])
+        .arg(name)
+        .current_dir(dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+fn install_in_manager(manager: &Manager, exe: &str, hooks: &Path) -> Result<PrePush, String> {
+    let file = manager.file();
+    // A committed husky script is shared; a path on this machine doesn't
+    // belong in it.
+    if let Manager::Husky(_) = manager
+        && is_tracked(file)
+    {
+        return Err(format!(
+            "{} is committed to the repository, so gitas won't write its own path into it. \
+             Add this line to it if everyone pushing has gitas: {}",
+            file.display(),
+            "gitas hook pre-push \"$@\""
+        ));
+    }
+    let text = std::fs::read_to_string(file).unwrap_or_default();
+    if let Manager::Lefthook { .. } = manager
+        && configures_pre_push(&text)
+    {
+        return Err(format!(
+            "{} already configures pre-push. Add a command running {} with use_stdin: true to it.",
+            file.display(),
+            "gitas hook pre-push {1} {2}"
+        ));
+    }
+    std::fs::write(file, add_block(&text, &manager.block(exe)))
+        .map_err(|e| format!("Could not write {}: {}", file.display(), e))?;
+
+    let note = match manager {
+        Manager::Husky(_) => {
+            make_executable(file)?;
+            None
+        }
+        // Lefthook only writes hooks its config had when it was installed.
+        Manager::Lefthook { root, .. } if !hooks.join("pre-push").exists() => {
+            let installed = Command::new("lefthook")
+                .arg("install")
+                .current_dir(root)
+                .output()
+                .is_ok_and(|o| o.status.success());
+            (!installed).then(|| "Run `lefthook install` so git runs it.".to_string())
+        }
+        Manager::Lefthook { .. } => None,
+    };
+    Ok(PrePush::Manager {
+        file: file.to_path_buf(),
+        note,
+    })
+}
+
+/// Install the pre-push check for the repository at `dir`, running `exe`.
+/// Husky and lefthook setups get an entry in their own files; otherwise a
+/// pre-push hook gitas didn't write is moved aside and run after the check.
+pub fn install_pre_push(dir: &Path, exe: &str) -> Result<PrePush, String> {
+    let hooks = hooks_dir(dir)?;
+    let exe = exe.replace('\\', "/");
+    if let Some(manager) = manager(dir, &hooks) {
+        return install_in_manager(&manager, &exe, &hooks);
+    }
+
+    let path = hooks.join("pre-push");
+    let original = hooks.join("pre-push.gitas-orig");
+    if path.exists() && !is_managed(&path) {
+        if original.exists() {
+            return Err(format!(
+                "{} and {} both exist. Remove one of them first.",
+                path.display(),
+                original.display()
+            ));
+        }
+        std::fs::rename(&path, &original)
+            .map_err(|e| format!("Could not move {} aside: {}", path.display(), e))?;
+    }
+    std::fs::create_dir_all(&hooks)
+        .map_err(|e| format!("Could not create {}: {}", hooks.display(), e))?;
+
+    let exe = exe.replace('\'', r"'\''");
+    let chained = original.exists();
+    // Both hooks read the ref updates from stdin, so it is read once here.
+    let script = if chained {
+        format!(
+            "#!/bin/sh\n{}: checks outgoing commit authors, then runs pre-push.gitas-orig. \
+             Remove with `gitas hook uninstall`.\n\
+             input=$(cat)\n\
+             printf '%s\\n' \"$input\" | '{}' hook pre-push \"$@\" || exit $?\n\
+             printf '%s\\n' \"$input\" | \"$(dirname \"$0\")/pre-push.gitas-orig\" \"$@\"\n",
+            MARKER, exe
+        )
+    } else {
+        format!(
+            "#!/bin/sh\n{}: checks outgoing commit authors. Remove with `gitas hook uninstall`.\n\
+             exec '{}' hook pre-push \"$@\"\n",
+            MARKER, exe
+        )
+    };
+    std::fs::write(&path, script)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    make_executable(&path)?;
+    Ok(if chained {
+        PrePush::Chained {
+            hook: path,
+            original,
+        }
+    } else {
+        PrePush::Hook(path)
+    })
+}
+
+/// Remove the pre-push check, putting back the hook it ran or dropping its
+/// entry from the hook manager's file. Ok(None) when there is none to remove.
+pub fn uninstall_pre_push(dir: &Path) -> Result<Option<PrePush>, String> {
+    let hooks = hooks_dir(dir)?;
+    if let Some(manager) = manager(dir, &hooks) {
+        let file = manager.file();
+        let Some(rest) = std::fs::read_to_string(file)
+            .ok()
+            .and_then(|text| remove_block(&text))
+        else {
+            return Ok(None);
+        };
+        if rest.trim().is_empty() {
+            std::fs::remove_file(file)
+        } else {
+            std::fs::write(file, rest)
+        }
+        .map_err(|e| format!("Could not update {}: {}", file.display(), e))?;
+        return Ok(Some(PrePush::Manager {
+            file: file.to_path_buf(),
+            note: None,
+        }));
+    }
+
+    let path = hooks.join("pre-push");
+    if !path.exists() {
+        return Ok(None);
+    }
+    if !is_managed(&path) {
+        return Err(format!(
+            "{} was not written by gitas; leaving it alone.",
+            path.display()
+        ));
+    }
+    let original = hooks.join("pre-push.gitas-orig");
+    if original.exists() {
+        std::fs::rename(&original, &path)
+            .map_err(|e| format!("Could not restore {}: {}", path.display(), e))?;
+        return Ok(Some(PrePush::Chained {
+            hook: path,
+            original,
+        }));
+    }
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Could not remove {}: {}", path.display(), e))?;
+    Ok(Some(PrePush::Hook(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_updates_into_a_range() {
+        let zero = "0".repeat(40);
+        let input = format!(
+            "refs/heads/main aaa refs/heads/main bbb\n\
+             refs/heads/topic ccc refs/heads/topic {zero}\n\
+             (delete) {zero} refs/heads/old ddd\n\
+             garbage\n"
+        );
+        let updates = parse_updates(&input);
+        assert_eq!(updates.len(), 3);
+        assert!(updates[1].is_new());
+        assert!(updates[2].is_delete());
+
+        let (revs, exclude) = push_range(&updates, "origin", |_| true);
+        assert_eq!(revs, ["aaa", "ccc"]);
+        assert_eq!(exclude, ["bbb", "--remotes=origin"]);
+
+        // A remote tip that was never fetched can't be excluded by sha.
+        let (_, exclude) = push_range(&updates[..1], "origin", |_| false);
+        assert_eq!(exclude, ["--remotes=origin"]);
+        let (_, exclude) = push_range(&updates[1..2], "git@github.com:o/r.git", |_| true);
+        assert_eq!(exclude, ["--remotes"]);
+        assert_eq!(
+            push_range(&updates[2..], "origin", |_| true),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn blocks_come_out_as_they_went_in() {
+        let manager = Manager::Husky(PathBuf::from(".husky/pre-push"));
+        let block = manager.block("/bin/gitas");
+        let script = "npm test\n";
+        let added = add_block(script, &block);
+        assert_eq!(added.matches(BEGIN).count(), 1);
+        assert_eq!(add_block(&added, &block), added);
+        assert_eq!(remove_block(&added).as_deref(), Some(script));
+        assert_eq!(remove_block(script), None);
+
+        let lefthook = Manager::Lefthook {
+            root: PathBuf::from("."),
+            local: PathBuf::from("lefthook-local.yml"),
+        };
+        let yaml = add_block("", &lefthook.block("/bin/gitas"));
+        assert!(!configures_pre_push(&yaml));
+        assert!(configures_pre_push("pre-push:\n  commands: {}\n"));
+    }
+}
@@ -0,0 +1,92 @@
+/// Canonical form of a host as typed by a user or found in a URL: no scheme,
+/// credentials, path, `www.` prefix, trailing dot or default HTTPS port, and
+/// lowercased. Non-default ports are kept since they identify the instance.
+pub fn normalize(input: &str) -> String {
+    let mut host = input.trim();
+    if let Some((_, rest)) = host.split_once("://") {
+        host = rest;
+    }
+    host = host.split(['/', '?', '#']).next().unwrap_or_default();
+    if let Some((_, rest)) = host.rsplit_once('@') {
+        host = rest;
+    }
+
+    let mut host = host.to_lowercase();
+    if let Some(stripped) = host.strip_suffix(":443") {
+        host = stripped.to_string();
+    }
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => {
+            (name.to_string(), Some(port.to_string()))
+        }
+        _ => (host.clone(), None),
+    };
+    let name = name.trim_end_matches('.');
+    let name = name.strip_prefix("www.").unwrap_or(name);
+
+    match port {
+        Some(port) => format!("{}:{}", name, port),
+        None => name.to_string(),
+    }
+}
+
+/// Normalized host for storing on an account; github.com and empty input mean
+/// "default" and are stored as `None`.
+pub fn for_account(input: &str) -> Option<String> {
+    let host = normalize(input);
+    (!host.is_empty() && host != "github.com").then_some(host)
+}
+
+/// Whether `host` matches `pattern`, which may start with `*.` to match any
+/// subdomain (but not the bare domain itself). A pattern without a port
+/// matches the host on any port.
+pub fn matches(pattern: &str, host: &str) -> bool {
+    let pattern = normalize(pattern);
+    let host = normalize(host);
+    let (pattern_name, pattern_port) = split_port(&pattern);
+    let (host_name, host_port) = split_port(&host);
+    if pattern_port.is_some() && pattern_port != host_port {
+        return false;
+    }
+    match pattern_name.strip_prefix("*.") {
+        Some(suffix) => host_name
+            .strip_suffix(suffix)
+            .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.')),
+        None => pattern_name == host_name,
+    }
+}
+
+fn split_port(host: &str) -> (&str, Option<&str>) {
+    match host.rsplit_once(':') {
+        Some((name, port)) => (name, Some(port)),
+        None => (host, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_user_input_and_urls() {
+        assert_eq!(normalize("https://GitHub.com/"), "github.com");
+        assert_eq!(normalize("www.github.com"), "github.com");
+        assert_eq!(normalize("https://user@ghe.corp:443/org/repo"), "ghe.corp");
+        assert_eq!(normalize("ghe.corp:8443"), "ghe.corp:8443");
+        assert_eq!(normalize("ghe.corp."), "ghe.corp");
+        assert_eq!(for_account(" https://github.com "), None);
+    }
+
+    #[test]
+    fn matches_wildcard_subdomains() {
+        assert!(matches("*.github.example.com", "eu.github.example.com"));
+        assert!(matches(
+            "*.github.example.com",
+            "A.B.github.example.com:8443"
+        ));
+        assert!(!matches("*.github.example.com", "github.example.com"));
+        assert!(!matches("*.example.com", "evilexample.com"));
+        assert!(matches("GHE.corp", "https://ghe.corp/"));
+        assert!(!matches("ghe.corp:8443", "ghe.corp"));
+    }
+}
@@ -1,23 +1,92 @@
+mod agent;
+mod audit;
+mod authorship;
+mod browser;
+mod cache;
 mod commands;
+mod confirm;
+mod cred_once;
+mod dates;
 mod github;
+mod hardware;
+mod hooks;
+mod host;
+mod migrate;
 mod models;
+mod notify;
+mod permissions;
+mod plugins;
+mod plus;
+mod policy;
+mod provider;
+mod push_check;
+mod registry;
+mod resolve;
+mod rules;
+mod signing;
+mod ssh;
+mod token_agent;
+mod tombstone;
+mod trust;
 mod tui;
 mod utils;
+mod warnings;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use colored::Colorize;
 use models::load_config;
 
 #[derive(Parser)]
 #[command(
     name = "gitas",
     about = "GitHub Account Switch — manage multiple git identities",
-    version
+    version,
+    disable_version_flag = true
 )]
 struct Cli {
+    /// Print version
+    #[arg(short = 'V', long)]
+    version: bool,
+
+    /// With --version, also print build details and paths for bug reports
+    #[arg(long, requires = "version")]
+    verbose: bool,
+
     /// Account username or alias (skip interactive selection for git)
     #[arg(short = 'a', long, global = true)]
     account: Option<String>,
 
+    /// Choose the account interactively even when a default is set
+    #[arg(long, global = true, conflicts_with = "account")]
+    pick: bool,
+
+    /// Never launch a browser; print URLs instead
+    #[arg(long, global = true)]
+    no_browser: bool,
+
+    /// Read-only mode for shared machines: nothing is written to config,
+    /// keychain, git config or logs
+    #[arg(long, global = true)]
+    ephemeral: bool,
+
+    /// Mask usernames and emails in everything gitas prints, for screen sharing
+    #[arg(long, global = true)]
+    redact: bool,
+
+    /// Never prompt, and keep git from asking for credentials, so CI fails
+    /// fast (also GITAS_STRICT=1)
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Only show and use the accounts in this profile (also GITAS_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Config file, or directory holding accounts.json, to use instead of the
+    /// default (also GITAS_CONFIG)
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -25,7 +94,63 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Add a new git account
-    Add,
+    Add {
+        /// Continue an interrupted GitHub browser login
+        #[arg(long)]
+        resume: bool,
+        /// Add several accounts with browser logins back to back
+        #[arg(long, conflicts_with_all = ["resume", "username"])]
+        batch: bool,
+        #[command(flatten)]
+        fields: commands::add::AddArgs,
+    },
+    /// Export the audit trail of switches, pushes and token changes
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+    /// Change fields of an account without the interactive editor
+    Edit {
+        /// Account username, alias, or username:alias
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        account: Option<String>,
+        #[command(flatten)]
+        fields: Box<commands::edit::EditArgs>,
+        /// Edit every account matching --where (all accounts without it)
+        #[arg(long, requires = "set")]
+        all: bool,
+        /// With --all: field to change, as host=, group= or provider= (repeatable)
+        #[arg(long, value_name = "KEY=VALUE", requires = "all")]
+        set: Vec<String>,
+        /// With --all: only accounts whose host, group or provider matches (repeatable)
+        #[arg(long = "where", value_name = "KEY=VALUE", requires = "all")]
+        filter: Vec<String>,
+    },
+    /// Show or set the account used when no -a is given
+    Default {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Remove the default account
+        #[arg(long, conflicts_with = "account")]
+        clear: bool,
+    },
+    /// Bind the current repository to an account for gitas git and exec
+    Use {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Remove this repository's binding
+        #[arg(long, conflicts_with = "account")]
+        clear: bool,
+        /// Show every bound repository
+        #[arg(long, conflicts_with_all = ["account", "clear"])]
+        list: bool,
+    },
+    /// Show git identity variables set in the environment that override gitas
+    Env {
+        /// Print shell commands that unset them (e.g. eval "$(gitas env --clear)")
+        #[arg(long)]
+        clear: bool,
+    },
     /// Run any git command as a specific account
     #[command(trailing_var_arg = true)]
     Git {
@@ -33,16 +158,1063 @@ enum Commands {
         #[arg(allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Clone a repository as the account that owns it, then set that account locally
+    Clone {
+        /// Repository URL; without one, pick from the repositories the account can access
+        url: Option<String>,
+        /// Directory to clone into
+        directory: Option<String>,
+        /// Extra arguments for git clone (after --)
+        #[arg(last = true)]
+        git_args: Vec<String>,
+    },
+    /// Run any command with a specific account's git identity (and gh login)
+    #[command(trailing_var_arg = true)]
+    Exec {
+        /// Command and arguments to run (e.g. gh pr create)
+        #[arg(allow_hyphen_values = true, required = true)]
+        args: Vec<String>,
+    },
+    /// Pin the certificate API calls to an enterprise host must chain to
+    Cert {
+        #[command(subcommand)]
+        command: CertCommands,
+    },
+    /// Run a command in every bound repository (optionally one group's), in parallel, each as its account
+    Foreach {
+        /// Only repositories bound to accounts in this group
+        #[arg(long)]
+        group: Option<String>,
+        /// How many repositories to run at once (default 8)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Command and arguments, after -- (e.g. -- git pull)
+        #[arg(last = true, required = true)]
+        args: Vec<String>,
+    },
+    /// GPG keys that sign the account's commits
+    Gpg {
+        #[command(subcommand)]
+        command: GpgCommands,
+    },
+    /// Use an account in every repository under a directory (git includeIf rules)
+    Rule {
+        #[command(subcommand)]
+        command: RuleCommands,
+    },
+    /// Keep the GitHub CLI logged in as the account
+    Gh {
+        #[command(subcommand)]
+        command: GhCommands,
+    },
+    /// Check outgoing commits for emails that don't belong to the account
+    #[command(trailing_var_arg = true)]
+    CheckPush {
+        /// Run this check automatically on `gitas git push`
+        #[arg(long, value_enum, conflicts_with = "disable")]
+        enable: Option<push_check::PushCheckMode>,
+        /// Stop checking on `gitas git push`
+        #[arg(long)]
+        disable: bool,
+        /// Remote and refspecs, as passed to git push
+        #[arg(allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Set up accounts, certificate pins, directory rules and hooks from a TOML manifest
+    Provision {
+        /// Manifest file
+        manifest: String,
+    },
+    /// Git hooks gitas manages in a repository
+    Hook {
+        #[command(subcommand)]
+        command: HookCommands,
+    },
+    /// Write accounts (and optionally tokens) to a file for backup or another machine
+    Export {
+        /// Output file (defaults to stdout)
+        file: Option<String>,
+        /// Include keychain tokens in plaintext
+        #[arg(long)]
+        with_tokens: bool,
+    },
+    /// Manage account groups and their email/host policies
+    Group {
+        #[command(subcommand)]
+        command: GroupCommands,
+    },
+    /// Show the audit log of actions taken through gitas
+    History {
+        /// Only show pushes
+        #[arg(long)]
+        pushes: bool,
+        /// Maximum number of entries to show (newest first)
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Add accounts from a `gitas export` file or another accounts.json
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Import {
+        /// File to read (`-` for stdin)
+        #[arg(required = true)]
+        file: Option<String>,
+        /// Replace all existing accounts instead of merging
+        #[arg(long)]
+        replace: bool,
+        /// Overwrite accounts with the same username and alias
+        #[arg(long)]
+        force: bool,
+        #[command(subcommand)]
+        command: Option<ImportCommands>,
+    },
+    /// Show unread notifications for an account
+    Inbox {
+        /// Account username or alias (defaults to -a, or asks)
+        account: Option<String>,
+    },
+    /// Summarize local history: most used accounts, repositories, caught mistakes
+    Insights {
+        /// Only consider the last N days
+        #[arg(long)]
+        days: Option<u64>,
+    },
+    /// Delete an account's token from the keychain and git's credential helpers
+    Logout {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Also revoke the token at the forge
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Upgrade the config file to the current schema
+    Migrate {
+        /// Report the changes and preserved unknown fields without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Open the repository's remote in the browser
+    Open {
+        /// Remote name (defaults to origin)
+        remote: Option<String>,
+    },
+    /// Package registry credentials (npm, cargo, Maven, Gradle) that follow an account
+    Packages {
+        #[command(subcommand)]
+        command: PackagesCommands,
+    },
+    /// Pull request helpers for the current branch
+    Pr {
+        #[command(subcommand)]
+        command: PrCommands,
+    },
+    /// Show or set whether amends and rebase/cherry-pick continuations keep
+    /// another person's authorship
+    PreserveAuthor {
+        /// ask (default), always or never
+        #[arg(value_enum)]
+        mode: Option<authorship::PreserveAuthor>,
+    },
+    /// Revalidate tokens and tidy caches; meant for cron or a systemd timer
+    #[command(args_conflicts_with_subcommands = true)]
+    Refresh {
+        /// Only print problems
+        #[arg(short, long)]
+        quiet: bool,
+        #[command(subcommand)]
+        command: Option<RefreshCommands>,
+    },
+    /// Repository remote helpers
+    Remote {
+        #[command(subcommand)]
+        command: RemoteCommands,
+    },
+    /// Remove an account and its keychain token
+    Remove {
+        /// Account username or alias
+        account: String,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Print, as JSON, the account gitas would use for a path or identifier
+    Resolve {
+        /// Repository path or account username/alias (defaults to the current directory)
+        target: Option<String>,
+    },
+    /// Restore a terminal left in raw mode by a crashed program (type it blind)
+    Sane,
+    /// Describe every subcommand and flag, and the JSON that commands print
+    Schema {
+        /// Print the description as JSON, for wrappers and editor integrations
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that this install works: parsing, config, keychain, terminal and git
+    Selftest,
+    /// Make `gitas credential` the first helper plain git asks, keeping the
+    /// helpers already set up after it
+    InstallHelper {
+        /// In this repository's config instead of the global one
+        #[arg(long)]
+        local: bool,
+    },
+    /// Undo install-helper, restoring the previous credential helpers
+    UninstallHelper {
+        /// In this repository's config instead of the global one
+        #[arg(long)]
+        local: bool,
+    },
+    /// Git credential helper answering with the repository's account token
+    /// (set up by install-helper)
+    Credential {
+        /// get, store or erase, appended by git
+        action: String,
+    },
+    /// Token agent started by `token agent start`
+    #[command(name = "_token-agent", hide = true)]
+    TokenAgent {
+        #[arg(long)]
+        ttl: u64,
+    },
+    /// Credential helper for `token delivery once`, run by git
+    #[command(name = "_cred-once", hide = true)]
+    CredOnce {
+        id: String,
+        /// get, store or erase, appended by git
+        action: Option<String>,
+    },
+    /// Cross-check accounts.json, the keychain and git config, and repair what disagrees
+    Fsck {
+        /// Apply every repair without asking
+        #[arg(long)]
+        fix: bool,
+    },
+    /// SSH keys that authenticate accounts over SSH remotes
+    Ssh {
+        #[command(subcommand)]
+        command: SshCommands,
+    },
+    /// Report the repository's identity, credential settings and remote accounts
+    Status,
+    /// Manage and validate stored tokens
+    Token {
+        #[command(subcommand)]
+        command: TokenCommands,
+    },
+    /// Check stored tokens against the API (same as `token check`); exits 1 if any fails
+    Verify {
+        /// Check every configured account in parallel
+        #[arg(long)]
+        all: bool,
+    },
+    /// Show the git identity in effect here and the account it belongs to
+    Whoami,
+    /// List the repository's worktrees with the identity each one uses
+    Worktrees {
+        /// Give the worktree at this path its own account (-a or a picker)
+        #[arg(long, value_name = "PATH", conflicts_with = "unset")]
+        set: Option<String>,
+        /// Drop the worktree's own identity so it follows the repository again
+        #[arg(long, value_name = "PATH")]
+        unset: Option<String>,
+    },
+    /// Desktop notifications on switches and guard violations
+    Notify {
+        #[command(subcommand)]
+        command: Option<NotifyCommands>,
+    },
+    /// Turn advisory warnings on or off, globally or for one account with -a
+    Warnings {
+        #[command(subcommand)]
+        command: Option<WarningsCommands>,
+    },
+    /// Named sets of accounts that --profile narrows gitas to
+    Profile {
+        #[command(subcommand)]
+        command: Option<ProfileCommands>,
+    },
+    /// Find other multi-account setups (GCM, gh, includeIf, direnv) and how gitas fits in
+    Detect,
+    /// Choose which actions ask for confirmation: always, never or tty-only
+    Confirm {
+        #[command(subcommand)]
+        command: Option<ConfirmCommands>,
+    },
+}
+
+impl Commands {
+    /// Name of the command when its whole purpose is to persist something.
+    fn persistent_name(&self) -> Option<&'static str> {
+        match self {
+            Commands::Add { .. } => Some("add"),
+            Commands::Rule {
+                command: RuleCommands::Add { .. } | RuleCommands::Remove { .. },
+            } => Some("rule"),
+            Commands::Cert {
+                command: CertCommands::Pin { .. } | CertCommands::Unpin { .. },
+            } => Some("cert pin/unpin"),
+            Commands::Edit { .. } => Some("edit"),
+            Commands::Default { account, clear } if account.is_some() || *clear => Some("default"),
+            Commands::Use { account, clear, .. } if account.is_some() || *clear => Some("use"),
+            Commands::CheckPush {
+                enable, disable, ..
+            } if enable.is_some() || *disable => Some("check-push --enable/--disable"),
+            Commands::Group {
+                command: GroupCommands::Set { .. } | GroupCommands::Remove { .. },
+            } => Some("group set/remove"),
+            Commands::Refresh {
+                command: Some(RefreshCommands::InstallTimer { .. }),
+                ..
+            } => Some("refresh install-timer"),
+            Commands::Migrate { dry_run: false } => Some("migrate"),
+            Commands::PreserveAuthor { mode: Some(_) } => Some("preserve-author"),
+            Commands::Remove { .. } => Some("remove"),
+            Commands::Worktrees { set, unset } if set.is_some() || unset.is_some() => {
+                Some("worktrees --set/--unset")
+            }
+            Commands::Remote {
+                command: RemoteCommands::Fix { dry_run: false, .. },
+            } => Some("remote fix"),
+            Commands::Logout { .. } => Some("logout"),
+            Commands::Ssh {
+                command: SshCommands::Keygen { .. },
+            } => Some("ssh keygen"),
+            Commands::Ssh {
+                command: SshCommands::Config { .. },
+            } => Some("ssh config"),
+            Commands::Ssh {
+                command:
+                    SshCommands::Agent {
+                        enable, disable, ..
+                    },
+            } if *enable || *disable => Some("ssh agent --enable/--disable"),
+            Commands::Gh {
+                command:
+                    GhCommands::Sync {
+                        enable, disable, ..
+                    },
+            } if *enable || *disable => Some("gh sync --enable/--disable"),
+            // Writes the token into gh's own store.
+            Commands::Gh {
+                command: GhCommands::Sync { .. },
+            } => Some("gh sync"),
+            Commands::Hook {
+                command: HookCommands::Install { .. } | HookCommands::Uninstall { .. },
+            } => Some("hook install/uninstall"),
+            Commands::Import { .. } => Some("import"),
+            Commands::Fsck { fix: true } => Some("fsck --fix"),
+            Commands::InstallHelper { .. } => Some("install-helper"),
+            Commands::UninstallHelper { .. } => Some("uninstall-helper"),
+            Commands::Provision { .. } => Some("provision"),
+            Commands::Token {
+                command: TokenCommands::Gc { .. },
+            } => Some("token gc"),
+            Commands::Token {
+                command: TokenCommands::Paste,
+            } => Some("token paste"),
+            Commands::Token {
+                command: TokenCommands::Delivery { mode: Some(_) },
+            } => Some("token delivery"),
+            Commands::Token {
+                command:
+                    TokenCommands::Agent {
+                        command: TokenAgentCommands::Ttl { duration: Some(_) },
+                    },
+            } => Some("token agent ttl"),
+            Commands::Token {
+                command: TokenCommands::Set { .. } | TokenCommands::Delete { .. },
+            } => Some("token set/delete"),
+            Commands::Packages {
+                command: PackagesCommands::Sync { dry_run: false },
+            } => Some("packages sync"),
+            Commands::Warnings {
+                command: Some(WarningsCommands::Enable { .. } | WarningsCommands::Disable { .. }),
+            } => Some("warnings enable/disable"),
+            Commands::Profile {
+                command:
+                    Some(
+                        ProfileCommands::Add { .. }
+                        | ProfileCommands::Remove { .. }
+                        | ProfileCommands::Rename { .. },
+                    ),
+            } => Some("profile add/remove/rename"),
+            Commands::Confirm {
+                command: Some(ConfirmCommands::Set { .. } | ConfirmCommands::Reset { .. }),
+            } => Some("confirm set/reset"),
+            Commands::Notify {
+                command: Some(NotifyCommands::Enable { .. } | NotifyCommands::Disable { .. }),
+            } => Some("notify enable/disable"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum AuditCommands {
+    /// Write the log, oldest first, as CSV or JSON (limit to one account with -a)
+    Export {
+        #[arg(long, value_enum, default_value_t = commands::audit::ExportFormat::Csv)]
+        format: commands::audit::ExportFormat,
+        /// First day to include (YYYY-MM-DD, UTC)
+        #[arg(long)]
+        since: Option<String>,
+        /// Last day to include (YYYY-MM-DD, UTC)
+        #[arg(long)]
+        until: Option<String>,
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum GroupCommands {
+    /// Create or update a group policy
+    Set {
+        name: String,
+        /// Required email domain for accounts in the group (empty to clear)
+        #[arg(long)]
+        email_domain: Option<String>,
+        /// Required host for accounts in the group (empty to clear)
+        #[arg(long)]
+        host: Option<String>,
+    },
+    /// Show groups, their policies, and member compliance
+    List,
+    /// Remove a group policy
+    Remove { name: String },
+}
+
+#[derive(Subcommand)]
+enum PackagesCommands {
+    /// Write the account's token into its enabled registry configs
+    Sync {
+        /// Show a diff of the changes without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PrCommands {
+    /// Show review and CI state of the current branch's pull request
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ImportCommands {
+    /// Find the identities commits were authored with and add yours as accounts
+    History {
+        /// Repositories, or directories of repositories, to scan (defaults to here)
+        paths: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RefreshCommands {
+    /// Run `gitas refresh` periodically via a systemd timer, launchd or Task Scheduler
+    InstallTimer {
+        /// Remove the installed timer instead
+        #[arg(long)]
+        uninstall: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GhCommands {
+    /// Store the account's token as gh's login for its host
+    Sync {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Also do this on every switch
+        #[arg(long, conflicts_with_all = ["disable", "account"])]
+        enable: bool,
+        /// Stop doing this on switch
+        #[arg(long, conflicts_with = "account")]
+        disable: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HookCommands {
+    /// Install a pre-push hook that blocks commits by other accounts' emails
+    Install {
+        /// Repository (defaults to the current one)
+        path: Option<String>,
+    },
+    /// Remove the pre-push hook gitas installed
+    Uninstall {
+        /// Repository (defaults to the current one)
+        path: Option<String>,
+    },
+    /// Run by the installed hook: check the refs git passes on stdin
+    #[command(hide = true)]
+    PrePush {
+        /// Name of the remote being pushed to
+        remote: String,
+        /// URL of the remote
+        url: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CertCommands {
+    /// Trust only this certificate (the host's, or its internal CA) for the host
+    Pin {
+        /// Host, e.g. ghe.corp.com
+        host: String,
+        /// PEM file holding the certificate (defaults to what the host presents)
+        #[arg(long)]
+        file: Option<String>,
+        /// Expected SHA-256 fingerprint; the certificate with it is pinned
+        #[arg(long)]
+        fingerprint: Option<String>,
+    },
+    /// Go back to the system's trusted certificates for the host
+    Unpin {
+        /// Host, e.g. ghe.corp.com
+        host: String,
+    },
+    /// Show pinned hosts and whether their certificate files are intact
+    List,
+}
+
+#[derive(Subcommand)]
+enum RuleCommands {
+    /// Use the account in every repository under DIR
+    Add {
+        /// Directory; `~/` is kept so the rule follows your home directory
+        dir: String,
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+    },
+    /// Show the directory rules in the global git config
+    List,
+    /// Remove the rule for DIR
+    Remove {
+        /// Directory, as given to `gitas rule add`
+        dir: String,
+    },
+    /// Show which account would be applied at PATH (defaults to here)
+    Test {
+        /// Path to check
+        path: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum GpgCommands {
+    /// Register the account's GPG public key on GitHub so its signatures verify
+    Upload {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemoteCommands {
+    /// Point SSH remotes at the account's ~/.ssh/config host alias (see `gitas ssh config`)
+    Fix {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Show the new URLs without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SshCommands {
+    /// Load the account's key into ssh-agent/Pageant and unload other accounts' keys
+    Agent {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Also do this on every switch
+        #[arg(long, conflicts_with_all = ["disable", "account"])]
+        enable: bool,
+        /// Stop doing this on switch
+        #[arg(long, conflicts_with = "account")]
+        disable: bool,
+    },
+    /// Write `Host <host>-<alias>` entries for every account key to ~/.ssh/config
+    Config {
+        /// Remove the gitas block instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Generate an ed25519 key for the account and save its path on the account
+    Keygen {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Create the key without a passphrase instead of asking for one
+        #[arg(long)]
+        no_passphrase: bool,
+        /// Add the public key to the account on its host using the stored token
+        #[arg(long)]
+        upload: bool,
+    },
+    /// Check that the account's key logs in to its host as that account
+    Test {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Test every account that has an SSH key
+        #[arg(long, conflicts_with = "account")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommands {
+    /// Validate tokens against the API and show scopes and expiry
+    Check {
+        /// Check every configured account in parallel
+        #[arg(long)]
+        all: bool,
+    },
+    /// Store the clipboard's contents as the account's token and clear the clipboard
+    Paste,
+    /// Store a token for an account, typed at a hidden prompt or read with --stdin
+    Set {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Read the token from the first line of stdin
+        #[arg(long)]
+        stdin: bool,
+    },
+    /// Show whether an account has a token, masked unless --show is given
+    Get {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+        /// Print the token itself to stdout
+        #[arg(long)]
+        show: bool,
+    },
+    /// Delete an account's token from the keychain
+    Delete {
+        /// Account username, alias, or username:alias
+        account: Option<String>,
+    },
+    /// Show or set how gitas git passes tokens to git: env (default) or once,
+    /// a single-use socket that keeps them out of the environment
+    Delivery {
+        #[arg(value_enum)]
+        mode: Option<cred_once::TokenDelivery>,
+    },
+    /// Keep tokens in memory for a while, so git operations don't ask the
+    /// keychain every time (Unix)
+    Agent {
+        #[command(subcommand)]
+        command: TokenAgentCommands,
+    },
+    /// List tokens kept from removed accounts and delete expired ones
+    Gc {
+        /// Delete every kept token now, expired or not
+        #[arg(long, conflicts_with = "restore")]
+        all: bool,
+        /// Move a removed account's token back (username, alias or username:alias)
+        #[arg(long, value_name = "ACCOUNT")]
+        restore: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenAgentCommands {
+    /// Read every account's token from the keychain once and serve them
+    Start {
+        /// How long to keep them, e.g. 30m, 8h or 1d (defaults to `token agent ttl`)
+        #[arg(long)]
+        ttl: Option<String>,
+    },
+    /// Stop the agent and wipe the tokens it holds
+    Stop,
+    /// Show whether an agent is running and for how long
+    Status,
+    /// Show or set how long `token agent start` keeps tokens
+    Ttl { duration: Option<String> },
+}
+
+#[derive(Subcommand)]
+enum NotifyCommands {
+    /// Show which events notify (the default without a subcommand)
+    List,
+    /// Notify on an event
+    Enable { event: notify::NotifyEvent },
+    /// Stop notifying on an event
+    Disable { event: notify::NotifyEvent },
+    /// Send a test notification
+    Test,
+}
+
+#[derive(Subcommand)]
+enum WarningsCommands {
+    /// Show which warnings are on (the default without a subcommand)
+    List,
+    /// Stop showing a warning
+    Disable { warning: warnings::Warning },
+    /// Show a warning again
+    Enable { warning: warnings::Warning },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// Show profiles and their accounts (the default without a subcommand)
+    List,
+    /// Put accounts in a profile, taking them out of any other
+    Add {
+        profile: String,
+        /// Account usernames, aliases, or username:alias
+        #[arg(required = true)]
+        accounts: Vec<String>,
+    },
+    /// Take accounts out of a profile (all of them when none are named)
+    Remove {
+        profile: String,
+        /// Account usernames, aliases, or username:alias
+        accounts: Vec<String>,
+    },
+    /// Rename a profile
+    Rename { from: String, to: String },
+}
+
+#[derive(Subcommand)]
+enum ConfirmCommands {
+    /// Show each action's policy (the default without a subcommand)
+    List,
+    /// Set when an action asks for confirmation
+    Set {
+        action: confirm::Action,
+        policy: confirm::Policy,
+    },
+    /// Go back to an action's default policy
+    Reset { action: confirm::Action },
+}
+
+/// Whether `args` parse as a gitas command line, for `gitas selftest`.
+fn parses(args: &[&str]) -> Result<(), String> {
+    Cli::try_parse_from(args)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
 }
 
 fn main() {
-    utils::check_git_installed();
     let cli = Cli::parse();
+    let config_override = cli.config.clone().or_else(|| {
+        std::env::var_os(models::CONFIG_ENV)
+            .filter(|v| !v.is_empty())
+            .map(std::path::PathBuf::from)
+    });
+    if let Some(path) = config_override {
+        models::set_config_path(&path);
+    }
+    let profile = cli.profile.clone().or_else(|| {
+        std::env::var(models::PROFILE_ENV)
+            .ok()
+            .filter(|v| !v.is_empty())
+    });
+    // Managing profiles needs every account in view.
+    if let Some(profile) = profile.clone()
+        && !matches!(cli.command, Some(Commands::Profile { .. }))
+    {
+        models::set_profile(profile);
+    }
+    if cli.redact {
+        models::set_redacted(true);
+    }
+    if cli.strict || std::env::var("GITAS_STRICT").is_ok_and(|v| !v.is_empty() && v != "0") {
+        models::set_strict();
+    }
+    if cli.ephemeral {
+        models::set_ephemeral();
+        if let Some(name) = cli.command.as_ref().and_then(Commands::persistent_name) {
+            eprintln!(
+                "\n  {} `gitas {}` only saves changes, which --ephemeral forbids.\n",
+                "✗".red().bold(),
+                name
+            );
+            std::process::exit(1);
+        }
+    }
+    if cli.version {
+        commands::version::run(cli.verbose);
+        return;
+    }
+    // Needs neither the config nor git; it must work when nothing else does.
+    if let Some(Commands::Sane) = cli.command {
+        commands::sane::run();
+        return;
+    }
+    // Run by git as a credential helper: stdout is the answer, nothing else.
+    if let Some(Commands::CredOnce { id, action }) = &cli.command {
+        cred_once::fetch(id, action.as_deref());
+        return;
+    }
+    if let Some(Commands::TokenAgent { ttl }) = cli.command {
+        token_agent::serve(ttl);
+        return;
+    }
+    utils::check_git_installed();
+    let first_run = !models::config_path().exists();
     let mut config = load_config();
+    browser::configure(cli.no_browser, config.browser.clone());
+    trust::init(&config.pinned_certs);
+    notify::init(&config.notifications);
+
+    // Only where gitas is about to be set up, and someone can answer.
+    if first_run
+        && matches!(cli.command, None | Some(Commands::Add { .. }))
+        && !cli.ephemeral
+        && !models::is_strict()
+        && std::io::IsTerminal::is_terminal(&std::io::stdin())
+    {
+        commands::detect::first_run(&mut config);
+    }
 
     match cli.command {
         None => commands::list::run(&mut config),
-        Some(Commands::Add) => commands::add::run(&mut config),
-        Some(Commands::Git { args }) => commands::git::run(&config, cli.account, args),
+        Some(Commands::Detect) => commands::detect::run(&mut config),
+        Some(Commands::Credential { action }) => commands::credential::run(&config, &action),
+        Some(Commands::InstallHelper { local }) => commands::helper::install(local),
+        Some(Commands::UninstallHelper { local }) => commands::helper::uninstall(local),
+        Some(Commands::Add {
+            resume,
+            batch,
+            fields,
+        }) => commands::add::run(&mut config, resume, batch, fields),
+        Some(Commands::Edit {
+            account,
+            fields,
+            set,
+            filter,
+            ..
+        }) => match account {
+            Some(account) => commands::edit::run(&mut config, account, *fields),
+            None => commands::edit::batch(&mut config, *fields, set, filter),
+        },
+        Some(Commands::Audit { command }) => match command {
+            AuditCommands::Export {
+                format,
+                since,
+                until,
+                output,
+            } => commands::audit::export(&config, cli.account, format, since, until, output),
+        },
+        Some(Commands::Default { account, clear }) => {
+            commands::default::run(&mut config, account, clear)
+        }
+        Some(Commands::Use {
+            account,
+            clear,
+            list,
+        }) => commands::bind::run(&mut config, account, clear, list),
+        Some(Commands::Env { clear }) => commands::env::run(clear),
+        Some(Commands::Export { file, with_tokens }) => {
+            commands::backup::export(&config, file, with_tokens)
+        }
+        Some(Commands::Git { args }) => {
+            commands::git::run(&mut config, cli.account, cli.pick, args)
+        }
+        Some(Commands::Clone {
+            url,
+            directory,
+            git_args,
+        }) => commands::clone::run(&mut config, cli.account, cli.pick, url, directory, git_args),
+        Some(Commands::Exec { args }) => {
+            commands::exec::run(&mut config, cli.account, cli.pick, args)
+        }
+        Some(Commands::Cert { command }) => match command {
+            CertCommands::Pin {
+                host,
+                file,
+                fingerprint,
+            } => commands::cert::pin(&mut config, host, file, fingerprint),
+            CertCommands::Unpin { host } => commands::cert::unpin(&mut config, host),
+            CertCommands::List => commands::cert::list(&config),
+        },
+        Some(Commands::Foreach { group, jobs, args }) => {
+            commands::foreach::run(&config, cli.account, group, jobs, args)
+        }
+        Some(Commands::Gpg { command }) => match command {
+            GpgCommands::Upload { account } => {
+                commands::gpg::upload(&config, account.or(cli.account))
+            }
+        },
+        Some(Commands::Rule { command }) => match command {
+            RuleCommands::Add { dir, account } => {
+                commands::rule::add(&config, &dir, account.or(cli.account))
+            }
+            RuleCommands::List => commands::rule::list(&config),
+            RuleCommands::Remove { dir } => commands::rule::remove(&dir),
+            RuleCommands::Test { path } => commands::rule::test(&config, path),
+        },
+        Some(Commands::Gh { command }) => match command {
+            GhCommands::Sync {
+                account,
+                enable,
+                disable,
+            } => commands::gh::sync(&mut config, account.or(cli.account), enable, disable),
+        },
+        Some(Commands::CheckPush {
+            enable,
+            disable,
+            args,
+        }) => commands::check_push::run(&mut config, cli.account, enable, disable, args),
+        Some(Commands::Provision { manifest }) => commands::provision::run(&mut config, &manifest),
+        Some(Commands::Hook { command }) => match command {
+            HookCommands::Install { path } => commands::hook::install(&config, path),
+            HookCommands::Uninstall { path } => commands::hook::uninstall(path),
+            HookCommands::PrePush { remote, url } => {
+                commands::hook::pre_push(&config, &remote, &url)
+            }
+        },
+        Some(Commands::Group { command }) => match command {
+            GroupCommands::Set {
+                name,
+                email_domain,
+                host,
+            } => commands::group::set(&mut config, name, email_domain, host),
+            GroupCommands::List => commands::group::list(&config),
+            GroupCommands::Remove { name } => commands::group::remove(&mut config, name),
+        },
+        Some(Commands::History { pushes, limit }) => {
+            commands::history::run(&config, cli.account, pushes, limit)
+        }
+        Some(Commands::Import {
+            file,
+            replace,
+            force,
+            command,
+        }) => match (command, file) {
+            (Some(ImportCommands::History { paths }), _) => {
+                commands::import_history::run(&mut config, paths)
+            }
+            (None, Some(file)) => commands::backup::import(&mut config, file, replace, force),
+            (None, None) => unreachable!("clap requires a file without a subcommand"),
+        },
+        Some(Commands::Inbox { account }) => commands::inbox::run(&config, account.or(cli.account)),
+        Some(Commands::Insights { days }) => commands::insights::run(&config, days),
+        Some(Commands::Migrate { dry_run }) => commands::migrate::run(dry_run),
+        Some(Commands::Open { remote }) => commands::open::run(remote),
+        Some(Commands::Packages { command }) => match command {
+            PackagesCommands::Sync { dry_run } => {
+                commands::packages::sync(&config, cli.account, dry_run)
+            }
+        },
+        Some(Commands::Pr { command }) => match command {
+            PrCommands::Status => commands::pr::status(&mut config, cli.account),
+        },
+        Some(Commands::PreserveAuthor { mode }) => {
+            commands::preserve_author::run(&mut config, mode)
+        }
+        Some(Commands::Refresh { quiet, command }) => match command {
+            Some(RefreshCommands::InstallTimer { uninstall }) => {
+                commands::refresh::install_timer(uninstall)
+            }
+            None => commands::refresh::run(&config, quiet),
+        },
+        Some(Commands::Logout { account, revoke }) => {
+            commands::logout::run(&config, account.or(cli.account), revoke)
+        }
+        Some(Commands::Remote { command }) => match command {
+            RemoteCommands::Fix { account, dry_run } => {
+                commands::remote::fix(&mut config, account.or(cli.account), dry_run)
+            }
+        },
+        Some(Commands::Remove { account, yes }) => commands::remove::run(&mut config, account, yes),
+        Some(Commands::Resolve { target }) => commands::resolve::run(&config, target),
+        Some(Commands::Sane | Commands::CredOnce { .. } | Commands::TokenAgent { .. }) => {
+            unreachable!("handled before the config is loaded")
+        }
+        Some(Commands::Schema { json }) => commands::schema::run(Cli::command(), json),
+        Some(Commands::Selftest) => commands::selftest::run(parses),
+        Some(Commands::Fsck { fix }) => commands::fsck::run(&mut config, fix),
+        Some(Commands::Ssh { command }) => match command {
+            SshCommands::Agent {
+                account,
+                enable,
+                disable,
+            } => commands::ssh::agent(&mut config, account.or(cli.account), enable, disable),
+            SshCommands::Config { remove } => commands::ssh::config(&config, remove),
+            SshCommands::Keygen {
+                account,
+                no_passphrase,
+                upload,
+            } => commands::ssh::keygen(&mut config, account.or(cli.account), no_passphrase, upload),
+            SshCommands::Test { account, all } => {
+                commands::ssh::test(&config, account.or(cli.account), all)
+            }
+        },
+        Some(Commands::Status) => commands::status::run(&config),
+        Some(Commands::Verify { all }) => commands::token::check(&config, cli.account, all),
+        Some(Commands::Token { command }) => match command {
+            TokenCommands::Check { all } => commands::token::check(&config, cli.account, all),
+            TokenCommands::Gc { all, restore } => commands::token::gc(all, restore),
+            TokenCommands::Delivery { mode } => commands::token::delivery(&mut config, mode),
+            TokenCommands::Agent { command } => match command {
+                TokenAgentCommands::Start { ttl } => commands::token::agent_start(&config, ttl),
+                TokenAgentCommands::Stop => commands::token::agent_stop(),
+                TokenAgentCommands::Status => commands::token::agent_status(),
+                TokenAgentCommands::Ttl { duration } => {
+                    commands::token::agent_ttl(&mut config, duration)
+                }
+            },
+            TokenCommands::Paste => commands::token::paste(&config, cli.account),
+            TokenCommands::Set { account, stdin } => {
+                commands::token::set(&config, account.or(cli.account), stdin)
+            }
+            TokenCommands::Get { account, show } => {
+                commands::token::get(&config, account.or(cli.account), show)
+            }
+            TokenCommands::Delete { account } => {
+                commands::token::delete(&config, account.or(cli.account))
+            }
+        },
+        Some(Commands::Whoami) => commands::whoami::run(&config),
+        Some(Commands::Worktrees { set, unset }) => match (set, unset) {
+            (Some(path), _) => commands::worktrees::set(&config, cli.account, &path),
+            (_, Some(path)) => commands::worktrees::unset(&config, &path),
+            _ => commands::worktrees::list(&config),
+        },
+        Some(Commands::Notify { command }) => match command {
+            None | Some(NotifyCommands::List) => commands::notify::list(&config),
+            Some(NotifyCommands::Enable { event }) => {
+                commands::notify::set(&mut config, event, true)
+            }
+            Some(NotifyCommands::Disable { event }) => {
+                commands::notify::set(&mut config, event, false)
+            }
+            Some(NotifyCommands::Test) => commands::notify::test(),
+        },
+        Some(Commands::Warnings { command }) => match command {
+            None | Some(WarningsCommands::List) => commands::warnings::list(&config, cli.account),
+            Some(WarningsCommands::Disable { warning }) => {
+                commands::warnings::set(&mut config, cli.account, warning, false)
+            }
+            Some(WarningsCommands::Enable { warning }) => {
+                commands::warnings::set(&mut config, cli.account, warning, true)
+            }
+        },
+        Some(Commands::Profile { command }) => match command {
+            None | Some(ProfileCommands::List) => {
+                commands::profile::list(&config, profile.as_deref())
+            }
+            Some(ProfileCommands::Add { profile, accounts }) => {
+                commands::profile::add(&mut config, profile, accounts)
+            }
+            Some(ProfileCommands::Remove { profile, accounts }) => {
+                commands::profile::remove(&mut config, profile, accounts)
+            }
+            Some(ProfileCommands::Rename { from, to }) => {
+                commands::profile::rename(&mut config, from, to)
+            }
+        },
+        Some(Commands::Confirm { command }) => match command {
+            None | Some(ConfirmCommands::List) => commands::confirm::list(&config),
+            Some(ConfirmCommands::Set { action, policy }) => {
+                commands::confirm::set(&mut config, action, policy)
+            }
+            Some(ConfirmCommands::Reset { action }) => {
+                commands::confirm::reset(&mut config, action)
+            }
+        },
     }
 }
@@ -0,0 +1,160 @@
+use crate::models::Config;
+use serde_json::{Map, Value};
+
+/// Schema version written by this build. Configs without a `version` are 0.
+pub const CONFIG_VERSION: u64 = 1;
+
+/// One transformation applied to the raw config.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub path: String,
+    pub description: String,
+}
+
+/// Bring a raw config up to `CONFIG_VERSION`, returning what changed. Unknown
+/// fields are left alone; a config from a newer version is not touched.
+pub fn upgrade(raw: &mut Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let Some(root) = raw.as_object_mut() else {
+        return changes;
+    };
+    let version = root.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version >= CONFIG_VERSION {
+        return changes;
+    }
+
+    if version < 1 {
+        v1_tidy_accounts(root, &mut changes);
+    }
+
+    root.insert("version".to_string(), Value::from(CONFIG_VERSION));
+    changes.push(Change {
+        path: "version".to_string(),
+        description: format!("{} → {}", version, CONFIG_VERSION),
+    });
+    changes
+}
+
+/// Version 1: hosts are stored normalized (no scheme, lowercase, github.com
+/// implied) and empty optional strings are dropped instead of kept as "".
+fn v1_tidy_accounts(root: &mut Map<String, Value>, changes: &mut Vec<Change>) {
+    let Some(accounts) = root.get_mut("accounts").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for (index, account) in accounts.iter_mut().enumerate() {
+        let Some(account) = account.as_object_mut() else {
+            continue;
+        };
+        let label = account
+            .get("username")
+            .and_then(Value::as_str)
+            .map(|name| format!("accounts[{}] ({})", index, name))
+            .unwrap_or_else(|| format!("accounts[{}]", index));
+
+        if let Some(host) = account.get("host").and_then(Value::as_str) {
+            match crate::host::for_account(host) {
+                Some(normalized) if normalized != host => {
+                    changes.push(Change {
+                        path: format!("{}.host", label),
+                        description: format!("{:?} → {:?}", host, normalized),
+                    });
+                    account.insert("host".to_string(), Value::from(normalized));
+                }
+                Some(_) => {}
+                None => {
+                    changes.push(Change {
+                        path: format!("{}.host", label),
+                        description: format!("{:?} removed (github.com is the default)", host),
+                    });
+                    account.remove("host");
+                }
+            }
+        }
+
+        for field in ["alias", "ssh_key", "gpg_key", "signing_key", "group"] {
+            if account.get(field).and_then(Value::as_str) == Some("") {
+                changes.push(Change {
+                    path: format!("{}.{}", label, field),
+                    description: "empty value removed".to_string(),
+                });
+                account.remove(field);
+            }
+        }
+    }
+}
+
+/// Fields this version doesn't recognise, or whose values it can't read,
+/// kept verbatim when saving.
+pub fn extras(config: &Config) -> Vec<String> {
+    let mut fields: Vec<String> = config
+        .extra
+        .keys()
+        .chain(config.opaque.keys())
+        .cloned()
+        .collect();
+    for (index, account) in config.accounts.iter().enumerate() {
+        fields.extend(
+            account
+                .extra
+                .keys()
+                .chain(account.opaque.keys())
+                .map(|key| format!("accounts[{}] ({}).{}", index, account.username, key)),
+        );
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn upgrades_legacy_accounts() {
+        let mut raw = json!({
+            "accounts": [
+                { "username": "octo", "email": "o@x.com", "host": "https://GitHub.com/", "alias": "" },
+                { "username": "work", "email": "w@x.com", "host": "HTTPS://GHE.Corp:443/org" }
+            ]
+        });
+        let changes = upgrade(&mut raw);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "accounts[0] (octo).host",
+                "accounts[0] (octo).alias",
+                "accounts[1] (work).host",
+                "version"
+            ]
+        );
+        assert_eq!(
+            raw["accounts"][0],
+            json!({ "username": "octo", "email": "o@x.com" })
+        );
+        assert_eq!(raw["accounts"][1]["host"], "ghe.corp");
+        assert_eq!(raw["version"], CONFIG_VERSION);
+        assert!(upgrade(&mut raw).is_empty());
+    }
+
+    #[test]
+    fn preserves_unknown_fields_round_trip() {
+        let raw = json!({
+            "version": CONFIG_VERSION,
+            "accounts": [{ "username": "octo", "email": "o@x.com", "future_flag": [1, 2] }],
+            "sync": { "remote": "dotfiles" }
+        });
+        let config: Config = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(extras(&config), ["sync", "accounts[0] (octo).future_flag"]);
+        assert_eq!(serde_json::to_value(&config).unwrap(), raw);
+    }
+
+    #[test]
+    fn leaves_newer_configs_alone() {
+        let mut raw =
+            json!({ "version": CONFIG_VERSION + 1, "accounts": [{ "host": "GitHub.com" }] });
+        let before = raw.clone();
+        assert!(upgrade(&mut raw).is_empty());
+        assert_eq!(raw, before);
+    }
+}
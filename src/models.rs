@@ -1,12 +1,66 @@
 use colored::Colorize;
 use keyring::Entry;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use zeroize::Zeroizing;
 
 const SERVICE_NAME: &str = "gitas";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+static EPHEMERAL: AtomicBool = AtomicBool::new(false);
+
+/// Read-only mode for shared machines: config, keychain, caches, logs and git
+/// config are never written; identity is only applied through `-c`/env.
+pub fn set_ephemeral() {
+    EPHEMERAL.store(true, Ordering::Relaxed);
+}
+
+pub fn is_ephemeral() -> bool {
+    EPHEMERAL.load(Ordering::Relaxed)
+}
+
+static REDACTED: AtomicBool = AtomicBool::new(false);
+
+/// Privacy mode for screen sharing: usernames and emails are shown masked,
+/// see `crate::utils::redact_name`. The list toggles it with `p`.
+pub fn set_redacted(on: bool) {
+    REDACTED.store(on, Ordering::Relaxed);
+}
+
+pub fn is_redacted() -> bool {
+    REDACTED.load(Ordering::Relaxed)
+}
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// CI mode: gitas never prompts, and git run through it may not ask for
+/// credentials, so a missing token fails fast instead of hanging.
+pub fn set_strict() {
+    STRICT.store(true, Ordering::Relaxed);
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// Environment variable selecting a profile, like `--profile`.
+pub const PROFILE_ENV: &str = "GITAS_PROFILE";
+
+static PROFILE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+/// Only show and use the accounts in `profile`; the others are kept out of
+/// the loaded config and written back untouched on save.
+pub fn set_profile(profile: String) {
+    PROFILE.set(profile).ok();
+}
+
+pub fn active_profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Account {
     pub username: String,
     pub email: String,
@@ -14,17 +68,246 @@ pub struct Account {
     pub alias: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub host: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Private key SSH remotes authenticate with (`ssh_key_path` is accepted too).
+    #[serde(alias = "ssh_key_path", skip_serializing_if = "Option::is_none")]
     pub ssh_key: Option<String>,
+    /// Authenticate over SSH only; tokens and credential helpers are never touched.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ssh_only: bool,
+    /// Load `ssh_key` into ssh-agent with `ssh-add -c`, so the agent asks
+    /// before each use of it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ssh_confirm: bool,
+    /// GPG key ID used for signing; may be a stub for a key on a smartcard.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpg_key: Option<String>,
+    /// Have switches sign commits with `gpg_key` when `signing_key` is unset.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sign_with_gpg_key: bool,
+    /// Key commits are signed with on switch (`user.signingkey`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+    /// `gpg.format` of `signing_key`. Absent means SSH.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_format: Option<crate::signing::SigningFormat>,
+    /// Forge type of `host`, detected when the account was added. Absent means GitHub.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<crate::provider::ProviderKind>,
+    /// Owners (organizations or users) whose repositories this account works
+    /// on, besides its own username; selects it from the `origin` remote.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orgs: Vec<String>,
+    /// Group whose policy (email domain, host) this account must follow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Timezone (`TZ`, e.g. `Europe/Berlin`) that commits made through
+    /// `gitas git` and `exec` are stamped in, instead of the machine's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Whether `gitas git` keeps or restamps author dates when rewriting commits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_date: Option<crate::dates::AuthorDate>,
+    /// Package registry configs that receive this account's token on switch.
+    #[serde(
+        default,
+        skip_serializing_if = "crate::registry::PackageSync::is_empty"
+    )]
+    pub packages: crate::registry::PackageSync,
+    /// Per-account overrides of the global `warnings` settings.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub warnings: crate::warnings::WarningSettings,
+    /// Template (see `crate::plus`) for the address each repository gets on
+    /// `gitas use` and `gitas clone`, e.g. `{local}+{repo}@{domain}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plus_template: Option<String>,
+    /// Plus addresses made from `plus_template` (lowercase), with the
+    /// repository each was made for.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plus_addresses: BTreeMap<String, String>,
+    /// Named set of accounts that `--profile` narrows gitas to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Fields from other gitas versions, written back untouched.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// Values of known fields this version can't read, see [`set_aside`].
+    #[serde(skip)]
+    pub opaque: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version, see `crate::migrate`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub version: u64,
     pub accounts: Vec<Account>,
+    /// Opt-in scan of outgoing commits for emails the account doesn't own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_check: Option<crate::push_check::PushCheckMode>,
+    /// Load the account's key into the SSH agent, and unload the others, on
+    /// every switch.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ssh_agent: bool,
+    /// Log gh in as the account on every switch.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gh_sync: bool,
+    /// Whether `gitas git` keeps the original author when amending or
+    /// continuing a cherry-pick/rebase of someone else's commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preserve_author: Option<crate::authorship::PreserveAuthor>,
+    /// How `gitas git` passes the token to git's credential helper.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_delivery: Option<crate::cred_once::TokenDelivery>,
+    /// Seconds `gitas token agent start` keeps tokens in memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_ttl: Option<u64>,
+    /// Command used to open URLs (e.g. `wslview`), for sandboxes where the
+    /// platform default picks the wrong browser.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, crate::policy::GroupPolicy>,
+    #[serde(default, skip_serializing_if = "crate::plugins::Hooks::is_empty")]
+    pub hooks: crate::plugins::Hooks,
+    /// Account (`username` or `username:alias`) that `gitas git`, `exec` and
+    /// `clone` use when no `-a` is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_account: Option<String>,
+    /// Repository roots bound to an account with `gitas use`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub repos: BTreeMap<String, String>,
+    /// SHA-256 fingerprints of the certificate API calls to each host must
+    /// chain to, pinned with `gitas cert pin`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pinned_certs: BTreeMap<String, String>,
+    /// Remote owners (`host/owner`, lowercase) confirmed to belong to an
+    /// account after gitas inferred it from `origin`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remote_owners: BTreeMap<String, String>,
+    /// Remote owners whose inferred account the user turned down, so gitas
+    /// doesn't ask about them again.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub declined_owners: BTreeMap<String, String>,
+    /// Days a removed account's token stays recoverable with `gitas token gc`
+    /// (default 7; 0 deletes it right away).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_grace_days: Option<u64>,
+    /// Events that raise a desktop notification, turned on with `gitas notify`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub notifications: BTreeSet<crate::notify::NotifyEvent>,
+    /// Advisory messages to silence (`false`) everywhere.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub warnings: crate::warnings::WarningSettings,
+    /// When to ask before deleting, switching globally and the like.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub confirm: crate::confirm::ConfirmSettings,
+    /// Fields from other gitas versions, written back untouched.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// Values of known fields this version can't read, see [`set_aside`].
+    #[serde(skip)]
+    pub opaque: serde_json::Map<String, serde_json::Value>,
+    /// Accounts outside the active profile, with their position in the file.
+    #[serde(skip)]
+    pub hidden: Vec<(usize, Account)>,
 }
 
-fn config_path() -> &'static PathBuf {
-    static CONFIG_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+impl Config {
+    /// Every account, including those outside the active profile. Checks of
+    /// settings shared by all profiles (git config, the keychain, ssh) use
+    /// this so they don't mistake another profile's accounts for strangers.
+    pub fn all_accounts(&self) -> Vec<Account> {
+        with_hidden(self)
+    }
+
+    /// Point the default account and repository bindings that name account
+    /// `from` at `to`, or drop them when the account is gone.
+    pub fn retarget_account(&mut self, from: &str, to: Option<&str>) {
+        if self.default_account.as_deref() == Some(from) {
+            self.default_account = to.map(str::to_string);
+        }
+        match to {
+            Some(to) => {
+                for id in self.repos.values_mut().filter(|id| *id == from) {
+                    *id = to.to_string();
+                }
+            }
+            None => self.repos.retain(|_, id| id != from),
+        }
+        match to {
+            Some(to) => {
+                for id in self.remote_owners.values_mut().filter(|id| *id == from) {
+                    *id = to.to_string();
+                }
+            }
+            None => self.remote_owners.retain(|_, id| id != from),
+        }
+        match to {
+            Some(to) => {
+                for id in self.declined_owners.values_mut().filter(|id| *id == from) {
+                    *id = to.to_string();
+                }
+            }
+            None => self.declined_owners.retain(|_, id| id != from),
+        }
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// Environment variable naming the config file or directory to use instead
+/// of the platform's config directory.
+pub const CONFIG_ENV: &str = "GITAS_CONFIG";
+
+static CONFIG_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+static CONFIG_OVERRIDDEN: AtomicBool = AtomicBool::new(false);
+
+static CONFIG_UNREADABLE: AtomicBool = AtomicBool::new(false);
+
+/// Use `path` (from `--config` or `GITAS_CONFIG`) for the config. Must run
+/// before anything reads the config.
+pub fn set_config_path(path: &std::path::Path) {
+    let path = config_file(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()));
+    if let Some(dir) = path.parent()
+        && let Err(e) = fs::create_dir_all(dir)
+    {
+        eprintln!(
+            "\n  {} Could not create {}: {}\n",
+            "✗".red().bold(),
+            dir.display(),
+            e
+        );
+        std::process::exit(1);
+    }
+    CONFIG_PATH.set(path).ok();
+    CONFIG_OVERRIDDEN.store(true, Ordering::Relaxed);
+}
+
+/// Whether `--config` or `GITAS_CONFIG` picked the config. The global git
+/// config and the keychain may then hold settings of another config.
+pub fn is_config_overridden() -> bool {
+    CONFIG_OVERRIDDEN.load(Ordering::Relaxed)
+}
+
+/// The config file `path` names: `accounts.json` inside it when it is a
+/// directory (or ends with a separator), the path itself otherwise.
+fn config_file(path: &std::path::Path) -> PathBuf {
+    let as_dir = path.is_dir()
+        || path
+            .as_os_str()
+            .to_string_lossy()
+            .ends_with(std::path::is_separator);
+    if as_dir {
+        path.join("accounts.json")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+pub(crate) fn config_path() -> &'static PathBuf {
     CONFIG_PATH.get_or_init(|| {
         let config_dir = dirs::config_dir()
             .expect("Could not determine config directory")
@@ -38,20 +321,207 @@ pub fn load_config() -> Config {
     let path = config_path();
     if path.exists() {
         let data = fs::read_to_string(path).expect("Could not read config file");
-        serde_json::from_str(&data).unwrap_or_default()
+        let mut config = match parse_config(&data) {
+            Ok((config, _)) => config,
+            Err(e) => {
+                // Saving the empty config would wipe every account.
+                CONFIG_UNREADABLE.store(true, Ordering::Relaxed);
+                eprintln!(
+                    "\n  {} Could not parse {}: {}\n  {}\n",
+                    "⚠".yellow(),
+                    path.display(),
+                    e,
+                    "Continuing without accounts; gitas won't save over it until it's fixed."
+                        .dimmed()
+                );
+                Config::default()
+            }
+        };
+        // Hand-edited configs may carry URLs or mixed case.
+        for account in &mut config.accounts {
+            account.host = account.host.as_deref().and_then(crate::host::for_account);
+        }
+        if let Some(profile) = active_profile() {
+            let (shown, hidden): (Vec<_>, Vec<_>) = std::mem::take(&mut config.accounts)
+                .into_iter()
+                .enumerate()
+                .partition(|(_, account)| account.profile.as_deref() == Some(profile));
+            config.accounts = shown.into_iter().map(|(_, account)| account).collect();
+            config.hidden = hidden;
+        }
+        config
     } else {
         Config::default()
     }
 }
 
+/// Parse a config, upgrading older schemas in memory. The changes are only
+/// persisted by the next save or by `gitas migrate`.
+pub(crate) fn parse_config(
+    data: &str,
+) -> serde_json::Result<(Config, Vec<crate::migrate::Change>)> {
+    use serde_json::{Value, json};
+
+    let mut raw: Value = serde_json::from_str(data)?;
+    let changes = crate::migrate::upgrade(&mut raw);
+
+    let mut opaque_accounts = Vec::new();
+    if let Some(accounts) = raw.get_mut("accounts").and_then(Value::as_array_mut) {
+        let base = json!({ "username": "", "email": "" });
+        for account in accounts {
+            opaque_accounts.push(match account.as_object_mut() {
+                Some(account) => set_aside::<Account>(account, base.as_object().unwrap()),
+                None => serde_json::Map::new(),
+            });
+        }
+    }
+    let opaque = match raw.as_object_mut() {
+        Some(root) => set_aside::<Config>(root, json!({ "accounts": [] }).as_object().unwrap()),
+        None => serde_json::Map::new(),
+    };
+
+    let mut config: Config = serde_json::from_value(raw)?;
+    config.opaque = opaque;
+    for (account, opaque) in config.accounts.iter_mut().zip(opaque_accounts) {
+        account.opaque = opaque;
+    }
+    Ok((config, changes))
+}
+
+/// Take out of `object` what `T` can't read, so a newer version's enum
+/// variant or map key doesn't make the whole config unreadable: unknown
+/// entries of a map or list, or the whole value otherwise. `base` holds the
+/// fields `T` requires, which are left alone. What's taken is returned to be
+/// written back by [`restore_opaque`].
+fn set_aside<T: serde::de::DeserializeOwned>(
+    object: &mut serde_json::Map<String, serde_json::Value>,
+    base: &serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+    use serde_json::Value;
+
+    let reads = |key: &str, value: Value| {
+        let mut probe = base.clone();
+        probe.insert(key.to_string(), value);
+        serde_json::from_value::<T>(Value::Object(probe)).is_ok()
+    };
+    let mut opaque = serde_json::Map::new();
+    let keys: Vec<String> = object.keys().cloned().collect();
+    for key in keys.into_iter().filter(|key| !base.contains_key(key)) {
+        if reads(&key, object[&key].clone()) {
+            continue;
+        }
+        let kept = match object.remove(&key).expect("key listed above") {
+            Value::Object(entries) => {
+                let (known, unknown): (serde_json::Map<_, _>, serde_json::Map<_, _>) =
+                    entries.into_iter().partition(|(entry, value)| {
+                        reads(
+                            &key,
+                            Value::Object([(entry.clone(), value.clone())].into_iter().collect()),
+                        )
+                    });
+                object.insert(key.clone(), Value::Object(known));
+                Value::Object(unknown)
+            }
+            Value::Array(items) => {
+                let (known, unknown): (Vec<_>, Vec<_>) = items
+                    .into_iter()
+                    .partition(|item| reads(&key, Value::Array(vec![item.clone()])));
+                object.insert(key.clone(), Value::Array(known));
+                Value::Array(unknown)
+            }
+            other => other,
+        };
+        opaque.insert(key, kept);
+    }
+    opaque
+}
+
+/// Put values [`set_aside`] took back into a serialized `object`: unknown map
+/// entries and list items join what this version wrote, a whole value only
+/// comes back when this version left the field unset.
+fn restore_opaque(
+    object: &mut serde_json::Value,
+    opaque: &serde_json::Map<String, serde_json::Value>,
+) {
+    use serde_json::Value;
+
+    let Some(object) = object.as_object_mut() else {
+        return;
+    };
+    for (key, kept) in opaque {
+        match (object.get_mut(key), kept) {
+            (Some(Value::Object(current)), Value::Object(kept)) => {
+                for (entry, value) in kept {
+                    current
+                        .entry(entry.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+            (Some(Value::Array(current)), Value::Array(kept)) => {
+                current.extend(kept.iter().cloned());
+            }
+            (Some(_), _) => {}
+            (None, kept) => {
+                object.insert(key.clone(), kept.clone());
+            }
+        }
+    }
+}
+
+/// The config as written to its file, with `accounts` in place of the
+/// profile's and what this version couldn't read put back.
+fn file_value(config: &Config, accounts: &[Account]) -> serde_json::Value {
+    let mut value = serde_json::to_value(config).expect("Could not serialize config");
+    restore_opaque(&mut value, &config.opaque);
+    let accounts = accounts
+        .iter()
+        .map(|account| {
+            let mut value = serde_json::to_value(account).expect("Could not serialize config");
+            restore_opaque(&mut value, &account.opaque);
+            value
+        })
+        .collect();
+    value["accounts"] = serde_json::Value::Array(accounts);
+    value
+}
+
 pub fn save_config(config: &Config) {
+    if is_ephemeral() {
+        return;
+    }
     let path = config_path();
-    let data = serde_json::to_string_pretty(config).expect("Could not serialize config");
+    if CONFIG_UNREADABLE.load(Ordering::Relaxed) {
+        eprintln!(
+            "\n  {} Not saving: {} could not be parsed. Fix or move it aside first.\n",
+            "✗".red().bold(),
+            path.display()
+        );
+        std::process::exit(1);
+    }
+    let accounts = with_hidden(config);
+    let value = file_value(config, &accounts);
+    let data = serde_json::to_string_pretty(&value).expect("Could not serialize config");
     fs::write(path, data).expect("Could not write config file");
+    // The ssh config block and the rule includes are global, and belong to
+    // the real config's accounts.
+    if !is_config_overridden() {
+        crate::ssh::refresh_hosts(&accounts);
+        crate::rules::refresh(&accounts);
+    }
+}
+
+/// Every account, with the ones outside the active profile back where they
+/// were in the file.
+fn with_hidden(config: &Config) -> Vec<Account> {
+    let mut accounts = config.accounts.clone();
+    for (index, account) in &config.hidden {
+        accounts.insert((*index).min(accounts.len()), account.clone());
+    }
+    accounts
 }
 
 /// Helper to construct the keychain entry key
-fn make_key(username: &str, alias: Option<&str>) -> String {
+pub(crate) fn make_key(username: &str, alias: Option<&str>) -> String {
     match alias {
         Some(a) => format!("{}::{}", username, a),
         None => username.to_string(),
@@ -60,44 +530,166 @@ fn make_key(username: &str, alias: Option<&str>) -> String {
 
 /// Securely store a token in the system keychain
 pub fn set_token(username: &str, alias: Option<&str>, token: &str) {
-    let key = make_key(username, alias);
-    match Entry::new(SERVICE_NAME, &key) {
+    set_token_by_key(&make_key(username, alias), token);
+}
+
+pub(crate) fn set_token_by_key(key: &str, token: &str) {
+    if is_ephemeral() {
+        return;
+    }
+    match Entry::new(SERVICE_NAME, key) {
         Ok(entry) => {
             if let Err(e) = entry.set_password(token) {
                 eprintln!("  {} Failed to store token in keychain: {}", "✗".red(), e);
             }
+            crate::token_agent::forget(key);
         }
         Err(e) => eprintln!("  {} Failed to create keychain entry: {}", "✗".red(), e),
     }
 }
 
-/// Retrieve a token from the system keychain
-pub fn get_token(username: &str, alias: Option<&str>) -> Option<String> {
-    let key = make_key(username, alias);
-    match Entry::new(SERVICE_NAME, &key) {
-        Ok(entry) => match entry.get_password() {
-            Ok(password) => Some(password),
-            Err(keyring::Error::NoEntry) => None,
-            Err(e) => {
-                eprintln!(
-                    "  {} Failed to retrieve token from keychain: {}",
-                    "✗".red(),
-                    e
-                );
-                None
-            }
-        },
-        Err(e) => {
-            eprintln!("  {} Failed to access keychain: {}", "✗".red(), e);
-            None
-        }
+/// Retrieve a token from the system keychain. The buffer is wiped on drop.
+pub fn get_token(username: &str, alias: Option<&str>) -> Option<Zeroizing<String>> {
+    get_token_by_key(&make_key(username, alias))
+}
+
+pub(crate) fn get_token_by_key(key: &str) -> Option<Zeroizing<String>> {
+    read_token_by_key(key).unwrap_or_else(|e| {
+        eprintln!("  {} {}", "✗".red(), e);
+        None
+    })
+}
+
+/// Like `get_token`, but a keychain that can't be read is an error rather
+/// than "no token", for callers that would otherwise delete or overwrite it.
+pub(crate) fn read_token(
+    username: &str,
+    alias: Option<&str>,
+) -> Result<Option<Zeroizing<String>>, String> {
+    read_token_by_key(&make_key(username, alias))
+}
+
+fn read_token_by_key(key: &str) -> Result<Option<Zeroizing<String>>, String> {
+    let entry =
+        Entry::new(SERVICE_NAME, key).map_err(|e| format!("Failed to access keychain: {}", e))?;
+    match entry.get_password() {
+        Ok(password) => Ok(Some(Zeroizing::new(password))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to retrieve token from keychain: {}", e)),
     }
 }
 
 /// Delete a token from the system keychain
 pub fn delete_token(username: &str, alias: Option<&str>) {
-    let key = make_key(username, alias);
-    if let Ok(entry) = Entry::new(SERVICE_NAME, &key) {
+    delete_token_by_key(&make_key(username, alias));
+}
+
+pub(crate) fn delete_token_by_key(key: &str) {
+    if is_ephemeral() {
+        return;
+    }
+    if let Ok(entry) = Entry::new(SERVICE_NAME, key) {
         let _ = entry.delete_credential();
     }
+    crate::token_agent::forget(key);
+}
+
+/// Store, read back and delete a throwaway secret, reporting the first
+/// keychain error instead of printing it.
+pub(crate) fn probe_keychain(key: &str) -> Result<(), String> {
+    let entry = Entry::new(SERVICE_NAME, key).map_err(|e| e.to_string())?;
+    let secret = format!("gitas-selftest-{}", std::process::id());
+    entry.set_password(&secret).map_err(|e| e.to_string())?;
+    let read = entry.get_password().map(Zeroizing::new);
+    let deleted = entry.delete_credential();
+    match read {
+        Ok(read) if *read == secret => {}
+        Ok(_) => return Err("read back a different secret".to_string()),
+        Err(e) => return Err(e.to_string()),
+    }
+    deleted.map_err(|e| format!("could not delete the test secret: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_what_a_newer_version_wrote() {
+        let data = r#"{
+            "accounts": [
+                { "username": "octo", "email": "o@x.com", "provider": "bitbucket",
+                  "warnings": { "from-the-future": false } }
+            ],
+            "warnings": { "from-the-future": true },
+            "notifications": ["guard", "from-the-future"]
+        }"#;
+        let (mut config, _) = parse_config(data).unwrap();
+        assert_eq!(config.accounts[0].username, "octo");
+        assert_eq!(config.accounts[0].provider, None);
+        assert_eq!(config.notifications.len(), 1);
+
+        config.accounts[0].email = "new@x.com".to_string();
+        let value = file_value(&config, &config.accounts);
+        let account = &value["accounts"][0];
+        assert_eq!(account["email"], "new@x.com");
+        assert_eq!(account["provider"], "bitbucket");
+        assert_eq!(account["warnings"]["from-the-future"], false);
+        assert_eq!(value["warnings"]["from-the-future"], true);
+        assert_eq!(
+            value["notifications"],
+            serde_json::json!(["guard", "from-the-future"])
+        );
+
+        assert!(parse_config(r#"{ "accounts": [{ "username": "octo" }] }"#).is_err());
+    }
+
+    #[test]
+    fn retargets_default_and_bindings() {
+        let mut config = Config {
+            default_account: Some("octo".to_string()),
+            ..Default::default()
+        };
+        config.repos.insert("/a".to_string(), "octo".to_string());
+        config.repos.insert("/b".to_string(), "hub".to_string());
+
+        config.retarget_account("octo", Some("octo:work"));
+        assert_eq!(config.default_account.as_deref(), Some("octo:work"));
+        assert_eq!(config.repos["/a"], "octo:work");
+
+        config.retarget_account("octo:work", None);
+        assert_eq!(config.default_account, None);
+        assert_eq!(config.repos.keys().collect::<Vec<_>>(), ["/b"]);
+    }
+
+    #[test]
+    fn hidden_accounts_go_back_in_place() {
+        let account = |username: &str| Account {
+            username: username.to_string(),
+            ..Default::default()
+        };
+        let config = Config {
+            accounts: vec![account("b"), account("d")],
+            hidden: vec![(0, account("a")), (2, account("c")), (9, account("e"))],
+            ..Default::default()
+        };
+        let names: Vec<String> = with_hidden(&config)
+            .into_iter()
+            .map(|a| a.username)
+            .collect();
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn config_override_names_a_file_or_directory() {
+        let dir = std::env::temp_dir();
+        assert_eq!(config_file(&dir), dir.join("accounts.json"));
+        let file = dir.join("gitas-work.json");
+        assert_eq!(config_file(&file), file);
+        let missing = std::path::Path::new("/nonexistent/gitas/");
+        assert_eq!(
+            config_file(missing),
+            std::path::Path::new("/nonexistent/gitas/accounts.json")
+        );
+    }
 }
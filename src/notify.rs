@@ -0,0 +1,177 @@
+use crate::audit::{Entry, Event};
+use crate::utils::{redact_email, redact_name};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Events that can raise a desktop notification, each turned on separately.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyEvent {
+    /// The identity was switched (list, clone, worktrees)
+    Switch,
+    /// A guard, such as the push check or pre-push hook, caught foreign emails
+    Guard,
+}
+
+impl NotifyEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            NotifyEvent::Switch => "switch",
+            NotifyEvent::Guard => "guard",
+        }
+    }
+
+    fn of(event: Event) -> Option<Self> {
+        match event {
+            Event::Switch => Some(NotifyEvent::Switch),
+            Event::Guard => Some(NotifyEvent::Guard),
+            Event::Push | Event::Token => None,
+        }
+    }
+}
+
+static ENABLED: OnceLock<BTreeSet<NotifyEvent>> = OnceLock::new();
+
+/// Make the config's notification settings apply for this run.
+pub fn init(events: &BTreeSet<NotifyEvent>) {
+    ENABLED.set(events.clone()).ok();
+}
+
+/// Announce an audit entry on the desktop when its event is turned on.
+/// Failures are ignored, like the audit log itself.
+pub fn entry(entry: &Entry) {
+    let Some(event) = NotifyEvent::of(entry.event) else {
+        return;
+    };
+    if ENABLED
+        .get()
+        .is_some_and(|enabled| enabled.contains(&event))
+        && let Some((title, body)) = message(entry)
+    {
+        send(&title, &body).ok();
+    }
+}
+
+/// Title and body for an entry, honouring `--redact`.
+fn message(entry: &Entry) -> Option<(String, String)> {
+    let repo = entry
+        .repo
+        .as_deref()
+        .map(|repo| repo.rsplit(['/', '\\']).next().unwrap_or(repo));
+    let account = redact_name(&entry.account);
+    match entry.event {
+        Event::Switch => {
+            let scope = match (entry.scope.as_deref(), repo) {
+                (Some("global") | None, _) | (_, None) => "globally".to_string(),
+                (Some(_), Some(repo)) => format!("in {}", repo),
+            };
+            Some((
+                format!("gitas: switched to {}", account),
+                format!("{} {}", redact_email(&entry.email), scope),
+            ))
+        }
+        Event::Guard => Some((
+            format!("gitas: commits not by {}", account),
+            match repo {
+                Some(repo) => format!("{}: {}", repo, entry.detail.as_deref().unwrap_or("")),
+                None => entry.detail.clone().unwrap_or_default(),
+            },
+        )),
+        Event::Push | Event::Token => None,
+    }
+}
+
+/// Show a notification with the platform's own tool. Doesn't wait for it;
+/// an error means the tool couldn't be started.
+pub fn send(title: &str, body: &str) -> Result<(), String> {
+    let (program, mut cmd) = command(title, body);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+        .map_err(|e| format!("Could not run {}: {}", program, e))
+}
+
+#[cfg(target_os = "macos")]
+fn command(title: &str, body: &str) -> (&'static str, Command) {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(format!(
+        "display notification {} with title {}",
+        quote(body),
+        quote(title)
+    ));
+    ("osascript", cmd)
+}
+
+#[cfg(windows)]
+fn command(title: &str, body: &str) -> (&'static str, Command) {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let script = format!(
+        "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+         $t = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+         $x = $t.GetElementsByTagName('text'); \
+         $x.Item(0).AppendChild($t.CreateTextNode({})) > $null; \
+         $x.Item(1).AppendChild($t.CreateTextNode({})) > $null; \
+         [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('gitas').Show([Windows.UI.Notifications.ToastNotification]::new($t))",
+        quote(title),
+        quote(body)
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    ("powershell", cmd)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn command(title: &str, body: &str) -> (&'static str, Command) {
+    let mut cmd = Command::new("notify-send");
+    cmd.args(["--app-name=gitas", title, body]);
+    ("notify-send", cmd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Account;
+
+    #[test]
+    fn describes_switches_and_guards() {
+        let account = Account {
+            username: "octo".to_string(),
+            alias: Some("work".to_string()),
+            email: "octo@acme.com".to_string(),
+            ..Default::default()
+        };
+        let switch = Entry {
+            repo: Some("/src/app".to_string()),
+            scope: Some("local".to_string()),
+            ..Entry::new(Event::Switch, &account)
+        };
+        assert_eq!(
+            message(&switch),
+            Some((
+                "gitas: switched to octo:work".to_string(),
+                "octo@acme.com in app".to_string()
+            ))
+        );
+        let global = Entry {
+            scope: Some("global".to_string()),
+            ..switch.clone()
+        };
+        assert_eq!(message(&global).unwrap().1, "octo@acme.com globally");
+
+        let guard = Entry {
+            repo: None,
+            detail: Some("push check (block): 1 foreign email(s) in 1 commit(s)".to_string()),
+            ..Entry::new(Event::Guard, &account)
+        };
+        assert!(message(&guard).unwrap().1.starts_with("push check"));
+        assert_eq!(message(&Entry::new(Event::Token, &account)), None);
+        assert_eq!(NotifyEvent::of(Event::Push), None);
+    }
+}
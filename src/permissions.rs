@@ -0,0 +1,166 @@
+use crate::provider::ProviderKind;
+
+/// What gitas (or git on its behalf) asked the forge to do with a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Clone, fetch or pull over HTTPS.
+    Read,
+    /// Push over HTTPS.
+    Write,
+    Emails,
+    Notifications,
+    /// Pull requests with their reviews and CI status.
+    PullRequests,
+    Repos,
+    SshKeys,
+    GpgKeys,
+}
+
+/// What a token needs for one operation on one forge.
+struct Requirement {
+    /// GitHub fine-grained PAT permission; `None` where those tokens can't
+    /// do the operation at all. Unused on other forges.
+    fine_grained: Option<&'static str>,
+    /// GitHub classic scope, GitLab scope, Gitea scope or Azure DevOps PAT scope.
+    scope: &'static str,
+}
+
+fn requirement(kind: ProviderKind, op: Operation) -> Requirement {
+    let (fine_grained, scope) = match (kind, op) {
+        (ProviderKind::Github, Operation::Read) => {
+            (Some("Repository permissions → Contents: Read-only"), "repo")
+        }
+        (ProviderKind::Github, Operation::Write) => (
+            Some("Repository permissions → Contents: Read and write"),
+            "repo",
+        ),
+        (ProviderKind::Github, Operation::Emails) => (
+            Some("Account permissions → Email addresses: Read-only"),
+            "user:email",
+        ),
+        (ProviderKind::Github, Operation::Notifications) => (None, "notifications"),
+        (ProviderKind::Github, Operation::PullRequests) => (
+            Some("Repository permissions → Pull requests, Commit statuses and Checks: Read-only"),
+            "repo",
+        ),
+        (ProviderKind::Github, Operation::Repos) => {
+            (Some("Repository permissions → Metadata: Read-only"), "repo")
+        }
+        (ProviderKind::Github, Operation::SshKeys) => (
+            Some("Account permissions → Git SSH keys: Read and write"),
+            "write:public_key",
+        ),
+        (ProviderKind::Github, Operation::GpgKeys) => (
+            Some("Account permissions → GPG keys: Read and write"),
+            "write:gpg_key",
+        ),
+
+        (ProviderKind::Gitlab, Operation::Read) => (None, "read_repository"),
+        (ProviderKind::Gitlab, Operation::Write) => (None, "write_repository"),
+        (ProviderKind::Gitlab, Operation::Emails) => (None, "read_user"),
+        (ProviderKind::Gitlab, Operation::SshKeys) => (None, "api"),
+        (ProviderKind::Gitlab, _) => (None, "read_api"),
+
+        (ProviderKind::Gitea, Operation::Write) => (None, "repository: Read and Write"),
+        (ProviderKind::Gitea, Operation::Emails) => (None, "user: Read"),
+        (ProviderKind::Gitea, Operation::Notifications) => (None, "notification: Read"),
+        (ProviderKind::Gitea, Operation::SshKeys) => (None, "user: Read and Write"),
+        (ProviderKind::Gitea, _) => (None, "repository: Read"),
+
+        (ProviderKind::AzureDevops, Operation::Write) => (None, "Code: Read & write"),
+        (ProviderKind::AzureDevops, Operation::Emails) => (None, "User Profile: Read"),
+        (ProviderKind::AzureDevops, _) => (None, "Code: Read"),
+    };
+    Requirement {
+        fine_grained,
+        scope,
+    }
+}
+
+/// One line telling the user what to enable on the token for `op`.
+pub fn advice(kind: ProviderKind, op: Operation) -> String {
+    let needs = requirement(kind, op);
+    match kind {
+        ProviderKind::Github => match needs.fine_grained {
+            Some(permission) => format!(
+                "Fine-grained token: enable {}. Classic token: add the `{}` scope.",
+                permission, needs.scope
+            ),
+            None => format!(
+                "Fine-grained tokens can't do this; use a classic token with the `{}` scope.",
+                needs.scope
+            ),
+        },
+        ProviderKind::Gitlab => format!("The token needs the `{}` scope.", needs.scope),
+        ProviderKind::Gitea => format!("The token needs the {} permission.", needs.scope),
+        ProviderKind::AzureDevops => format!("The PAT needs the {} scope.", needs.scope),
+    }
+}
+
+/// Explain why git failed to reach `repo` (`owner/name`) with the token, by
+/// asking the forge what the token can do with it. `None` when the token has
+/// the access `op` needs, or the forge doesn't say, so the failure lies
+/// elsewhere (a rejected non-fast-forward push or a hook, say).
+pub fn diagnose_git(
+    provider: &dyn crate::provider::Provider,
+    token: &str,
+    repo: &str,
+    op: Operation,
+) -> Option<String> {
+    use crate::provider::RepoAccess;
+    let kind = provider.kind();
+    match provider.repo_access(token, repo) {
+        Ok(RepoAccess::Hidden) => {
+            let access = if kind == ProviderKind::Github {
+                format!(
+                    "For a fine-grained token, also add {} under Repository access. ",
+                    repo
+                )
+            } else {
+                String::new()
+            };
+            Some(format!(
+                "The token can't see {} on {}. {}{}",
+                repo,
+                provider.host(),
+                access,
+                advice(kind, Operation::Read)
+            ))
+        }
+        Ok(RepoAccess::Visible { push: Some(false) }) if op == Operation::Write => Some(format!(
+            "The token can read {} but may not push to it. {}",
+            repo,
+            advice(kind, Operation::Write)
+        )),
+        Ok(RepoAccess::Visible { .. }) => None,
+        Err(e) => Some(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advice_names_the_permission_per_forge() {
+        assert_eq!(
+            advice(ProviderKind::Github, Operation::Write),
+            "Fine-grained token: enable Repository permissions → Contents: Read and write. \
+             Classic token: add the `repo` scope."
+        );
+        assert!(advice(ProviderKind::Github, Operation::Notifications).contains("can't"));
+        assert!(advice(ProviderKind::Github, Operation::GpgKeys).contains("`write:gpg_key`"));
+        assert_eq!(
+            advice(ProviderKind::Gitlab, Operation::Read),
+            "The token needs the `read_repository` scope."
+        );
+        assert_eq!(
+            advice(ProviderKind::Gitea, Operation::SshKeys),
+            "The token needs the user: Read and Write permission."
+        );
+        assert_eq!(
+            advice(ProviderKind::AzureDevops, Operation::Write),
+            "The PAT needs the Code: Read & write scope."
+        );
+    }
+}
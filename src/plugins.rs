@@ -0,0 +1,107 @@
+use crate::models::Account;
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+
+/// User commands run at fixed points, with the account in `GITAS_*` env vars.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Hooks {
+    /// Runs before a switch writes anything; a non-zero exit cancels it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_switch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_switch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_add: Option<String>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_switch.is_none() && self.post_switch.is_none() && self.post_add.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookPoint {
+    PreSwitch,
+    PostSwitch,
+    PostAdd,
+}
+
+impl HookPoint {
+    fn name(self) -> &'static str {
+        match self {
+            HookPoint::PreSwitch => "pre_switch",
+            HookPoint::PostSwitch => "post_switch",
+            HookPoint::PostAdd => "post_add",
+        }
+    }
+
+    fn command(self, hooks: &Hooks) -> Option<&str> {
+        match self {
+            HookPoint::PreSwitch => hooks.pre_switch.as_deref(),
+            HookPoint::PostSwitch => hooks.post_switch.as_deref(),
+            HookPoint::PostAdd => hooks.post_add.as_deref(),
+        }
+    }
+}
+
+/// Run the hook configured for `point`, if any. Output is captured so it
+/// can't scribble over the raw-mode UI; errors carry its stderr.
+pub fn run(
+    hooks: &Hooks,
+    point: HookPoint,
+    account: &Account,
+    scope: Option<&str>,
+) -> Result<(), String> {
+    let Some(command) = point.command(hooks).filter(|c| !c.trim().is_empty()) else {
+        return Ok(());
+    };
+
+    let mut cmd = shell(command);
+    cmd.env("GITAS_HOOK", point.name())
+        .env("GITAS_USERNAME", &account.username)
+        .env("GITAS_EMAIL", &account.email)
+        .env("GITAS_ALIAS", account.alias.as_deref().unwrap_or(""))
+        .env(
+            "GITAS_HOST",
+            account.host.as_deref().unwrap_or("github.com"),
+        )
+        .env("GITAS_SCOPE", scope.unwrap_or(""))
+        .stdin(Stdio::null());
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("{} hook failed to start: {}", point.name(), e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr.lines().last().unwrap_or("").trim();
+    Err(format!(
+        "{} hook exited with {}{}",
+        point.name(),
+        output
+            .status
+            .code()
+            .map_or("a signal".to_string(), |c| format!("status {}", c)),
+        if reason.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", reason)
+        }
+    ))
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
@@ -0,0 +1,142 @@
+use crate::models::Account;
+use std::path::Path;
+
+/// Template suggested for `gitas edit --plus-template`.
+pub const DEFAULT_TEMPLATE: &str = "{local}+{repo}@{domain}";
+
+/// `template` filled in for the account email `email` and the repository
+/// named `repo`: `{local}` and `{domain}` are the parts of the email around
+/// its `@`, `{repo}` the repository name reduced to what addresses allow.
+pub fn render(template: &str, email: &str, repo: &str) -> Result<String, String> {
+    if !template.contains("{repo}") {
+        return Err(format!(
+            "'{}' has no {{repo}}, so every repository would get the same address",
+            template
+        ));
+    }
+    let (local, domain) = email
+        .rsplit_once('@')
+        .ok_or_else(|| format!("'{}' is not an email address", email))?;
+    let address = template
+        .replace("{local}", local)
+        .replace("{domain}", domain)
+        .replace("{repo}", &tag(repo));
+    let valid = address.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty() && !domain.is_empty() && !domain.contains('@')
+    }) && !address.contains(char::is_whitespace);
+    if !valid {
+        return Err(format!(
+            "'{}' does not make an email address ({})",
+            template, address
+        ));
+    }
+    Ok(address)
+}
+
+/// Repository name as an address tag: lowercase letters, digits and single
+/// dashes.
+fn tag(repo: &str) -> String {
+    let mut tag = String::new();
+    for c in repo.trim_end_matches(".git").chars() {
+        if c.is_ascii_alphanumeric() {
+            tag.push(c.to_ascii_lowercase());
+        } else if !tag.is_empty() && !tag.ends_with('-') {
+            tag.push('-');
+        }
+    }
+    tag.trim_end_matches('-').to_string()
+}
+
+/// Give the repository at `toplevel` its own address from the account's
+/// template and record it on the account, along with the repository it was
+/// made for. The tag comes from the default remote's `owner/repo`, or the
+/// directory name without one. None when the account has no template.
+pub fn assign(account: &mut Account, toplevel: &Path) -> Option<Result<String, String>> {
+    let template = account.plus_template.as_deref()?;
+    let name = crate::utils::default_remote_at(toplevel)
+        .and_then(|remote| crate::utils::parse_remote_url(&remote.url))
+        .map(|(_, path)| path)
+        .or_else(|| {
+            toplevel
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+    let dir = toplevel.display().to_string();
+    Some(unused(account, template, &name, &dir).inspect(|address| {
+        account.plus_addresses.insert(address.to_lowercase(), dir);
+    }))
+}
+
+/// The address for `name` in `dir`, numbered (`api-2`, `api-3`, ...) past
+/// any already given to another repository, so none is taken over.
+fn unused(account: &Account, template: &str, name: &str, dir: &str) -> Result<String, String> {
+    let free = |address: &String| {
+        account
+            .plus_addresses
+            .get(&address.to_lowercase())
+            .is_none_or(|owner| owner == dir)
+    };
+    let address = render(template, &account.email, name)?;
+    if free(&address) {
+        return Ok(address);
+    }
+    (2..)
+        .map(|n| render(template, &account.email, &format!("{}-{}", name, n)))
+        .find(|address| address.as_ref().is_ok_and(free))
+        .expect("some numbered address is free")
+}
+
+/// Whether `email` is a plus address made for the account.
+pub fn owns(account: &Account, email: &str) -> bool {
+    account.plus_addresses.contains_key(&email.to_lowercase())
+}
+
+/// Commit email for the account in `dir`: the plus address its repository
+/// was given, otherwise the account's own.
+pub fn email_for(account: &Account, dir: &Path) -> String {
+    crate::utils::git_config_get_at(dir, "user.email")
+        .filter(|email| owns(account, email))
+        .unwrap_or_else(|| account.email.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_tagged_address_per_repository() {
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, "me@example.com", "My_Repo.git").as_deref(),
+            Ok("me+my-repo@example.com")
+        );
+        assert_eq!(
+            render("{repo}.{local}@{domain}", "me@example.com", "api").as_deref(),
+            Ok("api.me@example.com")
+        );
+        assert!(render("{local}@{domain}", "me@example.com", "api").is_err());
+        assert!(render("{local}+{repo}", "me@example.com", "api").is_err());
+
+        let mut account = Account {
+            email: "me@example.com".to_string(),
+            plus_template: Some(DEFAULT_TEMPLATE.to_string()),
+            ..Default::default()
+        };
+        let address = assign(&mut account, Path::new("/src/gitas")).unwrap();
+        assert_eq!(address.as_deref(), Ok("me+gitas@example.com"));
+        assert!(owns(&account, "Me+Gitas@example.com"));
+        assert!(!owns(&account, "me+other@example.com"));
+
+        // A second repository of the same name gets its own address, and
+        // the first keeps its.
+        let other = assign(&mut account, Path::new("/work/gitas")).unwrap();
+        assert_eq!(other.as_deref(), Ok("me+gitas-2@example.com"));
+        let again = assign(&mut account, Path::new("/src/gitas")).unwrap();
+        assert_eq!(again.as_deref(), Ok("me+gitas@example.com"));
+        assert_eq!(account.plus_addresses["me+gitas@example.com"], "/src/gitas");
+        assert_eq!(
+            account.plus_addresses["me+gitas-2@example.com"],
+            "/work/gitas"
+        );
+    }
+}
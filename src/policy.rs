@@ -0,0 +1,68 @@
+use crate::models::{Account, Config};
+use serde::{Deserialize, Serialize};
+
+/// Rules every account in a group must follow.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupPolicy {
+    /// Emails must be at this domain or one of its subdomains.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_domain: Option<String>,
+    /// Accounts must live on this host; `*.example.com` allows any subdomain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+pub fn email_in_domain(email: &str, domain: &str) -> bool {
+    let Some((_, email_domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+    let email_domain = email_domain.to_lowercase();
+    let domain = domain.trim_start_matches('@').to_lowercase();
+    email_domain == domain || email_domain.ends_with(&format!(".{}", domain))
+}
+
+/// Human-readable policy violations for an account; empty when compliant or ungrouped.
+pub fn violations(config: &Config, account: &Account) -> Vec<String> {
+    let Some(group) = account.group.as_deref() else {
+        return Vec::new();
+    };
+    let Some(policy) = config.groups.get(group) else {
+        return Vec::new();
+    };
+
+    let mut problems = Vec::new();
+    if let Some(domain) = &policy.email_domain
+        && !email_in_domain(&account.email, domain)
+    {
+        problems.push(format!(
+            "Email {} is outside group '{}' domain {}",
+            crate::utils::redact_email(&account.email),
+            group,
+            domain
+        ));
+    }
+    if let Some(host) = &policy.host {
+        let account_host = account.host.as_deref().unwrap_or("github.com");
+        if !crate::host::matches(host, account_host) {
+            problems.push(format!(
+                "Host {} is not group '{}' host {}",
+                account_host, group, host
+            ));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_domain_and_subdomains() {
+        assert!(email_in_domain("me@acme.com", "acme.com"));
+        assert!(email_in_domain("me@EU.Acme.com", "@acme.com"));
+        assert!(!email_in_domain("me@notacme.com", "acme.com"));
+        assert!(!email_in_domain("me@acme.com.evil", "acme.com"));
+        assert!(!email_in_domain("not-an-email", "acme.com"));
+    }
+}
@@ -0,0 +1,1166 @@
+use crate::github::{self, TokenInfo};
+use crate::models::Account;
+use crate::permissions::{Operation, advice};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The forge software behind a host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Github,
+    Gitlab,
+    /// Gitea and its fork Forgejo (which runs Codeberg); they share the API.
+    #[serde(alias = "forgejo")]
+    #[value(alias = "forgejo")]
+    Gitea,
+    /// Azure DevOps Services (dev.azure.com, formerly visualstudio.com).
+    #[serde(rename = "azure")]
+    #[value(name = "azure", alias = "ado")]
+    AzureDevops,
+}
+
+impl ProviderKind {
+    /// Every forge, in the order they're offered when detection fails.
+    pub const ALL: [ProviderKind; 4] = [
+        ProviderKind::Github,
+        ProviderKind::Gitlab,
+        ProviderKind::Gitea,
+        ProviderKind::AzureDevops,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProviderKind::Github => "GitHub",
+            ProviderKind::Gitlab => "GitLab",
+            ProviderKind::Gitea => "Gitea/Forgejo",
+            ProviderKind::AzureDevops => "Azure DevOps",
+        }
+    }
+}
+
+pub struct RemoteUser {
+    pub login: String,
+    pub email: Option<String>,
+}
+
+/// Outcome of an interactive browser/device login.
+pub struct LoginResult {
+    pub login: String,
+    pub name: Option<String>,
+    /// Suggested commit email (noreply when available).
+    pub email: String,
+    pub emails: Vec<RemoteEmail>,
+    pub scopes: Option<String>,
+    pub token: String,
+}
+
+pub struct RemoteEmail {
+    pub email: String,
+    pub primary: bool,
+    pub verified: bool,
+}
+
+/// An unread notification (GitHub/Gitea) or pending to-do (GitLab).
+pub struct Notification {
+    pub title: String,
+    pub repo: String,
+    /// Why it arrived, e.g. `review_requested` or `mention`.
+    pub reason: String,
+}
+
+/// Review and CI state of the pull request for a branch.
+pub struct PullStatus {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub draft: bool,
+    /// Latest review state per reviewer, e.g. (`octocat`, `APPROVED`).
+    pub reviews: Vec<(String, String)>,
+    /// CI contexts/check runs and their outcome, e.g. (`build`, `success`).
+    pub checks: Vec<(String, String)>,
+}
+
+/// What a token can do with a repository, as far as the forge says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoAccess {
+    /// The forge answers as if the repository didn't exist.
+    Hidden,
+    /// The token sees it; `push` is None where the forge doesn't say.
+    Visible { push: Option<bool> },
+}
+
+/// Operations gitas needs from a forge. Each host type implements these against
+/// its own REST API so commands don't need to know which forge they talk to.
+pub trait Provider: Send {
+    fn kind(&self) -> ProviderKind;
+
+    fn host(&self) -> &str;
+
+    /// Login a noreply address on this host stands for, e.g. `octocat` for
+    /// `123+octocat@users.noreply.github.com`; None for any other address.
+    fn noreply_login<'e>(&self, _email: &'e str) -> Option<&'e str> {
+        None
+    }
+
+    /// Whether git over HTTPS checks the username sent with a token.
+    fn checks_username(&self) -> bool {
+        true
+    }
+
+    /// Validate a token and report what the forge says about it. Only GitHub
+    /// reports scopes, expiry and rate limits.
+    fn check_token(&self, token: &str) -> Result<TokenInfo, String> {
+        Ok(TokenInfo {
+            login: self.fetch_user(token)?.login,
+            scopes: None,
+            expires_at: None,
+            rate_remaining: None,
+        })
+    }
+
+    /// Check a token about to be stored for `username`: the login it
+    /// authenticates as, or why it doesn't fit.
+    fn verify_token(&self, token: &str, username: &str) -> Result<String, String> {
+        let login = self
+            .fetch_user(token)
+            .map_err(|e| format!("API rejected the token: {}", e))?
+            .login;
+        if !self.checks_username() || login.eq_ignore_ascii_case(username) {
+            Ok(login)
+        } else {
+            Err(format!("Token belongs to '{}', not '{}'", login, username))
+        }
+    }
+
+    /// Usernames git's credential helpers should hold the account's token
+    /// under for `url`, or for the whole host when there is none.
+    fn credential_usernames(&self, account: &Account, _url: Option<&str>) -> Vec<String> {
+        vec![account.username.clone()]
+    }
+
+    /// Page listing the user's tokens, to revoke `token` by hand.
+    fn token_settings_url(&self, token: &str, orgs: &[String]) -> String;
+
+    /// Interactive browser/device login.
+    fn device_login(&self) -> Result<LoginResult, String> {
+        Err(format!(
+            "{} does not support browser login; use a personal access token",
+            self.kind().label()
+        ))
+    }
+
+    fn fetch_user(&self, token: &str) -> Result<RemoteUser, String>;
+
+    fn fetch_emails(&self, token: &str) -> Result<Vec<RemoteEmail>, String>;
+
+    /// Unread notifications for the token's user.
+    fn fetch_notifications(&self, token: &str) -> Result<Vec<Notification>, String>;
+
+    /// Open pull request in `repo` (`owner/name`) whose head is `branch` of
+    /// `head_owner`'s copy (the owner of the fork it was pushed to, or of
+    /// `repo` itself).
+    fn pull_request_status(
+        &self,
+        _token: &str,
+        _repo: &str,
+        _head_owner: &str,
+        _branch: &str,
+    ) -> Result<Option<PullStatus>, String> {
+        Err(format!(
+            "Pull request status is not supported for {} yet",
+            self.kind().label()
+        ))
+    }
+
+    /// Full names (`owner/repo`) of repositories the token can access.
+    fn list_repos(&self, token: &str) -> Result<Vec<String>, String>;
+
+    /// Add a public key to the token's user so SSH remotes accept it.
+    fn upload_ssh_key(&self, token: &str, title: &str, public_key: &str) -> Result<(), String>;
+
+    /// Whether the token can see `repo` (`owner/name`), and push to it. Forges
+    /// answer 404 for private repositories a token has no access to.
+    fn repo_access(&self, token: &str, repo: &str) -> Result<RepoAccess, String>;
+}
+
+pub fn for_host(host: &str, kind: ProviderKind) -> Box<dyn Provider> {
+    let host = host.to_string();
+    match kind {
+        ProviderKind::Github => Box::new(GitHub { host }),
+        ProviderKind::Gitlab => Box::new(GitLab { host }),
+        ProviderKind::Gitea => Box::new(Gitea { host }),
+        ProviderKind::AzureDevops => Box::new(AzureDevops { host }),
+    }
+}
+
+pub fn for_account(account: &Account) -> Box<dyn Provider> {
+    for_host(
+        account.host.as_deref().unwrap_or("github.com"),
+        account.provider.unwrap_or(ProviderKind::Github),
+    )
+}
+
+/// Login the account's token authenticates as, served from cache when recent.
+pub fn token_login(account: &Account, token: &str) -> Result<String, String> {
+    let key = crate::models::make_key(&account.username, account.alias.as_deref());
+    if let Some(login) = crate::cache::token_login(&key, token) {
+        return Ok(login);
+    }
+    let login = for_account(account).fetch_user(token)?.login;
+    crate::cache::store_token_login(&key, token, &login);
+    Ok(login)
+}
+
+/// Usernames to store the account's token under for `url` (or its whole host).
+pub fn credential_usernames(account: &Account, url: Option<&str>) -> Vec<String> {
+    for_account(account).credential_usernames(account, url)
+}
+
+/// A remote's host and path in the form its forge's API and web pages use,
+/// for forges whose SSH or legacy remotes look different. None for the rest.
+pub fn canonical_remote(host: &str, path: &str) -> Option<(String, String)> {
+    AzureDevops::canonical_remote(host, path)
+}
+
+/// Local part of `email` without a numeric `ID+` or `ID-` prefix, as
+/// noreply addresses carry the user id before the login.
+fn strip_user_id(local: &str) -> &str {
+    ['+', '-']
+        .into_iter()
+        .find_map(|separator| {
+            local
+                .split_once(separator)
+                .filter(|(id, _)| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()))
+                .map(|(_, login)| login)
+        })
+        .unwrap_or(local)
+}
+
+/// Local part of `email` when its domain is `domain`.
+fn local_part_at<'e>(email: &'e str, domain: &str) -> Option<&'e str> {
+    email
+        .rsplit_once('@')
+        .filter(|(_, d)| d.eq_ignore_ascii_case(domain))
+        .map(|(local, _)| local)
+}
+
+/// Forge of a public host whose software is known without asking it.
+fn known_kind(host: &str) -> Option<ProviderKind> {
+    match host {
+        "github.com" => Some(ProviderKind::Github),
+        "gitlab.com" => Some(ProviderKind::Gitlab),
+        "codeberg.org" => Some(ProviderKind::Gitea),
+        "dev.azure.com" | "ssh.dev.azure.com" => Some(ProviderKind::AzureDevops),
+        _ if host.ends_with(".visualstudio.com") => Some(ProviderKind::AzureDevops),
+        _ => None,
+    }
+}
+
+/// Probe a host's well-known API roots to work out which forge it runs.
+pub fn detect(host: &str) -> Option<ProviderKind> {
+    if let Some(kind) = known_kind(&crate::host::normalize(host)) {
+        return Some(kind);
+    }
+
+    let agent = github::agent_for(host).ok()?;
+    let status = |path: &str| {
+        agent
+            .get(format!("https://{}{}", host, path))
+            .header("Accept", "application/json")
+            .call()
+            .ok()
+            .map(|r| r.status().as_u16())
+    };
+
+    // GitHub Enterprise Server answers /meta anonymously.
+    if status("/api/v3/meta") == Some(200) {
+        return Some(ProviderKind::Github);
+    }
+    // GitLab requires auth for /version but still identifies itself with a 401.
+    if matches!(status("/api/v4/version"), Some(200 | 401)) {
+        return Some(ProviderKind::Gitlab);
+    }
+    // Forgejo answers here too, as it keeps Gitea's API.
+    if status("/api/v1/version") == Some(200) {
+        return Some(ProviderKind::Gitea);
+    }
+    None
+}
+
+/// Azure DevOps answers a bad token with a 203 and its sign-in page unless
+/// this header asks for a plain 401; other forges ignore it.
+const SUPPRESS_SIGN_IN: &str = "X-TFS-FedAuthRedirect";
+
+/// `need` names the operation so permission errors can say what the token
+/// is missing.
+fn request_json(
+    method: &str,
+    url: &str,
+    auth: (&str, String),
+    body: Option<Value>,
+    need: Option<(ProviderKind, Operation)>,
+) -> Result<Value, String> {
+    let agent = github::agent_for(url.split('/').nth(2).unwrap_or(url))?;
+    let res = match (method, body) {
+        ("POST", Some(body)) => agent
+            .post(url)
+            .header("Accept", "application/json")
+            .header(SUPPRESS_SIGN_IN, "Suppress")
+            .header(auth.0, &auth.1)
+            .send_json(body),
+        _ => agent
+            .get(url)
+            .header("Accept", "application/json")
+            .header(SUPPRESS_SIGN_IN, "Suppress")
+            .header(auth.0, &auth.1)
+            .call(),
+    };
+
+    let Ok(mut r) = res else {
+        return Err(format!("Failed to connect to {}", url));
+    };
+    if let Some(wait) = github::rate_limit_wait(&r) {
+        let host = url.split('/').nth(2).unwrap_or(url);
+        return Err(github::rate_limit_error(host, wait));
+    }
+    let status = r.status().as_u16();
+    match status {
+        200..=299 => r
+            .body_mut()
+            .read_json::<Value>()
+            .map_err(|_| "Failed to parse response".to_string()),
+        401 => Err("Token is invalid or revoked".to_string()),
+        403 | 404 => {
+            let message = if status == 403 {
+                "Token lacks the required permissions"
+            } else {
+                "Not found (or token cannot see it)"
+            };
+            Err(match need {
+                Some((kind, op)) => format!("{}. {}", message, advice(kind, op)),
+                None => message.to_string(),
+            })
+        }
+        422 => Err("Rejected by server (already exists or invalid)".to_string()),
+        code => Err(format!("Unexpected response ({})", code)),
+    }
+}
+
+/// The repository at `url`, or None when the token can't see it.
+fn visible(url: &str, auth: (&str, String)) -> Result<Option<Value>, String> {
+    let res = github::agent_for(url.split('/').nth(2).unwrap_or(url))?
+        .get(url)
+        .header("Accept", "application/json")
+        .header(SUPPRESS_SIGN_IN, "Suppress")
+        .header(auth.0, &auth.1)
+        .call();
+    let Ok(r) = res else {
+        return Err(format!("Failed to connect to {}", url));
+    };
+    if let Some(wait) = github::rate_limit_wait(&r) {
+        let host = url.split('/').nth(2).unwrap_or(url);
+        return Err(github::rate_limit_error(host, wait));
+    }
+    match r.status().as_u16() {
+        200..=299 => Ok(Some(r.into_body().read_json().unwrap_or(Value::Null))),
+        403 | 404 => Ok(None),
+        401 => Err("Token is invalid or revoked".to_string()),
+        code => Err(format!("Unexpected response ({})", code)),
+    }
+}
+
+/// Access from a GitHub or Gitea repository, whose `permissions.push` says
+/// whether the user may push.
+fn access_from_permissions(repo: Option<Value>) -> RepoAccess {
+    match repo {
+        None => RepoAccess::Hidden,
+        Some(repo) => RepoAccess::Visible {
+            push: repo.pointer("/permissions/push").and_then(Value::as_bool),
+        },
+    }
+}
+
+/// `value` percent-encoded for a URL query, e.g. a branch named `fix/a+b`.
+fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn array_field(value: Value, key: &str) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(|i| str_field(i, key)).collect())
+        .unwrap_or_default()
+}
+
+/// Notification threads as returned by GitHub and Gitea, which share a shape.
+fn thread_notifications(items: &Value) -> Vec<Notification> {
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| Notification {
+            title: item
+                .get("subject")
+                .and_then(|s| str_field(s, "title"))
+                .unwrap_or_default(),
+            repo: item
+                .get("repository")
+                .and_then(|r| str_field(r, "full_name"))
+                .unwrap_or_default(),
+            reason: str_field(item, "reason").unwrap_or_default(),
+        })
+        .collect()
+}
+
+struct GitHub {
+    host: String,
+}
+
+impl GitHub {
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", github::api_base(&self.host), path)
+    }
+
+    fn auth(token: &str) -> (&'static str, String) {
+        ("Authorization", format!("Bearer {}", token))
+    }
+}
+
+impl Provider for GitHub {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Github
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+
+    fn noreply_login<'e>(&self, email: &'e str) -> Option<&'e str> {
+        if !github::is_noreply(&self.host, email) {
+            return None;
+        }
+        let local = email.rsplit_once('@')?.0;
+        Some(local.rsplit_once('+').map_or(local, |(_, login)| login))
+    }
+
+    fn check_token(&self, token: &str) -> Result<TokenInfo, String> {
+        github::check_token(&self.host, token)
+    }
+
+    fn token_settings_url(&self, token: &str, _orgs: &[String]) -> String {
+        // gitas' own OAuth login shows up as an app authorization.
+        if token.starts_with("gho_") {
+            format!("https://{}/settings/applications", self.host)
+        } else {
+            format!("https://{}/settings/tokens", self.host)
+        }
+    }
+
+    fn device_login(&self) -> Result<LoginResult, String> {
+        if self.host != "github.com" {
+            return Err("Browser login is only available for github.com".to_string());
+        }
+        github::login().ok_or_else(|| "Login failed".to_string())
+    }
+
+    fn fetch_user(&self, token: &str) -> Result<RemoteUser, String> {
+        let user = request_json("GET", &self.url("/user"), Self::auth(token), None, None)?;
+        Ok(RemoteUser {
+            login: str_field(&user, "login").unwrap_or_default(),
+            email: str_field(&user, "email"),
+        })
+    }
+
+    fn fetch_emails(&self, token: &str) -> Result<Vec<RemoteEmail>, String> {
+        let emails = request_json(
+            "GET",
+            &self.url("/user/emails"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Emails)),
+        )?;
+        let mut emails: Vec<RemoteEmail> = emails
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| {
+                Some(RemoteEmail {
+                    email: str_field(e, "email")?,
+                    primary: e.get("primary").and_then(Value::as_bool).unwrap_or(false),
+                    verified: e.get("verified").and_then(Value::as_bool).unwrap_or(false),
+                })
+            })
+            .collect();
+        // Enterprise Server doesn't list the noreply address; offer it anyway.
+        if !emails
+            .iter()
+            .any(|e| github::is_noreply(&self.host, &e.email))
+            && let Ok(user) = request_json("GET", &self.url("/user"), Self::auth(token), None, None)
+            && let (Some(id), Some(login)) = (
+                user.get("id").and_then(Value::as_u64),
+                str_field(&user, "login"),
+            )
+        {
+            emails.push(RemoteEmail {
+                email: github::noreply_email(&self.host, id, &login),
+                primary: false,
+                verified: true,
+            });
+        }
+        Ok(emails)
+    }
+
+    fn fetch_notifications(&self, token: &str) -> Result<Vec<Notification>, String> {
+        let items = request_json(
+            "GET",
+            &self.url("/notifications?per_page=50"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Notifications)),
+        )?;
+        Ok(thread_notifications(&items))
+    }
+
+    fn pull_request_status(
+        &self,
+        token: &str,
+        repo: &str,
+        head_owner: &str,
+        branch: &str,
+    ) -> Result<Option<PullStatus>, String> {
+        let get = |path: String| {
+            request_json(
+                "GET",
+                &self.url(&path),
+                Self::auth(token),
+                None,
+                Some((self.kind(), Operation::PullRequests)),
+            )
+        };
+
+        // Forks often share branch names like `main`, so filter on the owner too.
+        let pulls = get(format!(
+            "/repos/{}/pulls?state=open&head={}:{}&per_page=100",
+            repo,
+            encode_query(head_owner),
+            encode_query(branch)
+        ))?;
+        let Some(pull) = pulls.as_array().into_iter().flatten().next() else {
+            return Ok(None);
+        };
+
+        let number = pull
+            .get("number")
+            .and_then(Value::as_u64)
+            .unwrap_or_default();
+        let sha = pull
+            .get("head")
+            .and_then(|h| str_field(h, "sha"))
+            .unwrap_or_default();
+
+        let mut reviews: Vec<(String, String)> = Vec::new();
+        let review_list = get(format!(
+            "/repos/{}/pulls/{}/reviews?per_page=100",
+            repo, number
+        ))?;
+        for review in review_list.as_array().into_iter().flatten() {
+            let Some(user) = review.get("user").and_then(|u| str_field(u, "login")) else {
+                continue;
+            };
+            let state = str_field(review, "state").unwrap_or_default();
+            if state == "COMMENTED" {
+                continue;
+            }
+            // Reviews come oldest first; keep each reviewer's latest verdict.
+            reviews.retain(|(login, _)| login != &user);
+            reviews.push((user, state));
+        }
+
+        let mut checks = Vec::new();
+        let status = get(format!("/repos/{}/commits/{}/status", repo, sha))?;
+        for entry in status
+            .get("statuses")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let (Some(context), Some(state)) =
+                (str_field(entry, "context"), str_field(entry, "state"))
+            {
+                checks.push((context, state));
+            }
+        }
+        let runs = get(format!(
+            "/repos/{}/commits/{}/check-runs?per_page=100",
+            repo, sha
+        ))?;
+        for run in runs
+            .get("check_runs")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let name = str_field(run, "name").unwrap_or_default();
+            let outcome = str_field(run, "conclusion")
+                .or_else(|| str_field(run, "status"))
+                .unwrap_or_default();
+            checks.push((name, outcome));
+        }
+
+        Ok(Some(PullStatus {
+            number,
+            title: str_field(pull, "title").unwrap_or_default(),
+            url: str_field(pull, "html_url").unwrap_or_default(),
+            draft: pull.get("draft").and_then(Value::as_bool).unwrap_or(false),
+            reviews,
+            checks,
+        }))
+    }
+
+    fn list_repos(&self, token: &str) -> Result<Vec<String>, String> {
+        let repos = request_json(
+            "GET",
+            &self.url("/user/repos?per_page=100"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Repos)),
+        )?;
+        Ok(array_field(repos, "full_name"))
+    }
+
+    fn upload_ssh_key(&self, token: &str, title: &str, public_key: &str) -> Result<(), String> {
+        let body = serde_json::json!({ "title": title, "key": public_key });
+        request_json(
+            "POST",
+            &self.url("/user/keys"),
+            Self::auth(token),
+            Some(body),
+            Some((self.kind(), Operation::SshKeys)),
+        )
+        .map(|_| ())
+    }
+
+    fn repo_access(&self, token: &str, repo: &str) -> Result<RepoAccess, String> {
+        visible(&self.url(&format!("/repos/{}", repo)), Self::auth(token))
+            .map(access_from_permissions)
+    }
+}
+
+struct GitLab {
+    host: String,
+}
+
+impl GitLab {
+    fn url(&self, path: &str) -> String {
+        format!("https://{}/api/v4{}", self.host, path)
+    }
+
+    fn auth(token: &str) -> (&'static str, String) {
+        ("PRIVATE-TOKEN", token.to_string())
+    }
+}
+
+impl Provider for GitLab {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Gitlab
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// `ID-login@users.noreply.<host>`.
+    fn noreply_login<'e>(&self, email: &'e str) -> Option<&'e str> {
+        local_part_at(email, &format!("users.noreply.{}", self.host)).map(strip_user_id)
+    }
+
+    fn token_settings_url(&self, _token: &str, _orgs: &[String]) -> String {
+        format!(
+            "https://{}/-/user_settings/personal_access_tokens",
+            self.host
+        )
+    }
+
+    fn fetch_user(&self, token: &str) -> Result<RemoteUser, String> {
+        let user = request_json("GET", &self.url("/user"), Self::auth(token), None, None)?;
+        Ok(RemoteUser {
+            login: str_field(&user, "username").unwrap_or_default(),
+            email: str_field(&user, "public_email").or_else(|| str_field(&user, "email")),
+        })
+    }
+
+    fn fetch_emails(&self, token: &str) -> Result<Vec<RemoteEmail>, String> {
+        let primary = self.fetch_user(token)?.email;
+        let emails = request_json(
+            "GET",
+            &self.url("/user/emails"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Emails)),
+        )?;
+        Ok(emails
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| {
+                let email = str_field(e, "email")?;
+                Some(RemoteEmail {
+                    primary: primary.as_deref() == Some(email.as_str()),
+                    verified: e.get("confirmed_at").is_some_and(|v| !v.is_null()),
+                    email,
+                })
+            })
+            .collect())
+    }
+
+    fn fetch_notifications(&self, token: &str) -> Result<Vec<Notification>, String> {
+        let todos = request_json(
+            "GET",
+            &self.url("/todos?state=pending&per_page=50"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Notifications)),
+        )?;
+        Ok(todos
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|todo| Notification {
+                title: todo
+                    .get("target")
+                    .and_then(|t| str_field(t, "title"))
+                    .or_else(|| str_field(todo, "body"))
+                    .unwrap_or_default(),
+                repo: todo
+                    .get("project")
+                    .and_then(|p| str_field(p, "path_with_namespace"))
+                    .unwrap_or_default(),
+                reason: str_field(todo, "action_name").unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    fn list_repos(&self, token: &str) -> Result<Vec<String>, String> {
+        let projects = request_json(
+            "GET",
+            &self.url("/projects?membership=true&per_page=100"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Repos)),
+        )?;
+        Ok(array_field(projects, "path_with_namespace"))
+    }
+
+    fn upload_ssh_key(&self, token: &str, title: &str, public_key: &str) -> Result<(), String> {
+        let body = serde_json::json!({ "title": title, "key": public_key });
+        request_json(
+            "POST",
+            &self.url("/user/keys"),
+            Self::auth(token),
+            Some(body),
+            Some((self.kind(), Operation::SshKeys)),
+        )
+        .map(|_| ())
+    }
+
+    fn repo_access(&self, token: &str, repo: &str) -> Result<RepoAccess, String> {
+        let id = repo.replace('/', "%2F");
+        let project = visible(&self.url(&format!("/projects/{}", id)), Self::auth(token))?;
+        // Developers (30) and up may push; members are listed per project or
+        // through a group.
+        Ok(match project {
+            None => RepoAccess::Hidden,
+            Some(project) => RepoAccess::Visible {
+                push: project.get("permissions").map(|permissions| {
+                    ["project_access", "group_access"].iter().any(|level| {
+                        permissions
+                            .pointer(&format!("/{}/access_level", level))
+                            .and_then(Value::as_u64)
+                            .is_some_and(|level| level >= 30)
+                    })
+                }),
+            },
+        })
+    }
+}
+
+struct Gitea {
+    host: String,
+}
+
+impl Gitea {
+    fn url(&self, path: &str) -> String {
+        format!("https://{}/api/v1{}", self.host, path)
+    }
+
+    fn auth(token: &str) -> (&'static str, String) {
+        ("Authorization", format!("token {}", token))
+    }
+}
+
+impl Provider for Gitea {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Gitea
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// `login@noreply.<host>`, without an id.
+    fn noreply_login<'e>(&self, email: &'e str) -> Option<&'e str> {
+        local_part_at(email, &format!("noreply.{}", self.host))
+    }
+
+    fn token_settings_url(&self, _token: &str, _orgs: &[String]) -> String {
+        format!("https://{}/user/settings/applications", self.host)
+    }
+
+    fn fetch_user(&self, token: &str) -> Result<RemoteUser, String> {
+        let user = request_json("GET", &self.url("/user"), Self::auth(token), None, None)?;
+        Ok(RemoteUser {
+            login: str_field(&user, "login").unwrap_or_default(),
+            email: str_field(&user, "email"),
+        })
+    }
+
+    fn fetch_emails(&self, token: &str) -> Result<Vec<RemoteEmail>, String> {
+        let emails = request_json(
+            "GET",
+            &self.url("/user/emails"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Emails)),
+        )?;
+        Ok(emails
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| {
+                Some(RemoteEmail {
+                    email: str_field(e, "email")?,
+                    primary: e.get("primary").and_then(Value::as_bool).unwrap_or(false),
+                    verified: e.get("verified").and_then(Value::as_bool).unwrap_or(false),
+                })
+            })
+            .collect())
+    }
+
+    fn fetch_notifications(&self, token: &str) -> Result<Vec<Notification>, String> {
+        let items = request_json(
+            "GET",
+            &self.url("/notifications?status-types=unread&limit=50"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Notifications)),
+        )?;
+        Ok(thread_notifications(&items))
+    }
+
+    fn list_repos(&self, token: &str) -> Result<Vec<String>, String> {
+        let repos = request_json(
+            "GET",
+            &self.url("/user/repos?limit=50"),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Repos)),
+        )?;
+        Ok(array_field(repos, "full_name"))
+    }
+
+    fn upload_ssh_key(&self, token: &str, title: &str, public_key: &str) -> Result<(), String> {
+        let body = serde_json::json!({ "title": title, "key": public_key });
+        request_json(
+            "POST",
+            &self.url("/user/keys"),
+            Self::auth(token),
+            Some(body),
+            Some((self.kind(), Operation::SshKeys)),
+        )
+        .map(|_| ())
+    }
+
+    fn repo_access(&self, token: &str, repo: &str) -> Result<RepoAccess, String> {
+        visible(&self.url(&format!("/repos/{}", repo)), Self::auth(token))
+            .map(access_from_permissions)
+    }
+}
+
+struct AzureDevops {
+    host: String,
+}
+
+/// Profile and organization APIs live on this host whichever host the
+/// repositories are on.
+const AZURE_PROFILE_HOST: &str = "app.vssps.visualstudio.com";
+
+impl AzureDevops {
+    /// SSH (`ssh.dev.azure.com:v3/org/project/repo`) and legacy
+    /// `org.visualstudio.com/project/_git/repo` remotes in their HTTPS form,
+    /// `dev.azure.com` and `org/project/_git/repo`.
+    fn canonical_remote(host: &str, path: &str) -> Option<(String, String)> {
+        let host = host.to_lowercase();
+        let path = match host.as_str() {
+            "ssh.dev.azure.com" | "vs-ssh.visualstudio.com" => {
+                let parts: Vec<&str> = path.split('/').collect();
+                match parts.as_slice() {
+                    ["v3", org, project, name] => format!("{}/{}/_git/{}", org, project, name),
+                    _ => return None,
+                }
+            }
+            _ => {
+                let org = host.strip_suffix(".visualstudio.com")?;
+                let path = path.strip_prefix("DefaultCollection/").unwrap_or(path);
+                format!("{}/{}", org, path)
+            }
+        };
+        Some(("dev.azure.com".to_string(), path))
+    }
+
+    /// URL under an organization, e.g. `https://dev.azure.com/fabrikam/_apis/...`.
+    fn url(&self, org: &str, path: &str) -> String {
+        format!("https://{}/{}{}", self.host, org, path)
+    }
+
+    /// PATs go in as the password of basic auth; the username is ignored.
+    fn auth(token: &str) -> (&'static str, String) {
+        use base64::Engine;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(format!(":{}", token));
+        ("Authorization", format!("Basic {}", encoded))
+    }
+
+    fn profile(&self, token: &str) -> Result<Value, String> {
+        request_json(
+            "GET",
+            &format!(
+                "https://{}/_apis/profile/profiles/me?api-version=7.1",
+                AZURE_PROFILE_HOST
+            ),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Emails)),
+        )
+    }
+}
+
+impl Provider for AzureDevops {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::AzureDevops
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Any name works with a PAT, and users are email addresses anyway.
+    fn checks_username(&self) -> bool {
+        false
+    }
+
+    /// Remotes carry the organization as their username
+    /// (`https://org@dev.azure.com/...`) and git looks credentials up by it,
+    /// so tokens are stored under the URL's username, or each of the
+    /// account's `orgs` for the whole host.
+    fn credential_usernames(&self, account: &Account, url: Option<&str>) -> Vec<String> {
+        let from_url = url
+            .filter(|url| crate::utils::is_http_url(url))
+            .and_then(|url| url.split_once("://"))
+            .and_then(|(_, rest)| rest.split('/').next()?.rsplit_once('@'))
+            .map(|(user, _)| user.split(':').next().unwrap_or(user).to_string())
+            .filter(|user| !user.is_empty());
+        if let Some(user) = from_url {
+            return vec![user];
+        }
+        let mut names = vec![account.username.clone()];
+        if url.is_none() {
+            names.extend(account.orgs.iter().cloned());
+        }
+        names
+    }
+
+    /// PATs are listed per organization.
+    fn token_settings_url(&self, _token: &str, orgs: &[String]) -> String {
+        match orgs.first() {
+            Some(org) => self.url(org, "/_usersSettings/tokens"),
+            None => "https://aex.dev.azure.com/me".to_string(),
+        }
+    }
+
+    fn fetch_user(&self, token: &str) -> Result<RemoteUser, String> {
+        let profile = self.profile(token)?;
+        let email = str_field(&profile, "emailAddress");
+        Ok(RemoteUser {
+            login: email.clone().unwrap_or_default(),
+            email,
+        })
+    }
+
+    fn fetch_emails(&self, token: &str) -> Result<Vec<RemoteEmail>, String> {
+        Ok(self
+            .fetch_user(token)?
+            .email
+            .map(|email| RemoteEmail {
+                email,
+                primary: true,
+                verified: true,
+            })
+            .into_iter()
+            .collect())
+    }
+
+    fn fetch_notifications(&self, _token: &str) -> Result<Vec<Notification>, String> {
+        Err("Azure DevOps has no notifications API".to_string())
+    }
+
+    fn list_repos(&self, token: &str) -> Result<Vec<String>, String> {
+        let member = str_field(&self.profile(token)?, "id").unwrap_or_default();
+        let orgs = request_json(
+            "GET",
+            &format!(
+                "https://{}/_apis/accounts?memberId={}&api-version=7.1",
+                AZURE_PROFILE_HOST, member
+            ),
+            Self::auth(token),
+            None,
+            Some((self.kind(), Operation::Repos)),
+        )?;
+        let mut repos = Vec::new();
+        for org in orgs
+            .get("value")
+            .map(|v| array_field(v.clone(), "accountName"))
+            .unwrap_or_default()
+        {
+            let found = request_json(
+                "GET",
+                &self.url(&org, "/_apis/git/repositories?api-version=7.1"),
+                Self::auth(token),
+                None,
+                Some((self.kind(), Operation::Repos)),
+            )?;
+            repos.extend(
+                found
+                    .get("value")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|repo| {
+                        let project = repo.get("project").and_then(|p| str_field(p, "name"))?;
+                        Some(format!(
+                            "{}/{}/_git/{}",
+                            org,
+                            project,
+                            str_field(repo, "name")?
+                        ))
+                    }),
+            );
+        }
+        Ok(repos)
+    }
+
+    fn upload_ssh_key(&self, _token: &str, _title: &str, _public_key: &str) -> Result<(), String> {
+        Err(
+            "Azure DevOps has no API for SSH keys; add it under User settings → SSH public keys"
+                .to_string(),
+        )
+    }
+
+    /// `repo` is `org/project/_git/name`, as `parse_remote_url` gives it.
+    fn repo_access(&self, token: &str, repo: &str) -> Result<RepoAccess, String> {
+        let Some((org, project, name)) = azure_repo(repo) else {
+            return Err(format!("{} is not an Azure DevOps repository path", repo));
+        };
+        visible(
+            &self.url(
+                org,
+                &format!(
+                    "/{}/_apis/git/repositories/{}?api-version=7.1",
+                    project, name
+                ),
+            ),
+            Self::auth(token),
+        )
+        .map(|repo| match repo {
+            None => RepoAccess::Hidden,
+            // Branch policies decide who may push; the repository doesn't say.
+            Some(_) => RepoAccess::Visible { push: None },
+        })
+    }
+}
+
+/// `(org, project, name)` of an `org/project/_git/name` path.
+fn azure_repo(repo: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = repo.split('/');
+    match (parts.next()?, parts.next()?, parts.next()?, parts.next()?) {
+        (org, project, "_git", name) if parts.next().is_none() => Some((org, project, name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forges_map_noreply_addresses_and_credentials() {
+        let github = for_host("github.com", ProviderKind::Github);
+        assert_eq!(
+            github.noreply_login("123+octo@users.noreply.github.com"),
+            Some("octo")
+        );
+        assert_eq!(github.noreply_login("octo@example.com"), None);
+        let gitlab = for_host("gitlab.com", ProviderKind::Gitlab);
+        assert_eq!(
+            gitlab.noreply_login("42-octo-cat@users.noreply.gitlab.com"),
+            Some("octo-cat")
+        );
+        let gitea = for_host("codeberg.org", ProviderKind::Gitea);
+        assert_eq!(
+            gitea.noreply_login("octo@noreply.codeberg.org"),
+            Some("octo")
+        );
+        assert!(!for_host("dev.azure.com", ProviderKind::AzureDevops).checks_username());
+
+        let account = Account {
+            username: "octo".to_string(),
+            provider: Some(ProviderKind::AzureDevops),
+            orgs: vec!["fabrikam".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            credential_usernames(
+                &account,
+                Some("https://fabrikam@dev.azure.com/fabrikam/web/_git/app")
+            ),
+            ["fabrikam"]
+        );
+        assert_eq!(credential_usernames(&account, None), ["octo", "fabrikam"]);
+        assert_eq!(
+            credential_usernames(&Account::default(), Some("https://x@github.com/o/r")),
+            [""]
+        );
+        assert_eq!(canonical_remote("github.com", "o/r"), None);
+    }
+
+    #[test]
+    fn encodes_branch_names_for_queries() {
+        assert_eq!(encode_query("fix/a+b #1"), "fix%2Fa%2Bb%20%231");
+        assert_eq!(encode_query("octo-cat_1.x"), "octo-cat_1.x");
+    }
+}
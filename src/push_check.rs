@@ -0,0 +1,339 @@
+use crate::models::Account;
+use crate::utils::{git_config_get, redact_email, redact_name};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Environment variable that skips the check for a single push.
+pub const SKIP_ENV: &str = "GITAS_SKIP_PUSH_CHECK";
+
+/// What to do when outgoing commits carry emails the account doesn't own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PushCheckMode {
+    Warn,
+    Block,
+}
+
+pub struct Commit {
+    pub sha: String,
+    pub author_email: String,
+    pub committer_email: String,
+    pub summary: String,
+}
+
+pub struct Violation {
+    pub commit: String,
+    pub summary: String,
+    pub role: &'static str,
+    pub email: String,
+}
+
+/// Whether an email plausibly belongs to the account: its configured address,
+/// a plus address gitas made for it, or a noreply address its forge issues
+/// for the account's login.
+pub fn account_owns_email(account: &Account, email: &str) -> bool {
+    if email.eq_ignore_ascii_case(&account.email) || crate::plus::owns(account, email) {
+        return true;
+    }
+    crate::provider::for_account(account)
+        .noreply_login(email)
+        .is_some_and(|login| login.eq_ignore_ascii_case(&account.username))
+}
+
+/// Commits reachable from `revs` but not from any ref in `exclude`.
+pub fn commits_in_range(revs: &[String], exclude: &[String]) -> Result<Vec<Commit>, String> {
+    let mut cmd = Command::new("git");
+    cmd.args(["log", "--format=%H%x00%ae%x00%ce%x00%s"]);
+    cmd.args(revs);
+    if !exclude.is_empty() {
+        cmd.arg("--not").args(exclude);
+    }
+    cmd.arg("--");
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Could not run git log: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git log {} failed: {}",
+            revs.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\0');
+            Some(Commit {
+                sha: parts.next()?.to_string(),
+                author_email: parts.next()?.to_string(),
+                committer_email: parts.next()?.to_string(),
+                summary: parts.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect())
+}
+
+pub fn find_violations(account: &Account, commits: &[Commit]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for commit in commits {
+        for (role, email) in [
+            ("author", &commit.author_email),
+            ("committer", &commit.committer_email),
+        ] {
+            if !account_owns_email(account, email) {
+                violations.push(Violation {
+                    commit: commit.sha.chars().take(10).collect(),
+                    summary: commit.summary.clone(),
+                    role,
+                    email: email.clone(),
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// Revisions a `git push <args>` invocation would send, and the remote-tracking
+/// refs to exclude. Falls back to HEAD against all remotes when unsure.
+pub fn push_range(push_args: &[String]) -> (Vec<String>, Vec<String>) {
+    let positional = positional_args(push_args);
+
+    let exclude = match positional.first() {
+        Some(remote) if !remote.contains('/') && !remote.contains(':') => {
+            vec![format!("--remotes={}", remote)]
+        }
+        _ => vec!["--remotes".to_string()],
+    };
+
+    let revs: Vec<String> = positional
+        .iter()
+        .skip(1)
+        .filter_map(|refspec| {
+            let source = refspec.trim_start_matches('+');
+            let source = source.split_once(':').map_or(source, |(src, _)| src);
+            (!source.is_empty()).then(|| source.to_string())
+        })
+        .collect();
+
+    if revs.is_empty() {
+        (vec!["HEAD".to_string()], exclude)
+    } else {
+        (revs, exclude)
+    }
+}
+
+/// `git push` options whose value may follow as the next argument.
+const OPTIONS_WITH_VALUE: [&str; 5] = ["-o", "--push-option", "--repo", "--receive-pack", "--exec"];
+
+fn positional_args(push_args: &[String]) -> Vec<&str> {
+    let mut positional = Vec::new();
+    let mut args = push_args.iter().map(String::as_str);
+    while let Some(arg) = args.next() {
+        if OPTIONS_WITH_VALUE.contains(&arg) {
+            args.next();
+        } else if !arg.starts_with('-') {
+            positional.push(arg);
+        }
+    }
+    positional
+}
+
+/// Remote and refspecs a `git push <args>` invocation targets, filling in the
+/// current branch and its push remote when they're implied.
+pub fn push_target(push_args: &[String]) -> (String, Vec<String>) {
+    let positional = positional_args(push_args);
+    let branch = crate::utils::git_current_branch();
+
+    let remote = match positional.first() {
+        Some(remote) => remote.to_string(),
+        None => branch
+            .as_deref()
+            .and_then(|b| git_config_get(&format!("branch.{}.pushRemote", b), ""))
+            .or_else(|| git_config_get("remote.pushDefault", ""))
+            .or_else(|| {
+                branch
+                    .as_deref()
+                    .and_then(|b| git_config_get(&format!("branch.{}.remote", b), ""))
+            })
+            .unwrap_or_else(|| "origin".to_string()),
+    };
+
+    let mut refspecs: Vec<String> = positional.iter().skip(1).map(|s| s.to_string()).collect();
+    if refspecs.is_empty()
+        && let Some(branch) = branch
+    {
+        refspecs.push(branch);
+    }
+    (remote, refspecs)
+}
+
+/// Scan the range and report problems. Returns false when the push should stop.
+pub fn check(account: &Account, revs: &[String], exclude: &[String], mode: PushCheckMode) -> bool {
+    if std::env::var_os(SKIP_ENV).is_some() {
+        return true;
+    }
+
+    // An unreadable range is not a clean one: blocking fails closed.
+    let commits = match commits_in_range(revs, exclude) {
+        Ok(commits) => commits,
+        Err(e) if mode == PushCheckMode::Block => {
+            eprintln!(
+                "\n  {} Push blocked: outgoing commits could not be checked ({}).",
+                "✗".red().bold(),
+                e
+            );
+            eprintln!(
+                "  Set {} to push anyway.\n",
+                format!("{}=1", SKIP_ENV).cyan()
+            );
+            return false;
+        }
+        Err(e) => {
+            eprintln!(
+                "\n  {} Outgoing commits could not be checked ({}).\n",
+                "⚠".yellow().bold(),
+                e
+            );
+            return true;
+        }
+    };
+    let violations = find_violations(account, &commits);
+    if violations.is_empty() {
+        return true;
+    }
+
+    crate::audit::record(&crate::audit::Entry {
+        detail: Some(format!(
+            "push check ({}): {} foreign email(s) in {} commit(s)",
+            match mode {
+                PushCheckMode::Warn => "warn",
+                PushCheckMode::Block => "block",
+            },
+            violations.len(),
+            commits.len()
+        )),
+        ..crate::audit::Entry::new(crate::audit::Event::Guard, account)
+    });
+
+    let (marker, heading) = match mode {
+        PushCheckMode::Warn => ("⚠".yellow().bold(), "Outgoing commits use emails".yellow()),
+        PushCheckMode::Block => (
+            "✗".red().bold(),
+            "Push blocked: outgoing commits use emails".red(),
+        ),
+    };
+    eprintln!(
+        "\n  {} {} not belonging to {} <{}>:",
+        marker,
+        heading,
+        redact_name(&account.username).cyan(),
+        redact_email(&account.email)
+    );
+    for violation in &violations {
+        eprintln!(
+            "    {} {} {} {}",
+            violation.commit.dimmed(),
+            format!("{}:", violation.role).dimmed(),
+            redact_email(&violation.email).yellow(),
+            violation.summary.dimmed()
+        );
+    }
+
+    if mode == PushCheckMode::Block {
+        eprintln!(
+            "\n  Rewrite them (e.g. {}) or set {} to push anyway.\n",
+            "git commit --amend --no-edit --reset-author".cyan(),
+            format!("{}=1", SKIP_ENV).cyan()
+        );
+        false
+    } else {
+        eprintln!();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account() -> Account {
+        Account {
+            username: "octocat".to_string(),
+            email: "octo@work.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_configured_and_noreply_emails() {
+        let account = account();
+        assert!(account_owns_email(&account, "Octo@Work.com"));
+        assert!(account_owns_email(
+            &account,
+            "123+octocat@users.noreply.github.com"
+        ));
+        assert!(!account_owns_email(
+            &account,
+            "123+octocat@noreply.ghe.corp"
+        ));
+        assert!(!account_owns_email(&account, "octo@personal.dev"));
+        assert!(!account_owns_email(
+            &account,
+            "123+someone@users.noreply.github.com"
+        ));
+
+        let enterprise = Account {
+            host: Some("ghe.corp".to_string()),
+            ..account
+        };
+        assert!(account_owns_email(
+            &enterprise,
+            "123+octocat@noreply.ghe.corp"
+        ));
+        assert!(!account_owns_email(
+            &enterprise,
+            "123+octocat@users.noreply.github.com"
+        ));
+
+        let codeberg = Account {
+            host: Some("codeberg.org".to_string()),
+            provider: Some(crate::provider::ProviderKind::Gitea),
+            ..enterprise
+        };
+        assert!(account_owns_email(
+            &codeberg,
+            "OctoCat@noreply.codeberg.org"
+        ));
+        assert!(!account_owns_email(
+            &codeberg,
+            "1+octocat@noreply.codeberg.org"
+        ));
+    }
+
+    #[test]
+    fn derives_push_range_from_refspecs() {
+        let args: Vec<String> = ["origin", "+feature:main", "--force"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (revs, exclude) = push_range(&args);
+        assert_eq!(revs, vec!["feature"]);
+        assert_eq!(exclude, vec!["--remotes=origin"]);
+
+        let (revs, exclude) = push_range(&[]);
+        assert_eq!(revs, vec!["HEAD"]);
+        assert_eq!(exclude, vec!["--remotes"]);
+
+        // Option values are not the remote or a refspec.
+        let args: Vec<String> = ["-o", "ci.skip", "--repo", "fork", "origin", "topic"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (revs, exclude) = push_range(&args);
+        assert_eq!(revs, vec!["topic"]);
+        assert_eq!(exclude, vec!["--remotes=origin"]);
+    }
+}
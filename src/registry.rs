@@ -0,0 +1,411 @@
+use crate::models::Account;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Package registry credentials that follow an account on switch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageSync {
+    /// Write the token to `~/.npmrc` for the host's npm registry.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub npm: bool,
+    /// Cargo registry (as named in `.cargo/config.toml`) whose token in
+    /// `~/.cargo/credentials.toml` is replaced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cargo_registry: Option<String>,
+    /// `<server>` id in `~/.m2/settings.xml` (e.g. `github`) to fill in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maven_server: Option<String>,
+    /// Set `gpr.user`/`gpr.key` in `~/.gradle/gradle.properties`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gradle: bool,
+}
+
+impl PackageSync {
+    pub fn is_empty(&self) -> bool {
+        *self == PackageSync::default()
+    }
+}
+
+/// npm registry serving GitHub Packages for a host.
+pub fn npm_registry(host: &str) -> String {
+    if host == "github.com" {
+        "npm.pkg.github.com".to_string()
+    } else {
+        format!("npm.{}", host)
+    }
+}
+
+/// A pending change to one registry config file.
+pub struct FileEdit {
+    pub path: PathBuf,
+    pub label: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Changes every enabled registry config needs to carry the account's token.
+pub fn plan(account: &Account, token: &str) -> Result<Vec<FileEdit>, String> {
+    let sync = &account.packages;
+    let home = dirs::home_dir().ok_or("Cannot locate the home directory")?;
+    let mut edits = Vec::new();
+    let mut add = |path: PathBuf, label: String, edit: &dyn Fn(&str) -> String| {
+        let before = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        let after = edit(&before);
+        edits.push(FileEdit {
+            path,
+            label,
+            before,
+            after,
+        });
+        Ok(())
+    };
+
+    if sync.npm {
+        let registry = npm_registry(account.host.as_deref().unwrap_or("github.com"));
+        add(
+            home.join(".npmrc"),
+            format!("~/.npmrc ({})", registry),
+            &|c| update_npmrc(c, &registry, token),
+        )?;
+    }
+    if let Some(name) = &sync.cargo_registry {
+        add(
+            cargo_home(&home).join("credentials.toml"),
+            format!("cargo credentials ({})", name),
+            &|c| update_cargo_credentials(c, name, token),
+        )?;
+    }
+    if let Some(id) = &sync.maven_server {
+        add(
+            home.join(".m2").join("settings.xml"),
+            format!("~/.m2/settings.xml ({})", id),
+            &|c| update_maven_settings(c, id, &account.username, token),
+        )?;
+    }
+    if sync.gradle {
+        let gradle_home = std::env::var_os("GRADLE_USER_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".gradle"));
+        add(
+            gradle_home.join("gradle.properties"),
+            "gradle.properties (gpr.user/gpr.key)".to_string(),
+            &|c| update_gradle_properties(c, &account.username, token),
+        )?;
+    }
+    Ok(edits)
+}
+
+/// Write the account's token into every enabled registry config. Returns a
+/// line per file changed, for the switch status. Writes nothing in
+/// ephemeral mode.
+pub fn sync(account: &Account, token: &str) -> Result<Vec<String>, String> {
+    if crate::models::is_ephemeral() {
+        return Ok(Vec::new());
+    }
+    let mut updated = Vec::new();
+    for edit in plan(account, token)? {
+        if edit.before != edit.after {
+            write_private(&edit.path, &edit.after)?;
+            updated.push(edit.label);
+        }
+    }
+    Ok(updated)
+}
+
+fn cargo_home(home: &Path) -> PathBuf {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".cargo"))
+}
+
+fn write_private(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // New files are private from the start; existing ones are narrowed
+    // before the token goes in.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+    let mut file = options.open(path).map_err(error)?;
+    restrict_permissions(&file);
+    file.write_all(content.as_bytes()).map_err(error)
+}
+
+#[cfg(unix)]
+fn restrict_permissions(file: &fs::File) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = file.set_permissions(fs::Permissions::from_mode(0o600));
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_file: &fs::File) {}
+
+/// Replace (or append) the `//registry/:_authToken=` line, leaving the rest.
+pub fn update_npmrc(content: &str, registry: &str, token: &str) -> String {
+    let key = format!("//{}/:_authToken=", registry);
+    let line = format!("{}{}", key, token);
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|l| {
+            if l.trim_start().starts_with(&key) {
+                found = true;
+                line.clone()
+            } else {
+                l.to_string()
+            }
+        })
+        .collect();
+    if !found {
+        lines.push(line);
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Set `token` in the `[registries.<name>]` table, leaving other tables and
+/// keys untouched.
+pub fn update_cargo_credentials(content: &str, name: &str, token: &str) -> String {
+    let header = format!("[registries.{}]", name);
+    let token_line = format!(
+        "token = \"{}\"",
+        token.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let Some(start) = lines.iter().position(|l| l.trim() == header) else {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(header);
+        lines.push(token_line);
+        return lines.join("\n") + "\n";
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    match (start + 1..end).find(|&i| {
+        lines[i]
+            .split_once('=')
+            .is_some_and(|(key, _)| key.trim() == "token")
+    }) {
+        Some(i) => lines[i] = token_line,
+        None => lines.insert(start + 1, token_line),
+    }
+    lines.join("\n") + "\n"
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Fill in username/password of the `<server>` with this id, adding the
+/// server (and `<servers>`, or the whole file) when missing.
+pub fn update_maven_settings(content: &str, id: &str, username: &str, token: &str) -> String {
+    let username = xml_escape(username);
+    let token = xml_escape(token);
+    let id_tag = format!("<id>{}</id>", xml_escape(id));
+
+    let mut search = 0;
+    while let Some(open) = content[search..].find("<server>").map(|i| search + i) {
+        let Some(close) = content[open..].find("</server>").map(|i| open + i) else {
+            break;
+        };
+        let block = &content[open..close];
+        if block.contains(&id_tag) {
+            let block = set_xml_element(block, "username", &username);
+            let block = set_xml_element(&block, "password", &token);
+            return format!("{}{}{}", &content[..open], block, &content[close..]);
+        }
+        search = close;
+    }
+
+    let server = format!(
+        "    <server>\n      {}\n      <username>{}</username>\n      <password>{}</password>\n    </server>\n",
+        id_tag, username, token
+    );
+    if let Some(pos) = content.find("</servers>") {
+        let line_start = content[..pos].rfind('\n').map_or(0, |i| i + 1);
+        return format!(
+            "{}{}{}",
+            &content[..line_start],
+            server,
+            &content[line_start..]
+        );
+    }
+    let servers = format!("  <servers>\n{}  </servers>\n", server);
+    if let Some(pos) = content.find("</settings>") {
+        let line_start = content[..pos].rfind('\n').map_or(0, |i| i + 1);
+        return format!(
+            "{}{}{}",
+            &content[..line_start],
+            servers,
+            &content[line_start..]
+        );
+    }
+    format!("<settings>\n{}</settings>\n", servers)
+}
+
+/// Replace the text of `<tag>` inside `block`, or add it after `<id>`.
+fn set_xml_element(block: &str, tag: &str, value: &str) -> String {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    if let Some(start) = block.find(&open)
+        && let Some(end) = block[start..].find(&close).map(|i| start + i)
+    {
+        return format!("{}{}{}{}", &block[..start], open, value, &block[end..]);
+    }
+    let insert_at = block
+        .find("</id>")
+        .map_or(block.len(), |i| i + "</id>".len());
+    format!(
+        "{}\n      {}{}{}{}",
+        &block[..insert_at],
+        open,
+        value,
+        close,
+        &block[insert_at..]
+    )
+}
+
+/// Set `gpr.user` and `gpr.key`, the property names GitHub's Gradle docs use.
+pub fn update_gradle_properties(content: &str, username: &str, token: &str) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    for (key, value) in [("gpr.user", username), ("gpr.key", token)] {
+        let line = format!("{}={}", key, value);
+        match lines.iter().position(|l| {
+            l.split_once(['=', ':'])
+                .is_some_and(|(k, _)| k.trim() == key)
+        }) {
+            Some(i) => lines[i] = line,
+            None => lines.push(line),
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Hide credential values in a registry config line.
+fn redact(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    if let Some(pos) = line.find(":_authToken=") {
+        format!("{}:_authToken=********", &line[..pos])
+    } else if trimmed.starts_with("<password>") {
+        format!("{}<password>********</password>", indent)
+    } else if trimmed.starts_with("gpr.key") {
+        format!("{}gpr.key=********", indent)
+    } else if trimmed
+        .split_once('=')
+        .is_some_and(|(key, _)| key.trim() == "token")
+    {
+        format!("{}token = \"********\"", indent)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Minimal line diff (LCS) for dry-run output, with credentials redacted.
+pub fn diff(before: &str, after: &str) -> Vec<String> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(format!("-{}", redact(a[i])));
+            i += 1;
+        } else {
+            out.push(format!("+{}", redact(b[j])));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_npm_token_line_only() {
+        let npmrc =
+            "@acme:registry=https://npm.pkg.github.com\n//npm.pkg.github.com/:_authToken=old\n";
+        assert_eq!(
+            update_npmrc(npmrc, "npm.pkg.github.com", "new"),
+            "@acme:registry=https://npm.pkg.github.com\n//npm.pkg.github.com/:_authToken=new\n"
+        );
+        assert_eq!(
+            update_npmrc("", "npm.ghe.corp", "t"),
+            "//npm.ghe.corp/:_authToken=t\n"
+        );
+    }
+
+    #[test]
+    fn edits_only_the_named_cargo_registry() {
+        let creds = "[registry]\ntoken = \"crates\"\n\n[registries.work]\ntoken = \"old\"\n";
+        let updated = update_cargo_credentials(creds, "work", "new");
+        assert_eq!(
+            updated,
+            "[registry]\ntoken = \"crates\"\n\n[registries.work]\ntoken = \"new\"\n"
+        );
+        let added = update_cargo_credentials("[registry]\ntoken = \"crates\"\n", "oss", "t");
+        assert!(added.ends_with("\n\n[registries.oss]\ntoken = \"t\"\n"));
+    }
+
+    #[test]
+    fn fills_in_maven_server_credentials() {
+        let settings = "<settings>\n  <servers>\n    <server>\n      <id>github</id>\n      <username>old</username>\n      <password>x</password>\n    </server>\n  </servers>\n</settings>\n";
+        let updated = update_maven_settings(settings, "github", "octo", "tok");
+        assert!(updated.contains("<username>octo</username>"));
+        assert!(updated.contains("<password>tok</password>"));
+        assert!(!updated.contains("old"));
+
+        let created = update_maven_settings("", "github", "octo", "tok");
+        assert!(created.starts_with("<settings>\n  <servers>\n    <server>"));
+        assert!(created.contains("<id>github</id>"));
+    }
+
+    #[test]
+    fn sets_gradle_properties_and_masks_diff() {
+        let updated =
+            update_gradle_properties("org.gradle.jvmargs=-Xmx2g\ngpr.key=old\n", "octo", "tok");
+        assert_eq!(
+            updated,
+            "org.gradle.jvmargs=-Xmx2g\ngpr.key=tok\ngpr.user=octo\n"
+        );
+        assert_eq!(
+            diff("a\ngpr.key=old\n", "a\ngpr.key=tok\n"),
+            vec!["-gpr.key=********", "+gpr.key=********"]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use crate::models::{Account, Config};
+use crate::utils::{
+    default_account, find_account, find_account_by_id, format_account_label, git_config_get_at,
+    git_toplevel_at, match_identity, parse_remote_url, resolve_account,
+};
+use colored::Colorize;
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// Why a particular account was chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// Named explicitly by username or alias.
+    Identifier,
+    /// The repository is bound to the account with `gitas use`.
+    Binding,
+    /// The repository's effective git identity matches a managed account.
+    RepoIdentity,
+    /// The owner of the `origin` remote is in the account's `orgs`, or is
+    /// its username.
+    RemoteOwner,
+    /// The account set with `gitas default`.
+    Default,
+}
+
+pub struct Resolution<'a> {
+    pub account: &'a Account,
+    pub source: Source,
+}
+
+/// Account gitas would act as for a repository directory, without prompting.
+/// This is the single place new selection mechanisms should plug into. An
+/// owner of `origin` counts only once the user has confirmed it.
+pub fn resolve_for_path<'a>(config: &'a Config, dir: &Path) -> Option<Resolution<'a>> {
+    if let Some(resolution) = resolve_binding(config, dir) {
+        return Some(resolution);
+    }
+    let name = git_config_get_at(dir, "user.name");
+    let email = git_config_get_at(dir, "user.email");
+    if let (Some(name), Some(email)) = (name, email) {
+        let alias = git_config_get_at(dir, "gitas.alias");
+        if let Some(account) = match_identity(config, &name, &email, alias.as_deref()) {
+            return Some(Resolution {
+                account,
+                source: Source::RepoIdentity,
+            });
+        }
+    }
+    let (resolution, key) = resolve_remote_owner(config, dir)?;
+    is_confirmed(config, resolution.account, &key).then_some(resolution)
+}
+
+/// Account that owns the repository's default remote (its upstream,
+/// `origin` or only remote), with the `host/owner` key its confirmation is
+/// stored under. Repositories without one infer nothing.
+fn resolve_remote_owner<'a>(config: &'a Config, dir: &Path) -> Option<(Resolution<'a>, String)> {
+    let url = crate::utils::default_remote_at(dir)?.url;
+    let account = account_for_url(&config.accounts, &url)?;
+    Some((
+        Resolution {
+            account,
+            source: Source::RemoteOwner,
+        },
+        owner_key(&url)?,
+    ))
+}
+
+/// The `host/owner` key a remote's inferred account is confirmed under.
+fn owner_key(url: &str) -> Option<String> {
+    let (host, path) = parse_remote_url(url)?;
+    let owner = path.split('/').next()?;
+    Some(format!("{}/{}", host, owner).to_lowercase())
+}
+
+/// Whether the account inferred for `key` may be acted as without asking:
+/// the user confirmed it, or the remote names it by its SSH host alias.
+fn is_confirmed(config: &Config, account: &Account, key: &str) -> bool {
+    let by_alias = account.ssh_key.is_some()
+        && key
+            .split_once('/')
+            .is_some_and(|(host, _)| crate::ssh::host_alias(account).eq_ignore_ascii_case(host));
+    by_alias || config.remote_owners.get(key) == Some(&crate::audit::account_id(account))
+}
+
+/// Account for a remote URL from its owner (the first path segment, so a
+/// GitLab subgroup counts as its top group) and host: one that lists the
+/// owner in `orgs`, otherwise one whose username is the owner. None when
+/// several accounts fit, e.g. aliases of the same user. An SSH host alias
+/// from `gitas ssh config` (`github.com-work`) names its account outright.
+pub fn account_for_url<'a>(accounts: &'a [Account], url: &str) -> Option<&'a Account> {
+    let (host, path) = parse_remote_url(url)?;
+    if let Some(account) = accounts
+        .iter()
+        .find(|a| a.ssh_key.is_some() && crate::ssh::host_alias(a).eq_ignore_ascii_case(&host))
+    {
+        return Some(account);
+    }
+    let owner = path.split('/').next()?;
+    let on_host = || {
+        accounts
+            .iter()
+            .filter(|a| crate::host::matches(a.host.as_deref().unwrap_or("github.com"), &host))
+    };
+    let only = |mut found: Vec<&'a Account>| (found.len() == 1).then(|| found.remove(0));
+    let listed: Vec<&Account> = on_host()
+        .filter(|a| a.orgs.iter().any(|org| org.eq_ignore_ascii_case(owner)))
+        .collect();
+    if !listed.is_empty() {
+        return only(listed);
+    }
+    only(
+        on_host()
+            .filter(|a| a.username.eq_ignore_ascii_case(owner))
+            .collect(),
+    )
+}
+
+/// Account whose commits a push from `dir` to `url` should carry, without
+/// prompting: the repository's account, otherwise the pushed remote's owner
+/// once confirmed.
+pub fn account_for_push<'a>(config: &'a Config, dir: &Path, url: &str) -> Option<&'a Account> {
+    match resolve_for_path(config, dir) {
+        Some(resolution) if resolution.source != Source::RemoteOwner => Some(resolution.account),
+        _ => account_for_url(&config.accounts, url).filter(|account| {
+            owner_key(url).is_some_and(|key| is_confirmed(config, account, &key))
+        }),
+    }
+}
+
+/// Whether to act as the account inferred from `origin`. The first time an
+/// owner maps to an account the user is asked, and the answer is kept in
+/// `remote_owners` or `declined_owners`; without a terminal an unconfirmed
+/// inference is not used.
+fn confirm_remote_owner(config: &mut Config, account: &Account, key: &str) -> bool {
+    let id = crate::audit::account_id(account);
+    if is_confirmed(config, account, key) {
+        return true;
+    }
+    if config.declined_owners.get(key) == Some(&id)
+        || crate::models::is_strict()
+        || !std::io::stdin().is_terminal()
+    {
+        return false;
+    }
+    let prompt = format!(
+        "origin belongs to {}. Use {} for its repositories?",
+        match key.split_once('/') {
+            Some((host, owner)) => format!("{}/{}", host, crate::utils::redact_name(owner)),
+            None => crate::utils::redact_name(key),
+        }
+        .cyan(),
+        format_account_label(account).cyan()
+    );
+    crate::tui::enter_raw_mode();
+    let answer = crate::tui::raw_confirm(&prompt, true);
+    crate::tui::exit_raw_mode();
+    let Some(confirmed) = answer else {
+        crate::tui::exit_cancelled()
+    };
+    if confirmed {
+        config.declined_owners.remove(key);
+        config.remote_owners.insert(key.to_string(), id);
+    } else {
+        config.declined_owners.insert(key.to_string(), id);
+    }
+    crate::models::save_config(config);
+    confirmed
+}
+
+/// Account inferred from `origin` for a command that acts as someone, once
+/// the mapping is confirmed.
+fn confirmed_remote_owner(config: &mut Config, dir: &Path) -> Option<Account> {
+    let (resolution, key) = resolve_remote_owner(config, dir)?;
+    let account = resolution.account.clone();
+    confirm_remote_owner(config, &account, &key).then_some(account)
+}
+
+/// Account the repository containing `dir` is bound to with `gitas use`.
+pub fn resolve_binding<'a>(config: &'a Config, dir: &Path) -> Option<Resolution<'a>> {
+    if config.repos.is_empty() {
+        return None;
+    }
+    let id = config.repos.get(&git_toplevel_at(dir)?)?;
+    find_account_by_id(config, id).map(|account| Resolution {
+        account,
+        source: Source::Binding,
+    })
+}
+
+pub fn resolve_identifier<'a>(config: &'a Config, id: &str) -> Option<Resolution<'a>> {
+    find_account(config, id).map(|account| Resolution {
+        account,
+        source: Source::Identifier,
+    })
+}
+
+/// Account a command acting as someone would use in `dir` without `-a`,
+/// never prompting: [`resolve_for_path`], then the default account.
+pub fn resolve_or_default<'a>(config: &'a Config, dir: &Path) -> Option<Resolution<'a>> {
+    resolve_for_path(config, dir).or_else(|| resolve_default(config))
+}
+
+/// The default account, if it still exists.
+pub fn resolve_default(config: &Config) -> Option<Resolution<'_>> {
+    default_account(config).map(|account| Resolution {
+        account,
+        source: Source::Default,
+    })
+}
+
+/// The repository's account: [`resolve_for_path`], or else the owner of its
+/// `origin` once the user confirms it now.
+fn repository_account(config: &mut Config, dir: &Path) -> Option<Account> {
+    match resolve_for_path(config, dir) {
+        Some(resolution) => Some(resolution.account.clone()),
+        None => confirmed_remote_owner(config, dir),
+    }
+}
+
+/// Account for a command that runs as someone: `-a` when given, otherwise the
+/// repository's account, then the default account unless `pick` asks for the
+/// picker, otherwise ask. Without prompts this is [`resolve_or_default`].
+pub fn account_or_default(
+    config: &mut Config,
+    account_id: Option<String>,
+    pick: bool,
+    prompt: &str,
+) -> Account {
+    if account_id.is_none() && !pick {
+        if let Some(account) = repository_account(config, Path::new(".")) {
+            return account;
+        }
+        if let Some(resolution) = resolve_default(config) {
+            return resolution.account.clone();
+        }
+    }
+    resolve_account(config, account_id, prompt)
+}
+
+/// Account for a command run inside a repository: `-a` when given, otherwise
+/// the repository's account, otherwise ask.
+pub fn account_for_cwd(config: &mut Config, account_id: Option<String>, prompt: &str) -> Account {
+    if account_id.is_none()
+        && let Some(account) = repository_account(config, Path::new("."))
+    {
+        return account;
+    }
+    resolve_account(config, account_id, prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(username: &str, alias: Option<&str>, host: Option<&str>) -> Account {
+        Account {
+            username: username.to_string(),
+            alias: alias.map(str::to_string),
+            host: host.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn picks_account_by_owner_and_host() {
+        let mut accounts = vec![
+            account("octo", None, None),
+            account("octo", None, Some("gitlab.com")),
+            account("hub", Some("a"), None),
+            account("hub", Some("b"), None),
+        ];
+        let found = account_for_url(&accounts, "git@gitlab.com:Octo/repo.git").unwrap();
+        assert_eq!(found.host.as_deref(), Some("gitlab.com"));
+        let found = account_for_url(&accounts, "https://github.com/octo/repo").unwrap();
+        assert_eq!(found.host, None);
+        assert!(account_for_url(&accounts, "https://github.com/hub/repo").is_none());
+        assert!(account_for_url(&accounts, "https://github.com/acme/repo").is_none());
+
+        // A listed org wins over the username, and tells aliases apart.
+        accounts[3].orgs = vec!["Acme".to_string(), "hub".to_string()];
+        let found = account_for_url(&accounts, "git@github.com:acme/repo.git").unwrap();
+        assert_eq!(found.alias.as_deref(), Some("b"));
+        let found = account_for_url(&accounts, "https://github.com/hub/repo").unwrap();
+        assert_eq!(found.alias.as_deref(), Some("b"));
+
+        // The SSH host alias of an account with a key picks it.
+        accounts[2].ssh_key = Some("~/.ssh/id_a".to_string());
+        let found = account_for_url(&accounts, "git@github.com-a:acme/repo.git").unwrap();
+        assert_eq!(found.alias.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn falls_back_to_the_default_account() {
+        let mut config = Config {
+            accounts: vec![account("octo", None, None), account("hub", Some("a"), None)],
+            ..Default::default()
+        };
+        let dir = Path::new("/nonexistent/gitas-test");
+        assert!(resolve_or_default(&config, dir).is_none());
+
+        config.default_account = Some("hub:a".to_string());
+        let resolution = resolve_or_default(&config, dir).unwrap();
+        assert_eq!(resolution.account.alias.as_deref(), Some("a"));
+        assert_eq!(resolution.source, Source::Default);
+    }
+
+    #[test]
+    fn pushes_act_only_as_confirmed_owners() {
+        let mut config = Config {
+            accounts: vec![account("octo", None, None), account("hub", Some("a"), None)],
+            ..Default::default()
+        };
+        let dir = Path::new("/nonexistent/gitas-test");
+        let url = "https://github.com/octo/repo";
+        assert!(account_for_push(&config, dir, url).is_none());
+
+        config
+            .declined_owners
+            .insert("github.com/octo".to_string(), "octo".to_string());
+        assert!(account_for_push(&config, dir, url).is_none());
+
+        config
+            .remote_owners
+            .insert("github.com/octo".to_string(), "octo".to_string());
+        let found = account_for_push(&config, dir, url).unwrap();
+        assert_eq!(found.username, "octo");
+
+        // Naming the account's SSH host alias is confirmation enough.
+        config.accounts[1].ssh_key = Some("~/.ssh/id_a".to_string());
+        let found = account_for_push(&config, dir, "git@github.com-a:acme/repo.git").unwrap();
+        assert_eq!(found.alias.as_deref(), Some("a"));
+    }
+}
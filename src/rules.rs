@@ -0,0 +1,324 @@
+use crate::models::Account;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An `[includeIf "gitdir:<pattern>"]` entry in the global git config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// The pattern after `gitdir:` (or `gitdir/i:`), e.g. `~/work/`.
+    pub pattern: String,
+    pub case_insensitive: bool,
+    /// File the entry includes.
+    pub path: String,
+}
+
+impl Rule {
+    fn key(&self) -> String {
+        let condition = if self.case_insensitive {
+            "gitdir/i"
+        } else {
+            "gitdir"
+        };
+        format!("includeIf.{}:{}.path", condition, self.pattern)
+    }
+}
+
+/// Directory holding the include files gitas writes for rules.
+pub fn dir() -> PathBuf {
+    crate::models::config_path()
+        .parent()
+        .map(|dir| dir.join("rules"))
+        .unwrap_or_else(|| PathBuf::from("rules"))
+}
+
+/// Include file for an account, e.g. `rules/octo-work.gitconfig`.
+pub fn include_path(account: &Account) -> PathBuf {
+    let name: String = crate::audit::account_id(account)
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    dir().join(format!("{}.gitconfig", name))
+}
+
+/// Whether gitas wrote the file the rule includes.
+pub fn is_managed(rule: &Rule) -> bool {
+    Path::new(&rule.path).starts_with(dir())
+}
+
+/// Account id (`username` or `username:alias`) an include file was written for.
+pub fn account_of(path: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--file", path, "--get", "gitas.account"])
+        .output()
+        .ok()?;
+    let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !id.is_empty()).then_some(id)
+}
+
+/// Every `includeIf "gitdir..."` entry in the global config, in file order.
+pub fn list() -> Vec<Rule> {
+    let Ok(output) = Command::new("git")
+        .args([
+            "config",
+            "--global",
+            "--null",
+            "--get-regexp",
+            r"^includeif\.gitdir",
+        ])
+        .output()
+    else {
+        return Vec::new();
+    };
+    parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Records of `git config --null --get-regexp`: `key\nvalue\0`. Git lowercases
+/// the section and variable but keeps the pattern as written.
+fn parse(text: &str) -> Vec<Rule> {
+    text.split('\0')
+        .filter_map(|record| {
+            let (key, value) = record.split_once('\n')?;
+            let condition = key.strip_prefix("includeif.")?.strip_suffix(".path")?;
+            let (case_insensitive, pattern) = match condition.strip_prefix("gitdir/i:") {
+                Some(pattern) => (true, pattern),
+                None => (false, condition.strip_prefix("gitdir:")?),
+            };
+            Some(Rule {
+                pattern: pattern.to_string(),
+                case_insensitive,
+                path: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn git_global(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["config", "--global"])
+        .args(args)
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "git config failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// (Re)write the include file with the settings a switch would write.
+pub fn write_include(account: &Account) -> Result<PathBuf, String> {
+    let path = include_path(account);
+    std::fs::create_dir_all(dir())
+        .map_err(|e| format!("Could not create {}: {}", dir().display(), e))?;
+    let mut contents = format!(
+        "# Written by gitas for `gitas rule`; changes are overwritten.\n[gitas]\n\taccount = {}\n",
+        quote(&crate::audit::account_id(account))
+    );
+    let mut last_header = None;
+    for (key, value) in crate::commands::list::switch_plan(account, &[], |_| None) {
+        let Some(value) = value else {
+            continue;
+        };
+        // Keys are `section.name` or `section.subsection.name`.
+        let (section, name) = key.rsplit_once('.').unwrap_or(("", &key));
+        let header = match section.split_once('.') {
+            Some((section, subsection)) => format!("[{} {}]", section, quote(subsection)),
+            None => format!("[{}]", section),
+        };
+        if last_header.as_ref() != Some(&header) {
+            contents.push_str(&format!("{}\n", header));
+            last_header = Some(header);
+        }
+        contents.push_str(&format!("\t{} = {}\n", name, quote(&value)));
+    }
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// A git config value or subsection in double quotes, escaped.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Apply `account` in every repository under `pattern`, replacing any rule
+/// gitas already keeps for the same pattern.
+pub fn add(pattern: &str, account: &Account) -> Result<(), String> {
+    // Removing first may delete the include file this rule reuses.
+    remove(pattern)?;
+    let path = write_include(account)?;
+    let path = path.to_string_lossy().replace('\\', "/");
+    git_global(&[
+        "--add",
+        &format!("includeIf.gitdir:{}.path", pattern),
+        &path,
+    ])
+}
+
+/// Drop the gitas-managed rules for `pattern`, the section when nothing else
+/// is left in it, and include files no other rule uses. Returns how many were
+/// removed.
+pub fn remove(pattern: &str) -> Result<usize, String> {
+    let managed: Vec<Rule> = list()
+        .into_iter()
+        .filter(|rule| rule.pattern == pattern && is_managed(rule))
+        .collect();
+    for rule in &managed {
+        git_global(&["--fixed-value", "--unset-all", &rule.key(), &rule.path])?;
+        if !section_in_use(rule) {
+            let section = rule.key();
+            let section = section.strip_suffix(".path").unwrap_or(&section);
+            git_global(&["--remove-section", section]).ok();
+        }
+    }
+    if !managed.is_empty() {
+        let remaining = list();
+        for rule in &managed {
+            if !remaining.iter().any(|other| other.path == rule.path) {
+                std::fs::remove_file(&rule.path).ok();
+            }
+        }
+    }
+    Ok(managed.len())
+}
+
+/// Whether the rule's `includeIf` section still holds any variable.
+fn section_in_use(rule: &Rule) -> bool {
+    let key = rule.key();
+    let prefix = key.strip_suffix("path").unwrap_or(&key);
+    let Ok(output) = Command::new("git")
+        .args(["config", "--global", "--null", "--list"])
+        .output()
+    else {
+        return true;
+    };
+    // `--list` lowercases the section name only.
+    let prefix = format!("includeif{}", &prefix["includeIf".len()..]);
+    String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .any(|entry| entry.starts_with(&prefix))
+}
+
+/// Rewrite the include file of every rule whose account changed. Does
+/// nothing until a rule exists.
+pub fn refresh(accounts: &[Account]) {
+    let Ok(entries) = std::fs::read_dir(dir()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "gitconfig") {
+            continue;
+        }
+        let Some(id) = account_of(&path.to_string_lossy()) else {
+            continue;
+        };
+        if let Some(account) = accounts.iter().find(|a| crate::audit::account_id(a) == id)
+            && include_path(account) == path
+        {
+            write_include(account).ok();
+        }
+    }
+}
+
+/// The rule git applies for a repository whose git directory is `gitdir`:
+/// the last one that matches, as later includes override earlier ones.
+pub fn applying<'a>(rules: &'a [Rule], gitdir: &str, home: &str) -> Option<&'a Rule> {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| pattern_matches(rule, gitdir, home))
+}
+
+/// git's `gitdir:` matching: `~/` is the home directory, a relative pattern
+/// matches anywhere (`**/`), and a trailing `/` covers everything below.
+/// Patterns relative to the including file (`./`) are not supported.
+fn pattern_matches(rule: &Rule, gitdir: &str, home: &str) -> bool {
+    let mut pattern = rule.pattern.replace('\\', "/");
+    if let Some(rest) = pattern.strip_prefix("~/") {
+        pattern = format!("{}/{}", home.trim_end_matches('/'), rest);
+    } else if pattern.starts_with("./") {
+        return false;
+    } else if !pattern.starts_with('/') && pattern.get(1..2) != Some(":") {
+        pattern = format!("**/{}", pattern);
+    }
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+    let gitdir = gitdir.replace('\\', "/");
+    if rule.case_insensitive {
+        glob_match(&pattern.to_lowercase(), &gitdir.to_lowercase())
+    } else {
+        glob_match(&pattern, &gitdir)
+    }
+}
+
+/// Wildcard match: `**` spans directories, `*` and `?` stay within one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    fn matches(p: &[char], t: &[char]) -> bool {
+        match p.first() {
+            None => t.is_empty(),
+            Some('*') if p.get(1) == Some(&'*') => {
+                let rest = &p[2..];
+                // `**/` also matches no directory at all.
+                if rest.first() == Some(&'/') && matches(&rest[1..], t) {
+                    return true;
+                }
+                (0..=t.len()).any(|i| matches(rest, &t[i..]))
+            }
+            Some('*') => (0..=t.len())
+                .take_while(|&i| i == 0 || t[i - 1] != '/')
+                .any(|i| matches(&p[1..], &t[i..])),
+            Some('?') => t.first().is_some_and(|&c| c != '/') && matches(&p[1..], &t[1..]),
+            Some(&c) => t.first() == Some(&c) && matches(&p[1..], &t[1..]),
+        }
+    }
+    matches(&pattern, &text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_matches_gitdir_rules() {
+        let rules = parse(
+            "includeif.gitdir:~/work/.path\n/cfg/rules/octo-work.gitconfig\0\
+             includeif.gitdir/i:C:/Src/OSS/.path\n/cfg/rules/octo.gitconfig\0",
+        );
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].pattern, "~/work/");
+        assert!(rules[1].case_insensitive);
+
+        let home = "/home/me";
+        let applied = |gitdir: &str| applying(&rules, gitdir, home).map(|r| r.path.as_str());
+        assert_eq!(
+            applied("/home/me/work/app/.git"),
+            Some("/cfg/rules/octo-work.gitconfig")
+        );
+        assert_eq!(applied("/home/me/personal/app/.git"), None);
+        assert_eq!(
+            applied("c:/src/oss/lib/.git"),
+            Some("/cfg/rules/octo.gitconfig")
+        );
+
+        let anywhere = Rule {
+            pattern: "clients/".to_string(),
+            case_insensitive: false,
+            path: String::new(),
+        };
+        assert!(pattern_matches(&anywhere, "/srv/clients/acme/.git", home));
+        assert!(!glob_match("/a/*/x", "/a/b/c/x"));
+    }
+}
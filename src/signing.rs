@@ -0,0 +1,121 @@
+use crate::models::Account;
+use serde::{Deserialize, Serialize};
+
+/// Config keys a switch writes for an account that signs commits.
+pub const KEYS: [&str; 3] = ["gpg.format", "user.signingkey", "commit.gpgsign"];
+
+/// `gpg.format` of an account's signing key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    /// SSH key file (git 2.34+)
+    #[default]
+    Ssh,
+    /// GPG key ID
+    Openpgp,
+    /// X.509 certificate, e.g. through gpgsm or smimesign
+    X509,
+}
+
+impl SigningFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SigningFormat::Ssh => "ssh",
+            SigningFormat::Openpgp => "openpgp",
+            SigningFormat::X509 => "x509",
+        }
+    }
+}
+
+/// Format and `user.signingkey` for the account: its `signing_key`, else its
+/// GPG key when it opted in with `sign_with_gpg_key`. SSH key paths use
+/// forward slashes, as git on Windows expects.
+fn signing_key(account: &Account) -> Option<(SigningFormat, String)> {
+    if let Some(key) = account.signing_key.as_deref() {
+        let format = account.signing_format.unwrap_or_default();
+        let key = match format {
+            SigningFormat::Ssh => key.replace('\\', "/"),
+            _ => key.to_string(),
+        };
+        return Some((format, key));
+    }
+    let key = account
+        .gpg_key
+        .as_deref()
+        .filter(|_| account.sign_with_gpg_key)?;
+    Some((SigningFormat::Openpgp, key.to_string()))
+}
+
+/// GPG key the account signs commits with, picked as for a switch (its GPG
+/// key whether or not switches sign with it); None when it signs with SSH or
+/// X.509.
+pub fn openpgp_key(account: &Account) -> Option<String> {
+    match account.signing_key {
+        Some(_) => signing_key(account)
+            .filter(|(format, _)| *format == SigningFormat::Openpgp)
+            .map(|(_, key)| key),
+        None => account.gpg_key.clone(),
+    }
+}
+
+/// Values for [`KEYS`] when the account signs commits; empty otherwise.
+pub fn settings(account: &Account) -> Vec<(String, String)> {
+    let Some((format, key)) = signing_key(account) else {
+        return Vec::new();
+    };
+    vec![
+        ("gpg.format".to_string(), format.as_str().to_string()),
+        ("user.signingkey".to_string(), key),
+        ("commit.gpgsign".to_string(), "true".to_string()),
+    ]
+}
+
+/// Whether `user.signingkey` was written by a switch to one of `accounts`,
+/// so switching to an account that doesn't sign may clear it.
+pub fn is_managed(accounts: &[Account], current_key: &str) -> bool {
+    accounts
+        .iter()
+        .filter_map(signing_key)
+        .any(|(_, key)| key == current_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_the_signing_key_or_the_gpg_key() {
+        let account = Account {
+            signing_key: Some(r"C:\Users\me\.ssh\id_ed25519.pub".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings(&account),
+            [
+                ("gpg.format".to_string(), "ssh".to_string()),
+                (
+                    "user.signingkey".to_string(),
+                    "C:/Users/me/.ssh/id_ed25519.pub".to_string()
+                ),
+                ("commit.gpgsign".to_string(), "true".to_string()),
+            ]
+        );
+        assert!(is_managed(
+            std::slice::from_ref(&account),
+            "C:/Users/me/.ssh/id_ed25519.pub"
+        ));
+        assert!(settings(&Account::default()).is_empty());
+
+        let mut gpg = Account {
+            gpg_key: Some("3AA5C34371567BD2".to_string()),
+            ..Default::default()
+        };
+        // Only signs with the GPG key once asked to.
+        assert!(settings(&gpg).is_empty());
+        gpg.sign_with_gpg_key = true;
+        assert_eq!(settings(&gpg)[0].1, "openpgp");
+        assert_eq!(settings(&gpg)[1].1, "3AA5C34371567BD2");
+        assert_eq!(openpgp_key(&gpg).as_deref(), Some("3AA5C34371567BD2"));
+        assert_eq!(openpgp_key(&account), None);
+    }
+}
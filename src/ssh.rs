@@ -0,0 +1,364 @@
+use crate::models::Account;
+use colored::Colorize;
+use std::process::Command;
+
+/// `core.sshCommand` / `GIT_SSH_COMMAND` that makes ssh offer only this key.
+pub fn command(ssh_key: &str) -> String {
+    let normalized_path = ssh_key.replace('\\', "/");
+    format!(
+        "ssh -i {} -o IdentitiesOnly=yes",
+        crate::utils::shell_quote(&normalized_path)
+    )
+}
+
+/// [`command`] for a single git run: in strict mode ssh must not stop to ask
+/// for a passphrase or to confirm an unknown host key either.
+pub fn run_command(ssh_key: &str) -> String {
+    let command = command(ssh_key);
+    if crate::models::is_strict() {
+        format!("{} -o BatchMode=yes", command)
+    } else {
+        command
+    }
+}
+
+/// Check that an SSH-only account has a usable private key configured.
+pub fn check_key(account: &Account) -> Result<(), String> {
+    let Some(ssh_key) = account.ssh_key.as_deref() else {
+        return Err(format!("{} has no SSH key configured", account.username));
+    };
+    let path = std::path::Path::new(ssh_key);
+    if !path.is_file() {
+        return Err(format!("SSH key not found: {}", ssh_key));
+    }
+    if std::fs::File::open(path).is_err() {
+        return Err(format!("SSH key is not readable: {}", ssh_key));
+    }
+    Ok(())
+}
+
+/// Login the forge greets this account's key as, from `ssh -T git@host`.
+/// Forges reject the shell but name the user first, so the exit code is moot.
+pub fn authenticated_as(account: &Account) -> Result<String, String> {
+    let Some(ssh_key) = account.ssh_key.as_deref() else {
+        return Err("no SSH key configured".to_string());
+    };
+    let host = account.host.as_deref().unwrap_or("github.com");
+    let output = Command::new("ssh")
+        .args([
+            "-T",
+            "-i",
+            ssh_key,
+            "-o",
+            "IdentitiesOnly=yes",
+            "-o",
+            "BatchMode=yes",
+        ])
+        .args(["-o", "ConnectTimeout=10"])
+        .arg(format!("git@{}", host))
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| format!("could not run ssh: {}", e))?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stderr),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    greeted_login(&text).ok_or_else(|| {
+        let last = text
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or("no answer");
+        format!("{} did not accept the key: {}", host, last.trim())
+    })
+}
+
+/// The login in a forge's SSH greeting: GitHub's "Hi octo! …", Gitea's
+/// "Hi there, octo! …" and GitLab's "Welcome to GitLab, @octo!".
+fn greeted_login(text: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let line = line.trim();
+        let rest = line
+            .strip_prefix("Hi there, ")
+            .or_else(|| line.strip_prefix("Hi "))
+            .or_else(|| {
+                line.strip_prefix("Welcome to ")
+                    .and_then(|r| r.split_once(", @"))
+                    .map(|(_, user)| user)
+            })?;
+        let login = rest.split('!').next()?.trim();
+        (!login.is_empty() && !login.contains(' ')).then(|| login.to_string())
+    })
+}
+
+const BLOCK_START: &str = "# >>> gitas managed hosts (edit with `gitas ssh config`) >>>";
+const BLOCK_END: &str = "# <<< gitas managed hosts <<<";
+
+pub fn config_path() -> Option<std::path::PathBuf> {
+    Some(dirs::home_dir()?.join(".ssh").join("config"))
+}
+
+/// `Host` name that selects this account's key, e.g. `github.com-work`.
+pub fn host_alias(account: &Account) -> String {
+    let host = account.host.as_deref().unwrap_or("github.com");
+    let name = account.alias.as_deref().unwrap_or(&account.username);
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .collect();
+    format!("{}-{}", host, name)
+}
+
+/// The managed block: one `Host` entry per account with an SSH key. Empty
+/// when no account has one.
+pub fn hosts_block(accounts: &[Account]) -> String {
+    let mut entries = Vec::new();
+    for account in accounts {
+        let Some(ssh_key) = account.ssh_key.as_deref() else {
+            continue;
+        };
+        let key = ssh_key.replace('\\', "/");
+        let key = if key.contains(char::is_whitespace) {
+            format!("\"{}\"", key)
+        } else {
+            key
+        };
+        entries.push(format!(
+            "Host {}\n    HostName {}\n    User git\n    IdentityFile {}\n    IdentitiesOnly yes\n",
+            host_alias(account),
+            account.host.as_deref().unwrap_or("github.com"),
+            key
+        ));
+    }
+    if entries.is_empty() {
+        return String::new();
+    }
+    format!("{}\n{}{}\n", BLOCK_START, entries.join("\n"), BLOCK_END)
+}
+
+/// `contents` with the managed block swapped for `block` (removed when
+/// empty). A block already in the file keeps its place; a new one goes at
+/// the end, where its `Host` lines can't capture the file's global options.
+pub fn replace_block(contents: &str, block: &str) -> String {
+    let start = contents.find(BLOCK_START);
+    let end = start.and_then(|start| {
+        contents[start..]
+            .find(BLOCK_END)
+            .map(|end| start + end + BLOCK_END.len())
+    });
+    match (start, end) {
+        (Some(start), Some(end)) => {
+            let mut before = &contents[..start];
+            // Take the blank line written in front of the block with it.
+            if block.is_empty() && before.ends_with("\n\n") {
+                before = &before[..before.len() - 1];
+            }
+            let after = contents[end..]
+                .strip_prefix('\n')
+                .unwrap_or(&contents[end..]);
+            format!("{}{}{}", before, block, after)
+        }
+        _ if block.is_empty() => contents.to_string(),
+        _ if contents.is_empty() || contents.ends_with("\n\n") => {
+            format!("{}{}", contents, block)
+        }
+        _ if contents.ends_with('\n') => format!("{}\n{}", contents, block),
+        _ => format!("{}\n\n{}", contents, block),
+    }
+}
+
+pub fn has_block(contents: &str) -> bool {
+    contents.contains(BLOCK_START)
+}
+
+/// Write `contents` to the ssh config, readable only by the user as ssh
+/// requires.
+pub fn write_config(path: &std::path::Path, contents: &str) -> std::io::Result<()> {
+    std::fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Keep an existing managed block in step with the accounts. Does nothing
+/// until `gitas ssh config` has added the block.
+pub fn refresh_hosts(accounts: &[Account]) {
+    let Some(path) = config_path() else {
+        return;
+    };
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    if !has_block(&contents) {
+        return;
+    }
+    let updated = replace_block(&contents, &hosts_block(accounts));
+    if updated != contents
+        && let Err(e) = write_config(&path, &updated)
+    {
+        eprintln!(
+            "  {} Could not update {}: {}",
+            "⚠".yellow(),
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Private keys in `~/.ssh` as picker rows (plus a manual entry row), their
+/// paths, and the row whose comment matches the account.
+pub fn scan_keys(
+    target_username: &str,
+    target_email: &str,
+) -> (Vec<String>, Vec<std::path::PathBuf>, usize) {
+    let mut display_items = Vec::new();
+    let mut paths = Vec::new();
+    let mut default_idx = 0;
+
+    let Some(entries) = dirs::home_dir()
+        .map(|h| h.join(".ssh"))
+        .and_then(|d| std::fs::read_dir(d).ok())
+    else {
+        display_items.push("Enter ssh key path manually".to_string());
+        return (display_items, paths, 0);
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let filename = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if filename.ends_with(".pub")
+            || filename.ends_with(".old")
+            || filename.ends_with(".bak")
+            || filename == "config"
+            || filename == "known_hosts"
+            || filename == "authorized_keys"
+        {
+            continue;
+        }
+
+        let is_private_key = std::fs::File::open(&path)
+            .and_then(|mut f| {
+                let mut buffer = [0; 128];
+                use std::io::Read;
+                let n = f.read(&mut buffer)?;
+                Ok(String::from_utf8_lossy(&buffer[..n]).starts_with("-----BEGIN"))
+            })
+            .unwrap_or(false);
+
+        if !is_private_key {
+            continue;
+        }
+
+        let pub_path = path.with_extension("pub");
+        let comment = std::fs::read_to_string(&pub_path).ok().and_then(|content| {
+            let parts: Vec<&str> = content.split_whitespace().collect();
+            if parts.len() >= 3 {
+                Some(parts[2..].join(" "))
+            } else {
+                None
+            }
+        });
+
+        let mut display_name = match &comment {
+            Some(c) => format!("{} ({})", filename, c),
+            None => filename.clone(),
+        };
+        if crate::hardware::is_sk_key(&path.to_string_lossy()) {
+            display_name.push_str(" [security key]");
+        }
+
+        let matches = comment.as_ref().is_some_and(|c| {
+            let c_lower = c.to_lowercase();
+            let u_lower = target_username.to_lowercase();
+            let e_lower = target_email.to_lowercase();
+
+            c_lower == e_lower
+                || c_lower == u_lower
+                || c_lower
+                    .find('@')
+                    .is_some_and(|idx| c_lower[..idx] == u_lower)
+        });
+
+        if matches {
+            default_idx = paths.len();
+        }
+
+        display_items.push(display_name);
+        paths.push(path);
+    }
+
+    display_items.push("Enter ssh key path manually".to_string());
+    if default_idx >= display_items.len() {
+        default_idx = display_items.len() - 1;
+    }
+    (display_items, paths, default_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managed_block_is_replaced_in_place_or_appended() {
+        let account = Account {
+            username: "octo".to_string(),
+            alias: Some("work".to_string()),
+            ssh_key: Some("/home/me/.ssh/gitas_work".to_string()),
+            ..Default::default()
+        };
+        let block = hosts_block(std::slice::from_ref(&account));
+        assert!(block.contains("Host github.com-work\n    HostName github.com\n"));
+        assert!(block.contains("IdentityFile /home/me/.ssh/gitas_work\n"));
+
+        let user = "Host *\n    AddKeysToAgent yes";
+        let added = replace_block(user, &block);
+        assert_eq!(added, format!("{}\n\n{}", user, block));
+        let kept = format!("{}\nHost other\n", added);
+        let removed = replace_block(&kept, "");
+        assert_eq!(removed, format!("{}\n\nHost other\n", user));
+        assert_eq!(replace_block(&kept, &block), kept);
+    }
+
+    #[test]
+    fn formats_ssh_command() {
+        assert_eq!(
+            command(r"C:\Users\me\.ssh\id_ed25519"),
+            r#"ssh -i "C:/Users/me/.ssh/id_ed25519" -o IdentitiesOnly=yes"#
+        );
+    }
+
+    #[test]
+    fn reads_login_from_forge_greetings() {
+        assert_eq!(
+            greeted_login("Hi octo! You've successfully authenticated, but GitHub does not provide shell access.").as_deref(),
+            Some("octo")
+        );
+        assert_eq!(
+            greeted_login("Welcome to GitLab, @octo-cat!").as_deref(),
+            Some("octo-cat")
+        );
+        assert_eq!(
+            greeted_login(
+                "Hi there, octo! You've successfully authenticated with the key named work"
+            )
+            .as_deref(),
+            Some("octo")
+        );
+        assert_eq!(
+            greeted_login("git@github.com: Permission denied (publickey)."),
+            None
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use crate::models::{Account, get_token, make_key};
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+/// How long `gitas token agent start` keeps tokens when neither `--ttl` nor
+/// `agent_ttl` says otherwise.
+pub const DEFAULT_TTL: u64 = 8 * 60 * 60;
+
+/// Longest an agent may hold tokens: a week.
+pub const MAX_TTL: u64 = 7 * 24 * 60 * 60;
+
+/// Seconds in a duration like `90s`, `30m`, `8h` or `1d`; a bare number is
+/// seconds. Durations over [`MAX_TTL`] are refused.
+pub fn parse_ttl(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let (number, unit) = match text.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&text[..i], c.to_ascii_lowercase()),
+        _ => (text, 's'),
+    };
+    let scale = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        _ => 0,
+    };
+    match number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+    {
+        Some(secs) if secs > MAX_TTL => Err(format!(
+            "'{}' is longer than the {} an agent may run",
+            text,
+            format_ttl(MAX_TTL)
+        )),
+        Some(secs) if secs > 0 => Ok(secs),
+        _ => Err(format!("'{}' is not a duration like 30m, 8h or 1d", text)),
+    }
+}
+
+/// A TTL in the largest unit that divides it, the way it was most likely given.
+pub fn format_ttl(secs: u64) -> String {
+    [("d", 86400), ("h", 3600), ("m", 60)]
+        .into_iter()
+        .find(|(_, scale)| secs >= *scale && secs.is_multiple_of(*scale))
+        .map_or(format!("{}s", secs), |(unit, scale)| {
+            format!("{}{}", secs / scale, unit)
+        })
+}
+
+/// Directory holding the agent's socket, private to the user: the session's
+/// runtime directory when there is one, the temp directory otherwise.
+fn socket_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) => PathBuf::from(runtime).join("gitas-agent"),
+        None => {
+            // Named after the user, since the temp directory may be shared.
+            let name = match std::env::var("USER").or_else(|_| std::env::var("LOGNAME")) {
+                Ok(user) if !user.is_empty() => format!("gitas-agent-{}", user),
+                _ => "gitas-agent".to_string(),
+            };
+            std::env::temp_dir().join(name)
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    socket_dir().join("socket")
+}
+
+/// Whether `dir` is a directory (not a symlink) of this user's that nobody
+/// else can enter. The temp directory is shared, so another user could make
+/// the agent's directory there first and answer with tokens of their own.
+#[cfg(unix)]
+fn is_private_dir(dir: &std::path::Path) -> bool {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    let uid = unsafe { libc::geteuid() };
+    std::fs::symlink_metadata(dir).is_ok_and(|meta| {
+        meta.is_dir() && meta.uid() == uid && meta.permissions().mode() & 0o777 == 0o700
+    })
+}
+
+/// Send one request line and read the reply. None when no agent answers.
+#[cfg(unix)]
+fn ask(request: &str) -> Option<Zeroizing<String>> {
+    use std::io::{Read, Write};
+    if !is_private_dir(&socket_dir()) {
+        return None;
+    }
+    let mut stream = std::os::unix::net::UnixStream::connect(socket_path()).ok()?;
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(2)))
+        .ok()?;
+    stream.write_all(format!("{}\n", request).as_bytes()).ok()?;
+    let mut reply = Zeroizing::new(String::new());
+    stream.read_to_string(&mut reply).ok()?;
+    Some(reply)
+}
+
+#[cfg(not(unix))]
+fn ask(_request: &str) -> Option<Zeroizing<String>> {
+    None
+}
+
+/// The account's token: from the agent while one runs, so the keychain isn't
+/// asked on every git operation, otherwise from the keychain.
+pub fn token(account: &Account) -> Option<Zeroizing<String>> {
+    let key = make_key(&account.username, account.alias.as_deref());
+    ask(&format!("get {}", key))
+        .map(|reply| Zeroizing::new(reply.trim_end_matches('\n').to_string()))
+        .filter(|token| !token.is_empty())
+        .or_else(|| get_token(&account.username, account.alias.as_deref()))
+}
+
+/// Make the agent drop a token that changed in the keychain.
+pub fn forget(key: &str) {
+    ask(&format!("forget {}", key));
+}
+
+/// Tokens held and seconds left, when an agent is running.
+pub fn status() -> Option<(usize, u64)> {
+    let reply = ask("status")?;
+    let (count, left) = reply.trim().split_once(' ')?;
+    Some((count.parse().ok()?, left.parse().ok()?))
+}
+
+pub fn stop() -> bool {
+    ask("stop").is_some()
+}
+
+/// Start an agent holding every account's token for `ttl` seconds. The
+/// keychain is read here, where it can prompt, and the tokens reach the
+/// background process on its stdin, never in argv or the environment.
+#[cfg(unix)]
+pub fn start(accounts: &[Account], ttl: u64) -> Result<usize, String> {
+    use std::io::Write;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let mut input = Zeroizing::new(String::new());
+    let mut count = 0;
+    for account in accounts.iter().filter(|a| !a.ssh_only) {
+        if let Some(token) =
+            get_token(&account.username, account.alias.as_deref()).filter(|t| !t.is_empty())
+        {
+            let key = make_key(&account.username, account.alias.as_deref());
+            input.push_str(&format!("{}\t{}\n", key, token.as_str()));
+            count += 1;
+        }
+    }
+    if count == 0 {
+        return Err("No account has a token in the keychain".to_string());
+    }
+
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Cannot locate the gitas executable: {}", e))?;
+    // Its own process group, so closing the terminal doesn't stop it.
+    let mut child = Command::new(exe)
+        .args(["_token-agent", "--ttl", &ttl.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("Could not start the agent: {}", e))?;
+    child
+        .stdin
+        .take()
+        .ok_or("The agent has no stdin")?
+        .write_all(input.as_bytes())
+        .map_err(|e| format!("Could not hand the tokens to the agent: {}", e))?;
+
+    for _ in 0..40 {
+        if status().is_some() {
+            return Ok(count);
+        }
+        if let Ok(Some(exit)) = child.try_wait() {
+            return Err(format!("The agent exited ({})", exit));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    Err("The agent did not come up".to_string())
+}
+
+#[cfg(not(unix))]
+pub fn start(_accounts: &[Account], _ttl: u64) -> Result<usize, String> {
+    Err("The token agent needs Unix sockets".to_string())
+}
+
+/// `gitas _token-agent --ttl N`, started by [`start`]: read `key\ttoken`
+/// lines from stdin, then answer on the socket until `ttl` seconds pass or
+/// it is told to stop. Tokens are wiped on the way out.
+#[cfg(unix)]
+pub fn serve(ttl: u64) {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::UnixListener;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    let mut input = Zeroizing::new(String::new());
+    if std::io::stdin().read_to_string(&mut input).is_err() {
+        return;
+    }
+    let tokens: HashMap<String, Zeroizing<String>> = input
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(key, token)| (key.to_string(), Zeroizing::new(token.to_string())))
+        .collect();
+    let tokens = Arc::new(Mutex::new(tokens));
+
+    let dir = socket_dir();
+    if std::fs::DirBuilder::new().mode(0o700).create(&dir).is_ok() {
+        // The umask may have taken bits off; make it exactly 0700.
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).ok();
+    }
+    // Reuse our own directory, never one others own or can get into.
+    if !is_private_dir(&dir) {
+        return;
+    }
+    let path = socket_path();
+    std::fs::remove_file(&path).ok();
+    let Ok(listener) = UnixListener::bind(&path) else {
+        return;
+    };
+
+    let shutdown = {
+        let tokens = Arc::clone(&tokens);
+        let path = path.clone();
+        move || {
+            if let Ok(mut tokens) = tokens.lock() {
+                tokens.clear();
+            }
+            std::fs::remove_file(&path).ok();
+            std::process::exit(0);
+        }
+    };
+    // `agent_ttl` may have been edited by hand past what `parse_ttl` allows.
+    let ttl = ttl.min(MAX_TTL);
+    let deadline = Instant::now() + Duration::from_secs(ttl);
+    {
+        let shutdown = shutdown.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(ttl));
+            shutdown();
+        });
+    }
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        // Requests are handled one at a time, so a client that connects and
+        // says nothing must not hold the others up.
+        if stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .is_err()
+        {
+            continue;
+        }
+        let mut line = Zeroizing::new(String::new());
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            continue;
+        }
+        let (command, key) = line
+            .trim_end()
+            .split_once(' ')
+            .unwrap_or((line.trim_end(), ""));
+        let reply = match command {
+            "get" => tokens
+                .lock()
+                .ok()
+                .and_then(|tokens| tokens.get(key).cloned())
+                .unwrap_or_default(),
+            "forget" => {
+                if let Ok(mut tokens) = tokens.lock() {
+                    tokens.remove(key);
+                }
+                Zeroizing::new(String::new())
+            }
+            "status" => Zeroizing::new(format!(
+                "{} {}",
+                tokens.lock().map_or(0, |tokens| tokens.len()),
+                deadline.saturating_duration_since(Instant::now()).as_secs()
+            )),
+            "stop" => {
+                stream.write_all(b"ok\n").ok();
+                shutdown();
+                continue;
+            }
+            _ => Zeroizing::new(String::new()),
+        };
+        stream.write_all(reply.as_bytes()).ok();
+        stream.write_all(b"\n").ok();
+    }
+}
+
+#[cfg(not(unix))]
+pub fn serve(_ttl: u64) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_durations() {
+        assert_eq!(parse_ttl("90"), Ok(90));
+        assert_eq!(parse_ttl("30m"), Ok(1800));
+        assert_eq!(parse_ttl("8H"), Ok(DEFAULT_TTL));
+        assert!(parse_ttl("0h").is_err());
+        assert!(parse_ttl("8w").is_err());
+        assert!(parse_ttl("").is_err());
+        assert_eq!(parse_ttl("7d"), Ok(MAX_TTL));
+        assert!(parse_ttl("8d").is_err());
+        assert!(parse_ttl("18446744073709551615d").is_err());
+        assert_eq!(format_ttl(DEFAULT_TTL), "8h");
+        assert_eq!(format_ttl(90), "90s");
+        assert_eq!(format_ttl(86400 * 2), "2d");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_uses_a_private_directory() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("gitas-agent-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let set_mode = |mode| std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(mode));
+        set_mode(0o700).unwrap();
+        assert!(is_private_dir(&dir));
+        set_mode(0o755).unwrap();
+        assert!(!is_private_dir(&dir));
+        let link = dir.with_extension("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        set_mode(0o700).unwrap();
+        assert!(!is_private_dir(&link));
+        std::fs::remove_file(&link).ok();
+        std::fs::remove_dir(&dir).ok();
+    }
+}
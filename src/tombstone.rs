@@ -0,0 +1,196 @@
+use crate::models::{
+    Account, delete_token_by_key, get_token_by_key, is_ephemeral, make_key, set_token_by_key,
+};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Grace period used when the config doesn't set `token_grace_days`.
+pub const DEFAULT_GRACE_DAYS: u64 = 7;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Token of a removed account, parked under its own keychain entry so an
+/// accidental removal can be undone with `gitas token gc --restore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Unix seconds.
+    pub removed_at: u64,
+    pub expires_at: u64,
+}
+
+impl Tombstone {
+    fn keychain_key(&self) -> String {
+        format!(
+            "removed::{}::{}",
+            self.removed_at,
+            make_key(&self.username, self.alias.as_deref())
+        )
+    }
+
+    /// The account as `username` or `username:alias`.
+    pub fn label(&self) -> String {
+        crate::audit::account_id(&Account {
+            username: self.username.clone(),
+            alias: self.alias.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// Whether `id` names this token's account, as username, alias or `username:alias`.
+    pub fn matches(&self, id: &str) -> bool {
+        self.username == id || self.alias.as_deref() == Some(id) || self.label() == id
+    }
+}
+
+fn tombstones_path() -> Option<PathBuf> {
+    let dir = dirs::data_local_dir()?.join("gitas");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("removed-tokens.json"))
+}
+
+pub fn load() -> Vec<Tombstone> {
+    tombstones_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save(tombstones: &[Tombstone]) {
+    if is_ephemeral() {
+        return;
+    }
+    if let Some(path) = tombstones_path()
+        && let Ok(data) = serde_json::to_string_pretty(tombstones)
+    {
+        let _ = fs::write(path, data);
+    }
+}
+
+/// Move a removed account's token aside for `days` days, or delete it when
+/// `days` is 0. Returns whether a token was kept. The original entry is only
+/// deleted once the parked copy reads back; if it doesn't, the token stays
+/// where it was.
+pub fn bury(account: &Account, days: u64) -> bool {
+    let key = make_key(&account.username, account.alias.as_deref());
+    let token = get_token_by_key(&key).filter(|t| !t.is_empty());
+    let (Some(token), true) = (token, days > 0) else {
+        delete_token_by_key(&key);
+        return false;
+    };
+
+    let now = crate::cache::now_secs();
+    let tombstone = Tombstone {
+        username: account.username.clone(),
+        alias: account.alias.clone(),
+        removed_at: now,
+        expires_at: now.saturating_add(days.saturating_mul(DAY_SECS)),
+    };
+    set_token_by_key(&tombstone.keychain_key(), &token);
+    if get_token_by_key(&tombstone.keychain_key()).as_deref() != Some(&*token) {
+        eprintln!(
+            "  {} Could not park the token of {}; it stays in the keychain as '{}'.",
+            "⚠".yellow(),
+            tombstone.label(),
+            key
+        );
+        return false;
+    }
+    delete_token_by_key(&key);
+    let mut tombstones = load();
+    tombstones.push(tombstone);
+    save(&tombstones);
+    true
+}
+
+/// Put the most recently removed token matching `id` back under its account key.
+pub fn restore(id: &str) -> Result<Tombstone, String> {
+    let mut tombstones = load();
+    let index = tombstones
+        .iter()
+        .rposition(|t| t.matches(id))
+        .ok_or_else(|| format!("No removed token for '{}'", id))?;
+    let tombstone = &tombstones[index];
+
+    let key = make_key(&tombstone.username, tombstone.alias.as_deref());
+    if get_token_by_key(&key).is_some_and(|t| !t.is_empty()) {
+        return Err(format!("'{}' already has a token in the keychain", key));
+    }
+    let token = get_token_by_key(&tombstone.keychain_key())
+        .ok_or_else(|| "The parked token is no longer in the keychain".to_string())?;
+    set_token_by_key(&key, &token);
+    delete_token_by_key(&tombstone.keychain_key());
+
+    let tombstone = tombstones.remove(index);
+    save(&tombstones);
+    Ok(tombstone)
+}
+
+/// Split tombstones into those to keep and those due for deletion at `now`.
+fn partition(tombstones: Vec<Tombstone>, now: u64, all: bool) -> (Vec<Tombstone>, Vec<Tombstone>) {
+    let (purge, keep) = tombstones
+        .into_iter()
+        .partition(|t| all || t.expires_at <= now);
+    (keep, purge)
+}
+
+/// Delete expired parked tokens (or all of them). Returns `(kept, deleted)`.
+pub fn gc(all: bool) -> (Vec<Tombstone>, Vec<Tombstone>) {
+    let (keep, purge) = partition(load(), crate::cache::now_secs(), all);
+    for tombstone in &purge {
+        delete_token_by_key(&tombstone.keychain_key());
+    }
+    if !purge.is_empty() {
+        save(&keep);
+    }
+    (keep, purge)
+}
+
+/// Whole days left before `tombstone` expires, rounded up.
+pub fn days_left(tombstone: &Tombstone) -> u64 {
+    tombstone
+        .expires_at
+        .saturating_sub(crate::cache::now_secs())
+        .div_ceil(DAY_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tombstone(username: &str, alias: Option<&str>, expires_at: u64) -> Tombstone {
+        Tombstone {
+            username: username.to_string(),
+            alias: alias.map(str::to_string),
+            removed_at: 0,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn partitions_by_expiry() {
+        let list = vec![tombstone("a", None, 10), tombstone("b", None, 20)];
+        let (keep, purge) = partition(list.clone(), 15, false);
+        assert_eq!(keep.len(), 1);
+        assert_eq!(keep[0].username, "b");
+        assert_eq!(purge[0].username, "a");
+
+        let (keep, purge) = partition(list, 15, true);
+        assert!(keep.is_empty());
+        assert_eq!(purge.len(), 2);
+    }
+
+    #[test]
+    fn matches_username_alias_and_id() {
+        let t = tombstone("octo", Some("work"), 0);
+        assert!(t.matches("octo"));
+        assert!(t.matches("work"));
+        assert!(t.matches("octo:work"));
+        assert!(!t.matches("other"));
+        assert_eq!(t.keychain_key(), "removed::0::octo::work");
+    }
+}
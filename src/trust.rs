@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+use ureq::tls::{Certificate, PemItem, RootCerts};
+
+static PINS: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Make the config's pinned certificates apply to API calls for this run,
+/// again after a pin changes.
+pub fn init(pins: &BTreeMap<String, String>) {
+    if let Ok(mut current) = PINS.write() {
+        *current = pins.clone();
+    }
+}
+
+/// SHA-256 fingerprint of a DER certificate, as `openssl x509 -fingerprint
+/// -sha256` prints it: uppercase hex pairs joined by colons.
+pub fn fingerprint(der: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, der)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// A fingerprint as users paste it (`sha256//`, `SHA256:` prefixes, any case,
+/// with or without colons) in the form `fingerprint` returns. None when it
+/// isn't 32 bytes of hex.
+pub fn normalize_fingerprint(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value
+        .split_once('=')
+        .map_or(value, |(_, hex)| hex)
+        .trim_start_matches("sha256//")
+        .trim_start_matches("SHA256:")
+        .trim_start_matches("sha256:");
+    let hex: String = value.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = hex.to_uppercase();
+    Some(
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| String::from_utf8_lossy(pair).to_string())
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// Where the pinned certificate for `host` is kept, also the file git's
+/// `http.sslCAInfo` should point at.
+pub fn cert_path(host: &str) -> PathBuf {
+    let name: String = host
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    crate::models::config_path()
+        .parent()
+        .map(|dir| dir.join("certs"))
+        .unwrap_or_else(|| PathBuf::from("certs"))
+        .join(format!("{}.pem", name))
+}
+
+/// Certificates in PEM text, in order, each with its own PEM block.
+pub fn parse_certs(text: &str) -> Vec<(String, Certificate<'static>)> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certs = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(BEGIN)
+        && let Some(len) = rest[start..].find(END)
+    {
+        let block = format!("{}\n", &rest[start..start + len + END.len()]);
+        rest = &rest[start + len + END.len()..];
+        let cert = match ureq::tls::parse_pem(block.as_bytes()).next() {
+            Some(Ok(PemItem::Certificate(cert))) => Some(cert),
+            _ => None,
+        };
+        if let Some(cert) = cert {
+            certs.push((block, cert));
+        }
+    }
+    certs
+}
+
+/// Root certificates for API calls to `host`: None to use the usual trust
+/// store, or only the pinned certificate. An error when the pinned file is
+/// missing or no longer has the recorded fingerprint, so a swapped file can't
+/// quietly widen trust.
+pub fn roots_for(host: &str) -> Result<Option<RootCerts>, String> {
+    let Some(expected) = PINS
+        .read()
+        .ok()
+        .and_then(|pins| pins.get(&crate::host::normalize(host)).cloned())
+    else {
+        return Ok(None);
+    };
+    let path = cert_path(host);
+    let pem = std::fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "Pinned certificate for {} is unreadable ({}): {}",
+            host,
+            path.display(),
+            e
+        )
+    })?;
+    let (_, cert) = parse_certs(&pem)
+        .into_iter()
+        .find(|(_, cert)| fingerprint(cert.der()) == expected)
+        .ok_or_else(|| {
+            format!(
+                "{} no longer holds the certificate pinned for {}. Pin it again with `gitas cert pin {}`",
+                path.display(),
+                host,
+                host
+            )
+        })?;
+    Ok(Some(RootCerts::Specific(Arc::new(vec![cert]))))
+}
+
+/// One certificate a server presented, with the subject openssl showed for it.
+pub struct Presented {
+    pub subject: String,
+    pub pem: String,
+    pub cert: Certificate<'static>,
+}
+
+/// The chain `host` presents, fetched with `openssl s_client` since the TLS
+/// stack here never hands out an unverified chain.
+pub fn fetch_chain(host: &str) -> Result<Vec<Presented>, String> {
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:443", host)
+    };
+    let name = host.split(':').next().unwrap_or(host);
+    let output = Command::new("openssl")
+        .args([
+            "s_client",
+            "-showcerts",
+            "-connect",
+            &address,
+            "-servername",
+            name,
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run openssl to fetch the certificate: {}", e))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let subjects: Vec<String> = text
+        .lines()
+        .filter_map(|line| line.trim_start().split_once(" s:"))
+        .filter(|(index, _)| index.trim().chars().all(|c| c.is_ascii_digit()))
+        .map(|(_, subject)| subject.trim().to_string())
+        .collect();
+    let certs = parse_certs(&text);
+    if certs.is_empty() {
+        return Err(format!(
+            "{} did not present a certificate over TLS",
+            address
+        ));
+    }
+    Ok(certs
+        .into_iter()
+        .enumerate()
+        .map(|(i, (pem, cert))| Presented {
+            subject: subjects
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| format!("certificate {}", i)),
+            pem,
+            cert,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_fingerprints_and_splits_pem() {
+        let canonical = fingerprint(b"not really a certificate");
+        assert_eq!(canonical.len(), 95);
+        let bare = canonical.replace(':', "").to_lowercase();
+        assert_eq!(
+            normalize_fingerprint(&bare).as_deref(),
+            Some(canonical.as_str())
+        );
+        assert_eq!(
+            normalize_fingerprint(&format!("sha256 Fingerprint={}", canonical)).as_deref(),
+            Some(canonical.as_str())
+        );
+        assert_eq!(normalize_fingerprint("AB:CD"), None);
+
+        let pem = "subject=CN = a\n-----BEGIN CERTIFICATE-----\nAAEC\n-----END CERTIFICATE-----\n\
+                   -----BEGIN CERTIFICATE-----\nAwQF\n-----END CERTIFICATE-----\n";
+        let parsed = parse_certs(pem);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].1.der(), [0, 1, 2]);
+        assert!(parsed[1].0.starts_with("-----BEGIN CERTIFICATE-----\nAwQF"));
+    }
+}
@@ -6,8 +6,10 @@ use crossterm::{
     terminal::{self, BeginSynchronizedUpdate, ClearType, EndSynchronizedUpdate},
 };
 use std::io::{Write, stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
 
 fn character_width(character: char) -> usize {
     match character as u32 {
@@ -126,6 +128,35 @@ fn status_display_duration_ms(line_count: usize, has_issue: bool) -> u64 {
     }
 }
 
+// Keys mean the same in every prompt: Esc (and `q` in menus) backs out of the
+// prompt, and the caller returns to the level it came from. Ctrl-C abandons the
+// whole flow: the prompt returns None like Esc, but every later prompt returns
+// None at once too, so nested menus unwind to the top, which restores the
+// terminal and exits.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C was pressed in a prompt.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Whether `key` backs out of a prompt: Esc, `q` in menus, or Ctrl-C, which
+/// also marks the flow as interrupted.
+fn backs_out(key: &event::KeyEvent, menu: bool) -> bool {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(event::KeyModifiers::CONTROL) {
+        INTERRUPTED.store(true, Ordering::Relaxed);
+        return true;
+    }
+    key.code == KeyCode::Esc || (menu && key.code == KeyCode::Char('q'))
+}
+
+/// Leave raw mode and exit after the user backed out of a top-level prompt:
+/// 130 after Ctrl-C, like a shell, 0 after Esc.
+pub fn exit_cancelled() -> ! {
+    exit_raw_mode();
+    std::process::exit(if interrupted() { 130 } else { 0 });
+}
+
 /// Enter raw mode and hide cursor.
 pub fn enter_raw_mode() {
     terminal::enable_raw_mode().ok();
@@ -256,8 +287,11 @@ pub fn raw_clear_lines(stdout: &mut impl Write, count: usize) {
     stdout.flush().ok();
 }
 
-/// Arrow-key select menu. Returns selected index or None on Esc.
+/// Arrow-key select menu. Returns selected index or None on Esc/Ctrl-C.
 pub fn raw_select(prompt: &str, items: &[String], default: usize) -> Option<usize> {
+    if interrupted() {
+        return None;
+    }
     let mut stdout = stdout();
     let mut pos = default;
     let mut prev_lines = 0;
@@ -293,11 +327,72 @@ pub fn raw_select(prompt: &str, items: &[String], default: usize) -> Option<usiz
                 raw_clear_lines(&mut stdout, prev_lines);
                 return Some(pos);
             }
-            KeyCode::Esc | KeyCode::Char('q') => {
+            _ if backs_out(&key, true) => {
                 raw_clear_lines(&mut stdout, prev_lines);
                 return None;
             }
-            KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            _ => {}
+        }
+    }
+}
+
+/// Checkbox list: Space toggles the row, `a` toggles every row, Enter
+/// confirms. Returns the checked indices, or None on Esc/Ctrl-C.
+pub fn raw_multi_select(prompt: &str, items: &[String], checked: &[usize]) -> Option<Vec<usize>> {
+    if interrupted() {
+        return None;
+    }
+    let mut stdout = stdout();
+    let mut selected: Vec<bool> = (0..items.len()).map(|i| checked.contains(&i)).collect();
+    let mut pos = checked.first().copied().unwrap_or(0);
+    let mut prev_lines = 0;
+
+    loop {
+        let mut lines = Vec::new();
+        lines.push(format!(
+            "  {} {}",
+            prompt,
+            "(Space toggle · a all · Enter confirm)".dimmed()
+        ));
+        for (i, item) in items.iter().enumerate() {
+            let mark = if selected[i] {
+                "[x]".green().to_string()
+            } else {
+                "[ ]".dimmed().to_string()
+            };
+            if i == pos {
+                lines.push(format!("  {} {} {}", ">".yellow().bold(), mark, item));
+            } else {
+                lines.push(format!("    {} {}", mark, item));
+            }
+        }
+
+        raw_render_lines(&mut stdout, &lines, prev_lines);
+        prev_lines = lines.len();
+
+        let Ok(Event::Key(key)) = event::read() else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                pos = if pos == 0 { items.len() - 1 } else { pos - 1 };
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                pos = (pos + 1) % items.len();
+            }
+            KeyCode::Char(' ') => selected[pos] = !selected[pos],
+            KeyCode::Char('a') => {
+                let all = selected.iter().all(|&s| s);
+                selected.iter_mut().for_each(|s| *s = !all);
+            }
+            KeyCode::Enter => {
+                raw_clear_lines(&mut stdout, prev_lines);
+                return Some((0..items.len()).filter(|&i| selected[i]).collect());
+            }
+            _ if backs_out(&key, true) => {
                 raw_clear_lines(&mut stdout, prev_lines);
                 return None;
             }
@@ -306,8 +401,11 @@ pub fn raw_select(prompt: &str, items: &[String], default: usize) -> Option<usiz
     }
 }
 
-/// y/n confirmation. Returns Some(bool) or None on Esc.
+/// y/n confirmation. Returns Some(bool) or None on Esc/Ctrl-C.
 pub fn raw_confirm(prompt: &str, default: bool) -> Option<bool> {
+    if interrupted() {
+        return None;
+    }
     let mut stdout = stdout();
     let hint = if default { "[Y/n]" } else { "[y/N]" };
     let line = format!("  {} {}", prompt, hint.dimmed());
@@ -340,11 +438,7 @@ pub fn raw_confirm(prompt: &str, default: bool) -> Option<bool> {
                 raw_clear_lines(&mut stdout, 1);
                 return Some(default);
             }
-            KeyCode::Esc | KeyCode::Char('q') => {
-                raw_clear_lines(&mut stdout, 1);
-                return None;
-            }
-            KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            _ if backs_out(&key, true) => {
                 raw_clear_lines(&mut stdout, 1);
                 return None;
             }
@@ -353,8 +447,11 @@ pub fn raw_confirm(prompt: &str, default: bool) -> Option<bool> {
     }
 }
 
-/// Text input with default. Returns Some(value) on Enter, None on Esc.
+/// Text input with default. Returns Some(value) on Enter, None on Esc/Ctrl-C.
 pub fn raw_input(prompt: &str, default: &str) -> Option<String> {
+    if interrupted() {
+        return None;
+    }
     let mut stdout = stdout();
     let mut value = default.to_string();
     let mut cursor_index = value.len();
@@ -391,7 +488,7 @@ pub fn raw_input(prompt: &str, default: &str) -> Option<String> {
                 execute!(stdout, cursor::Hide).ok();
                 return Some(value);
             }
-            KeyCode::Esc => {
+            _ if backs_out(&key, false) => {
                 crossterm::queue!(
                     stdout,
                     cursor::MoveToColumn(0),
@@ -401,28 +498,20 @@ pub fn raw_input(prompt: &str, default: &str) -> Option<String> {
                 execute!(stdout, cursor::Hide).ok();
                 return None;
             }
-            KeyCode::Backspace => {
-                if cursor_index > 0 {
-                    let prev_index = prev_char_boundary(&value, cursor_index);
-                    value.drain(prev_index..cursor_index);
-                    cursor_index = prev_index;
-                }
+            KeyCode::Backspace if cursor_index > 0 => {
+                let prev_index = prev_char_boundary(&value, cursor_index);
+                value.drain(prev_index..cursor_index);
+                cursor_index = prev_index;
             }
-            KeyCode::Delete => {
-                if cursor_index < value.len() {
-                    let next_index = next_char_boundary(&value, cursor_index);
-                    value.drain(cursor_index..next_index);
-                }
+            KeyCode::Delete if cursor_index < value.len() => {
+                let next_index = next_char_boundary(&value, cursor_index);
+                value.drain(cursor_index..next_index);
             }
-            KeyCode::Left => {
-                if cursor_index > 0 {
-                    cursor_index = prev_char_boundary(&value, cursor_index);
-                }
+            KeyCode::Left if cursor_index > 0 => {
+                cursor_index = prev_char_boundary(&value, cursor_index);
             }
-            KeyCode::Right => {
-                if cursor_index < value.len() {
-                    cursor_index = next_char_boundary(&value, cursor_index);
-                }
+            KeyCode::Right if cursor_index < value.len() => {
+                cursor_index = next_char_boundary(&value, cursor_index);
             }
             KeyCode::Home => {
                 cursor_index = 0;
@@ -434,16 +523,6 @@ pub fn raw_input(prompt: &str, default: &str) -> Option<String> {
                 value.clear();
                 cursor_index = 0;
             }
-            KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                crossterm::queue!(
-                    stdout,
-                    cursor::MoveToColumn(0),
-                    terminal::Clear(ClearType::CurrentLine),
-                )
-                .ok();
-                execute!(stdout, cursor::Hide).ok();
-                return None;
-            }
             KeyCode::Char(c) => {
                 value.insert(cursor_index, c);
                 cursor_index += c.len_utf8();
@@ -454,9 +533,14 @@ pub fn raw_input(prompt: &str, default: &str) -> Option<String> {
 }
 
 /// Password input (masked). Returns Some(value) or None.
-pub fn raw_password(prompt: &str) -> Option<String> {
+/// Masked input; the buffer is wiped on drop and pre-sized so pasting a token
+/// doesn't leave copies behind in reallocated memory.
+pub fn raw_password(prompt: &str) -> Option<Zeroizing<String>> {
+    if interrupted() {
+        return None;
+    }
     let mut stdout = stdout();
-    let mut value = String::new();
+    let mut value = Zeroizing::new(String::with_capacity(256));
     let mut cursor_index = 0;
 
     execute!(stdout, cursor::Show).ok();
@@ -491,7 +575,7 @@ pub fn raw_password(prompt: &str) -> Option<String> {
                 execute!(stdout, cursor::Hide).ok();
                 return Some(value);
             }
-            KeyCode::Esc => {
+            _ if backs_out(&key, false) => {
                 crossterm::queue!(
                     stdout,
                     cursor::MoveToColumn(0),
@@ -501,28 +585,20 @@ pub fn raw_password(prompt: &str) -> Option<String> {
                 execute!(stdout, cursor::Hide).ok();
                 return None;
             }
-            KeyCode::Backspace => {
-                if cursor_index > 0 {
-                    let prev_index = prev_char_boundary(&value, cursor_index);
-                    value.drain(prev_index..cursor_index);
-                    cursor_index = prev_index;
-                }
+            KeyCode::Backspace if cursor_index > 0 => {
+                let prev_index = prev_char_boundary(&value, cursor_index);
+                value.drain(prev_index..cursor_index);
+                cursor_index = prev_index;
             }
-            KeyCode::Delete => {
-                if cursor_index < value.len() {
-                    let next_index = next_char_boundary(&value, cursor_index);
-                    value.drain(cursor_index..next_index);
-                }
+            KeyCode::Delete if cursor_index < value.len() => {
+                let next_index = next_char_boundary(&value, cursor_index);
+                value.drain(cursor_index..next_index);
             }
-            KeyCode::Left => {
-                if cursor_index > 0 {
-                    cursor_index = prev_char_boundary(&value, cursor_index);
-                }
+            KeyCode::Left if cursor_index > 0 => {
+                cursor_index = prev_char_boundary(&value, cursor_index);
             }
-            KeyCode::Right => {
-                if cursor_index < value.len() {
-                    cursor_index = next_char_boundary(&value, cursor_index);
-                }
+            KeyCode::Right if cursor_index < value.len() => {
+                cursor_index = next_char_boundary(&value, cursor_index);
             }
             KeyCode::Home => {
                 cursor_index = 0;
@@ -534,16 +610,6 @@ pub fn raw_password(prompt: &str) -> Option<String> {
                 value.clear();
                 cursor_index = 0;
             }
-            KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                crossterm::queue!(
-                    stdout,
-                    cursor::MoveToColumn(0),
-                    terminal::Clear(ClearType::CurrentLine),
-                )
-                .ok();
-                execute!(stdout, cursor::Hide).ok();
-                return None;
-            }
             KeyCode::Char(c) => {
                 value.insert(cursor_index, c);
                 cursor_index += c.len_utf8();
@@ -14,6 +14,9 @@ pub fn check_git_installed() {
 }
 
 pub fn git_config_set(key: &str, value: &str, scope: &str) {
+    if crate::models::is_ephemeral() {
+        return;
+    }
     let scope_flag = if scope == "local" {
         "--local"
     } else {
@@ -30,6 +33,9 @@ pub fn git_config_set(key: &str, value: &str, scope: &str) {
 }
 
 pub fn git_config_unset(key: &str, scope: &str) {
+    if crate::models::is_ephemeral() {
+        return;
+    }
     let scope_flag = if scope == "local" {
         "--local"
     } else {
@@ -56,9 +62,12 @@ pub fn git_config_get(key: &str, scope: &str) -> Option<String> {
     }
 }
 
-pub fn git_toplevel() -> Option<String> {
+/// Effective config value as seen from inside `dir`.
+pub fn git_config_get_at(dir: &std::path::Path, key: &str) -> Option<String> {
     let output = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
+        .arg("-C")
+        .arg(dir)
+        .args(["config", "--get", key])
         .output()
         .ok()?;
     if output.status.success() {
@@ -69,6 +78,53 @@ pub fn git_toplevel() -> Option<String> {
     }
 }
 
+pub fn git_toplevel() -> Option<String> {
+    git_toplevel_at(std::path::Path::new("."))
+}
+
+/// Root of the work tree containing `dir`, or the repository itself when
+/// it is bare and has no work tree.
+pub fn git_toplevel_at(dir: &std::path::Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    if output.status.success() {
+        let val = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return if val.is_empty() { None } else { Some(val) };
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--is-bare-repository", "--absolute-git-dir"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    match text.lines().collect::<Vec<_>>().as_slice() {
+        ["true", git_dir] if output.status.success() => Some(git_dir.to_string()),
+        _ => None,
+    }
+}
+
+/// Short name of the checked-out branch; `None` when HEAD is detached.
+pub fn git_current_branch() -> Option<String> {
+    git_current_branch_at(std::path::Path::new("."))
+}
+
+fn git_current_branch_at(dir: &std::path::Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !branch.is_empty()).then_some(branch)
+}
+
+#[derive(Clone)]
 pub struct Remote {
     pub name: String,
     pub url: String,
@@ -78,8 +134,14 @@ pub fn is_http_url(url: &str) -> bool {
     url.starts_with("http://") || url.starts_with("https://")
 }
 
-pub fn get_http_remotes() -> Vec<Remote> {
+pub fn get_remotes() -> Vec<Remote> {
+    get_remotes_at(std::path::Path::new("."))
+}
+
+pub fn get_remotes_at(dir: &std::path::Path) -> Vec<Remote> {
     let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(dir)
         .args(["config", "--get-regexp", r"remote\..*\.url"])
         .output()
     else {
@@ -97,7 +159,7 @@ pub fn get_http_remotes() -> Vec<Remote> {
             let key = parts.next()?;
             let url = parts.next()?;
 
-            if key.starts_with("remote.") && key.ends_with(".url") && is_http_url(url) {
+            if key.starts_with("remote.") && key.ends_with(".url") {
                 let name = key
                     .trim_start_matches("remote.")
                     .trim_end_matches(".url")
@@ -113,6 +175,93 @@ pub fn get_http_remotes() -> Vec<Remote> {
         .collect()
 }
 
+/// The remote commands default to: the branch's upstream remote, then
+/// `origin`, then the only remote there is. None when there are no remotes,
+/// or several and none of those.
+fn default_remote_of<'a>(remotes: &'a [Remote], upstream: Option<&str>) -> Option<&'a Remote> {
+    upstream
+        .and_then(|name| remotes.iter().find(|r| r.name == name))
+        .or_else(|| remotes.iter().find(|r| r.name == "origin"))
+        .or(match remotes {
+            [only] => Some(only),
+            _ => None,
+        })
+}
+
+/// [`default_remote_of`] for the repository at `dir`.
+pub fn default_remote_at(dir: &std::path::Path) -> Option<Remote> {
+    let upstream = git_current_branch_at(dir)
+        .and_then(|branch| git_config_get_at(dir, &format!("branch.{}.remote", branch)));
+    default_remote_of(&get_remotes_at(dir), upstream.as_deref()).cloned()
+}
+
+/// The default remote of the current repository, or one the user picks when
+/// several leave it open. None when there are no remotes, or the choice
+/// can't be asked (no terminal, strict mode) or was cancelled.
+pub fn choose_remote(prompt: &str) -> Option<Remote> {
+    if let Some(remote) = default_remote_at(std::path::Path::new(".")) {
+        return Some(remote);
+    }
+    let remotes = get_remotes();
+    if remotes.is_empty()
+        || crate::models::is_strict()
+        || !std::io::IsTerminal::is_terminal(&std::io::stdin())
+    {
+        return None;
+    }
+    let items: Vec<String> = remotes
+        .iter()
+        .map(|r| format!("{} ({})", r.name, r.url))
+        .collect();
+    enter_raw_mode();
+    let choice = raw_select(prompt, &items, 0);
+    exit_raw_mode();
+    choice.map(|index| remotes[index].clone())
+}
+
+pub fn get_http_remotes() -> Vec<Remote> {
+    get_remotes()
+        .into_iter()
+        .filter(|remote| is_http_url(&remote.url))
+        .collect()
+}
+
+/// Host and repository path (`owner/repo`) of a remote, from any of git's
+/// https/ssh/scp-like forms. Ports are dropped: SSH ports never match the web port.
+/// Azure DevOps remotes all come out as `dev.azure.com` and
+/// `org/project/_git/repo`, their HTTPS form.
+pub fn parse_remote_url(url: &str) -> Option<(String, String)> {
+    let (host, path) = if let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("ssh://"))
+        .or_else(|| url.strip_prefix("git://"))
+    {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+        let host = host.split_once(':').map_or(host, |(h, _)| h);
+        (host, path)
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+        (host, path)
+    };
+
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some(
+        crate::provider::canonical_remote(host, path)
+            .unwrap_or_else(|| (host.to_string(), path.to_string())),
+    )
+}
+
+/// Browser URL for a remote.
+pub fn remote_web_url(url: &str) -> Option<String> {
+    parse_remote_url(url).map(|(host, path)| format!("https://{}/{}", host, path))
+}
+
 pub fn has_http_remotes() -> bool {
     !get_http_remotes().is_empty()
 }
@@ -122,9 +271,86 @@ pub fn git_args_use_http_transport(args: &[String]) -> bool {
         || (git_args_may_use_configured_remote(args) && has_http_remotes())
 }
 
-pub fn git_ssh_command(ssh_key: &str) -> String {
-    let normalized_path = ssh_key.replace('\\', "/");
-    format!("ssh -i \"{}\" -o IdentitiesOnly=yes", normalized_path)
+/// `ssh://` URLs and scp-like `user@host:path` remotes.
+pub fn is_ssh_url(url: &str) -> bool {
+    url.starts_with("ssh://")
+        || (!url.contains("://")
+            && url
+                .split_once(':')
+                .is_some_and(|(authority, _)| authority.contains('@') && !authority.contains('/')))
+}
+
+pub fn git_args_use_ssh_transport(args: &[String]) -> bool {
+    args.iter().any(|arg| is_ssh_url(arg))
+        || (git_args_may_use_configured_remote(args)
+            && get_remotes().iter().any(|remote| is_ssh_url(&remote.url)))
+}
+
+/// Quote a word for the POSIX shell git runs `core.sshCommand` and `!` helpers
+/// through. Plain paths keep the double-quoted form older configs were written
+/// with; anything the shell would expand is single-quoted instead.
+pub fn shell_quote(value: &str) -> String {
+    if !value.contains(['"', '$', '`', '\\', '!']) {
+        format!("\"{}\"", value)
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Inline credential helper that answers `get` from the environment, so
+/// usernames and tokens never go through shell parsing or show up in `ps`.
+pub const CREDENTIAL_HELPER: &str = "!f() { if [ \"$1\" = get ]; then printf 'username=%s\\npassword=%s\\n' \"$GITAS_CREDENTIAL_USERNAME\" \"$GITAS_CREDENTIAL_PASSWORD\"; fi; }; f";
+
+/// Environment that feeds [`CREDENTIAL_HELPER`].
+pub fn credential_env(username: &str, token: &str) -> [(&'static str, String); 2] {
+    [
+        ("GITAS_CREDENTIAL_USERNAME", username.to_string()),
+        ("GITAS_CREDENTIAL_PASSWORD", token.to_string()),
+    ]
+}
+
+/// Config that stops git from asking for credentials in strict mode.
+pub const STRICT_GIT_CONFIG: [(&str, &str); 2] =
+    [("credential.interactive", "false"), ("core.askPass", "")];
+
+/// Environment half of strict mode: no terminal prompts, no askpass
+/// programs, and Git Credential Manager kept from opening its own windows.
+pub fn apply_strict_env(cmd: &mut Command) {
+    cmd.env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .env_remove("GIT_ASKPASS")
+        .env_remove("SSH_ASKPASS");
+}
+
+/// Pass config entries to a child through `GIT_CONFIG_COUNT`/`KEY`/`VALUE`,
+/// appending to any entries this process already inherited.
+pub fn append_git_config_env(cmd: &mut Command, entries: &[(&str, &str)]) {
+    let base = std::env::var("GIT_CONFIG_COUNT")
+        .ok()
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(0);
+    for (offset, (key, value)) in entries.iter().enumerate() {
+        cmd.env(format!("GIT_CONFIG_KEY_{}", base + offset), key)
+            .env(format!("GIT_CONFIG_VALUE_{}", base + offset), value);
+    }
+    cmd.env("GIT_CONFIG_COUNT", (base + entries.len()).to_string());
+}
+
+/// Reject names and emails git would mangle: it strips `<`, `>` and newlines
+/// from identities, and the credential protocol is line-based.
+pub fn validate_identity(name: &str, email: &str) -> Result<(), String> {
+    for (field, value) in [("Username", name), ("Email", email)] {
+        if value.trim().is_empty() {
+            return Err(format!("{} must not be empty", field));
+        }
+        if value.contains(['<', '>']) || value.chars().any(char::is_control) {
+            return Err(format!(
+                "{} must not contain '<', '>' or control characters",
+                field
+            ));
+        }
+    }
+    Ok(())
 }
 
 fn git_args_may_use_configured_remote(args: &[String]) -> bool {
@@ -139,9 +365,14 @@ fn git_args_may_use_configured_remote(args: &[String]) -> bool {
 }
 
 fn git_subcommand(args: &[String]) -> Option<&str> {
-    let mut iter = args.iter().map(String::as_str);
+    git_subcommand_index(args).map(|index| args[index].as_str())
+}
 
-    while let Some(arg) = iter.next() {
+/// Position of the git subcommand in `args`, skipping git's global options.
+pub(crate) fn git_subcommand_index(args: &[String]) -> Option<usize> {
+    let mut iter = args.iter().map(String::as_str).enumerate();
+
+    while let Some((index, arg)) = iter.next() {
         if matches!(
             arg,
             "-C" | "-c" | "--exec-path" | "--git-dir" | "--work-tree"
@@ -169,7 +400,7 @@ fn git_subcommand(args: &[String]) -> Option<&str> {
             continue;
         }
 
-        return Some(arg);
+        return Some(index);
     }
 
     None
@@ -190,6 +421,26 @@ pub fn check_credential_helper() -> Option<String> {
     }
 }
 
+/// Warn when a token actually belongs to a different login than the account's
+/// username. Lookup failures stay silent; this is advisory only.
+pub fn check_token_owner(account: &Account, token: &str) -> Option<String> {
+    if !crate::provider::for_account(account).checks_username() {
+        return None;
+    }
+    let login = crate::provider::token_login(account, token).ok()?;
+    if login.eq_ignore_ascii_case(&account.username) {
+        return None;
+    }
+    Some(format!(
+        "  {} {} token belongs to '{}', not '{}'. Pushes will be attributed to '{}'.",
+        "⚠".yellow().bold(),
+        "MISMATCH:".red().bold(),
+        redact_name(&login).yellow(),
+        redact_name(&account.username).cyan(),
+        redact_name(&login).yellow()
+    ))
+}
+
 pub fn git_credential_approve(
     username: &str,
     token: &str,
@@ -222,13 +473,199 @@ pub fn git_credential_approve(
     }
 }
 
+/// Erase what git's credential helpers store for `username` on `host`, under
+/// every path. Helpers that hold nothing for it succeed silently.
+pub fn git_credential_reject(username: &str, host: &str) -> Result<(), String> {
+    use std::io::Write;
+    let input = format!("protocol=https\nhost={host}\nusername={username}\n\n");
+    let mut child = Command::new("git")
+        .args(["credential", "reject"])
+        .stdin(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute git credential reject: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).ok();
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait for git credential reject: {}", e))?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        Err(format!("Failed to reject git credential: {}", err.trim()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Username the credential helper chain now returns for a host (or URL),
+/// without ever prompting. `None` when no helper answers.
+pub fn git_credential_fill(host: &str, url: Option<&str>) -> Option<String> {
+    use std::io::Write;
+    let input = match url {
+        Some(u) => format!("url={u}\n\n"),
+        None => format!("protocol=https\nhost={host}\n\n"),
+    };
+    // Git Credential Manager opens its own sign-in window unless told not to.
+    let mut child = Command::new("git")
+        .args([
+            "-c",
+            "core.askPass=",
+            "-c",
+            "credential.interactive=never",
+            "credential",
+            "fill",
+        ])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .env_remove("GIT_ASKPASS")
+        .env_remove("SSH_ASKPASS")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .ok()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).ok();
+    }
+    let output = child.wait_with_output().ok()?;
+    // The reply carries the password too; wipe it once the username is out.
+    let stdout = zeroize::Zeroizing::new(output.stdout);
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("username="))
+        .map(str::to_string)
+}
+
 pub fn format_account_label(account: &Account) -> String {
+    let name = redact_name(&account.username);
+    let email = redact_email(&account.email);
     match &account.alias {
-        Some(alias) => format!("{}:{} <{}>", account.username, alias, account.email),
-        None => format!("{} <{}>", account.username, account.email),
+        Some(alias) => format!("{}:{} <{}>", name, alias, email),
+        None => format!("{} <{}>", name, email),
+    }
+}
+
+/// A username as it may be shown: in privacy mode only its first character
+/// (`j***`).
+pub fn redact_name(name: &str) -> String {
+    if !crate::models::is_redacted() {
+        return name.to_string();
+    }
+    mask_start(name)
+}
+
+/// An email as it may be shown: in privacy mode the local part is masked and
+/// the domain kept (`j***@acme.com`).
+pub fn redact_email(email: &str) -> String {
+    if !crate::models::is_redacted() {
+        return email.to_string();
+    }
+    mask_email(email)
+}
+
+/// An account id (`username` or `username:alias`) as it may be shown: in
+/// privacy mode the username is masked and the alias kept (`j***:work`).
+pub fn redact_id(id: &str) -> String {
+    if !crate::models::is_redacted() {
+        return id.to_string();
+    }
+    mask_id(id)
+}
+
+fn mask_id(id: &str) -> String {
+    match id.split_once(':') {
+        Some((username, alias)) => format!("{}:{}", mask_start(username), alias),
+        None => mask_start(id),
     }
 }
 
+fn mask_email(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((local, domain)) => format!("{}@{}", mask_start(local), domain),
+        None => mask_start(email),
+    }
+}
+
+fn mask_start(value: &str) -> String {
+    match value.chars().next() {
+        Some(first) => format!("{}***", first),
+        None => String::new(),
+    }
+}
+
+/// Environment variables that override `user.name`/`user.email` from any config.
+pub const IDENTITY_ENV_VARS: [&str; 4] = [
+    "GIT_AUTHOR_NAME",
+    "GIT_AUTHOR_EMAIL",
+    "GIT_COMMITTER_NAME",
+    "GIT_COMMITTER_EMAIL",
+];
+
+/// Identity overrides currently set in the environment.
+pub fn identity_env_overrides() -> Vec<(&'static str, String)> {
+    IDENTITY_ENV_VARS
+        .iter()
+        .filter_map(|&var| {
+            std::env::var(var)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .map(|v| (var, v))
+        })
+        .collect()
+}
+
+/// Find an account by username, alias, or `username:alias`.
+pub fn find_account<'a>(config: &'a Config, id: &str) -> Option<&'a Account> {
+    find_account_index(config, id).map(|index| &config.accounts[index])
+}
+
+pub fn find_account_index(config: &Config, id: &str) -> Option<usize> {
+    config.accounts.iter().position(|a| {
+        a.username == id
+            || a.alias.as_deref() == Some(id)
+            || a.alias
+                .as_ref()
+                .is_some_and(|alias| id == format!("{}:{}", a.username, alias))
+    })
+}
+
+/// The account whose id (`username` or `username:alias`) is exactly `id`, as
+/// stored for the default account and repository bindings.
+pub fn find_account_by_id<'a>(config: &'a Config, id: &str) -> Option<&'a Account> {
+    config
+        .accounts
+        .iter()
+        .find(|a| crate::audit::account_id(a) == id)
+}
+
+/// The account set with `gitas default`, if it still exists.
+pub fn default_account(config: &Config) -> Option<&Account> {
+    find_account_by_id(config, config.default_account.as_deref()?)
+}
+
+/// The managed account with this name and email, preferring the one whose alias
+/// matches when several share an identity.
+pub fn match_identity<'a>(
+    config: &'a Config,
+    name: &str,
+    email: &str,
+    alias: Option<&str>,
+) -> Option<&'a Account> {
+    let mut matches = config
+        .accounts
+        .iter()
+        .filter(|a| a.username == name && (a.email == email || crate::plus::owns(a, email)));
+    let first = matches.next()?;
+    std::iter::once(first)
+        .chain(matches)
+        .find(|a| a.alias.as_deref() == alias)
+        .or(Some(first))
+}
+
 /// Resolve an account by identifier (username or alias), or show interactive selection.
 pub fn resolve_account(config: &Config, identifier: Option<String>, prompt: &str) -> Account {
     if config.accounts.is_empty() {
@@ -239,13 +676,7 @@ pub fn resolve_account(config: &Config, identifier: Option<String>, prompt: &str
 
     match identifier {
         Some(id) => {
-            let found = config.accounts.iter().find(|a| {
-                a.username == id
-                    || a.alias.as_deref() == Some(&id)
-                    || a.alias
-                        .as_ref()
-                        .is_some_and(|alias| id == format!("{}:{}", a.username, alias))
-            });
+            let found = find_account(config, &id);
             match found {
                 Some(a) => a.clone(),
                 None => {
@@ -258,118 +689,202 @@ pub fn resolve_account(config: &Config, identifier: Option<String>, prompt: &str
                 }
             }
         }
+        None if crate::models::is_strict() => {
+            eprintln!(
+                "\n  {} No account given and strict mode never prompts. Pass {}.\n",
+                "\u{2717}".red().bold(),
+                "-a <account>".cyan()
+            );
+            std::process::exit(1);
+        }
         None => {
             let labels: Vec<String> = config.accounts.iter().map(format_account_label).collect();
+            let initial = default_account(config)
+                .and_then(|default| {
+                    config
+                        .accounts
+                        .iter()
+                        .position(|a| std::ptr::eq(a, default))
+                })
+                .unwrap_or(0);
 
             enter_raw_mode();
-            let selection = raw_select(prompt, &labels, 0);
+            let selection = raw_select(prompt, &labels, initial);
             exit_raw_mode();
 
             match selection {
                 Some(index) => config.accounts[index].clone(),
-                None => {
-                    std::process::exit(0);
-                }
+                None => crate::tui::exit_cancelled(),
             }
         }
     }
 }
 
-pub fn scan_ssh_keys(
-    target_username: &str,
-    target_email: &str,
-) -> (Vec<String>, Vec<std::path::PathBuf>, usize) {
-    let mut display_items = Vec::new();
-    let mut paths = Vec::new();
-    let mut default_idx = 0;
-
-    let Some(entries) = dirs::home_dir()
-        .map(|h| h.join(".ssh"))
-        .and_then(|d| std::fs::read_dir(d).ok())
-    else {
-        display_items.push("Enter ssh key path manually".to_string());
-        return (display_items, paths, 0);
-    };
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
+/// Let the user pick one of the addresses the forge knows about, keeping the
+/// current value selected and manual entry as the last option.
+pub fn choose_email(emails: &[crate::provider::RemoteEmail], current: &str) -> Option<String> {
+    let mut items: Vec<String> = emails
+        .iter()
+        .map(|e| {
+            let mut tags = Vec::new();
+            if e.primary {
+                tags.push("primary");
+            }
+            if e.email.contains("noreply.") {
+                tags.push("noreply");
+            }
+            tags.push(if e.verified { "verified" } else { "unverified" });
+            format!(
+                "{} {}",
+                redact_email(&e.email),
+                format!("({})", tags.join(", ")).dimmed()
+            )
+        })
+        .collect();
+    items.push("Enter email manually".to_string());
+
+    let default = emails
+        .iter()
+        .position(|e| e.email.eq_ignore_ascii_case(current))
+        .unwrap_or(0);
+    // Esc on manual entry goes back to the list.
+    loop {
+        let selection = raw_select("Email", &items, default)?;
+        if selection < emails.len() {
+            return Some(emails[selection].email.clone());
         }
-
-        let filename = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        if filename.ends_with(".pub")
-            || filename.ends_with(".old")
-            || filename.ends_with(".bak")
-            || filename == "config"
-            || filename == "known_hosts"
-            || filename == "authorized_keys"
-        {
-            continue;
+        if let Some(email) = crate::tui::raw_input("Email", current).filter(|v| !v.is_empty()) {
+            return Some(email);
         }
+    }
+}
 
-        let is_private_key = std::fs::File::open(&path)
-            .and_then(|mut f| {
-                let mut buffer = [0; 128];
-                use std::io::Read;
-                let n = f.read(&mut buffer)?;
-                Ok(String::from_utf8_lossy(&buffer[..n]).starts_with("-----BEGIN"))
-            })
-            .unwrap_or(false);
-
-        if !is_private_key {
-            continue;
+/// Hosts to offer when picking one: github.com, then hosts already used by
+/// accounts and group policies (wildcard patterns skipped), without duplicates.
+pub fn known_hosts(config: &Config) -> Vec<String> {
+    let mut hosts = vec!["github.com".to_string()];
+    let candidates = config
+        .accounts
+        .iter()
+        .filter_map(|a| a.host.as_deref())
+        .chain(config.groups.values().filter_map(|p| p.host.as_deref()))
+        .filter(|host| !host.starts_with("*."))
+        .map(crate::host::normalize);
+    for host in candidates {
+        if !host.is_empty() && !hosts.contains(&host) {
+            hosts.push(host);
         }
+    }
+    hosts
+}
 
-        let pub_path = path.with_extension("pub");
-        let comment = std::fs::read_to_string(&pub_path).ok().and_then(|content| {
-            let parts: Vec<&str> = content.split_whitespace().collect();
-            if parts.len() >= 3 {
-                Some(parts[2..].join(" "))
-            } else {
-                None
+/// Let the user pick a known host or type another one. The result is in the
+/// form stored on accounts (`None` for github.com); `None` overall on cancel.
+pub fn choose_host(config: &Config, prompt: &str, current: Option<&str>) -> Option<Option<String>> {
+    let hosts = known_hosts(config);
+    let current = crate::host::normalize(current.unwrap_or("github.com"));
+    let mut items = hosts.clone();
+    items.push("Enter host manually".to_string());
+    let default = hosts.iter().position(|h| *h == current).unwrap_or(0);
+
+    // Esc on manual entry goes back to the list.
+    loop {
+        let selection = raw_select(prompt, &items, default)?;
+        let host = if selection < hosts.len() {
+            hosts[selection].clone()
+        } else {
+            match crate::tui::raw_input(prompt, "") {
+                Some(host) => host,
+                None => continue,
             }
-        });
-
-        let display_name = match &comment {
-            Some(c) => format!("{} ({})", filename, c),
-            None => filename.clone(),
         };
+        return Some(crate::host::for_account(&host));
+    }
+}
 
-        let matches = comment.as_ref().is_some_and(|c| {
-            let c_lower = c.to_lowercase();
-            let u_lower = target_username.to_lowercase();
-            let e_lower = target_email.to_lowercase();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            c_lower == e_lower
-                || c_lower == u_lower
-                || c_lower
-                    .find('@')
-                    .is_some_and(|idx| c_lower[..idx] == u_lower)
-        });
+    #[test]
+    fn redaction_keeps_first_character_and_domain() {
+        assert_eq!(mask_start("Élise"), "É***");
+        assert_eq!(mask_start(""), "");
+        assert_eq!(mask_email("jane.doe@acme.com"), "j***@acme.com");
+        assert_eq!(mask_email("not-an-email"), "n***");
+        assert_eq!(mask_id("jane:work"), "j***:work");
+        assert_eq!(mask_id("jane"), "j***");
+    }
 
-        if matches {
-            default_idx = paths.len();
+    #[test]
+    fn lists_known_hosts_once() {
+        let mut config = Config::default();
+        for host in [Some("GHE.corp"), None, Some("ghe.corp"), Some("gitlab.com")] {
+            config.accounts.push(Account {
+                host: host.map(str::to_string),
+                ..Default::default()
+            });
         }
-
-        display_items.push(display_name);
-        paths.push(path);
+        for (name, host) in [("a", "*.corp"), ("b", "git.acme.com")] {
+            config.groups.insert(
+                name.to_string(),
+                crate::policy::GroupPolicy {
+                    host: Some(host.to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(
+            known_hosts(&config),
+            ["github.com", "ghe.corp", "gitlab.com", "git.acme.com"]
+        );
     }
 
-    display_items.push("Enter ssh key path manually".to_string());
-    if default_idx >= display_items.len() {
-        default_idx = display_items.len() - 1;
+    #[test]
+    fn default_account_matches_exact_id() {
+        let mut config = Config::default();
+        for alias in [Some("work"), None] {
+            config.accounts.push(Account {
+                username: "octo".to_string(),
+                alias: alias.map(str::to_string),
+                ..Default::default()
+            });
+        }
+        config.default_account = Some("octo".to_string());
+        assert_eq!(default_account(&config).unwrap().alias, None);
+        config.default_account = Some("octo:work".to_string());
+        assert_eq!(
+            default_account(&config).unwrap().alias.as_deref(),
+            Some("work")
+        );
+        config.default_account = Some("gone".to_string());
+        assert!(default_account(&config).is_none());
     }
-    (display_items, paths, default_idx)
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn defaults_to_upstream_then_origin_then_the_only_remote() {
+        let remote = |name: &str| Remote {
+            name: name.to_string(),
+            url: format!("https://github.com/{}/repo", name),
+        };
+        let several = [remote("fork"), remote("origin"), remote("upstream")];
+        let name = |r: Option<&Remote>| r.map(|r| r.name.clone());
+        assert_eq!(
+            name(default_remote_of(&several, Some("upstream"))).as_deref(),
+            Some("upstream")
+        );
+        assert_eq!(
+            name(default_remote_of(&several, Some("gone"))).as_deref(),
+            Some("origin")
+        );
+        let no_origin = [remote("fork"), remote("upstream")];
+        assert!(default_remote_of(&no_origin, None).is_none());
+        assert_eq!(
+            name(default_remote_of(&no_origin[..1], None)).as_deref(),
+            Some("fork")
+        );
+        assert!(default_remote_of(&[], None).is_none());
+    }
 
     #[test]
     fn detects_http_urls() {
@@ -377,6 +892,47 @@ mod tests {
         assert!(is_http_url("http://github.com/owner/repo.git"));
         assert!(!is_http_url("git@github.com:owner/repo.git"));
         assert!(!is_http_url("ssh://git@github.com/owner/repo.git"));
+        assert!(is_ssh_url("git@github.com:owner/repo.git"));
+        assert!(is_ssh_url("ssh://git@github.com/owner/repo.git"));
+        assert!(!is_ssh_url("https://user@github.com/owner/repo.git"));
+        assert!(!is_ssh_url("./dir/a@b:c"));
+    }
+
+    #[test]
+    fn converts_remotes_to_web_urls() {
+        assert_eq!(
+            remote_web_url("git@github.com:owner/repo.git").as_deref(),
+            Some("https://github.com/owner/repo")
+        );
+        assert_eq!(
+            remote_web_url("ssh://git@ghe.corp:2222/team/app.git").as_deref(),
+            Some("https://ghe.corp/team/app")
+        );
+        assert_eq!(
+            remote_web_url("https://user@github.com/owner/repo").as_deref(),
+            Some("https://github.com/owner/repo")
+        );
+        assert_eq!(remote_web_url("/srv/git/repo.git"), None);
+    }
+
+    #[test]
+    fn reads_azure_devops_remotes_in_https_form() {
+        let https = Some((
+            "dev.azure.com".to_string(),
+            "fabrikam/web/_git/app".to_string(),
+        ));
+        assert_eq!(
+            parse_remote_url("https://fabrikam@dev.azure.com/fabrikam/web/_git/app"),
+            https
+        );
+        assert_eq!(
+            parse_remote_url("git@ssh.dev.azure.com:v3/fabrikam/web/app"),
+            https
+        );
+        assert_eq!(
+            parse_remote_url("https://fabrikam.visualstudio.com/DefaultCollection/web/_git/app"),
+            https
+        );
     }
 
     #[test]
@@ -393,10 +949,47 @@ mod tests {
     }
 
     #[test]
-    fn formats_gitas_ssh_command() {
-        assert_eq!(
-            git_ssh_command(r"C:\Users\me\.ssh\id_ed25519"),
-            r#"ssh -i "C:/Users/me/.ssh/id_ed25519" -o IdentitiesOnly=yes"#
-        );
+    fn rejects_identities_git_would_mangle() {
+        assert!(validate_identity("Zoë O'Brien \"zo\"", "zoë+tag@exämple.com").is_ok());
+        assert!(validate_identity("Evil <x>", "e@x.com").is_err());
+        assert!(validate_identity("name", "a@b.com\nx").is_err());
+        assert!(validate_identity("  ", "a@b.com").is_err());
+    }
+
+    #[cfg(unix)]
+    mod shell {
+        use super::super::*;
+        use proptest::prelude::*;
+
+        fn sh(script: &str, env: &[(&str, String)]) -> String {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .envs(env.iter().map(|(k, v)| (k, v)))
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn shell_quote_round_trips(value in "[^\\x00]{0,40}") {
+                let quoted = shell_quote(&value);
+                prop_assert_eq!(sh(&format!("printf %s {}", quoted), &[]), value);
+            }
+
+            #[test]
+            fn credential_helper_round_trips(
+                username in "[^\\x00\\n]{1,30}",
+                token in "[^\\x00\\n]{1,60}",
+            ) {
+                let helper = CREDENTIAL_HELPER.trim_start_matches('!');
+                let env = credential_env(&username, &token);
+                let output = sh(&format!("{} get", helper), &env);
+                prop_assert_eq!(output, format!("username={}\npassword={}\n", username, token));
+            }
+        }
     }
 }
@@ -0,0 +1,71 @@
+use crate::models::{Account, Config};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Advisory messages that can be turned off, globally or for one account.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Warning {
+    /// credential.helper is missing or only caches tokens temporarily
+    CredentialHelper,
+    /// No token is stored, so git may prompt for authentication
+    NoToken,
+    /// The chosen commit email is public while a noreply address exists
+    Noreply,
+    /// A local switch found no HTTP remote to attach the token to
+    NoHttpRemote,
+    /// GIT_AUTHOR_*/GIT_COMMITTER_* variables are dropped for a command
+    EnvOverride,
+}
+
+impl Warning {
+    pub fn name(self) -> &'static str {
+        match self {
+            Warning::CredentialHelper => "credential-helper",
+            Warning::NoToken => "no-token",
+            Warning::Noreply => "noreply",
+            Warning::NoHttpRemote => "no-http-remote",
+            Warning::EnvOverride => "env-override",
+        }
+    }
+}
+
+/// `false` turns a warning off; an account's entry wins over the global one.
+pub type WarningSettings = BTreeMap<Warning, bool>;
+
+pub fn enabled(config: &Config, account: Option<&Account>, warning: Warning) -> bool {
+    account
+        .and_then(|a| a.warnings.get(&warning))
+        .or_else(|| config.warnings.get(&warning))
+        .copied()
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_setting_overrides_global() {
+        let mut config = Config::default();
+        let mut account = Account::default();
+        assert!(enabled(&config, Some(&account), Warning::NoToken));
+
+        config.warnings.insert(Warning::NoToken, false);
+        assert!(!enabled(&config, Some(&account), Warning::NoToken));
+        assert!(enabled(&config, None, Warning::CredentialHelper));
+
+        account.warnings.insert(Warning::NoToken, true);
+        assert!(enabled(&config, Some(&account), Warning::NoToken));
+        assert!(!enabled(&config, None, Warning::NoToken));
+    }
+
+    #[test]
+    fn serializes_as_kebab_case_keys() {
+        let settings = WarningSettings::from([(Warning::CredentialHelper, false)]);
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(json, r#"{"credential-helper":false}"#);
+    }
+}